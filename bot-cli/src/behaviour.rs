@@ -0,0 +1,248 @@
+use client::{Client, Join, WorldExt};
+use common::{
+    comp::{self, ControllerInputs},
+    terrain::{Block, BlockKind},
+    util::Dir,
+};
+use rand::prelude::*;
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use vek::*;
+
+/// How long a bot sticks to one behaviour before switching to the next one
+const BEHAVIOUR_DURATION: Duration = Duration::from_secs(15);
+/// Bots only attack entities closer than this
+const FIGHT_RANGE: f32 = 30.0;
+/// Bots attack once they are this close to their target
+const MELEE_RANGE: f32 = 3.0;
+
+const CHAT_MESSAGES: &[&str] = &[
+    "Hello!",
+    "Anyone around?",
+    "Nice weather today",
+    "Where is the nearest town?",
+    "Watch out, wolves!",
+    "brb",
+];
+
+/// Scripted activity of a bot, exercising a different part of the server
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Behaviour {
+    /// Walks around in random directions, jumping now and then
+    Walk,
+    /// Attacks the closest entity, or the air if there is none
+    Fight,
+    /// Places and removes blocks, which needs admin rights for build mode
+    Build,
+    /// Sends chat messages
+    Chat,
+}
+
+impl Behaviour {
+    pub const ALL: &'static [Behaviour] = &[
+        Behaviour::Walk,
+        Behaviour::Fight,
+        Behaviour::Build,
+        Behaviour::Chat,
+    ];
+
+    pub fn keyword(self) -> &'static str {
+        match self {
+            Behaviour::Walk => "walk",
+            Behaviour::Fight => "fight",
+            Behaviour::Build => "build",
+            Behaviour::Chat => "chat",
+        }
+    }
+}
+
+impl FromStr for Behaviour {
+    type Err = String;
+
+    fn from_str(keyword: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|behaviour| behaviour.keyword() == keyword)
+            .ok_or_else(|| format!("Unknown behaviour '{}'", keyword))
+    }
+}
+
+/// Cycles through the behaviours of a bot, deciding its inputs every tick
+pub struct Behaviours {
+    behaviours: Vec<Behaviour>,
+    current: usize,
+    since: Instant,
+    move_dir: Vec2<f32>,
+    next_turn: Instant,
+    next_action: Instant,
+    /// Block the bot placed, which it removes next
+    placed_block: Option<Vec3<i32>>,
+    requested_build_mode: bool,
+}
+
+impl Behaviours {
+    pub fn new(behaviours: Vec<Behaviour>, rng: &mut impl Rng) -> Self {
+        let now = Instant::now();
+        Self {
+            // Start the bots with different behaviours, so that all of them are exercised at
+            // the same time
+            current: rng.gen_range(0, behaviours.len().max(1)),
+            behaviours,
+            since: now,
+            move_dir: Vec2::zero(),
+            next_turn: now,
+            next_action: now,
+            placed_block: None,
+            requested_build_mode: false,
+        }
+    }
+
+    pub fn tick(&mut self, client: &mut Client, rng: &mut impl Rng) -> ControllerInputs {
+        let now = Instant::now();
+        if now.duration_since(self.since) > BEHAVIOUR_DURATION {
+            self.current = (self.current + 1) % self.behaviours.len().max(1);
+            self.since = now;
+        }
+        let mut inputs = ControllerInputs::default();
+        match self.behaviours.get(self.current) {
+            Some(Behaviour::Walk) => self.walk(&mut inputs, rng),
+            Some(Behaviour::Fight) => self.fight(client, &mut inputs, rng),
+            Some(Behaviour::Build) => self.build(client, &mut inputs, rng),
+            Some(Behaviour::Chat) => self.chat(client, rng),
+            None => {},
+        }
+        inputs
+    }
+
+    fn walk(&mut self, inputs: &mut ControllerInputs, rng: &mut impl Rng) {
+        let now = Instant::now();
+        if now > self.next_turn {
+            let angle = rng.gen_range(0.0, std::f32::consts::PI * 2.0);
+            self.move_dir = Vec2::new(angle.cos(), angle.sin());
+            self.next_turn = now + Duration::from_millis(rng.gen_range(2000, 5000));
+        }
+        inputs.move_dir = self.move_dir;
+        inputs.look_dir = Dir::from_unnormalized(self.move_dir.into()).unwrap_or_default();
+        inputs.jump.set_state(rng.gen_bool(0.02));
+    }
+
+    fn fight(&mut self, client: &mut Client, inputs: &mut ControllerInputs, rng: &mut impl Rng) {
+        let now = Instant::now();
+        let entity = client.entity();
+        let (pos, wielding) = {
+            let ecs = client.state().ecs();
+            (
+                ecs.read_storage::<comp::Pos>().get(entity).copied(),
+                ecs.read_storage::<comp::CharacterState>()
+                    .get(entity)
+                    .map_or(false, |cs| cs.is_wield()),
+            )
+        };
+        let pos = match pos {
+            Some(pos) => pos.0,
+            None => return,
+        };
+        if !wielding {
+            if now > self.next_action {
+                client.toggle_wield();
+                self.next_action = now + Duration::from_secs(1);
+            }
+            return;
+        }
+
+        let target = {
+            let ecs = client.state().ecs();
+            (
+                &ecs.entities(),
+                &ecs.read_storage::<comp::Pos>(),
+                &ecs.read_storage::<comp::Stats>(),
+            )
+                .join()
+                .filter(|(e, _, stats)| *e != entity && !stats.is_dead)
+                .map(|(_, target_pos, _)| target_pos.0)
+                .filter(|target_pos| target_pos.distance_squared(pos) < FIGHT_RANGE.powi(2))
+                .min_by(|a, b| {
+                    a.distance_squared(pos)
+                        .partial_cmp(&b.distance_squared(pos))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+        };
+
+        match target {
+            Some(target) => {
+                let to_target = target - pos;
+                inputs.look_dir = Dir::from_unnormalized(to_target).unwrap_or_default();
+                if to_target.xy().magnitude_squared() > MELEE_RANGE.powi(2) {
+                    inputs.move_dir = to_target.xy().try_normalized().unwrap_or_default();
+                } else {
+                    inputs.primary.set_state(true);
+                }
+            },
+            None => {
+                if now > self.next_turn {
+                    let angle = rng.gen_range(0.0, std::f32::consts::PI * 2.0);
+                    self.move_dir = Vec2::new(angle.cos(), angle.sin());
+                    self.next_turn = now + Duration::from_secs(2);
+                }
+                inputs.look_dir = Dir::from_unnormalized(self.move_dir.into()).unwrap_or_default();
+                inputs.primary.set_state(rng.gen_bool(0.5));
+            },
+        }
+    }
+
+    fn build(&mut self, client: &mut Client, inputs: &mut ControllerInputs, rng: &mut impl Rng) {
+        let now = Instant::now();
+        let entity = client.entity();
+        let (pos, can_build) = {
+            let ecs = client.state().ecs();
+            (
+                ecs.read_storage::<comp::Pos>().get(entity).copied(),
+                ecs.read_storage::<comp::CanBuild>().get(entity).is_some(),
+            )
+        };
+        let pos = match pos {
+            Some(pos) => pos.0,
+            None => return,
+        };
+        if !can_build {
+            // Build mode needs admin rights, the server ignores the blocks otherwise
+            if !self.requested_build_mode && client.is_admin() {
+                client.send_chat("/build".to_owned());
+                self.requested_build_mode = true;
+            }
+            return;
+        }
+        if now < self.next_action {
+            return;
+        }
+        self.next_action = now + Duration::from_millis(500);
+
+        match self.placed_block.take() {
+            Some(block_pos) => client.remove_block(block_pos),
+            None => {
+                let angle = rng.gen_range(0.0, std::f32::consts::PI * 2.0);
+                let offset = Vec2::new(angle.cos(), angle.sin()) * 2.5;
+                let block_pos =
+                    (pos + Vec3::from(offset) + Vec3::unit_z()).map(|e| e.floor() as i32);
+                let color = Rgb::new(rng.gen(), rng.gen(), rng.gen());
+                client.place_block(block_pos, Block::new(BlockKind::Misc, color));
+                inputs.look_dir = Dir::from_unnormalized(Vec3::from(offset)).unwrap_or_default();
+                self.placed_block = Some(block_pos);
+            },
+        }
+    }
+
+    fn chat(&mut self, client: &mut Client, rng: &mut impl Rng) {
+        let now = Instant::now();
+        if now < self.next_action {
+            return;
+        }
+        self.next_action = now + Duration::from_millis(rng.gen_range(3000, 8000));
+        if let Some(msg) = CHAT_MESSAGES.choose(rng) {
+            client.send_chat((*msg).to_owned());
+        }
+    }
+}
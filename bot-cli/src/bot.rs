@@ -0,0 +1,164 @@
+use crate::behaviour::{Behaviour, Behaviours};
+use client::{Client, Event};
+use common::{clock::Clock, comp};
+use rand::prelude::*;
+use serde::Serialize;
+use std::{
+    net::SocketAddr,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+use tracing::{debug, info};
+
+const VIEW_DISTANCE: u32 = 5;
+const STARTER_SWORD: &str = "common.items.weapons.sword.starter_sword";
+
+pub struct BotSettings {
+    pub name: String,
+    pub password: String,
+    pub server_addr: SocketAddr,
+    pub behaviours: Vec<Behaviour>,
+    pub tps: u64,
+}
+
+/// What a bot measured while it was running
+#[derive(Debug, Default, Serialize)]
+pub struct BotReport {
+    pub name: String,
+    /// Seconds from connecting until the character was in game
+    pub join_time: Option<f64>,
+    pub ticks: u64,
+    /// Ping in milliseconds, sampled every second while in game
+    #[serde(skip)]
+    pub pings: Vec<f64>,
+    pub msgs_sent: u64,
+    pub bytes_sent: u64,
+    pub msgs_received: u64,
+    pub bytes_received: u64,
+    /// Why the bot stopped early
+    pub error: Option<String>,
+}
+
+enum Phase {
+    LoadingCharacters { created: bool },
+    Joining,
+    InGame(Behaviours),
+}
+
+/// Runs a bot until `stop` is set or it fails
+pub fn run(settings: BotSettings, stop: &AtomicBool) -> BotReport {
+    let mut report = BotReport {
+        name: settings.name.clone(),
+        ..Default::default()
+    };
+    if let Err(e) = run_bot(&settings, stop, &mut report) {
+        info!(?e, name = ?settings.name, "Bot stopped");
+        report.error = Some(e);
+    }
+    report
+}
+
+fn run_bot(
+    settings: &BotSettings,
+    stop: &AtomicBool,
+    report: &mut BotReport,
+) -> Result<(), String> {
+    let start = Instant::now();
+    let mut rng = thread_rng();
+
+    let mut client = Client::new(settings.server_addr, Some(VIEW_DISTANCE))
+        .map_err(|e| format!("Failed to connect: {:?}", e))?;
+    client.track_network_stats();
+    // Bots are only run against the tester's own servers, so their auth provider is
+    // trusted
+    client
+        .register(settings.name.clone(), settings.password.clone(), |_| true)
+        .map_err(|e| format!("Failed to register: {:?}", e))?;
+    client.load_character_list();
+
+    let mut clock = Clock::start();
+    let mut phase = Phase::LoadingCharacters { created: false };
+    let mut last_ping_sample = Instant::now();
+
+    let result = loop {
+        if stop.load(Ordering::Relaxed) {
+            break Ok(());
+        }
+
+        let inputs = match &mut phase {
+            Phase::LoadingCharacters { created } => {
+                if let Some(error) = client.character_list.error.take() {
+                    break Err(format!("Failed to load the characters: {}", error));
+                }
+                if !client.character_list.loading {
+                    let character_id = client
+                        .character_list
+                        .characters
+                        .iter()
+                        .find_map(|character| character.character.id);
+                    match character_id {
+                        Some(character_id) => {
+                            client.request_character(character_id);
+                            phase = Phase::Joining;
+                        },
+                        None if !*created => {
+                            client.create_character(
+                                settings.name.clone(),
+                                Some(STARTER_SWORD.to_owned()),
+                                comp::Body::Humanoid(comp::humanoid::Body::random()),
+                            );
+                            *created = true;
+                        },
+                        None => break Err("Failed to create a character".to_owned()),
+                    }
+                }
+                comp::ControllerInputs::default()
+            },
+            Phase::Joining => {
+                if client.in_game().is_none() {
+                    let error = client.character_list.error.take().unwrap_or_default();
+                    break Err(format!("Failed to join: {}", error));
+                }
+                if client.current_chunk().is_some() {
+                    debug!(name = ?settings.name, "Bot is in game");
+                    report.join_time = Some(start.elapsed().as_secs_f64());
+                    phase = Phase::InGame(Behaviours::new(settings.behaviours.clone(), &mut rng));
+                }
+                comp::ControllerInputs::default()
+            },
+            Phase::InGame(behaviours) => {
+                if last_ping_sample.elapsed() > Duration::from_secs(1) {
+                    report.pings.push(client.get_ping_ms());
+                    last_ping_sample = Instant::now();
+                }
+                behaviours.tick(&mut client, &mut rng)
+            },
+        };
+
+        let events = match client.tick(inputs, clock.get_last_delta(), |_| {}) {
+            Ok(events) => events,
+            Err(e) => break Err(format!("Failed to tick: {:?}", e)),
+        };
+        let disconnected = events.into_iter().find_map(|event| match event {
+            Event::Disconnect => Some("Disconnected by the server".to_owned()),
+            Event::Kicked(reason) => Some(format!("Kicked: {}", reason)),
+            _ => None,
+        });
+        if let Some(error) = disconnected {
+            break Err(error);
+        }
+        client.cleanup();
+        report.ticks += 1;
+
+        clock.tick(Duration::from_millis(1000 / settings.tps));
+    };
+
+    if let Some(stats) = client.network_stats() {
+        report.msgs_sent = stats.msgs_sent;
+        report.bytes_sent = stats.bytes_sent;
+        report.msgs_received = stats.msgs_received;
+        report.bytes_received = stats.bytes_received;
+    }
+
+    result
+}
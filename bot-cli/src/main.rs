@@ -0,0 +1,223 @@
+#![deny(unsafe_code)]
+#![deny(clippy::clone_on_ref_ptr)]
+
+//! Runs a number of headless clients that play scripted behaviours against a
+//! server, and reports latency, bandwidth and server tick times as JSON. Unless
+//! `--server` is given, the bots play on a server started in this process.
+
+mod behaviour;
+mod bot;
+mod report;
+mod server;
+
+use crate::{
+    behaviour::Behaviour,
+    bot::{BotReport, BotSettings},
+    report::{scrape_tick_time, Report},
+    server::InProcessServer,
+};
+use clap::{App, Arg};
+use std::{
+    fs::File,
+    io::{self, Write},
+    net::{SocketAddr, ToSocketAddrs},
+    path::PathBuf,
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::{error, info, warn};
+use tracing_subscriber::{filter::LevelFilter, EnvFilter};
+
+fn main() {
+    let matches = App::new("Veloren bot cli")
+        .version(common::util::DISPLAY_VERSION_LONG.as_str())
+        .author("The veloren devs <https://gitlab.com/veloren/veloren>")
+        .about("Runs headless bots against a server for load testing")
+        .args(&[
+            Arg::with_name("server")
+                .long("server")
+                .takes_value(true)
+                .help("Address of the server, one is started in process if not given"),
+            Arg::with_name("metrics")
+                .long("metrics")
+                .takes_value(true)
+                .help("Metrics address of the server, to measure its tick time"),
+            Arg::with_name("bots")
+                .short("n")
+                .long("bots")
+                .takes_value(true)
+                .default_value("10")
+                .help("Number of bots"),
+            Arg::with_name("duration")
+                .short("d")
+                .long("duration")
+                .takes_value(true)
+                .default_value("60")
+                .help("Seconds to run the bots for"),
+            Arg::with_name("behaviours")
+                .long("behaviours")
+                .takes_value(true)
+                .default_value("walk,fight,build,chat")
+                .help("Comma separated behaviours the bots cycle through"),
+            Arg::with_name("prefix")
+                .long("prefix")
+                .takes_value(true)
+                .default_value("bot")
+                .help("Prefix of the usernames of the bots, which are numbered"),
+            Arg::with_name("password")
+                .long("password")
+                .takes_value(true)
+                .default_value("")
+                .help("Password of the bots, if the server uses auth"),
+            Arg::with_name("tps")
+                .long("tps")
+                .takes_value(true)
+                .default_value("30")
+                .help("Ticks per second of each bot"),
+            Arg::with_name("spawn-interval")
+                .long("spawn-interval")
+                .takes_value(true)
+                .default_value("200")
+                .help("Milliseconds between connecting two bots"),
+            Arg::with_name("data-dir")
+                .long("data-dir")
+                .takes_value(true)
+                .help("Data directory of the in process server"),
+            Arg::with_name("report")
+                .short("o")
+                .long("report")
+                .takes_value(true)
+                .help("File to write the report to, instead of stdout"),
+        ])
+        .get_matches();
+
+    // The report can be written to stdout, so log to stderr
+    let filter = EnvFilter::from_default_env().add_directive(LevelFilter::INFO.into());
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        .init();
+
+    let parse = |name: &str| -> u64 {
+        matches
+            .value_of(name)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_else(|| exit_with(&format!("Invalid value for --{}", name)))
+    };
+    let bot_count = parse("bots") as usize;
+    let duration = Duration::from_secs(parse("duration"));
+    let tps = parse("tps").max(1);
+    let spawn_interval = Duration::from_millis(parse("spawn-interval"));
+    let behaviours = matches
+        .value_of("behaviours")
+        .unwrap_or_default()
+        .split(',')
+        .map(|keyword| keyword.trim().parse::<Behaviour>())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| exit_with(&e));
+    let prefix = matches.value_of("prefix").unwrap_or("bot");
+    let password = matches.value_of("password").unwrap_or_default().to_owned();
+    let names = (0..bot_count)
+        .map(|i| format!("{}{}", prefix, i))
+        .collect::<Vec<_>>();
+
+    let resolve = |addr: &str| -> SocketAddr {
+        addr.to_socket_addrs()
+            .ok()
+            .and_then(|mut addrs| addrs.next())
+            .unwrap_or_else(|| exit_with(&format!("Invalid address '{}'", addr)))
+    };
+    let metrics_addr = matches.value_of("metrics").map(resolve);
+    let (server, server_addr) = match matches.value_of("server") {
+        Some(addr) => (None, resolve(addr)),
+        None => {
+            let data_dir = matches.value_of("data-dir").map_or_else(
+                || std::env::temp_dir().join("veloren-bot-cli"),
+                PathBuf::from,
+            );
+            info!("Starting the server...");
+            let server = InProcessServer::start(&data_dir, &names, bot_count)
+                .unwrap_or_else(|e| exit_with(&e));
+            let addr = server.addr;
+            (Some(server), addr)
+        },
+    };
+
+    info!(?bot_count, ?server_addr, "Starting the bots");
+    let start = Instant::now();
+    let stop = Arc::new(AtomicBool::new(false));
+    let bots = names
+        .into_iter()
+        .map(|name| {
+            let settings = BotSettings {
+                name: name.clone(),
+                password: password.clone(),
+                server_addr,
+                behaviours: behaviours.clone(),
+                tps,
+            };
+            let stop = Arc::clone(&stop);
+            let bot = thread::spawn(move || bot::run(settings, &stop));
+            thread::sleep(spawn_interval);
+            (name, bot)
+        })
+        .collect::<Vec<_>>();
+
+    let mut tick_times = Vec::new();
+    while start.elapsed() < duration {
+        thread::sleep(Duration::from_secs(1));
+        if let Some(metrics_addr) = metrics_addr {
+            match scrape_tick_time(metrics_addr) {
+                Ok(Some(tick_time)) => tick_times.push(tick_time),
+                Ok(None) => {},
+                Err(e) => warn!(?e, "Failed to read the server metrics"),
+            }
+        }
+    }
+
+    info!("Stopping the bots");
+    stop.store(true, Ordering::Relaxed);
+    let bot_reports = bots
+        .into_iter()
+        .map(|(name, bot)| {
+            bot.join().unwrap_or_else(|_| BotReport {
+                name,
+                error: Some("The bot panicked".to_owned()),
+                ..Default::default()
+            })
+        })
+        .collect::<Vec<_>>();
+    let elapsed = start.elapsed();
+    if let Some(server) = server {
+        tick_times = server.stop();
+    }
+
+    let report = Report::new(bot_reports, &tick_times, elapsed);
+    let json = serde_json::to_string_pretty(&report).expect("Failed to serialize the report");
+    let written = match matches.value_of("report") {
+        Some(path) => File::create(path).and_then(|mut file| writeln!(file, "{}", json)),
+        None => writeln!(io::stdout(), "{}", json),
+    };
+    if let Err(e) = written {
+        exit_with(&format!("Failed to write the report: {}", e));
+    }
+
+    if !report.is_success() {
+        error!(
+            failed = report.failed,
+            joined = report.joined,
+            "Not all bots ran successfully"
+        );
+        process::exit(1);
+    }
+}
+
+fn exit_with(error: &str) -> ! {
+    error!("{}", error);
+    process::exit(1);
+}
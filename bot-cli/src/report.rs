@@ -0,0 +1,155 @@
+use crate::bot::BotReport;
+use serde::Serialize;
+use std::{
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpStream},
+    time::Duration,
+};
+
+/// Distribution of a measurement
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Summary {
+    pub samples: usize,
+    pub min: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+impl Summary {
+    /// Summarizes the samples, or returns `None` if there are none
+    pub fn new(samples: &[f64]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let percentile = |p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+        Some(Self {
+            samples: sorted.len(),
+            min: sorted[0],
+            mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            max: sorted[sorted.len() - 1],
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Bandwidth {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// Bytes sent per second by all bots together
+    pub sent_per_sec: f64,
+    /// Bytes received per second by all bots together
+    pub received_per_sec: f64,
+}
+
+/// Results of a bot run, written as JSON
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub duration_secs: f64,
+    pub bot_count: usize,
+    /// Bots that made it in game
+    pub joined: usize,
+    /// Bots that stopped early because of an error
+    pub failed: usize,
+    /// Seconds it took the bots to get in game
+    pub join_time: Option<Summary>,
+    /// Ping of all bots in milliseconds
+    pub latency_ms: Option<Summary>,
+    pub bandwidth: Bandwidth,
+    /// Duration of the server ticks in milliseconds, if they could be measured
+    pub server_tick_ms: Option<Summary>,
+    pub bots: Vec<BotReport>,
+}
+
+impl Report {
+    pub fn new(bots: Vec<BotReport>, server_tick_times: &[f64], duration: Duration) -> Self {
+        let duration_secs = duration.as_secs_f64();
+        let join_times = bots
+            .iter()
+            .filter_map(|bot| bot.join_time)
+            .collect::<Vec<_>>();
+        let pings = bots
+            .iter()
+            .flat_map(|bot| bot.pings.iter().copied())
+            .collect::<Vec<_>>();
+        let bytes_sent = bots.iter().map(|bot| bot.bytes_sent).sum::<u64>();
+        let bytes_received = bots.iter().map(|bot| bot.bytes_received).sum::<u64>();
+
+        Self {
+            duration_secs,
+            bot_count: bots.len(),
+            joined: join_times.len(),
+            failed: bots.iter().filter(|bot| bot.error.is_some()).count(),
+            join_time: Summary::new(&join_times),
+            latency_ms: Summary::new(&pings),
+            bandwidth: Bandwidth {
+                bytes_sent,
+                bytes_received,
+                sent_per_sec: bytes_sent as f64 / duration_secs.max(1.0),
+                received_per_sec: bytes_received as f64 / duration_secs.max(1.0),
+            },
+            server_tick_ms: Summary::new(server_tick_times),
+            bots,
+        }
+    }
+
+    /// Whether all bots made it in game and ran until the end
+    pub fn is_success(&self) -> bool { self.failed == 0 && self.joined == self.bot_count }
+}
+
+/// Reads the duration of the last server tick in milliseconds from the metrics
+/// of a server at `addr`
+pub fn scrape_tick_time(addr: SocketAddr) -> io::Result<Option<f64>> {
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(1))?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    stream.write_all(b"GET /metrics HTTP/1.0\r\n\r\n")?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    Ok(parse_tick_time(&response))
+}
+
+/// Sums up the `tick_time` gauges of all periods of a tick, which are in
+/// nanoseconds
+fn parse_tick_time(metrics: &str) -> Option<f64> {
+    let times = metrics
+        .lines()
+        .filter(|line| line.starts_with("tick_time{"))
+        .filter_map(|line| line.rsplit(' ').next()?.parse::<f64>().ok())
+        .collect::<Vec<_>>();
+    if times.is_empty() {
+        None
+    } else {
+        Some(times.iter().sum::<f64>() / 1_000_000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(Summary::new(&[]), None);
+        let samples = (1..=100).rev().map(f64::from).collect::<Vec<_>>();
+        let summary = Summary::new(&samples).unwrap();
+        assert_eq!(summary.samples, 100);
+        assert!((summary.min - 1.0).abs() < 0.001);
+        assert!((summary.max - 100.0).abs() < 0.001);
+        assert!((summary.mean - 50.5).abs() < 0.001);
+        assert!((summary.p95 - 95.0).abs() <= 1.0);
+    }
+
+    #[test]
+    fn test_parse_tick_time() {
+        let metrics = "# HELP tick_time time in ns required for a tick of the server\n# TYPE \
+                       tick_time gauge\ntick_time{period=\"entity sync\"} \
+                       1000000\ntick_time{period=\"world\"} 4000000\ntime_of_day 3600\n";
+        assert_eq!(parse_tick_time(metrics), Some(5.0));
+        assert_eq!(parse_tick_time("time_of_day 3600\n"), None);
+    }
+}
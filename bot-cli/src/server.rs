@@ -0,0 +1,101 @@
+use common::clock::Clock;
+use server::{login_provider::LoginProvider, EditableSettings, Event, Input, Server, Settings};
+use std::{
+    net::SocketAddr,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use tracing::{error, trace};
+
+const TPS: u64 = 30;
+
+/// A server running on a background thread, for running the bots without a
+/// separate server
+pub struct InProcessServer {
+    pub addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<Vec<f64>>,
+}
+
+impl InProcessServer {
+    /// Starts the server with its data in `data_dir` and waits until it
+    /// accepts connections. All `admins` are made admins, so that the bots can
+    /// build.
+    pub fn start(data_dir: &Path, admins: &[String], max_players: usize) -> Result<Self, String> {
+        let mut settings = Settings::singleplayer(data_dir);
+        settings.server_name = "Bot test".to_owned();
+        settings.max_players = settings.max_players.max(max_players);
+        let mut editable_settings = EditableSettings::singleplayer(data_dir);
+        let login_provider = LoginProvider::new(None);
+        for admin in admins {
+            if let Ok(uuid) = login_provider.username_to_uuid(admin) {
+                editable_settings.admins.insert(uuid);
+            }
+        }
+
+        let addr = settings.gameserver_address;
+        let data_dir = data_dir.to_owned();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop2 = Arc::clone(&stop);
+        let (ready_s, ready_r) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            let server = match Server::new(settings, editable_settings, &data_dir) {
+                Ok(server) => {
+                    let _ = ready_s.send(Ok(()));
+                    server
+                },
+                Err(e) => {
+                    let _ = ready_s.send(Err(format!("Failed to start the server: {:?}", e)));
+                    return Vec::new();
+                },
+            };
+            run_server(server, &stop2)
+        });
+
+        ready_r
+            .recv()
+            .map_err(|_| "The server thread panicked".to_owned())??;
+
+        Ok(Self { addr, stop, thread })
+    }
+
+    /// Stops the server, returning the time each of its ticks took in
+    /// milliseconds
+    pub fn stop(self) -> Vec<f64> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread.join().unwrap_or_default()
+    }
+}
+
+fn run_server(mut server: Server, stop: &AtomicBool) -> Vec<f64> {
+    let mut clock = Clock::start();
+    let mut tick_times = Vec::new();
+
+    while !stop.load(Ordering::Relaxed) {
+        let start = Instant::now();
+        let events = match server.tick(Input::default(), clock.get_last_delta()) {
+            Ok(events) => events,
+            Err(e) => {
+                error!(?e, "Failed to tick the server");
+                break;
+            },
+        };
+        tick_times.push(start.elapsed().as_secs_f64() * 1000.0);
+
+        for event in events {
+            if let Event::Chat { entity: _, msg } = event {
+                trace!("[Bot] {}", msg);
+            }
+        }
+
+        server.cleanup();
+        clock.tick(Duration::from_millis(1000 / TPS));
+    }
+
+    tick_times
+}
@@ -0,0 +1,82 @@
+//! Events printed one JSON object per line with `--json`, for programs that
+//! relay the chat elsewhere
+
+use client::Client;
+use common::{comp, sync::Uid};
+use serde::Serialize;
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JsonEvent<'a> {
+    Connected {
+        server: &'a str,
+        players: Vec<&'a str>,
+    },
+    Chat {
+        chat_type: &'static str,
+        /// Alias of the player that sent the message
+        sender: Option<&'a str>,
+        /// Alias of the player a `tell` was sent to
+        target: Option<&'a str>,
+        /// Group or faction the message was sent to
+        group: Option<&'a str>,
+        message: &'a str,
+        /// The message formatted like it is shown in the chat
+        text: String,
+    },
+    DisconnectionNotification {
+        seconds: u64,
+    },
+    Kicked {
+        reason: &'a str,
+    },
+    Disconnect,
+}
+
+impl<'a> JsonEvent<'a> {
+    pub fn chat(client: &'a Client, msg: &'a comp::ChatMsg) -> Self {
+        let alias = |uid: &Uid| {
+            client
+                .player_list
+                .get(uid)
+                .map(|player| player.player_alias.as_str())
+        };
+        let (chat_type, sender, target, group) = match &msg.chat_type {
+            comp::ChatType::Online(uid) => ("online", alias(uid), None, None),
+            comp::ChatType::Offline(uid) => ("offline", alias(uid), None, None),
+            comp::ChatType::CommandInfo => ("command_info", None, None, None),
+            comp::ChatType::CommandError => ("command_error", None, None, None),
+            comp::ChatType::Kill(_, victim) => ("kill", None, alias(victim), None),
+            comp::ChatType::GroupMeta(group) => ("group_meta", None, None, Some(group.as_str())),
+            comp::ChatType::FactionMeta(faction) => {
+                ("faction_meta", None, None, Some(faction.as_str()))
+            },
+            comp::ChatType::Tell(from, to) => ("tell", alias(from), alias(to), None),
+            comp::ChatType::Say(uid) => ("say", alias(uid), None, None),
+            comp::ChatType::Group(uid, group) => ("group", alias(uid), None, Some(group.as_str())),
+            comp::ChatType::Faction(uid, faction) => {
+                ("faction", alias(uid), None, Some(faction.as_str()))
+            },
+            comp::ChatType::Region(uid) => ("region", alias(uid), None, None),
+            comp::ChatType::World(uid) => ("world", alias(uid), None, None),
+            comp::ChatType::Npc(uid, _) => ("npc", alias(uid), None, None),
+            comp::ChatType::Meta => ("meta", None, None, None),
+            comp::ChatType::Loot => ("loot", None, None, None),
+        };
+        JsonEvent::Chat {
+            chat_type,
+            sender,
+            target,
+            group,
+            message: &msg.message,
+            text: client.format_message(msg, false),
+        }
+    }
+
+    pub fn print(&self) {
+        match serde_json::to_string(self) {
+            Ok(json) => println!("{}", json),
+            Err(e) => tracing::error!(?e, "Failed to serialize event"),
+        }
+    }
+}
@@ -2,9 +2,20 @@
 #![allow(clippy::option_map_unit_fn)]
 #![deny(clippy::clone_on_ref_ptr)]
 
+mod json;
+
+use crate::json::JsonEvent;
+use clap::{App, Arg};
 use client::{Client, Event};
 use common::{clock::Clock, comp};
-use std::{io, net::ToSocketAddrs, sync::mpsc, thread, time::Duration};
+use std::{
+    io::{self, BufRead},
+    net::ToSocketAddrs,
+    process,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
 use tracing::{error, info};
 
 const TPS: u64 = 10; // Low value is okay, just reading messages.
@@ -19,56 +30,147 @@ fn read_input() -> String {
     buffer.trim().to_string()
 }
 
+/// Takes the value of an argument, or asks for it on stdin if it wasn't given
+fn arg_or_prompt(value: Option<&str>, prompt: &str) -> String {
+    value.map(str::to_owned).unwrap_or_else(|| {
+        eprintln!("{}", prompt);
+        read_input()
+    })
+}
+
 fn main() {
-    // Initialize logging.
-    tracing_subscriber::fmt::init();
+    let matches = App::new("Veloren chat cli")
+        .version(common::util::DISPLAY_VERSION_LONG.as_str())
+        .author("The veloren devs <https://gitlab.com/veloren/veloren>")
+        .about(
+            "Reads the chat of a server and sends each line of stdin as a chat message or command",
+        )
+        .args(&[
+            Arg::with_name("username")
+                .short("u")
+                .long("username")
+                .env("VELOREN_USERNAME")
+                .takes_value(true)
+                .help("Username to log in with, asked for if not given"),
+            Arg::with_name("server")
+                .short("s")
+                .long("server")
+                .env("VELOREN_SERVER")
+                .takes_value(true)
+                .help("Address of the server, asked for if not given"),
+            Arg::with_name("password")
+                .short("p")
+                .long("password")
+                .env("VELOREN_PASSWORD")
+                .hide_env_values(true)
+                .takes_value(true)
+                .help("Password, asked for if the server uses auth and it is not given"),
+            Arg::with_name("trusted-auth")
+                .long("trusted-auth")
+                .env("VELOREN_TRUSTED_AUTH")
+                .takes_value(true)
+                .multiple(true)
+                .use_delimiter(true)
+                .default_value("https://auth.veloren.net")
+                .help("Auth providers to trust with the password, comma separated"),
+            Arg::with_name("json")
+                .long("json")
+                .help("Prints events as one JSON object per line"),
+            Arg::with_name("exit-delay")
+                .long("exit-delay")
+                .takes_value(true)
+                .default_value("1")
+                .help("Seconds to keep running after stdin is closed, to receive replies"),
+        ])
+        .get_matches();
+
+    // Initialize logging, stdout is kept for the chat
+    tracing_subscriber::fmt().with_writer(io::stderr).init();
 
     info!("Starting chat-cli...");
 
+    let json = matches.is_present("json");
+    let trusted_auth = matches
+        .values_of("trusted-auth")
+        .map(|values| values.map(str::to_owned).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let exit_delay = matches
+        .value_of("exit-delay")
+        .and_then(|delay| delay.parse::<f64>().ok())
+        .filter(|delay| *delay >= 0.0)
+        .map(Duration::from_secs_f64)
+        .unwrap_or_else(|| exit_with("Invalid value for --exit-delay"));
+
     // Set up an fps clock.
     let mut clock = Clock::start();
 
-    println!("Enter your username");
-    let username = read_input();
-
-    println!("Enter the server address");
-    let server_addr = read_input();
-
-    println!("Enter your password");
-    let password = read_input();
+    let username = arg_or_prompt(matches.value_of("username"), "Enter your username");
+    let server_addr = arg_or_prompt(matches.value_of("server"), "Enter the server address");
 
     // Create a client.
-    let mut client = Client::new(
-        server_addr
-            .to_socket_addrs()
-            .expect("Invalid server address")
-            .next()
-            .unwrap(),
-        None,
-    )
-    .expect("Failed to create client instance");
-
-    println!("Server info: {:?}", client.server_info);
-
-    println!("Players online: {:?}", client.get_players());
+    let server_addr = server_addr
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .unwrap_or_else(|| exit_with("Invalid server address"));
+    let mut client = Client::new(server_addr, None)
+        .unwrap_or_else(|e| exit_with(&format!("Failed to connect: {:?}", e)));
+
+    // Only ask for a password if it's needed
+    let password = match (
+        matches.value_of("password"),
+        &client.server_info.auth_provider,
+    ) {
+        (Some(password), _) => password.to_owned(),
+        (None, Some(_)) => arg_or_prompt(None, "Enter your password"),
+        (None, None) => String::new(),
+    };
+
+    if json {
+        let players = client.get_players();
+        JsonEvent::Connected {
+            server: &client.server_info.name,
+            players: players.iter().map(|player| player.alias.as_str()).collect(),
+        }
+        .print();
+    } else {
+        println!("Server info: {:?}", client.server_info);
+        println!("Players online: {:?}", client.get_players());
+    }
 
     client
         .register(username, password, |provider| {
-            provider == "https://auth.veloren.net"
+            trusted_auth.iter().any(|trusted| trusted == provider)
         })
-        .unwrap();
+        .unwrap_or_else(|e| exit_with(&format!("Failed to register: {:?}", e)));
 
     let (tx, rx) = mpsc::channel();
     thread::spawn(move || {
-        loop {
-            let msg = read_input();
-            tx.send(msg).unwrap();
+        // The channel is closed when stdin is, or when the main thread stopped
+        for line in io::stdin().lock().lines() {
+            match line {
+                Ok(line) if line.trim().is_empty() => {},
+                Ok(line) if tx.send(line.trim().to_owned()).is_ok() => {},
+                _ => break,
+            }
         }
     });
 
+    let mut stdin_closed_at = None;
     loop {
-        for msg in rx.try_iter() {
-            client.send_chat(msg)
+        loop {
+            match rx.try_recv() {
+                Ok(msg) => client.send_chat(msg),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    stdin_closed_at.get_or_insert_with(Instant::now);
+                    break;
+                },
+            }
+        }
+        if stdin_closed_at.map_or(false, |closed_at| closed_at.elapsed() > exit_delay) {
+            info!("Stdin was closed, exiting");
+            break;
         }
 
         let events = match client.tick(
@@ -79,12 +181,24 @@ fn main() {
             Ok(events) => events,
             Err(err) => {
                 error!("Error: {:?}", err);
-                break;
+                process::exit(1);
             },
         };
 
         const SHOW_NAME: bool = false;
         for event in events {
+            if json {
+                match &event {
+                    Event::Chat(m) => JsonEvent::chat(&client, m).print(),
+                    Event::Disconnect => JsonEvent::Disconnect.print(),
+                    Event::DisconnectionNotification(seconds) => {
+                        JsonEvent::DisconnectionNotification { seconds: *seconds }.print()
+                    },
+                    Event::Kicked(reason) => JsonEvent::Kicked { reason }.print(),
+                    _ => {},
+                }
+                continue;
+            }
             match event {
                 Event::Chat(m) => println!("{}", client.format_message(&m, SHOW_NAME)),
                 Event::Disconnect => {}, // TODO
@@ -107,3 +221,8 @@ fn main() {
         clock.tick(Duration::from_millis(1000 / TPS));
     }
 }
+
+fn exit_with(error: &str) -> ! {
+    error!("{}", error);
+    process::exit(1);
+}
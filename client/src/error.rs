@@ -1,6 +1,8 @@
+use crate::replay::ReplayError;
 use authc::AuthClientError;
 pub use network::NetworkError;
 use network::{ParticipantError, StreamError};
+use std::time::Duration;
 
 #[derive(Debug)]
 pub enum Error {
@@ -16,9 +18,15 @@ pub enum Error {
     AuthErr(String),
     AuthClientError(AuthClientError),
     AuthServerNotTrusted,
-    Banned(String),
+    Banned {
+        reason: String,
+        /// `None` if the ban is permanent
+        remaining: Option<Duration>,
+    },
     /// Persisted character data is invalid or missing
     InvalidCharacter,
+    /// A replay couldn't be recorded or played back
+    Replay(ReplayError),
     //TODO: InvalidAlias,
     Other(String),
 }
@@ -38,3 +46,7 @@ impl From<StreamError> for Error {
 impl From<AuthClientError> for Error {
     fn from(err: AuthClientError) -> Self { Self::AuthClientError(err) }
 }
+
+impl From<ReplayError> for Error {
+    fn from(err: ReplayError) -> Self { Self::Replay(err) }
+}
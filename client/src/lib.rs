@@ -4,6 +4,7 @@
 
 pub mod cmd;
 pub mod error;
+pub mod replay;
 
 // Reexports
 pub use crate::error::Error;
@@ -14,27 +15,32 @@ pub use specs::{
     Builder, DispatcherBuilder, Entity as EcsEntity, ReadStorage, WorldExt,
 };
 
+use crate::replay::{ReplayHeader, ReplayMsg, ReplayReader, ReplayStream, ReplayWriter};
 use byteorder::{ByteOrder, LittleEndian};
 use common::{
     character::{CharacterId, CharacterItem},
     comp::{
         self,
         chat::{KillSource, KillType},
-        group, ControlAction, ControlEvent, Controller, ControllerInputs, GroupManip,
-        InventoryManip, InventoryUpdateEvent,
+        group::{self, InviteKind},
+        ControlAction, ControlEvent, Controller, ControllerInputs, GroupManip, InventoryManip,
+        InventoryUpdateEvent,
     },
     event::{EventBus, LocalEvent},
     msg::{
-        validate_chat_msg, ChatMsgValidationError, ClientGeneral, ClientInGame, ClientMsg,
-        ClientRegister, ClientType, DisconnectReason, InviteAnswer, Notification, PingMsg,
-        PlayerInfo, PlayerListUpdate, RegisterError, ServerGeneral, ServerInfo, ServerInit,
-        ServerRegisterAnswer, MAX_BYTES_CHAT_MSG,
+        validate_chat_msg,
+        world_msg::{PoiInfo, SiteInfo},
+        ChatMsgValidationError, ClientGeneral, ClientInGame, ClientMsg, ClientRegister, ClientType,
+        DisconnectReason, InviteAnswer, Notification, PingMsg, PlayerInfo, PlayerListUpdate,
+        RegisterError, ServerGeneral, ServerInfo, ServerInit, ServerRegisterAnswer,
+        MAX_BYTES_CHAT_MSG,
     },
     outcome::Outcome,
     recipe::RecipeBook,
     state::State,
     sync::{Uid, UidAllocator, WorldSyncExt},
     terrain::{block::Block, neighbors, TerrainChunk, TerrainChunkSize},
+    trade::{EconomyInfo, PendingTrade, SiteId, SitePrices, TradeAction, TradeId, TradeResult},
     vol::RectVolSize,
 };
 use futures_executor::block_on;
@@ -45,9 +51,11 @@ use image::DynamicImage;
 use network::{Network, Participant, Pid, ProtocolAddr, Stream};
 use num::traits::FloatConst;
 use rayon::prelude::*;
+use serde::Serialize;
 use std::{
     collections::VecDeque,
     net::SocketAddr,
+    path::Path,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -66,6 +74,11 @@ pub enum Event {
     Notification(Notification),
     SetViewDistance(u32),
     Outcome(Outcome),
+    TradeComplete(TradeResult),
+    /// The character took on the quest with this asset specifier
+    QuestStarted(String),
+    /// The character completed the quest with this asset specifier
+    QuestCompleted(String),
 }
 
 pub struct Client {
@@ -95,6 +108,9 @@ pub struct Client {
     /// chunk (i.e. the sea level) in its x coordinate, and the maximum land
     /// height above this height (i.e. the max height) in its y coordinate.
     pub world_map: (Arc<DynamicImage>, Vec2<u16>, Vec2<f32>),
+    sites: Vec<SiteInfo>,
+    pois: Vec<PoiInfo>,
+    tracks: Vec<Vec<Vec2<i32>>>,
     pub player_list: HashMap<Uid, PlayerInfo>,
     pub character_list: CharacterList,
     pub active_character_id: Option<CharacterId>,
@@ -103,20 +119,24 @@ pub struct Client {
 
     max_group_size: u32,
     // Client has received an invite (inviter uid, time out instant)
-    group_invite: Option<(Uid, std::time::Instant, std::time::Duration)>,
+    invite: Option<(Uid, std::time::Instant, std::time::Duration, InviteKind)>,
     group_leader: Option<Uid>,
     // Note: potentially representable as a client only component
     group_members: HashMap<Uid, group::Role>,
     // Pending invites that this client has sent out
     pending_invites: HashSet<Uid>,
-
-    _network: Network,
-    participant: Option<Participant>,
-    general_stream: Stream,
-    ping_stream: Stream,
-    register_stream: Stream,
-    character_screen_stream: Stream,
-    in_game_stream: Stream,
+    // The trade the client is in
+    pending_trade: Option<ClientTrade>,
+    // The economies of the sites the server told the client about
+    site_economies: HashMap<SiteId, EconomyInfo>,
+    // The quests of the character, as the server last sent them
+    quest_log: comp::QuestLog,
+
+    /// `None` while playing back a replay
+    connection: Option<Connection>,
+    recorder: Option<ReplayWriter>,
+    playback: Option<ReplayReader>,
+    network_stats: Option<NetworkStats>,
 
     client_timeout: Duration,
     last_server_ping: f64,
@@ -135,6 +155,67 @@ pub struct Client {
     pending_chunks: HashMap<Vec2<i32>, Instant>,
 }
 
+/// The connection to the server
+struct Connection {
+    _network: Network,
+    participant: Option<Participant>,
+    general_stream: Stream,
+    ping_stream: Stream,
+    register_stream: Stream,
+    character_screen_stream: Stream,
+    in_game_stream: Stream,
+}
+
+/// Everything the client is initialized with from `ServerInit::GameSync`
+struct InitialSync {
+    state: State,
+    entity: EcsEntity,
+    lod_base: Vec<u32>,
+    lod_alt: Vec<u32>,
+    lod_horizon: Vec<u32>,
+    world_map: (Arc<DynamicImage>, Vec2<u16>, Vec2<f32>),
+    sites: Vec<SiteInfo>,
+    pois: Vec<PoiInfo>,
+    tracks: Vec<Vec<Vec2<i32>>>,
+    recipe_book: RecipeBook,
+    max_group_size: u32,
+    client_timeout: Duration,
+}
+
+/// Amount of data exchanged with the server, counted as the size of the
+/// serialized messages before compression and framing
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NetworkStats {
+    pub msgs_sent: u64,
+    pub bytes_sent: u64,
+    pub msgs_received: u64,
+    pub bytes_received: u64,
+}
+
+impl NetworkStats {
+    fn count_sent(&mut self, msg: &impl Serialize) {
+        self.msgs_sent += 1;
+        self.bytes_sent += bincode::serialized_size(msg).unwrap_or(0);
+    }
+
+    fn count_received(&mut self, msg: &impl Serialize) {
+        self.msgs_received += 1;
+        self.bytes_received += bincode::serialized_size(msg).unwrap_or(0);
+    }
+}
+
+/// The trade the client is in, as last sent by the server
+#[derive(Clone, Debug)]
+pub struct ClientTrade {
+    pub id: TradeId,
+    pub trade: PendingTrade,
+    /// Items of the other party the client can see, by inventory slot. This
+    /// is the whole stock of a merchant, or what another player offers.
+    pub counterparty_items: Vec<(usize, comp::Item)>,
+    /// Prices of the merchant the client trades with
+    pub prices: Option<SitePrices>,
+}
+
 /// Holds data related to the current players characters, as well as some
 /// additional state to handle UI.
 #[derive(Default)]
@@ -147,6 +228,51 @@ pub struct CharacterList {
 impl Client {
     /// Create a new `Client`.
     pub fn new<A: Into<SocketAddr>>(addr: A, view_distance: Option<u32>) -> Result<Self, Error> {
+        Self::connect(addr.into(), view_distance, None)
+    }
+
+    /// Create a new `Client` that records the messages it receives to a replay
+    /// file at `record`, which can be played back with `Client::from_replay`.
+    pub fn new_recorded<A: Into<SocketAddr>>(
+        addr: A,
+        view_distance: Option<u32>,
+        record: &Path,
+    ) -> Result<Self, Error> {
+        Self::connect(addr.into(), view_distance, Some(record))
+    }
+
+    /// Create a new `Client` that plays back a replay instead of connecting to
+    /// a server. Each tick handles the messages that were handled in the same
+    /// tick of the recording, and messages to the server are dropped. If
+    /// `record` is given, the playback is recorded again.
+    pub fn from_replay(replay: &Path, record: Option<&Path>) -> Result<Self, Error> {
+        let (playback, header) = ReplayReader::open(replay)?;
+        let recorder = record
+            .map(|path| ReplayWriter::create(path, &header))
+            .transpose()?;
+        let initial_sync = Self::initial_sync(header.init, || Ok(()))?;
+
+        let mut thread_pool = ThreadPoolBuilder::new()
+            .name("veloren-worker".into())
+            .build();
+        thread_pool.set_num_threads((num_cpus::get() - 1).max(1));
+
+        Ok(Self::from_initial_sync(
+            initial_sync,
+            header.server_info,
+            None,
+            thread_pool,
+            None,
+            recorder,
+            Some(playback),
+        ))
+    }
+
+    fn connect(
+        addr: SocketAddr,
+        view_distance: Option<u32>,
+        record: Option<&Path>,
+    ) -> Result<Self, Error> {
         let mut thread_pool = ThreadPoolBuilder::new()
             .name("veloren-worker".into())
             .build();
@@ -156,7 +282,7 @@ impl Client {
         let (network, scheduler) = Network::new(Pid::new());
         thread_pool.execute(scheduler);
 
-        let participant = block_on(network.connect(ProtocolAddr::Tcp(addr.into())))?;
+        let participant = block_on(network.connect(ProtocolAddr::Tcp(addr)))?;
         let stream = block_on(participant.opened())?;
         let mut ping_stream = block_on(participant.opened())?;
         let mut register_stream = block_on(participant.opened())?;
@@ -181,17 +307,59 @@ impl Client {
         ping_stream.send(PingMsg::Ping)?;
 
         // Wait for initial sync
-        let (
-            state,
-            entity,
-            lod_base,
-            lod_alt,
-            lod_horizon,
-            world_map,
-            recipe_book,
-            max_group_size,
-            client_timeout,
-        ) = match block_on(register_stream.recv())? {
+        let init: ServerInit = block_on(register_stream.recv())?;
+        let replay_header = record.map(|path| {
+            (path, ReplayHeader {
+                server_info: server_info.clone(),
+                init: init.clone(),
+            })
+        });
+        let initial_sync = Self::initial_sync(init, || {
+            ping_stream.send(PingMsg::Ping).map_err(Error::from)
+        })?;
+        ping_stream.send(PingMsg::Ping)?;
+
+        let recorder = replay_header
+            .map(|(path, header)| ReplayWriter::create(path, &header))
+            .transpose()?;
+
+        let mut thread_pool = ThreadPoolBuilder::new()
+            .name("veloren-worker".into())
+            .build();
+        // We reduce the thread count by 1 to keep rendering smooth
+        thread_pool.set_num_threads((num_cpus::get() - 1).max(1));
+
+        debug!("Initial sync done");
+
+        let connection = Connection {
+            _network: network,
+            participant: Some(participant),
+            general_stream: stream,
+            ping_stream,
+            register_stream,
+            character_screen_stream,
+            in_game_stream,
+        };
+
+        Ok(Self::from_initial_sync(
+            initial_sync,
+            server_info,
+            view_distance,
+            thread_pool,
+            Some(connection),
+            recorder,
+            None,
+        ))
+    }
+
+    /// Initializes the state from the initial sync with the server.
+    /// `keep_alive` is called regularly while processing the world map, which
+    /// takes a while.
+    fn initial_sync(
+        init: ServerInit,
+        mut keep_alive: impl FnMut() -> Result<(), Error>,
+    ) -> Result<InitialSync, Error> {
+        match init {
             ServerInit::GameSync {
                 entity_package,
                 time_of_day,
@@ -230,11 +398,14 @@ impl Client {
                     return Err(Error::Other("Server sent a bad altitude map.".into()));
                 }
                 let [west, east] = world_map.horizons;
+                let sites = world_map.sites;
+                let pois = world_map.pois;
+                let tracks = world_map.tracks;
                 let scale_angle =
                     |a: u8| (a as f32 / 255.0 * <f32 as FloatConst>::FRAC_PI_2()).tan();
                 let scale_height = |h: u8| h as f32 / 255.0 * max_height;
                 let scale_height_big = |h: u32| (h >> 3) as f32 / 8191.0 * max_height;
-                ping_stream.send(PingMsg::Ping)?;
+                keep_alive()?;
 
                 debug!("Preparing image...");
                 let unzip_horizons = |(angles, heights): &(Vec<_>, Vec<_>)| {
@@ -262,7 +433,7 @@ impl Client {
                         && pos.x < map_size.x as i32
                         && pos.y < map_size.y as i32
                 };
-                ping_stream.send(PingMsg::Ping)?;
+                keep_alive()?;
                 map_config.generate(
                     |pos| {
                         let (rgba, alt, downhill_wpos) = if bounds_check(pos) {
@@ -321,7 +492,7 @@ impl Client {
                             u32::from_le_bytes([r, g, b, a]);
                     },
                 );
-                ping_stream.send(PingMsg::Ping)?;
+                keep_alive()?;
                 let make_raw = |rgba| -> Result<_, Error> {
                     let mut raw = vec![0u8; 4 * world_map.len()];
                     LittleEndian::write_u32_into(rgba, &mut raw);
@@ -337,7 +508,7 @@ impl Client {
                         .flipv(),
                     ))
                 };
-                ping_stream.send(PingMsg::Ping)?;
+                keep_alive()?;
                 let lod_base = rgba;
                 let lod_alt = alt;
                 let world_map = make_raw(&world_map)?;
@@ -349,36 +520,58 @@ impl Client {
                 let map_bounds = Vec2::new(sea_level, max_height);
                 debug!("Done preparing image...");
 
-                Ok((
+                Ok(InitialSync {
                     state,
                     entity,
                     lod_base,
                     lod_alt,
                     lod_horizon,
-                    (world_map, map_size, map_bounds),
+                    world_map: (world_map, map_size, map_bounds),
+                    sites,
+                    pois,
+                    tracks,
                     recipe_book,
                     max_group_size,
                     client_timeout,
-                ))
+                })
             },
             ServerInit::TooManyPlayers => Err(Error::TooManyPlayers),
-        }?;
-        ping_stream.send(PingMsg::Ping)?;
-
-        let mut thread_pool = ThreadPoolBuilder::new()
-            .name("veloren-worker".into())
-            .build();
-        // We reduce the thread count by 1 to keep rendering smooth
-        thread_pool.set_num_threads((num_cpus::get() - 1).max(1));
+        }
+    }
 
-        debug!("Initial sync done");
+    fn from_initial_sync(
+        initial_sync: InitialSync,
+        server_info: ServerInfo,
+        view_distance: Option<u32>,
+        thread_pool: ThreadPool,
+        connection: Option<Connection>,
+        recorder: Option<ReplayWriter>,
+        playback: Option<ReplayReader>,
+    ) -> Self {
+        let InitialSync {
+            state,
+            entity,
+            lod_base,
+            lod_alt,
+            lod_horizon,
+            world_map,
+            sites,
+            pois,
+            tracks,
+            recipe_book,
+            max_group_size,
+            client_timeout,
+        } = initial_sync;
 
-        Ok(Self {
+        Self {
             registered: false,
             in_game: None,
             thread_pool,
             server_info,
             world_map,
+            sites,
+            pois,
+            tracks,
             lod_base,
             lod_alt,
             lod_horizon,
@@ -389,18 +582,18 @@ impl Client {
             available_recipes: HashSet::default(),
 
             max_group_size,
-            group_invite: None,
+            invite: None,
             group_leader: None,
             group_members: HashMap::new(),
             pending_invites: HashSet::new(),
+            pending_trade: None,
+            site_economies: HashMap::new(),
+            quest_log: comp::QuestLog::default(),
 
-            _network: network,
-            participant: Some(participant),
-            general_stream: stream,
-            ping_stream,
-            register_stream,
-            character_screen_stream,
-            in_game_stream,
+            connection,
+            recorder,
+            playback,
+            network_stats: None,
 
             client_timeout,
 
@@ -416,7 +609,7 @@ impl Client {
             loaded_distance: 0.0,
 
             pending_chunks: HashMap::new(),
-        })
+        }
     }
 
     pub fn with_thread_pool(mut self, thread_pool: ThreadPool) -> Self {
@@ -445,12 +638,22 @@ impl Client {
 
         self.send_msg_err(ClientRegister { token_or_username })?;
 
-        match block_on(self.register_stream.recv::<ServerRegisterAnswer>())? {
+        let register_stream = match &mut self.connection {
+            Some(connection) => &mut connection.register_stream,
+            None => {
+                return Err(Error::Other(
+                    "Can't register while playing back a replay".into(),
+                ));
+            },
+        };
+        match block_on(register_stream.recv::<ServerRegisterAnswer>())? {
             Err(RegisterError::AlreadyLoggedIn) => Err(Error::AlreadyLoggedIn),
             Err(RegisterError::AuthError(err)) => Err(Error::AuthErr(err)),
             Err(RegisterError::InvalidCharacter) => Err(Error::InvalidCharacter),
             Err(RegisterError::NotOnWhitelist) => Err(Error::NotOnWhitelist),
-            Err(RegisterError::Banned(reason)) => Err(Error::Banned(reason)),
+            Err(RegisterError::Banned { reason, remaining }) => {
+                Err(Error::Banned { reason, remaining })
+            },
             Ok(()) => {
                 self.registered = true;
                 Ok(())
@@ -475,16 +678,29 @@ impl Client {
                 )
             );
         }
+        // Nothing is sent while playing back a replay
+        let connection = match &mut self.connection {
+            Some(connection) => connection,
+            None => return Ok(()),
+        };
+        if let Some(stats) = &mut self.network_stats {
+            match &msg {
+                ClientMsg::Type(msg) => stats.count_sent(msg),
+                ClientMsg::Register(msg) => stats.count_sent(msg),
+                ClientMsg::General(msg) => stats.count_sent(msg),
+                ClientMsg::Ping(msg) => stats.count_sent(msg),
+            }
+        }
         match msg {
-            ClientMsg::Type(msg) => self.register_stream.send(msg),
-            ClientMsg::Register(msg) => self.register_stream.send(msg),
+            ClientMsg::Type(msg) => connection.register_stream.send(msg),
+            ClientMsg::Register(msg) => connection.register_stream.send(msg),
             ClientMsg::General(msg) => {
                 let stream = match msg {
                     ClientGeneral::RequestCharacterList
                     | ClientGeneral::CreateCharacter { .. }
                     | ClientGeneral::DeleteCharacter(_)
                     | ClientGeneral::Character(_)
-                    | ClientGeneral::Spectate => &mut self.character_screen_stream,
+                    | ClientGeneral::Spectate => &mut connection.character_screen_stream,
                     //Only in game
                     ClientGeneral::ControllerInputs(_)
                     | ClientGeneral::ControlEvent(_)
@@ -497,15 +713,19 @@ impl Client {
                     | ClientGeneral::TerrainChunkRequest { .. }
                     | ClientGeneral::UnlockSkill(_)
                     | ClientGeneral::RefundSkill(_)
-                    | ClientGeneral::UnlockSkillGroup(_) => &mut self.in_game_stream,
+                    | ClientGeneral::UnlockSkillGroup(_)
+                    | ClientGeneral::InitiateTrade(_)
+                    | ClientGeneral::UpdatePendingTrade(_, _)
+                    | ClientGeneral::RequestSiteInfo(_)
+                    | ClientGeneral::Talk(_) => &mut connection.in_game_stream,
                     //Always possible
                     ClientGeneral::ChatMsg(_)
                     | ClientGeneral::Disconnect
-                    | ClientGeneral::Terminate => &mut self.general_stream,
+                    | ClientGeneral::Terminate => &mut connection.general_stream,
                 };
                 stream.send(msg)
             },
-            ClientMsg::Ping(msg) => self.ping_stream.send(msg),
+            ClientMsg::Ping(msg) => connection.ping_stream.send(msg),
         }
     }
 
@@ -593,9 +813,11 @@ impl Client {
 
     pub fn recipe_book(&self) -> &RecipeBook { &self.recipe_book }
 
+    /// The recipes the character has the ingredients for, regardless of the
+    /// stations they need
     pub fn available_recipes(&self) -> &HashSet<String> { &self.available_recipes }
 
-    pub fn can_craft_recipe(&self, recipe: &str) -> bool {
+    pub fn has_ingredients(&self, recipe: &str) -> bool {
         self.recipe_book
             .get(recipe)
             .zip(self.inventories().get(self.entity))
@@ -603,6 +825,23 @@ impl Client {
             .unwrap_or(false)
     }
 
+    /// Whether the character is near the station the recipe needs, if any
+    pub fn is_near_station(&self, recipe: &str) -> bool {
+        let pos = self
+            .state
+            .read_component_copied::<comp::Pos>(self.entity)
+            .map(|pos| pos.0);
+        self.recipe_book.get(recipe).map_or(false, |recipe| {
+            recipe.station.map_or(true, |station| {
+                pos.map_or(false, |pos| station.is_near(self.state.ecs(), pos))
+            })
+        })
+    }
+
+    pub fn can_craft_recipe(&self, recipe: &str) -> bool {
+        self.has_ingredients(recipe) && self.is_near_station(recipe)
+    }
+
     pub fn craft_recipe(&mut self, recipe: &str) -> bool {
         if self.can_craft_recipe(recipe) {
             self.send_msg(ClientGeneral::ControlEvent(ControlEvent::InventoryManip(
@@ -619,7 +858,7 @@ impl Client {
             .recipe_book
             .iter()
             .map(|(name, _)| name.clone())
-            .filter(|name| self.can_craft_recipe(name))
+            .filter(|name| self.has_ingredients(name))
             .collect();
     }
 
@@ -631,10 +870,49 @@ impl Client {
         self.send_msg(ClientGeneral::ControlEvent(ControlEvent::DisableLantern));
     }
 
+    pub fn pending_trade(&self) -> Option<&ClientTrade> { self.pending_trade.as_ref() }
+
+    /// Asks an entity to trade, the server answers with the new trade or
+    /// declines it
+    pub fn initiate_trade(&mut self, counterparty: Uid) {
+        self.send_msg(ClientGeneral::InitiateTrade(counterparty));
+    }
+
+    /// Changes or accepts the trade the client is in
+    pub fn perform_trade_action(&mut self, action: TradeAction) {
+        if let Some(id) = self.pending_trade.as_ref().map(|trade| trade.id) {
+            self.send_msg(ClientGeneral::UpdatePendingTrade(id, action));
+        }
+    }
+
+    /// Asks the server for the economy of a site, which is available from
+    /// `site_economy` once it answered
+    pub fn request_site_economy(&mut self, id: SiteId) {
+        self.send_msg(ClientGeneral::RequestSiteInfo(id));
+    }
+
+    /// The economy of a site as of the last time the client asked for it
+    pub fn site_economy(&self, id: SiteId) -> Option<&EconomyInfo> { self.site_economies.get(&id) }
+
+    /// Talks to an NPC, which may count for quests or offer a new one
+    pub fn talk(&mut self, npc: Uid) { self.send_msg(ClientGeneral::Talk(npc)); }
+
+    /// The quests of the character
+    pub fn quest_log(&self) -> &comp::QuestLog { &self.quest_log }
+
+    /// Towns, dungeons and castles to show on the map
+    pub fn sites(&self) -> &[SiteInfo] { &self.sites }
+
+    /// Places of interest to show on the map
+    pub fn pois(&self) -> &[PoiInfo] { &self.pois }
+
+    /// The tracks between sites, as lines through world positions
+    pub fn tracks(&self) -> &[Vec<Vec2<i32>>] { &self.tracks }
+
     pub fn max_group_size(&self) -> u32 { self.max_group_size }
 
-    pub fn group_invite(&self) -> Option<(Uid, std::time::Instant, std::time::Duration)> {
-        self.group_invite
+    pub fn invite(&self) -> Option<(Uid, std::time::Instant, std::time::Duration, InviteKind)> {
+        self.invite
     }
 
     pub fn group_info(&self) -> Option<(String, Uid)> {
@@ -651,17 +929,17 @@ impl Client {
         )))
     }
 
-    pub fn accept_group_invite(&mut self) {
+    pub fn accept_invite(&mut self) {
         // Clear invite
-        self.group_invite.take();
+        self.invite.take();
         self.send_msg(ClientGeneral::ControlEvent(ControlEvent::GroupManip(
             GroupManip::Accept,
         )));
     }
 
-    pub fn decline_group_invite(&mut self) {
+    pub fn decline_invite(&mut self) {
         // Clear invite
-        self.group_invite.take();
+        self.invite.take();
         self.send_msg(ClientGeneral::ControlEvent(ControlEvent::GroupManip(
             GroupManip::Decline,
         )));
@@ -954,12 +1232,12 @@ impl Client {
         frontend_events.append(&mut self.handle_new_messages()?);
 
         // 3) Update client local data
-        // Check if the group invite has timed out and remove if so
+        // Check if the invite has timed out and remove if so
         if self
-            .group_invite
-            .map_or(false, |(_, timeout, dur)| timeout.elapsed() > dur)
+            .invite
+            .map_or(false, |(_, timeout, dur, _)| timeout.elapsed() > dur)
         {
-            self.group_invite = None;
+            self.invite = None;
         }
 
         // 4) Tick the client's LocalState
@@ -1072,8 +1350,10 @@ impl Client {
                 self.state.read_storage().get(self.entity).cloned(),
                 self.state.read_storage().get(self.entity).cloned(),
             ) {
-                self.in_game_stream
-                    .send(ClientGeneral::PlayerPhysics { pos, vel, ori })?;
+                // Messages received in the next tick are handled before the client's physics
+                // tick, so the physics are played back at the start of the next tick as well
+                self.record(self.tick + 1, ReplayMsg::PlayerPhysics { pos, vel, ori });
+                self.send_msg_err(ClientGeneral::PlayerPhysics { pos, vel, ori })?;
             }
         }
 
@@ -1142,6 +1422,17 @@ impl Client {
                     );
                 }
             },
+            ServerGeneral::PlayerListUpdate(PlayerListUpdate::Pvp(uid, pvp)) => {
+                if let Some(player_info) = self.player_list.get_mut(&uid) {
+                    player_info.pvp = pvp;
+                } else {
+                    warn!(
+                        "Received msg to update pvp status of uid {}, but they were not in the \
+                         list.",
+                        uid
+                    );
+                }
+            },
             ServerGeneral::PlayerListUpdate(PlayerListUpdate::SelectedCharacter(
                 uid,
                 char_info,
@@ -1328,16 +1619,25 @@ impl Client {
                     },
                 }
             },
-            ServerGeneral::GroupInvite { inviter, timeout } => {
-                self.group_invite = Some((inviter, std::time::Instant::now(), timeout));
+            ServerGeneral::Invite {
+                inviter,
+                timeout,
+                kind,
+            } => {
+                self.invite = Some((inviter, std::time::Instant::now(), timeout, kind));
             },
             ServerGeneral::InvitePending(uid) => {
                 if !self.pending_invites.insert(uid) {
                     warn!("Received message about pending invite that was already pending");
                 }
             },
-            ServerGeneral::InviteComplete { target, answer } => {
-                if !self.pending_invites.remove(&target) {
+            ServerGeneral::InviteComplete {
+                target,
+                answer,
+                kind,
+            } => {
+                // The server doesn't tell the inviter about pending trade invites
+                if !self.pending_invites.remove(&target) && kind == InviteKind::Group {
                     warn!(
                         "Received completed invite message for invite that was not in the list of \
                          pending invites"
@@ -1345,11 +1645,17 @@ impl Client {
                 }
                 // TODO: expose this as a new event variant instead of going
                 // through the chat
-                let msg = match answer {
+                let msg = match (kind, answer) {
                     // TODO: say who accepted/declined/timed out the invite
-                    InviteAnswer::Accepted => "Invite accepted",
-                    InviteAnswer::Declined => "Invite declined",
-                    InviteAnswer::TimedOut => "Invite timed out",
+                    (InviteKind::Group, InviteAnswer::Accepted) => "Invite accepted",
+                    (InviteKind::Group, InviteAnswer::Declined) => "Invite declined",
+                    (InviteKind::Group, InviteAnswer::TimedOut) => "Invite timed out",
+                    (InviteKind::Trade, InviteAnswer::Accepted) => "Trade invite accepted",
+                    (InviteKind::Trade, InviteAnswer::Declined) => "Trade invite declined",
+                    (InviteKind::Trade, InviteAnswer::TimedOut) => "Trade invite timed out",
+                    (InviteKind::Quest, InviteAnswer::Accepted) => "Quest accepted",
+                    (InviteKind::Quest, InviteAnswer::Declined) => "Quest declined",
+                    (InviteKind::Quest, InviteAnswer::TimedOut) => "Quest offer timed out",
                 };
                 frontend_events.push(Event::Chat(comp::ChatType::Meta.chat_msg(msg)));
             },
@@ -1399,6 +1705,34 @@ impl Client {
                         impulse,
                     });
             },
+            ServerGeneral::WeatherUpdate(weather) => {
+                *self.state.ecs_mut().write_resource() = weather;
+            },
+            ServerGeneral::UpdatePendingTrade {
+                id,
+                trade,
+                counterparty_items,
+                prices,
+            } => {
+                self.pending_trade = Some(ClientTrade {
+                    id,
+                    trade,
+                    counterparty_items,
+                    prices,
+                });
+            },
+            ServerGeneral::FinishedTrade(result) => {
+                self.pending_trade = None;
+                frontend_events.push(Event::TradeComplete(result));
+            },
+            ServerGeneral::SiteEconomy(info) => {
+                self.site_economies.insert(info.id, info);
+            },
+            ServerGeneral::QuestLog(quest_log) => self.quest_log = quest_log,
+            ServerGeneral::QuestStarted(quest) => frontend_events.push(Event::QuestStarted(quest)),
+            ServerGeneral::QuestCompleted(quest) => {
+                frontend_events.push(Event::QuestCompleted(quest))
+            },
             _ => unreachable!("Not a in_game message"),
         }
         Ok(())
@@ -1458,32 +1792,105 @@ impl Client {
         cnt: &mut u64,
     ) -> Result<(), Error> {
         loop {
+            let connection = match &mut self.connection {
+                Some(connection) => connection,
+                None => return Ok(()),
+            };
             let (m1, m2, m3, m4) = select!(
-                msg = self.general_stream.recv().fuse() => (Some(msg), None, None, None),
-                msg = self.ping_stream.recv().fuse() => (None, Some(msg), None, None),
-                msg = self.character_screen_stream.recv().fuse() => (None, None, Some(msg), None),
-                msg = self.in_game_stream.recv().fuse() => (None, None, None, Some(msg)),
+                msg = connection.general_stream.recv().fuse() => (Some(msg), None, None, None),
+                msg = connection.ping_stream.recv().fuse() => (None, Some(msg), None, None),
+                msg = connection.character_screen_stream.recv().fuse() => (None, None, Some(msg), None),
+                msg = connection.in_game_stream.recv().fuse() => (None, None, None, Some(msg)),
             );
             *cnt += 1;
             if let Some(msg) = m1 {
-                self.handle_server_msg(frontend_events, msg?)?;
+                self.handle_stream_msg(frontend_events, ReplayStream::General, msg?)?;
             }
             if let Some(msg) = m2 {
-                self.handle_ping_msg(msg?)?;
+                let msg = msg?;
+                if let Some(stats) = &mut self.network_stats {
+                    stats.count_received(&msg);
+                }
+                self.handle_ping_msg(msg)?;
             }
             if let Some(msg) = m3 {
-                self.handle_server_character_screen_msg(msg?)?;
+                self.handle_stream_msg(frontend_events, ReplayStream::CharacterScreen, msg?)?;
             }
             if let Some(msg) = m4 {
-                self.handle_server_in_game_msg(frontend_events, msg?)?;
+                self.handle_stream_msg(frontend_events, ReplayStream::InGame, msg?)?;
+            }
+        }
+    }
+
+    /// Records and handles a message from the server, received on `stream`
+    fn handle_stream_msg(
+        &mut self,
+        frontend_events: &mut Vec<Event>,
+        stream: ReplayStream,
+        msg: ServerGeneral,
+    ) -> Result<(), Error> {
+        if let Some(stats) = &mut self.network_stats {
+            stats.count_received(&msg);
+        }
+        if self.recorder.is_some() {
+            self.record(self.tick, ReplayMsg::Server(stream, msg.clone()));
+        }
+        match stream {
+            ReplayStream::General => self.handle_server_msg(frontend_events, msg),
+            ReplayStream::CharacterScreen => self.handle_server_character_screen_msg(msg),
+            ReplayStream::InGame => self.handle_server_in_game_msg(frontend_events, msg),
+        }
+    }
+
+    fn record(&mut self, tick: u64, msg: ReplayMsg) {
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = recorder.write(tick, msg) {
+                warn!(?e, "Failed to record message, stopping the recording");
+                self.recorder = None;
+            }
+        }
+    }
+
+    /// Handles the messages recorded in the current tick of the replay that is
+    /// played back
+    fn handle_playback_messages(&mut self, frontend_events: &mut Vec<Event>) -> Result<(), Error> {
+        let frames = match &mut self.playback {
+            Some(playback) => playback.frames_until(self.tick)?,
+            None => return Ok(()),
+        };
+        for frame in frames {
+            match frame.msg {
+                ReplayMsg::Server(stream, msg) => {
+                    self.handle_stream_msg(frontend_events, stream, msg)?
+                },
+                ReplayMsg::PlayerPhysics { pos, vel, ori } => {
+                    self.record(frame.tick, ReplayMsg::PlayerPhysics { pos, vel, ori });
+                    self.state.write_component(self.entity, pos);
+                    self.state.write_component(self.entity, vel);
+                    self.state.write_component(self.entity, ori);
+                },
             }
         }
+        Ok(())
+    }
+
+    /// Whether the replay that is played back has ended, always `false` when
+    /// connected to a server
+    pub fn is_replay_finished(&self) -> bool {
+        self.playback
+            .as_ref()
+            .map_or(false, ReplayReader::is_finished)
     }
 
     /// Handle new server messages.
     fn handle_new_messages(&mut self) -> Result<Vec<Event>, Error> {
         let mut frontend_events = Vec::new();
 
+        if self.playback.is_some() {
+            self.handle_playback_messages(&mut frontend_events)?;
+            return Ok(frontend_events);
+        }
+
         // Check that we have an valid connection.
         // Use the last ping time as a 1s rate limiter, we only notify the user once per
         // second
@@ -1532,6 +1939,16 @@ impl Client {
 
     pub fn get_tick(&self) -> u64 { self.tick }
 
+    /// Start counting the messages exchanged with the server, which costs a
+    /// bit of time for every message
+    pub fn track_network_stats(&mut self) {
+        self.network_stats.get_or_insert_with(NetworkStats::default);
+    }
+
+    /// The messages exchanged with the server since `track_network_stats` was
+    /// called
+    pub fn network_stats(&self) -> Option<NetworkStats> { self.network_stats }
+
     pub fn get_ping_ms(&self) -> f64 { self.last_ping_delta * 1000.0 }
 
     pub fn get_ping_ms_rolling_avg(&self) -> f64 {
@@ -1605,6 +2022,7 @@ impl Client {
             .allocate(entity_builder.entity, Some(client_uid));
 
         self.entity = entity_builder.with(uid).build();
+        self.quest_log = comp::QuestLog::default();
     }
 
     /// Change player alias to "You" if client belongs to matching player
@@ -1811,8 +2229,14 @@ impl Drop for Client {
         } else {
             trace!("no disconnect msg necessary as client wasn't registered")
         }
-        if let Err(e) = block_on(self.participant.take().unwrap().disconnect()) {
-            warn!(?e, "error when disconnecting, couldn't send all data");
+        if let Some(participant) = self
+            .connection
+            .as_mut()
+            .and_then(|connection| connection.participant.take())
+        {
+            if let Err(e) = block_on(participant.disconnect()) {
+                warn!(?e, "error when disconnecting, couldn't send all data");
+            }
         }
     }
 }
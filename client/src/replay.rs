@@ -0,0 +1,397 @@
+//! Recording of the messages a client receives from the server, and playback of
+//! such recordings without a connection to a server.
+//!
+//! A replay file starts with [`REPLAY_MAGIC`] and the little endian `u32`
+//! format version, followed by bincode encoded frames that are each prefixed
+//! with their little endian `u32` length. The first frame is the
+//! [`ReplayHeader`], all following ones are [`ReplayFrame`]s.
+
+use common::{
+    comp,
+    msg::{ServerGeneral, ServerInfo, ServerInit},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+    time::Instant,
+};
+
+pub const REPLAY_MAGIC: &[u8; 8] = b"VELOREPL";
+/// Version of the replay format, which has to be increased whenever the
+/// format or any of the recorded messages change
+pub const REPLAY_VERSION: u32 = 7;
+/// Frames larger than this are considered corrupt
+const MAX_FRAME_SIZE: u32 = 1 << 30;
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Encoding(bincode::Error),
+    /// The file is not a replay
+    NotAReplay,
+    /// The replay was recorded with a different version of the format
+    UnsupportedVersion(u32),
+    /// The replay doesn't start with the initial sync of a game
+    MissingGameSync,
+}
+
+impl From<io::Error> for ReplayError {
+    fn from(err: io::Error) -> Self { Self::Io(err) }
+}
+
+impl From<bincode::Error> for ReplayError {
+    fn from(err: bincode::Error) -> Self { Self::Encoding(err) }
+}
+
+/// What the client knew of the server when the recording started
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplayHeader {
+    pub server_info: ServerInfo,
+    /// Always a `ServerInit::GameSync`
+    pub init: ServerInit,
+}
+
+/// The stream a message was received on, which decides how it is handled
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplayStream {
+    General,
+    CharacterScreen,
+    InGame,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ReplayMsg {
+    Server(ReplayStream, ServerGeneral),
+    /// Physics of the client's own entity, which the server doesn't sync back
+    /// to the client that controls it
+    PlayerPhysics {
+        pos: comp::Pos,
+        vel: comp::Vel,
+        ori: comp::Ori,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    /// Client tick in which the message is handled
+    pub tick: u64,
+    /// Seconds since the recording started
+    pub time: f64,
+    pub msg: ReplayMsg,
+}
+
+/// Writes the messages a client receives to a replay file
+pub struct ReplayWriter {
+    writer: Box<dyn Write + Send>,
+    start: Instant,
+}
+
+impl ReplayWriter {
+    pub fn create(path: &Path, header: &ReplayHeader) -> Result<Self, ReplayError> {
+        Self::new(BufWriter::new(File::create(path)?), header)
+    }
+
+    pub fn new(
+        mut writer: impl Write + Send + 'static,
+        header: &ReplayHeader,
+    ) -> Result<Self, ReplayError> {
+        writer.write_all(REPLAY_MAGIC)?;
+        writer.write_all(&REPLAY_VERSION.to_le_bytes())?;
+        write_frame(&mut writer, header)?;
+        Ok(Self {
+            writer: Box::new(writer),
+            start: Instant::now(),
+        })
+    }
+
+    pub fn write(&mut self, tick: u64, msg: ReplayMsg) -> Result<(), ReplayError> {
+        write_frame(&mut self.writer, &ReplayFrame {
+            tick,
+            time: self.start.elapsed().as_secs_f64(),
+            msg,
+        })
+    }
+
+    pub fn flush(&mut self) -> Result<(), ReplayError> { Ok(self.writer.flush()?) }
+}
+
+impl Drop for ReplayWriter {
+    fn drop(&mut self) { let _ = self.writer.flush(); }
+}
+
+fn write_frame(writer: &mut impl Write, frame: &impl Serialize) -> Result<(), ReplayError> {
+    let bytes = bincode::serialize(frame)?;
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Reads the frames of a replay file, tick by tick
+pub struct ReplayReader {
+    reader: Box<dyn Read + Send>,
+    /// Frame that was read, but belongs to a later tick
+    next: Option<ReplayFrame>,
+    finished: bool,
+}
+
+impl ReplayReader {
+    pub fn open(path: &Path) -> Result<(Self, ReplayHeader), ReplayError> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+
+    pub fn new(
+        mut reader: impl Read + Send + 'static,
+    ) -> Result<(Self, ReplayHeader), ReplayError> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != REPLAY_MAGIC {
+            return Err(ReplayError::NotAReplay);
+        }
+        let mut version = [0; 4];
+        reader.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        let header =
+            read_frame::<ReplayHeader>(&mut reader)?.ok_or(ReplayError::MissingGameSync)?;
+        if !matches!(header.init, ServerInit::GameSync { .. }) {
+            return Err(ReplayError::MissingGameSync);
+        }
+
+        Ok((
+            Self {
+                reader: Box::new(reader),
+                next: None,
+                finished: false,
+            },
+            header,
+        ))
+    }
+
+    /// Returns the frames of all ticks up to and including `tick` that haven't
+    /// been returned yet
+    pub fn frames_until(&mut self, tick: u64) -> Result<Vec<ReplayFrame>, ReplayError> {
+        let mut frames = Vec::new();
+        loop {
+            let frame = match self.next.take() {
+                Some(frame) => frame,
+                None if self.finished => break,
+                None => match read_frame::<ReplayFrame>(&mut self.reader)? {
+                    Some(frame) => frame,
+                    None => {
+                        self.finished = true;
+                        break;
+                    },
+                },
+            };
+            if frame.tick > tick {
+                self.next = Some(frame);
+                break;
+            }
+            frames.push(frame);
+        }
+        Ok(frames)
+    }
+
+    /// Whether all frames have been returned
+    pub fn is_finished(&self) -> bool { self.finished && self.next.is_none() }
+}
+
+/// Reads the next frame, or `None` at the end of the file
+fn read_frame<T: DeserializeOwned>(reader: &mut impl Read) -> Result<Option<T>, ReplayError> {
+    let mut len = [0; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {},
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u32::from_le_bytes(len);
+    if len > MAX_FRAME_SIZE {
+        return Err(ReplayError::Io(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Replay frame of {} bytes is too large", len),
+        )));
+    }
+    let mut bytes = vec![0; len as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(Some(bincode::deserialize(&bytes)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Client, Event};
+    use common::{
+        comp::{ChatType, ControllerInputs},
+        msg::{EcsCompPacket, WorldMapMsg},
+        recipe::default_recipe_book,
+        state::TimeOfDay,
+        sync::{CompSyncPackage, EntityPackage, Uid},
+    };
+    use specs::{Join, WorldExt};
+    use std::time::Duration;
+    use vek::*;
+
+    const TICKS: u64 = 8;
+
+    #[derive(Debug, PartialEq)]
+    struct Snapshot {
+        time_of_day: f64,
+        entities: Vec<(Uid, Option<comp::Pos>, Option<comp::Vel>)>,
+        chat_msgs: usize,
+    }
+
+    fn snapshot(client: &Client, events: &[Event]) -> Snapshot {
+        let ecs = client.state().ecs();
+        let mut entities = (
+            &ecs.read_storage::<Uid>(),
+            ecs.read_storage::<comp::Pos>().maybe(),
+            ecs.read_storage::<comp::Vel>().maybe(),
+        )
+            .join()
+            .map(|(uid, pos, vel)| (*uid, pos.copied(), vel.copied()))
+            .collect::<Vec<_>>();
+        entities.sort_by_key(|(uid, _, _)| uid.0);
+        Snapshot {
+            time_of_day: ecs.read_resource::<TimeOfDay>().0,
+            entities,
+            chat_msgs: events
+                .iter()
+                .filter(|event| matches!(event, Event::Chat(_)))
+                .count(),
+        }
+    }
+
+    fn play(path: &Path, record: Option<&Path>) -> Vec<Snapshot> {
+        let mut client = Client::from_replay(path, record).unwrap();
+        (0..TICKS)
+            .map(|_| {
+                let events = client
+                    .tick(
+                        ControllerInputs::default(),
+                        Duration::from_millis(33),
+                        |_| {},
+                    )
+                    .unwrap();
+                client.cleanup();
+                snapshot(&client, &events)
+            })
+            .collect()
+    }
+
+    fn entity_package(uid: u64, pos: Vec3<f32>, vel: Vec3<f32>) -> EntityPackage<EcsCompPacket> {
+        EntityPackage {
+            uid,
+            comps: vec![
+                comp::Pos(pos).into(),
+                comp::Vel(vel).into(),
+                comp::Ori::default().into(),
+            ],
+        }
+    }
+
+    fn write_replay(path: &Path) {
+        let header = ReplayHeader {
+            server_info: ServerInfo {
+                name: "Replay test".to_owned(),
+                description: String::new(),
+                git_hash: String::new(),
+                git_date: String::new(),
+                auth_provider: None,
+            },
+            init: ServerInit::GameSync {
+                entity_package: entity_package(0, Vec3::zero(), Vec3::zero()),
+                time_of_day: TimeOfDay(0.0),
+                max_group_size: 6,
+                client_timeout: Duration::from_secs(40),
+                world_map: WorldMapMsg {
+                    dimensions_lg: Vec2::new(1, 1),
+                    sea_level: 0.0,
+                    max_height: 100.0,
+                    rgba: vec![0; 4],
+                    alt: vec![0; 4],
+                    horizons: [(vec![0; 4], vec![0; 4]), (vec![0; 4], vec![0; 4])],
+                    sites: Vec::new(),
+                    pois: Vec::new(),
+                    tracks: Vec::new(),
+                },
+                recipe_book: (*default_recipe_book()).clone(),
+            },
+        };
+        let mut comp_sync = CompSyncPackage::new();
+        comp_sync.comp_modified(Uid(1), comp::Pos(Vec3::new(20.0, 0.0, 0.0)));
+        let frames = vec![
+            (
+                0,
+                ReplayMsg::Server(
+                    ReplayStream::General,
+                    ServerGeneral::CreateEntity(entity_package(
+                        1,
+                        Vec3::new(10.0, 0.0, 0.0),
+                        Vec3::new(1.0, 0.0, 0.0),
+                    )),
+                ),
+            ),
+            (
+                0,
+                ReplayMsg::Server(
+                    ReplayStream::General,
+                    ServerGeneral::TimeOfDay(TimeOfDay(100.0)),
+                ),
+            ),
+            (1, ReplayMsg::PlayerPhysics {
+                pos: comp::Pos(Vec3::new(5.0, 5.0, 5.0)),
+                vel: comp::Vel(Vec3::zero()),
+                ori: comp::Ori::default(),
+            }),
+            (
+                2,
+                ReplayMsg::Server(ReplayStream::General, ServerGeneral::CompSync(comp_sync)),
+            ),
+            (
+                3,
+                ReplayMsg::Server(
+                    ReplayStream::General,
+                    ServerGeneral::ChatMsg(ChatType::Meta.chat_msg("Hello")),
+                ),
+            ),
+            (
+                5,
+                ReplayMsg::Server(ReplayStream::General, ServerGeneral::DeleteEntity(Uid(1))),
+            ),
+        ];
+
+        let mut writer = ReplayWriter::create(path, &header).unwrap();
+        for (tick, msg) in frames {
+            writer.write(tick, msg).unwrap();
+        }
+        writer.flush().unwrap();
+    }
+
+    #[test]
+    fn test_replay_playback() {
+        let dir = std::env::temp_dir();
+        let original = dir.join(format!("veloren-replay-test-{}.replay", std::process::id()));
+        let rerecorded = dir.join(format!(
+            "veloren-replay-test-{}-2.replay",
+            std::process::id()
+        ));
+        write_replay(&original);
+
+        let first = play(&original, Some(&rerecorded));
+        let second = play(&rerecorded, None);
+        let _ = std::fs::remove_file(&original);
+        let _ = std::fs::remove_file(&rerecorded);
+
+        let has_entity =
+            |snapshot: &Snapshot| snapshot.entities.iter().any(|(uid, _, _)| *uid == Uid(1));
+        assert!(first[0].time_of_day >= 100.0);
+        assert!(first[..5].iter().all(has_entity));
+        assert!(!has_entity(&first[5]));
+        assert_eq!(first[3].chat_msgs, 1);
+        assert_eq!(first, second);
+    }
+}
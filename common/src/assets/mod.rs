@@ -473,9 +473,9 @@ pub fn read_dir(specifier: &str) -> Result<ReadDir, Error> {
     }
 }
 
-// Finds all files matching the provided glob specifier - includes files from
-// subdirectories
-fn get_glob_matches(specifier: &str) -> Result<Vec<String>, Error> {
+/// Lists the specifiers of all assets matching a glob, including the ones in
+/// subdirectories
+pub fn get_glob_matches(specifier: &str) -> Result<Vec<String>, Error> {
     let specifier = specifier.trim_end_matches(".*");
     read_dir(specifier).map(|dir| {
         dir.filter_map(|direntry| {
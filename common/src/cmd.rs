@@ -63,6 +63,7 @@ pub enum ChatCommand {
     Motd,
     Object,
     Players,
+    Pvp,
     Region,
     RemoveLights,
     Say,
@@ -110,6 +111,7 @@ pub static CHAT_COMMANDS: &[ChatCommand] = &[
     ChatCommand::Motd,
     ChatCommand::Object,
     ChatCommand::Players,
+    ChatCommand::Pvp,
     ChatCommand::Region,
     ChatCommand::RemoveLights,
     ChatCommand::Say,
@@ -207,8 +209,13 @@ impl ChatCommand {
             ),
             ChatCommand::Alias => cmd(vec![Any("name", Required)], "Change your alias", NoAdmin),
             ChatCommand::Ban => cmd(
-                vec![Any("username", Required), Message(Optional)],
-                "Ban a player with a given username",
+                vec![
+                    Any("username", Required),
+                    Any("duration", Optional),
+                    Message(Optional),
+                ],
+                "Ban a player with a given username, for a duration like 30m, 12h or 7d. Bans \
+                 without a duration are permanent",
                 Admin,
             ),
             ChatCommand::Build => cmd(vec![], "Toggles build mode on and off", Admin),
@@ -332,6 +339,7 @@ impl ChatCommand {
                 Admin,
             ),
             ChatCommand::Players => cmd(vec![], "Lists players currently online", NoAdmin),
+            ChatCommand::Pvp => cmd(vec![], "Toggle whether you take part in PvP", NoAdmin),
             ChatCommand::RemoveLights => cmd(
                 vec![Float("radius", 20.0, Optional)],
                 "Removes all lights spawned by players",
@@ -438,6 +446,7 @@ impl ChatCommand {
             ChatCommand::Motd => "motd",
             ChatCommand::Object => "object",
             ChatCommand::Players => "players",
+            ChatCommand::Pvp => "pvp",
             ChatCommand::Region => "region",
             ChatCommand::RemoveLights => "remove_lights",
             ChatCommand::Say => "say",
@@ -635,4 +644,124 @@ impl ArgumentSpec {
             },
         }
     }
+
+    /// Completions of `part` as a value of this argument, where `players` are
+    /// the aliases of the players online
+    pub fn complete(&self, part: &str, players: &[String]) -> Vec<String> {
+        match self {
+            ArgumentSpec::PlayerName(_) => complete_player(part, players),
+            ArgumentSpec::Float(_, x, _) => {
+                if part.is_empty() {
+                    vec![format!("{:.1}", x)]
+                } else {
+                    vec![]
+                }
+            },
+            ArgumentSpec::Integer(_, x, _) => {
+                if part.is_empty() {
+                    vec![format!("{}", x)]
+                } else {
+                    vec![]
+                }
+            },
+            ArgumentSpec::Any(_, _) => vec![],
+            ArgumentSpec::Command(_) => complete_command(part),
+            ArgumentSpec::Message(_) => complete_player(part, players),
+            ArgumentSpec::SubCommand => complete_command(part),
+            ArgumentSpec::Enum(_, strings, _) => strings
+                .iter()
+                .filter(|string| string.starts_with(part))
+                .map(|c| c.to_string())
+                .collect(),
+            ArgumentSpec::Boolean(_, _, _) => vec!["true", "false"]
+                .iter()
+                .filter(|string| string.starts_with(part))
+                .map(|c| c.to_string())
+                .collect(),
+        }
+    }
+}
+
+fn complete_player(part: &str, players: &[String]) -> Vec<String> {
+    players
+        .iter()
+        .filter(|alias| alias.starts_with(part))
+        .cloned()
+        .collect()
+}
+
+fn complete_command(part: &str) -> Vec<String> {
+    CHAT_SHORTCUTS
+        .keys()
+        .map(ToString::to_string)
+        .chain(CHAT_COMMANDS.iter().map(ToString::to_string))
+        .filter(|kwd| kwd.starts_with(part) || format!("/{}", kwd).starts_with(part))
+        .map(|c| format!("/{}", c))
+        .collect()
+}
+
+// Get the byte index of the nth word. Used in completing "/sudo p /subcmd"
+fn nth_word(line: &str, n: usize) -> Option<usize> {
+    let mut is_space = false;
+    let mut j = 0;
+    for (i, c) in line.char_indices() {
+        match (is_space, c.is_whitespace()) {
+            (true, true) => {},
+            (true, false) => {
+                is_space = false;
+                j += 1;
+            },
+            (false, true) => {
+                is_space = true;
+            },
+            (false, false) => {},
+        }
+        if j == n {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Completions of the last word of `line`, using the arguments of the chat
+/// command being typed. `players` are the aliases of the players online.
+pub fn complete(line: &str, players: &[String]) -> Vec<String> {
+    let word = if line.chars().last().map_or(true, char::is_whitespace) {
+        ""
+    } else {
+        line.split_whitespace().last().unwrap_or("")
+    };
+    if line.starts_with('/') {
+        let mut iter = line.split_whitespace();
+        let cmd = iter.next().unwrap();
+        let i = iter.count() + if word.is_empty() { 1 } else { 0 };
+        if i == 0 {
+            // Completing chat command name
+            complete_command(word)
+        } else if let Ok(cmd) = cmd.parse::<ChatCommand>() {
+            if let Some(arg) = cmd.data().args.get(i - 1) {
+                // Complete ith argument
+                arg.complete(word, players)
+            } else {
+                // Complete past the last argument
+                match cmd.data().args.last() {
+                    Some(ArgumentSpec::SubCommand) => {
+                        if let Some(index) = nth_word(line, cmd.data().args.len()) {
+                            complete(&line[index..], players)
+                        } else {
+                            vec![]
+                        }
+                    },
+                    Some(ArgumentSpec::Message(_)) => complete_player(word, players),
+                    _ => vec![], // End of command. Nothing to complete
+                }
+            }
+        } else {
+            // Completing for unknown chat command
+            complete_player(word, players)
+        }
+    } else {
+        // Not completing a command
+        complete_player(word, players)
+    }
 }
@@ -136,6 +136,9 @@ impl<'a> From<&'a Body> for Psyche {
 #[derive(Clone, Debug, Default)]
 pub struct Agent {
     pub patrol_origin: Option<Vec3<f32>>,
+    /// Where the agent walks to when it has nothing else to do, like the next
+    /// point of a journey
+    pub travel_to: Option<Vec3<f32>>,
     pub activity: Activity,
     /// Does the agent talk when e.g. hit by the player
     // TODO move speech patterns into a Behavior component
@@ -167,6 +170,10 @@ impl Component for Agent {
 #[derive(Clone, Debug)]
 pub enum Activity {
     Idle(Vec2<f32>),
+    /// Walking to `Agent::travel_to`
+    Travel {
+        chaser: Chaser,
+    },
     Follow {
         target: EcsEntity,
         chaser: Chaser,
@@ -0,0 +1,195 @@
+use crate::sync::Uid;
+use serde::{Deserialize, Serialize};
+use specs::{Component, FlaggedStorage};
+use specs_idvs::IdvStorage;
+use std::time::Duration;
+
+/// De/buff kinds, every kind decides what `BuffData::strength` means
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BuffKind {
+    /// Heals `strength` health per second
+    Regeneration,
+    /// Deals `strength` damage per second
+    Poisoned,
+    /// Deals `strength` damage per second
+    Burning,
+    /// Reduces movement acceleration by the fraction `strength` (0.0 to 1.0)
+    Slowed,
+    /// Reduces incoming damage by the fraction `strength` (0.0 to 1.0)
+    Protected,
+}
+
+impl BuffKind {
+    /// Whether the buff is beneficial for the entity that has it
+    pub fn is_buff(self) -> bool {
+        match self {
+            BuffKind::Regeneration | BuffKind::Protected => true,
+            BuffKind::Poisoned | BuffKind::Burning | BuffKind::Slowed => false,
+        }
+    }
+
+    /// Whether several buffs of this kind are all active at once. Buffs of
+    /// kinds that don't stack replace each other, keeping the strongest one.
+    pub fn stacks(self) -> bool { matches!(self, BuffKind::Poisoned | BuffKind::Burning) }
+
+    /// Health change per second caused by a buff of this kind, if any
+    pub fn health_rate(self, strength: f32) -> Option<f32> {
+        match self {
+            BuffKind::Regeneration => Some(strength),
+            BuffKind::Poisoned | BuffKind::Burning => Some(-strength),
+            BuffKind::Slowed | BuffKind::Protected => None,
+        }
+    }
+}
+
+/// Strength and duration of a buff, this is what abilities and items specify
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BuffData {
+    pub strength: f32,
+    /// `None` means the buff lasts until it is removed explicitly
+    pub duration: Option<Duration>,
+}
+
+/// Who or what applied a buff
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BuffSource {
+    Character { by: Uid },
+    Item,
+    World,
+    Command,
+    Unknown,
+}
+
+impl BuffSource {
+    pub fn owner(&self) -> Option<Uid> {
+        match self {
+            BuffSource::Character { by } => Some(*by),
+            _ => None,
+        }
+    }
+}
+
+/// An active buff on an entity
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Buff {
+    pub kind: BuffKind,
+    pub data: BuffData,
+    /// Time left until the buff runs out
+    pub time: Option<Duration>,
+    pub source: BuffSource,
+    /// Health change accumulated since the last whole point was applied
+    #[serde(skip)]
+    pub accumulated: f32,
+}
+
+impl Buff {
+    pub fn new(kind: BuffKind, data: BuffData, source: BuffSource) -> Self {
+        Self {
+            kind,
+            data,
+            time: data.duration,
+            source,
+            accumulated: 0.0,
+        }
+    }
+}
+
+/// Changes made to an entity's buffs through `ServerEvent::Buff`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BuffChange {
+    Add(Buff),
+    /// Removes all buffs of the kind
+    RemoveKind(BuffKind),
+    /// Removes all debuffs, e.g. when using a cure
+    RemoveDebuffs,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Buffs {
+    pub buffs: Vec<Buff>,
+}
+
+impl Buffs {
+    pub fn add(&mut self, buff: Buff) {
+        if !buff.kind.stacks() {
+            if let Some(existing) = self.buffs.iter_mut().find(|b| b.kind == buff.kind) {
+                // Refresh the buff if the new one is at least as strong
+                if buff.data.strength >= existing.data.strength {
+                    *existing = buff;
+                }
+                return;
+            }
+        }
+        self.buffs.push(buff);
+    }
+
+    pub fn remove_kind(&mut self, kind: BuffKind) { self.buffs.retain(|b| b.kind != kind); }
+
+    pub fn remove_debuffs(&mut self) { self.buffs.retain(|b| b.kind.is_buff()); }
+
+    pub fn apply_change(&mut self, change: BuffChange) {
+        match change {
+            BuffChange::Add(buff) => self.add(buff),
+            BuffChange::RemoveKind(kind) => self.remove_kind(kind),
+            BuffChange::RemoveDebuffs => self.remove_debuffs(),
+        }
+    }
+
+    pub fn contains(&self, kind: BuffKind) -> bool { self.buffs.iter().any(|b| b.kind == kind) }
+
+    fn strongest(&self, kind: BuffKind) -> f32 {
+        self.buffs
+            .iter()
+            .filter(|b| b.kind == kind)
+            .map(|b| b.data.strength)
+            .fold(0.0, f32::max)
+            .min(1.0)
+    }
+
+    /// Factor applied to movement acceleration
+    pub fn speed_modifier(&self) -> f32 { 1.0 - self.strongest(BuffKind::Slowed) }
+
+    /// Factor applied to incoming damage
+    pub fn damage_modifier(&self) -> f32 { 1.0 - self.strongest(BuffKind::Protected) }
+}
+
+impl Component for Buffs {
+    type Storage = FlaggedStorage<Self, IdvStorage<Self>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buff(kind: BuffKind, strength: f32) -> Buff {
+        Buff::new(
+            kind,
+            BuffData {
+                strength,
+                duration: Some(Duration::from_secs(10)),
+            },
+            BuffSource::Unknown,
+        )
+    }
+
+    #[test]
+    fn stacking_buffs_accumulate() {
+        let mut buffs = Buffs::default();
+        buffs.add(buff(BuffKind::Poisoned, 2.0));
+        buffs.add(buff(BuffKind::Poisoned, 1.0));
+        assert_eq!(buffs.buffs.len(), 2);
+    }
+
+    #[test]
+    fn non_stacking_buffs_keep_the_strongest() {
+        let mut buffs = Buffs::default();
+        buffs.add(buff(BuffKind::Slowed, 0.5));
+        buffs.add(buff(BuffKind::Slowed, 0.2));
+        assert_eq!(buffs.buffs.len(), 1);
+        assert!((buffs.speed_modifier() - 0.5).abs() < f32::EPSILON);
+
+        buffs.add(buff(BuffKind::Slowed, 0.8));
+        assert_eq!(buffs.buffs.len(), 1);
+        assert!((buffs.speed_modifier() - 0.2).abs() < 1e-6);
+    }
+}
@@ -42,6 +42,8 @@ pub enum CharacterState {
     Sit,
     Dance,
     Sneak,
+    /// Player is crafting a recipe that takes time
+    Crafting(crafting::Data),
     Glide,
     GlideWield,
     /// A basic blocking state
@@ -28,14 +28,26 @@ impl Component for Group {
     type Storage = FlaggedStorage<Self, IdvStorage<Self>>;
 }
 
-pub struct Invite(pub specs::Entity);
+/// What an invite asks the invited entity to do
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InviteKind {
+    Group,
+    Trade,
+    /// Take on the quest the inviting NPC offers
+    Quest,
+}
+
+pub struct Invite {
+    pub inviter: specs::Entity,
+    pub kind: InviteKind,
+}
 impl Component for Invite {
     type Storage = IdvStorage<Self>;
 }
 
 // Pending invites that an entity currently has sent out
-// (invited entity, instant when invite times out)
-pub struct PendingInvites(pub Vec<(specs::Entity, std::time::Instant)>);
+// (invited entity, kind of invite, instant when invite times out)
+pub struct PendingInvites(pub Vec<(specs::Entity, InviteKind, std::time::Instant)>);
 impl Component for PendingInvites {
     type Storage = IdvStorage<Self>;
 }
@@ -7,11 +7,10 @@ pub use tool::{Hands, Tool, ToolCategory, ToolKind};
 use crate::{
     assets::{self, Asset, Error},
     effect::Effect,
-    lottery::Lottery,
+    loot::{LootSpec, LootTable},
     terrain::{Block, SpriteKind},
 };
 use crossbeam::atomic::AtomicCell;
-use rand::prelude::*;
 use serde::{Deserialize, Serialize};
 use specs::{Component, FlaggedStorage};
 use specs_idvs::IdvStorage;
@@ -42,6 +41,7 @@ pub enum Reagent {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Utility {
     Collar,
+    Coins,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -62,6 +62,18 @@ impl Lantern {
 pub struct Glider {
     pub kind: String,
 }
+/// Groups of items that can stand in for one another, such as in the inputs of
+/// recipes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ItemTag {
+    Wood,
+    Stone,
+    Ore,
+    Leather,
+    Cloth,
+    Gem,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Copy)]
 pub enum Quality {
     Low,       // Grey
@@ -136,6 +148,8 @@ pub struct ItemDef {
     pub description: String,
     pub kind: ItemKind,
     pub quality: Quality,
+    #[serde(default)]
+    pub tags: Vec<ItemTag>,
 }
 
 impl PartialEq for ItemDef {
@@ -149,6 +163,8 @@ impl ItemDef {
             | ItemKind::Throwable { .. }
             | ItemKind::Utility { .. })
     }
+
+    pub fn has_tag(&self, tag: ItemTag) -> bool { self.tags.contains(&tag) }
 }
 
 impl PartialEq for Item {
@@ -286,6 +302,8 @@ impl Item {
 
     pub fn is_stackable(&self) -> bool { self.item_def.is_stackable() }
 
+    pub fn has_tag(&self, tag: ItemTag) -> bool { self.item_def.has_tag(tag) }
+
     pub fn name(&self) -> &str { &self.item_def.name }
 
     pub fn description(&self) -> &str { &self.item_def.description }
@@ -297,8 +315,14 @@ impl Item {
     pub fn quality(&self) -> Quality { self.item_def.quality }
 
     pub fn try_reclaim_from_block(block: Block) -> Option<Self> {
-        let chosen;
         let mut rng = rand::thread_rng();
+        // Containers only give a single item when reclaimed
+        let mut roll_loot_table = |specifier: &str| {
+            LootTable::load_expect(specifier)
+                .roll(&mut rng)
+                .into_iter()
+                .next()
+        };
         Some(Item::new_from_asset_expect(match block.get_sprite()? {
             SpriteKind::Apple => "common.items.food.apple",
             SpriteKind::Mushroom => "common.items.food.mushroom",
@@ -315,24 +339,8 @@ impl Item {
             SpriteKind::MediumGrass => "common.items.grasses.medium",
             SpriteKind::ShortGrass => "common.items.grasses.short",
             SpriteKind::Coconut => "common.items.food.coconut",
-            SpriteKind::Chest => {
-                chosen = Lottery::<String>::load_expect(match rng.gen_range(0, 7) {
-                    0 => "common.loot_tables.loot_table_weapon_uncommon",
-                    1 => "common.loot_tables.loot_table_weapon_common",
-                    2 => "common.loot_tables.loot_table_armor_light",
-                    3 => "common.loot_tables.loot_table_armor_cloth",
-                    4 => "common.loot_tables.loot_table_armor_heavy",
-                    _ => "common.loot_tables.loot_table_armor_misc",
-                });
-                chosen.choose()
-            },
-            SpriteKind::Crate => {
-                chosen = Lottery::<String>::load_expect(match rng.gen_range(0, 4) {
-                    0 => "common.loot_tables.loot_table_crafting",
-                    _ => "common.loot_tables.loot_table_food",
-                });
-                chosen.choose()
-            },
+            SpriteKind::Chest => return roll_loot_table("common.loot_tables.chest"),
+            SpriteKind::Crate => return roll_loot_table("common.loot_tables.crate"),
             SpriteKind::Beehive => "common.items.crafting_ing.honey",
             SpriteKind::Stones => "common.items.crafting_ing.stones",
             SpriteKind::Twigs => "common.items.crafting_ing.twigs",
@@ -375,8 +383,10 @@ impl Component for Item {
     type Storage = FlaggedStorage<Self, IdvStorage<Self>>;
 }
 
+/// Loot an entity drops when it's destroyed, instead of the loot table of its
+/// body
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub struct ItemDrop(pub Item);
+pub struct ItemDrop(pub LootSpec);
 
 impl Component for ItemDrop {
     type Storage = FlaggedStorage<Self, IdvStorage<Self>>;
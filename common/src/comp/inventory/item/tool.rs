@@ -2,7 +2,10 @@
 // version in voxygen\src\meta.rs in order to reset save files to being empty
 
 use crate::{
-    comp::{body::object, projectile, Body, CharacterAbility, Gravity, LightEmitter, Projectile},
+    comp::{
+        body::object, projectile, Body, BuffData, BuffKind, CharacterAbility, Gravity,
+        LightEmitter, Projectile,
+    },
     states::combo_melee,
     Explosion,
 };
@@ -439,6 +442,13 @@ impl Tool {
                             projectile::Effect::Vanish,
                         ],
                         hit_entity: vec![
+                            projectile::Effect::Buff {
+                                kind: BuffKind::Burning,
+                                data: BuffData {
+                                    strength: 5.0 * self.base_power(),
+                                    duration: Some(Duration::from_secs(3)),
+                                },
+                            },
                             projectile::Effect::Explode(Explosion {
                                 radius: 5.0,
                                 max_damage: (100.0 * self.base_power()) as u32,
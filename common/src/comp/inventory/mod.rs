@@ -1,7 +1,10 @@
 pub mod item;
 pub mod slot;
 
-use crate::{comp::inventory::item::ItemDef, recipe::Recipe};
+use crate::{
+    comp::inventory::item::ItemDef,
+    recipe::{Recipe, RecipeInput},
+};
 use core::ops::Not;
 use item::Item;
 use serde::{Deserialize, Serialize};
@@ -220,6 +223,17 @@ impl Inventory {
             .sum()
     }
 
+    /// Determine how many items there are in the inventory that the input of a
+    /// recipe accepts.
+    pub fn input_count(&self, input: &RecipeInput) -> u64 {
+        self.slots()
+            .iter()
+            .flatten()
+            .filter(|it| input.matches(it))
+            .map(|it| u64::from(it.amount()))
+            .sum()
+    }
+
     /// Determine whether the inventory contains the ingredients for a recipe.
     /// If it does, return a vector of numbers, where is number corresponds
     /// to an inventory slot, along with the number of items that need
@@ -228,15 +242,15 @@ impl Inventory {
     pub fn contains_ingredients<'a>(
         &self,
         recipe: &'a Recipe,
-    ) -> Result<Vec<u32>, Vec<(&'a ItemDef, u32)>> {
+    ) -> Result<Vec<u32>, Vec<(&'a RecipeInput, u32)>> {
         let mut slot_claims = vec![0; self.slots.len()];
-        let mut missing = Vec::<(&ItemDef, u32)>::new();
+        let mut missing = Vec::<(&RecipeInput, u32)>::new();
 
         for (input, mut needed) in recipe.inputs() {
             let mut contains_any = false;
 
             for (i, slot) in self.slots().iter().enumerate() {
-                if let Some(item) = slot.as_ref().filter(|item| item.is_same_item_def(&*input)) {
+                if let Some(item) = slot.as_ref().filter(|item| input.matches(item)) {
                     let can_claim = (item.amount() - slot_claims[i]).min(needed);
                     slot_claims[i] += can_claim;
                     needed -= can_claim;
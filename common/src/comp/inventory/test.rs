@@ -1,4 +1,5 @@
 use super::*;
+use crate::assets::Asset;
 use lazy_static::lazy_static;
 lazy_static! {
     static ref TEST_ITEMS: Vec<Item> = vec![
@@ -69,3 +70,49 @@ fn push_all_unique_empty() {
         "Pushing unique items into an empty inventory that didn't contain them didn't work!",
     );
 }
+
+/// A tag input should take the items it needs from every slot holding items
+/// with the tag, and no more than it needs.
+#[test]
+fn contains_ingredients_across_tagged_slots() {
+    let stack = |specifier: &str, amount: u32| {
+        let mut item = Item::new_from_asset_expect(specifier);
+        item.set_amount(amount)
+            .expect("Ingredients should be stackable");
+        Some(item)
+    };
+    let inv = Inventory {
+        slots: vec![
+            stack("common.items.crafting_ing.twigs", 3),
+            stack("common.items.food.apple", 4),
+            stack("common.items.crafting_ing.branch", 4),
+            None,
+        ],
+        amount: 3,
+    };
+    let recipe = |wood: u32| Recipe {
+        output: (
+            ItemDef::load_expect("common.items.crafting_tools.craftsman_hammer"),
+            1,
+        ),
+        inputs: vec![(RecipeInput::Tag(item::ItemTag::Wood), wood)],
+        station: None,
+        craft_time: std::time::Duration::default(),
+    };
+
+    assert_eq!(
+        inv.contains_ingredients(&recipe(5)).ok(),
+        Some(vec![3, 0, 2, 0])
+    );
+    assert_eq!(
+        inv.contains_ingredients(&recipe(7)).ok(),
+        Some(vec![3, 0, 4, 0])
+    );
+    let missing = inv.contains_ingredients(&recipe(8)).err().map(|missing| {
+        missing
+            .into_iter()
+            .map(|(_, amount)| amount)
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(missing, Some(vec![1]));
+}
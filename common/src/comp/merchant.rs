@@ -0,0 +1,13 @@
+use crate::trade::SitePrices;
+use specs::Component;
+use specs_idvs::IdvStorage;
+
+/// An NPC that trades with players, at the prices of its site
+#[derive(Clone, Debug)]
+pub struct Merchant {
+    pub prices: SitePrices,
+}
+
+impl Component for Merchant {
+    type Storage = IdvStorage<Self>;
+}
@@ -3,6 +3,7 @@ mod admin;
 pub mod agent;
 pub mod beam;
 pub mod body;
+mod buff;
 mod character_state;
 pub mod chat;
 mod controller;
@@ -13,10 +14,13 @@ mod inputs;
 mod inventory;
 mod last;
 mod location;
+mod merchant;
 mod misc;
 mod phys;
 mod player;
 pub mod projectile;
+mod pvp;
+mod quest;
 pub mod shockwave;
 pub mod skills;
 mod stats;
@@ -31,6 +35,7 @@ pub use body::{
     biped_large, bird_medium, bird_small, dragon, fish_medium, fish_small, golem, humanoid, object,
     quadruped_low, quadruped_medium, quadruped_small, theropod, AllBodies, Body, BodyData,
 };
+pub use buff::{Buff, BuffChange, BuffData, BuffKind, BuffSource, Buffs};
 pub use character_state::{Attacking, CharacterState, StateUpdate};
 pub use chat::{
     ChatMode, ChatMsg, ChatType, Faction, SpeechBubble, SpeechBubbleType, UnresolvedChatMsg,
@@ -50,10 +55,13 @@ pub use inventory::{
 };
 pub use last::Last;
 pub use location::{Waypoint, WaypointArea};
+pub use merchant::Merchant;
 pub use misc::Object;
 pub use phys::{Collider, ForceUpdate, Gravity, Mass, Ori, PhysicsState, Pos, Scale, Sticky, Vel};
 pub use player::{Player, MAX_MOUNT_RANGE_SQR};
 pub use projectile::Projectile;
+pub use pvp::{PvpMode, PvpOptIn, PvpRules};
+pub use quest::{QuestLog, QuestProgress};
 pub use shockwave::{Shockwave, ShockwaveHitEntities};
 pub use skills::{Skill, SkillGroup, SkillGroupType, SkillSet};
 pub use stats::{Exp, HealthChange, HealthSource, Level, Stats};
@@ -1,4 +1,8 @@
-use crate::{sync::Uid, Explosion};
+use crate::{
+    comp::{BuffData, BuffKind},
+    sync::Uid,
+    Explosion,
+};
 use serde::{Deserialize, Serialize};
 use specs::{Component, FlaggedStorage};
 use specs_idvs::IdvStorage;
@@ -13,6 +17,12 @@ pub enum Effect {
     Vanish,
     Stick,
     Possess,
+    /// Applies a buff to the hit entity, with the projectile's owner as the
+    /// source
+    Buff {
+        kind: BuffKind,
+        data: BuffData,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
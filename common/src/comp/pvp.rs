@@ -0,0 +1,235 @@
+use crate::comp::{Group, Player, Pos};
+use serde::{Deserialize, Serialize};
+use specs::{Component, Entity, NullStorage, ReadStorage};
+use vek::*;
+
+/// Decides whether players can damage each other
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PvpMode {
+    /// Players can never damage each other
+    Off,
+    /// Players can damage each other, unless they are in the same group
+    On,
+    /// Players can only damage each other if both of them opted in with `/pvp`
+    OptIn,
+    /// Players can damage each other, except within the safe zones around
+    /// sites like towns
+    Zones,
+}
+
+/// Marks a player who opted into PvP while the server uses
+/// [`PvpMode::OptIn`]
+#[derive(Clone, Copy, Default)]
+pub struct PvpOptIn;
+
+impl Component for PvpOptIn {
+    type Storage = NullStorage<Self>;
+}
+
+/// Resource holding the server's PvP rules, every system that emits
+/// `ServerEvent::Damage` needs to check [`PvpRules::allows_damage`] first.
+#[derive(Clone, Debug)]
+pub struct PvpRules {
+    pub mode: PvpMode,
+    /// Areas in which players can't damage each other with
+    /// [`PvpMode::Zones`]
+    pub safe_zones: Vec<Aabr<i32>>,
+}
+
+impl Default for PvpRules {
+    fn default() -> Self {
+        Self {
+            mode: PvpMode::On,
+            safe_zones: Vec::new(),
+        }
+    }
+}
+
+impl PvpRules {
+    /// Returns whether `attacker` may damage `target`. Only hits between two
+    /// different players are restricted, and players never damage the members
+    /// of their own group.
+    pub fn allows_damage(
+        &self,
+        attacker: Entity,
+        target: Entity,
+        players: &ReadStorage<'_, Player>,
+        opt_ins: &ReadStorage<'_, PvpOptIn>,
+        positions: &ReadStorage<'_, Pos>,
+        groups: &ReadStorage<'_, Group>,
+    ) -> bool {
+        if attacker == target || !players.contains(attacker) || !players.contains(target) {
+            return true;
+        }
+        if groups
+            .get(attacker)
+            .map_or(false, |group| Some(group) == groups.get(target))
+        {
+            return false;
+        }
+        match self.mode {
+            PvpMode::Off => false,
+            PvpMode::On => true,
+            PvpMode::OptIn => opt_ins.contains(attacker) && opt_ins.contains(target),
+            PvpMode::Zones => [attacker, target].iter().all(|entity| {
+                positions
+                    .get(*entity)
+                    .map_or(false, |pos| !self.in_safe_zone(pos.0))
+            }),
+        }
+    }
+
+    pub fn in_safe_zone(&self, pos: Vec3<f32>) -> bool {
+        let pos = pos.xy().map(|e| e.floor() as i32);
+        self.safe_zones.iter().any(|zone| zone.contains_point(pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comp::group;
+    use authc::Uuid;
+    use specs::{Builder, World, WorldExt};
+
+    struct Setup {
+        world: World,
+        a: Entity,
+        b: Entity,
+        npc: Entity,
+    }
+
+    fn player(world: &mut World, alias: &str) -> Entity {
+        world
+            .create_entity()
+            .with(Player::new(alias.to_owned(), None, None, Uuid::nil()))
+            .with(Pos(Vec3::new(0.0, 0.0, 0.0)))
+            .build()
+    }
+
+    fn setup() -> Setup {
+        let mut world = World::new();
+        world.register::<Player>();
+        world.register::<PvpOptIn>();
+        world.register::<Pos>();
+        world.register::<Group>();
+        let a = player(&mut world, "a");
+        let b = player(&mut world, "b");
+        let npc = world
+            .create_entity()
+            .with(Pos(Vec3::new(0.0, 0.0, 0.0)))
+            .build();
+        Setup { world, a, b, npc }
+    }
+
+    fn allows(rules: &PvpRules, setup: &Setup, attacker: Entity, target: Entity) -> bool {
+        rules.allows_damage(
+            attacker,
+            target,
+            &setup.world.read_storage(),
+            &setup.world.read_storage(),
+            &setup.world.read_storage(),
+            &setup.world.read_storage(),
+        )
+    }
+
+    fn rules(mode: PvpMode) -> PvpRules {
+        PvpRules {
+            mode,
+            safe_zones: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn non_player_owners_can_always_damage() {
+        let setup = setup();
+        let off = rules(PvpMode::Off);
+        assert!(allows(&off, &setup, setup.npc, setup.a));
+        assert!(allows(&off, &setup, setup.a, setup.npc));
+        assert!(allows(&off, &setup, setup.a, setup.a));
+    }
+
+    #[test]
+    fn mode_decides_between_players() {
+        let setup = setup();
+        assert!(!allows(&rules(PvpMode::Off), &setup, setup.a, setup.b));
+        assert!(allows(&rules(PvpMode::On), &setup, setup.a, setup.b));
+    }
+
+    #[test]
+    fn opt_in_needs_both_players() {
+        let setup = setup();
+        let opt_in = rules(PvpMode::OptIn);
+        assert!(!allows(&opt_in, &setup, setup.a, setup.b));
+
+        setup
+            .world
+            .write_storage()
+            .insert(setup.a, PvpOptIn)
+            .unwrap();
+        assert!(!allows(&opt_in, &setup, setup.a, setup.b));
+        assert!(!allows(&opt_in, &setup, setup.b, setup.a));
+
+        setup
+            .world
+            .write_storage()
+            .insert(setup.b, PvpOptIn)
+            .unwrap();
+        assert!(allows(&opt_in, &setup, setup.a, setup.b));
+
+        setup.world.write_storage::<PvpOptIn>().remove(setup.a);
+        assert!(!allows(&opt_in, &setup, setup.a, setup.b));
+    }
+
+    #[test]
+    fn players_in_the_same_group_cant_damage_each_other() {
+        let setup = setup();
+        let on = rules(PvpMode::On);
+        setup
+            .world
+            .write_storage()
+            .insert(setup.a, group::NPC)
+            .unwrap();
+        assert!(allows(&on, &setup, setup.a, setup.b));
+
+        setup
+            .world
+            .write_storage()
+            .insert(setup.b, group::NPC)
+            .unwrap();
+        assert!(!allows(&on, &setup, setup.a, setup.b));
+        // Groups don't protect against non-players
+        setup
+            .world
+            .write_storage()
+            .insert(setup.npc, group::NPC)
+            .unwrap();
+        assert!(allows(&on, &setup, setup.npc, setup.a));
+    }
+
+    #[test]
+    fn safe_zones_protect_players() {
+        let setup = setup();
+        let mut zones = rules(PvpMode::Zones);
+        assert!(allows(&zones, &setup, setup.a, setup.b));
+
+        zones.safe_zones.push(Aabr {
+            min: Vec2::new(-10, -10),
+            max: Vec2::new(10, 10),
+        });
+        assert!(!allows(&zones, &setup, setup.a, setup.b));
+
+        setup
+            .world
+            .write_storage()
+            .insert(setup.b, Pos(Vec3::new(20.0, 0.0, 0.0)))
+            .unwrap();
+        assert!(!allows(&zones, &setup, setup.a, setup.b));
+        setup
+            .world
+            .write_storage()
+            .insert(setup.a, Pos(Vec3::new(0.0, 20.0, 0.0)))
+            .unwrap();
+        assert!(allows(&zones, &setup, setup.a, setup.b));
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{
+    assets::Asset,
+    quest::{Objective, QuestDef, QuestEvent},
+};
+use serde::{Deserialize, Serialize};
+use specs::Component;
+use specs_idvs::IdvStorage;
+use tracing::warn;
+
+/// The progress of a character on one of its quests
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QuestProgress {
+    /// Asset specifier of the quest definition
+    pub quest: String,
+    /// Progress on each objective, in the order of the definition
+    pub objectives: Vec<u32>,
+}
+
+impl QuestProgress {
+    pub fn is_complete(&self) -> bool {
+        QuestDef::load(&self.quest).map_or(false, |def| {
+            def.objectives
+                .iter()
+                .zip(self.objectives.iter())
+                .all(|(objective, progress)| *progress >= objective.required())
+        })
+    }
+}
+
+/// The quests a character took on and completed
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuestLog {
+    pub active: Vec<QuestProgress>,
+    /// Asset specifiers of the completed quests, in the order they were
+    /// completed
+    pub completed: Vec<String>,
+}
+
+impl QuestLog {
+    /// Whether the quest is active or was completed already
+    pub fn has_quest(&self, quest: &str) -> bool {
+        self.active.iter().any(|progress| progress.quest == quest)
+            || self.completed.iter().any(|completed| completed == quest)
+    }
+
+    /// Asset specifiers of the items the active quests ask to collect
+    pub fn wanted_items(&self) -> Vec<String> {
+        let mut items = Vec::new();
+        for progress in &self.active {
+            if let Ok(def) = QuestDef::load(&progress.quest) {
+                for objective in &def.objectives {
+                    if let Objective::Collect { item, .. } = objective {
+                        if !items.contains(item) {
+                            items.push(item.clone());
+                        }
+                    }
+                }
+            }
+        }
+        items
+    }
+
+    /// Takes on a quest, returning whether it was started
+    pub fn start(&mut self, quest: &str) -> bool {
+        if self.has_quest(quest) {
+            return false;
+        }
+        match QuestDef::load(quest) {
+            Ok(def) => {
+                self.active.push(QuestProgress {
+                    quest: quest.to_owned(),
+                    objectives: vec![0; def.objectives.len()],
+                });
+                true
+            },
+            Err(e) => {
+                warn!(?e, ?quest, "Could not start a quest without a definition");
+                false
+            },
+        }
+    }
+
+    /// Advances the objectives of the active quests by the event, and moves
+    /// the quests with all objectives done to the completed ones. Returns
+    /// whether the log changed.
+    pub fn record(&mut self, event: &QuestEvent) -> bool {
+        let mut changed = false;
+        for progress in &mut self.active {
+            let def = match QuestDef::load(&progress.quest) {
+                Ok(def) => def,
+                Err(_) => continue,
+            };
+            for (objective, done) in def.objectives.iter().zip(progress.objectives.iter_mut()) {
+                let progress = objective.progress_after(*done, event);
+                if progress != *done {
+                    *done = progress;
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            let completed = &mut self.completed;
+            self.active.retain(|progress| {
+                if progress.is_complete() {
+                    completed.push(progress.quest.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        changed
+    }
+}
+
+impl Component for QuestLog {
+    type Storage = IdvStorage<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        msg::world_msg::SiteKind,
+        npc::NpcKind,
+        quest::{NpcRole, QuestNpc},
+    };
+
+    const WOLF_HUNT: &str = "common.quests.wolf_hunt";
+    const FORAGING: &str = "common.quests.foraging";
+    const INTO_THE_DEPTHS: &str = "common.quests.into_the_depths";
+    const APPLE: &str = "common.items.food.apple";
+
+    fn npc(kind: NpcKind, role: Option<NpcRole>) -> QuestNpc { QuestNpc { kind, role } }
+
+    fn wolf() -> QuestNpc { npc(NpcKind::Wolf, None) }
+
+    #[test]
+    fn quests_are_taken_on_once() {
+        let mut log = QuestLog::default();
+        assert!(log.start(WOLF_HUNT));
+        assert!(!log.start(WOLF_HUNT));
+        assert_eq!(log.active, vec![QuestProgress {
+            quest: WOLF_HUNT.to_owned(),
+            objectives: vec![0],
+        }]);
+        assert!(!log.start("common.quests.missing"));
+        assert_eq!(log.active.len(), 1);
+
+        // Completed quests can't be taken on again either
+        for _ in 0..5 {
+            log.record(&QuestEvent::Killed(wolf()));
+        }
+        assert!(!log.start(WOLF_HUNT));
+        assert!(log.active.is_empty());
+    }
+
+    #[test]
+    fn objectives_complete_the_quest() {
+        let mut log = QuestLog::default();
+        log.start(WOLF_HUNT);
+        assert!(!log.record(&QuestEvent::Killed(npc(NpcKind::Pig, None))));
+        for _ in 0..4 {
+            assert!(log.record(&QuestEvent::Killed(wolf())));
+        }
+        assert_eq!(log.active[0].objectives, vec![4]);
+
+        assert!(log.record(&QuestEvent::Killed(wolf())));
+        assert!(log.active.is_empty());
+        assert_eq!(log.completed, vec![WOLF_HUNT.to_owned()]);
+        // Completed quests don't take any more progress
+        assert!(!log.record(&QuestEvent::Killed(wolf())));
+    }
+
+    #[test]
+    fn collecting_counts_the_items_held() {
+        let mut log = QuestLog::default();
+        log.start(FORAGING);
+        assert_eq!(log.wanted_items(), vec![
+            APPLE.to_owned(),
+            "common.items.food.mushroom".to_owned()
+        ]);
+
+        // Holding more than asked for only does the objective
+        assert!(log.record(&QuestEvent::Holds {
+            item: APPLE,
+            amount: 12,
+        }));
+        assert_eq!(log.active[0].objectives, vec![5, 0]);
+        assert!(!log.record(&QuestEvent::Holds {
+            item: APPLE,
+            amount: 7,
+        }));
+
+        // Dropping items takes the progress back, so that picking them up
+        // again doesn't count twice
+        assert!(log.record(&QuestEvent::Holds {
+            item: APPLE,
+            amount: 2,
+        }));
+        assert!(!log.record(&QuestEvent::Holds {
+            item: APPLE,
+            amount: 2,
+        }));
+        assert_eq!(log.active[0].objectives, vec![2, 0]);
+    }
+
+    #[test]
+    fn any_dungeon_of_the_difficulty_is_reached() {
+        let mut log = QuestLog::default();
+        log.start(INTO_THE_DEPTHS);
+        assert!(!log.record(&QuestEvent::Reached(SiteKind::Town)));
+        assert!(log.record(&QuestEvent::Reached(SiteKind::Dungeon { difficulty: 4 })));
+        assert_eq!(log.active[0].objectives, vec![1, 0]);
+        // Reaching another dungeon doesn't count twice
+        assert!(!log.record(&QuestEvent::Reached(SiteKind::Dungeon { difficulty: 1 })));
+    }
+
+    #[test]
+    fn only_cultists_count_for_the_dungeon() {
+        let mut log = QuestLog::default();
+        log.start(INTO_THE_DEPTHS);
+        for role in [None, Some(NpcRole::Villager), Some(NpcRole::Guard)].iter() {
+            assert!(!log.record(&QuestEvent::Killed(npc(NpcKind::Humanoid, *role))));
+        }
+        assert!(log.record(&QuestEvent::Killed(npc(
+            NpcKind::Humanoid,
+            Some(NpcRole::Cultist)
+        ))));
+        assert_eq!(log.active[0].objectives, vec![0, 1]);
+    }
+}
@@ -31,6 +31,34 @@ lazy_static! {
 
         defs
     };
+
+    // Skills that have to be unlocked before a skill can be unlocked. A skill can only be
+    // refunded while no unlocked skill requires it.
+    pub static ref SKILL_PREREQUISITES: HashMap<Skill, HashSet<Skill>> = {
+        let mut prereqs = HashMap::new();
+        prereqs.insert(Skill::TestT1Skill2, [Skill::TestT1Skill1].iter().cloned().collect());
+        prereqs.insert(Skill::TestT1Skill3, [Skill::TestT1Skill2].iter().cloned().collect());
+        prereqs.insert(Skill::TestT1Skill5, [Skill::TestT1Skill3, Skill::TestT1Skill4]
+                                            .iter().cloned().collect());
+        prereqs.insert(Skill::TestSwordSkill3, [Skill::TestSwordSkill2].iter().cloned().collect());
+        prereqs.insert(Skill::TestAxeSkill3, [Skill::TestAxeSkill2].iter().cloned().collect());
+
+        prereqs
+    };
+}
+
+/// Reasons why a skill can't be unlocked or refunded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkillError {
+    AlreadyUnlocked,
+    NotUnlocked,
+    /// The skill isn't part of any skill group in `SKILL_GROUP_DEFS`
+    UnknownSkill,
+    MissingSkillGroup,
+    InsufficientSkillPoints,
+    MissingPrerequisite(Skill),
+    /// Another unlocked skill depends on the skill
+    RequiredBy(Skill),
 }
 
 /// Represents a skill that a player can unlock, that either grants them some
@@ -148,31 +176,44 @@ impl SkillSet {
     /// assert_eq!(skillset.skills.len(), 1);
     /// ```
     pub fn unlock_skill(&mut self, skill: Skill) {
-        if !self.skills.contains(&skill) {
-            if let Some(skill_group_type) = SkillSet::get_skill_group_type_for_skill(&skill) {
-                if let Some(mut skill_group) = self
-                    .skill_groups
-                    .iter_mut()
-                    .find(|x| x.skill_group_type == skill_group_type)
-                {
-                    if skill_group.available_sp > 0 {
-                        skill_group.available_sp -= 1;
-                        self.skills.insert(skill);
-                    } else {
-                        warn!("Tried to unlock skill for skill group with no available SP");
-                    }
-                } else {
-                    warn!("Tried to unlock skill for a skill group that player does not have");
+        match self.can_unlock_skill(skill) {
+            Ok(skill_group_type) => {
+                if let Some(skill_group) = self.skill_group_mut(skill_group_type) {
+                    skill_group.available_sp -= 1;
+                    self.skills.insert(skill);
                 }
-            } else {
-                warn!(
-                    ?skill,
-                    "Tried to unlock skill that does not exist in any skill group!"
-                );
-            }
-        } else {
-            warn!("Tried to unlock already unlocked skill");
+            },
+            Err(error) => warn!(
+                ?skill,
+                ?error,
+                "Tried to unlock a skill that can't be unlocked"
+            ),
+        }
+    }
+
+    /// Checks whether a skill can be unlocked, returning the skill group the
+    /// skill point would be taken from.
+    pub fn can_unlock_skill(&self, skill: Skill) -> Result<SkillGroupType, SkillError> {
+        if self.skills.contains(&skill) {
+            return Err(SkillError::AlreadyUnlocked);
+        }
+        let skill_group_type =
+            SkillSet::get_skill_group_type_for_skill(&skill).ok_or(SkillError::UnknownSkill)?;
+        let skill_group = self
+            .skill_groups
+            .iter()
+            .find(|x| x.skill_group_type == skill_group_type)
+            .ok_or(SkillError::MissingSkillGroup)?;
+        if skill_group.available_sp == 0 {
+            return Err(SkillError::InsufficientSkillPoints);
         }
+        if let Some(missing) = SKILL_PREREQUISITES
+            .get(&skill)
+            .and_then(|prereqs| prereqs.iter().find(|p| !self.skills.contains(p)))
+        {
+            return Err(SkillError::MissingPrerequisite(*missing));
+        }
+        Ok(skill_group_type)
     }
 
     /// Removes a skill from a player and refunds 1 skill point in the relevant
@@ -191,32 +232,55 @@ impl SkillSet {
     /// assert_eq!(skillset.skills.len(), 0);
     /// ```
     pub fn refund_skill(&mut self, skill: Skill) {
-        if self.skills.contains(&skill) {
-            if let Some(skill_group_type) = SkillSet::get_skill_group_type_for_skill(&skill) {
-                if let Some(mut skill_group) = self
-                    .skill_groups
-                    .iter_mut()
-                    .find(|x| x.skill_group_type == skill_group_type)
-                {
+        match self.can_refund_skill(skill) {
+            Ok(skill_group_type) => {
+                if let Some(skill_group) = self.skill_group_mut(skill_group_type) {
                     skill_group.available_sp += 1;
                     self.skills.remove(&skill);
-                } else {
-                    warn!("Tried to refund skill for a skill group that player does not have");
                 }
-            } else {
-                warn!(
-                    ?skill,
-                    "Tried to refund skill that does not exist in any skill group"
-                )
-            }
-        } else {
-            warn!("Tried to refund skill that has not been unlocked");
+            },
+            Err(error) => warn!(
+                ?skill,
+                ?error,
+                "Tried to refund a skill that can't be refunded"
+            ),
         }
     }
 
+    /// Checks whether a skill can be refunded, returning the skill group the
+    /// skill point would be returned to.
+    pub fn can_refund_skill(&self, skill: Skill) -> Result<SkillGroupType, SkillError> {
+        if !self.skills.contains(&skill) {
+            return Err(SkillError::NotUnlocked);
+        }
+        let skill_group_type =
+            SkillSet::get_skill_group_type_for_skill(&skill).ok_or(SkillError::UnknownSkill)?;
+        if !self
+            .skill_groups
+            .iter()
+            .any(|x| x.skill_group_type == skill_group_type)
+        {
+            return Err(SkillError::MissingSkillGroup);
+        }
+        if let Some(dependent) = self.skills.iter().find(|s| {
+            SKILL_PREREQUISITES
+                .get(s)
+                .map_or(false, |prereqs| prereqs.contains(&skill))
+        }) {
+            return Err(SkillError::RequiredBy(*dependent));
+        }
+        Ok(skill_group_type)
+    }
+
+    fn skill_group_mut(&mut self, skill_group_type: SkillGroupType) -> Option<&mut SkillGroup> {
+        self.skill_groups
+            .iter_mut()
+            .find(|x| x.skill_group_type == skill_group_type)
+    }
+
     /// Returns the skill group type for a skill from the static skill group
     /// definitions.
-    fn get_skill_group_type_for_skill(skill: &Skill) -> Option<SkillGroupType> {
+    pub fn get_skill_group_type_for_skill(skill: &Skill) -> Option<SkillGroupType> {
         SKILL_GROUP_DEFS.iter().find_map(|(key, val)| {
             if val.contains(&skill) {
                 Some(*key)
@@ -318,6 +382,30 @@ mod tests {
         assert_eq!(skillset.skills.get(&Skill::TestAxeSkill1), None);
     }
 
+    #[test]
+    fn test_skill_prerequisites() {
+        let mut skillset = SkillSet::new();
+        skillset.unlock_skill_group(SkillGroupType::Axes);
+        skillset.add_skill_points(SkillGroupType::Axes, 2);
+
+        assert_eq!(
+            skillset.can_unlock_skill(Skill::TestAxeSkill3),
+            Err(SkillError::MissingPrerequisite(Skill::TestAxeSkill2))
+        );
+
+        skillset.unlock_skill(Skill::TestAxeSkill2);
+        skillset.unlock_skill(Skill::TestAxeSkill3);
+        assert_eq!(skillset.skills.len(), 2);
+
+        // A skill can't be refunded while another skill depends on it
+        assert_eq!(
+            skillset.can_refund_skill(Skill::TestAxeSkill2),
+            Err(SkillError::RequiredBy(Skill::TestAxeSkill3))
+        );
+        skillset.refund_skill(Skill::TestAxeSkill2);
+        assert_eq!(skillset.skills.len(), 2);
+    }
+
     #[test]
     fn test_add_skill_points() {
         let mut skillset = SkillSet::new();
@@ -27,6 +27,8 @@ pub enum HealthSource {
     LevelUp,
     Item,
     Healing { by: Option<Uid> },
+    /// Health changed by a buff such as poison or regeneration
+    Buff { owner: Option<Uid> },
     Unknown,
 }
 
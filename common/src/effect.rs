@@ -2,10 +2,14 @@ use crate::comp;
 use serde::{Deserialize, Serialize};
 
 /// An effect that may be applied to an entity
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Effect {
     Health(comp::HealthChange),
     Xp(i64),
+    Buff {
+        kind: comp::BuffKind,
+        data: comp::BuffData,
+    },
 }
 
 impl Effect {
@@ -13,6 +17,10 @@ impl Effect {
         match self {
             Effect::Health(c) => format!("{:+} health", c.amount),
             Effect::Xp(n) => format!("{:+} exp", n),
+            Effect::Buff { kind, data } => match data.duration {
+                Some(duration) => format!("{:?} for {}s", kind, duration.as_secs()),
+                None => format!("{:?}", kind),
+            },
         }
     }
 }
@@ -1,8 +1,14 @@
-use crate::{character::CharacterId, comp, sync::Uid, util::Dir, Explosion};
-use comp::{
-    item::{Item, Reagent},
-    Ori, Pos,
+use crate::{
+    character::CharacterId,
+    comp,
+    loot::LootSpec,
+    rtsim::RtSimEntity,
+    sync::Uid,
+    trade::{SiteId, TradeAction, TradeId, TradingInformation},
+    util::Dir,
+    Explosion,
 };
+use comp::{item::Reagent, Ori, Pos};
 use parking_lot::Mutex;
 use specs::Entity as EcsEntity;
 use std::{collections::VecDeque, ops::DerefMut};
@@ -38,11 +44,33 @@ pub enum ServerEvent {
         uid: Uid,
         change: comp::HealthChange,
     },
+    Buff {
+        uid: Uid,
+        buff_change: comp::BuffChange,
+    },
     Destroy {
         entity: EcsEntity,
         cause: comp::HealthSource,
     },
     InventoryManip(EcsEntity, comp::InventoryManip),
+    /// The craft time of a recipe ran out, so the recipe can be crafted
+    FinishCraft {
+        entity: EcsEntity,
+        recipe: String,
+    },
+    /// An entity asks the entity with the uid to trade
+    InitiateTrade(EcsEntity, Uid),
+    ProcessTradeAction(EcsEntity, TradeId, TradeAction),
+    /// A client asks for the economy of a site
+    RequestSiteInfo {
+        entity: EcsEntity,
+        id: SiteId,
+    },
+    /// A character talks to the NPC with the uid
+    Talk {
+        entity: EcsEntity,
+        npc: Uid,
+    },
     GroupManip(EcsEntity, comp::GroupManip),
     Respawn(EcsEntity),
     Shoot {
@@ -85,7 +113,14 @@ pub enum ServerEvent {
     },
     UpdateCharacterData {
         entity: EcsEntity,
-        components: (comp::Body, comp::Stats, comp::Inventory, comp::Loadout),
+        components: (
+            comp::Body,
+            comp::Stats,
+            comp::Inventory,
+            comp::Loadout,
+            Option<comp::Pos>,
+            Option<comp::Waypoint>,
+        ),
     },
     ExitIngame {
         entity: EcsEntity,
@@ -98,7 +133,9 @@ pub enum ServerEvent {
         agent: Option<comp::Agent>,
         alignment: comp::Alignment,
         scale: comp::Scale,
-        drop_item: Option<Item>,
+        drop_item: Option<LootSpec>,
+        trading_information: Option<TradingInformation>,
+        rtsim_entity: Option<RtSimEntity>,
     },
     CreateWaypoint(Vec3<f32>),
     ClientDisconnect(EcsEntity),
@@ -1,6 +1,8 @@
 use crate::{
     comp::{self, humanoid, Alignment, Body, Item},
+    loot::LootSpec,
     npc::{self, NPC_NAMES},
+    trade::TradingInformation,
 };
 use vek::*;
 
@@ -20,7 +22,8 @@ pub struct EntityInfo {
     pub second_tool: Option<Item>,
     pub scale: f32,
     pub level: Option<u32>,
-    pub loot_drop: Option<Item>,
+    pub loot_drop: Option<LootSpec>,
+    pub trading_information: Option<TradingInformation>,
 }
 
 impl EntityInfo {
@@ -38,6 +41,7 @@ impl EntityInfo {
             scale: 1.0,
             level: None,
             loot_drop: None,
+            trading_information: None,
         }
     }
 
@@ -88,7 +92,7 @@ impl EntityInfo {
         self
     }
 
-    pub fn with_loot_drop(mut self, loot_drop: Item) -> Self {
+    pub fn with_loot_drop(mut self, loot_drop: LootSpec) -> Self {
         self.loot_drop = Some(loot_drop);
         self
     }
@@ -103,6 +107,11 @@ impl EntityInfo {
         self
     }
 
+    pub fn with_trading_information(mut self, trading_information: TradingInformation) -> Self {
+        self.trading_information = Some(trading_information);
+        self
+    }
+
     pub fn with_automatic_name(mut self) -> Self {
         self.name = match &self.body {
             Body::Humanoid(body) => Some(get_npc_name(&NPC_NAMES.humanoid, body.species)),
@@ -29,15 +29,18 @@ pub mod explosion;
 pub mod figure;
 pub mod generation;
 pub mod loadout_builder;
+pub mod loot;
 pub mod lottery;
 pub mod metrics;
 pub mod msg;
 pub mod npc;
 pub mod outcome;
 pub mod path;
+pub mod quest;
 pub mod ray;
 pub mod recipe;
 pub mod region;
+pub mod rtsim;
 pub mod spiral;
 pub mod state;
 pub mod states;
@@ -46,10 +49,12 @@ pub mod sync;
 pub mod sys;
 pub mod terrain;
 pub mod time;
+pub mod trade;
 pub mod typed;
 pub mod util;
 pub mod vol;
 pub mod volumes;
+pub mod weather;
 
 pub use explosion::Explosion;
 pub use loadout_builder::LoadoutBuilder;
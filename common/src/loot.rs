@@ -0,0 +1,322 @@
+use crate::{
+    assets::{self, Asset, Ron},
+    comp::{item::ItemDef, Body, Item},
+    lottery::Lottery,
+    npc::{SpeciesNames, NPC_NAMES},
+};
+use hashbrown::HashMap;
+use lazy_static::lazy_static;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{fs::File, io::BufReader, sync::Arc};
+use tracing::warn;
+
+/// Loot tables nested deeper than this are not rolled, so that a table which
+/// (indirectly) includes itself can't loop forever
+const MAX_NESTING: usize = 8;
+
+lazy_static! {
+    pub static ref LOOT_CONFIG: Arc<LootConfig> =
+        Ron::<LootConfig>::load_expect("common.loot_config");
+}
+
+/// Something that can be dropped as loot
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LootSpec {
+    /// Asset specifier of an item
+    Item(String),
+    /// Asset specifier of an item, and the inclusive range of how many of it
+    /// are dropped
+    ItemQuantity(String, u32, u32),
+    /// Asset specifier of another loot table to roll on
+    LootTable(String),
+    /// Drops nothing
+    Nothing,
+}
+
+impl LootSpec {
+    /// Rolls the items dropped by this spec
+    pub fn roll(&self, rng: &mut impl Rng) -> Vec<Item> {
+        let mut items = Vec::new();
+        self.roll_into(rng, &mut items, 0);
+        items
+    }
+
+    fn roll_into(&self, rng: &mut impl Rng, items: &mut Vec<Item>, depth: usize) {
+        match self {
+            LootSpec::Item(specifier) => items.extend(create_items(specifier, 1)),
+            LootSpec::ItemQuantity(specifier, min, max) => {
+                let amount = if max > min {
+                    rng.gen_range(*min, *max + 1)
+                } else {
+                    *min
+                };
+                items.extend(create_items(specifier, amount));
+            },
+            LootSpec::LootTable(specifier) => {
+                if depth >= MAX_NESTING {
+                    warn!(?specifier, "Loot tables are nested too deeply, ignoring");
+                    return;
+                }
+                match LootTable::load(specifier) {
+                    Ok(table) => table.roll_into(rng, items, depth + 1),
+                    Err(e) => warn!(?e, ?specifier, "Failed to load loot table"),
+                }
+            },
+            LootSpec::Nothing => {},
+        }
+    }
+}
+
+/// Creates `amount` of an item, as a single stack if the item is stackable
+fn create_items(specifier: &str, amount: u32) -> Vec<Item> {
+    if amount == 0 {
+        return Vec::new();
+    }
+    let mut item = match Item::new_from_asset(specifier) {
+        Ok(item) => item,
+        Err(e) => {
+            warn!(?e, ?specifier, "Failed to load loot item");
+            return Vec::new();
+        },
+    };
+    if item.is_stackable() {
+        let _ = item.set_amount(amount);
+        vec![item]
+    } else {
+        (1..amount)
+            .map(|_| item.duplicate())
+            .chain(std::iter::once(item))
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct LootTableFile {
+    #[serde(default)]
+    guaranteed: Vec<LootSpec>,
+    #[serde(default)]
+    entries: Vec<(f32, LootSpec)>,
+}
+
+/// A weighted table of loot, of which one entry is rolled every time the table
+/// is. Guaranteed drops are given in addition to the rolled entry.
+#[derive(Clone, Debug)]
+pub struct LootTable {
+    pub guaranteed: Vec<LootSpec>,
+    pub entries: Option<Lottery<LootSpec>>,
+}
+
+impl Asset for LootTable {
+    const ENDINGS: &'static [&'static str] = &["ron"];
+
+    fn parse(buf_reader: BufReader<File>, _specifier: &str) -> Result<Self, assets::Error> {
+        ron::de::from_reader::<BufReader<File>, LootTableFile>(buf_reader)
+            .map(|file| LootTable {
+                guaranteed: file.guaranteed,
+                entries: if file.entries.is_empty() {
+                    None
+                } else {
+                    Some(Lottery::from_rates(file.entries.into_iter()))
+                },
+            })
+            .map_err(assets::Error::parse_error)
+    }
+}
+
+impl LootTable {
+    pub fn roll(&self, rng: &mut impl Rng) -> Vec<Item> {
+        let mut items = Vec::new();
+        self.roll_into(rng, &mut items, 0);
+        items
+    }
+
+    fn roll_into(&self, rng: &mut impl Rng, items: &mut Vec<Item>, depth: usize) {
+        for spec in &self.guaranteed {
+            spec.roll_into(rng, items, depth);
+        }
+        if let Some(entries) = &self.entries {
+            entries
+                .choose_seeded(rng.gen())
+                .roll_into(rng, items, depth);
+        }
+    }
+
+    /// All specs of the table, guaranteed or not
+    pub fn specs(&self) -> impl Iterator<Item = &LootSpec> {
+        self.guaranteed.iter().chain(
+            self.entries
+                .iter()
+                .flat_map(|entries| entries.iter().map(|(_, spec)| spec)),
+        )
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BodyLoot {
+    pub table: String,
+    /// Overrides for species of the body, keyed by their keyword in
+    /// `common.npc_names`
+    #[serde(default)]
+    pub species: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SiteLoot {
+    pub table: String,
+    /// Overrides for levels of the site, e.g. the floors of a dungeon counted
+    /// from the top
+    #[serde(default)]
+    pub levels: HashMap<u32, String>,
+    #[serde(default)]
+    pub boss: Option<String>,
+}
+
+/// Decides which loot table NPCs drop from, loaded from `common.loot_config`
+#[derive(Clone, Debug, Deserialize)]
+pub struct LootConfig {
+    /// Loot table for NPCs without a more specific one
+    pub default: String,
+    /// Loot tables by body, keyed by the body keyword in `common.npc_names`
+    pub bodies: HashMap<String, BodyLoot>,
+    /// Loot tables of NPCs spawned by sites, these take precedence over the
+    /// loot table of the NPC's body
+    #[serde(default)]
+    pub sites: HashMap<String, SiteLoot>,
+}
+
+impl LootConfig {
+    /// Loot table dropped by an NPC with `body`
+    pub fn body_table(&self, body: &Body) -> &str {
+        match self.bodies.get(&NPC_NAMES[body].keyword) {
+            Some(body_loot) => species_names(body)
+                .and_then(|names| body_loot.species.get(&names.keyword))
+                .unwrap_or(&body_loot.table)
+                .as_str(),
+            None => self.default.as_str(),
+        }
+    }
+
+    /// Loot table dropped by NPCs at `level` of a site, if the site overrides
+    /// their loot
+    pub fn site_table(&self, site: &str, level: u32) -> Option<&str> {
+        self.sites.get(site).map(|site_loot| {
+            site_loot
+                .levels
+                .get(&level)
+                .unwrap_or(&site_loot.table)
+                .as_str()
+        })
+    }
+
+    /// Loot table dropped by the boss of a site
+    pub fn site_boss_table(&self, site: &str) -> Option<&str> {
+        self.sites
+            .get(site)
+            .and_then(|site_loot| site_loot.boss.as_deref())
+    }
+
+    /// All loot tables referenced by the config
+    pub fn tables(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.default.as_str())
+            .chain(
+                self.bodies
+                    .values()
+                    .flat_map(|body_loot| {
+                        std::iter::once(&body_loot.table).chain(body_loot.species.values())
+                    })
+                    .map(String::as_str),
+            )
+            .chain(
+                self.sites
+                    .values()
+                    .flat_map(|site_loot| {
+                        std::iter::once(&site_loot.table)
+                            .chain(site_loot.levels.values())
+                            .chain(site_loot.boss.iter())
+                    })
+                    .map(String::as_str),
+            )
+    }
+}
+
+fn species_names(body: &Body) -> Option<&'static SpeciesNames> {
+    let npc_names = &*NPC_NAMES;
+    Some(match body {
+        Body::Humanoid(body) => &npc_names.humanoid.species[&body.species],
+        Body::QuadrupedSmall(body) => &npc_names.quadruped_small.species[&body.species],
+        Body::QuadrupedMedium(body) => &npc_names.quadruped_medium.species[&body.species],
+        Body::BirdMedium(body) => &npc_names.bird_medium.species[&body.species],
+        Body::Dragon(body) => &npc_names.dragon.species[&body.species],
+        Body::BipedLarge(body) => &npc_names.biped_large.species[&body.species],
+        Body::Golem(body) => &npc_names.golem.species[&body.species],
+        Body::Theropod(body) => &npc_names.theropod.species[&body.species],
+        Body::QuadrupedLow(body) => &npc_names.quadruped_low.species[&body.species],
+        _ => return None,
+    })
+}
+
+/// Checks all loot tables in `common.loot_tables` and the loot config,
+/// returning a description of every loot table or item asset that is referenced
+/// but can't be loaded
+pub fn validate_loot_tables() -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let mut tables = assets::get_glob_matches("common.loot_tables.*").unwrap_or_else(|e| {
+        problems.push(format!("Failed to list loot tables: {:?}", e));
+        Vec::new()
+    });
+    match Ron::<LootConfig>::load("common.loot_config") {
+        Ok(config) => tables.extend(config.tables().map(str::to_owned)),
+        Err(e) => problems.push(format!("Failed to load the loot config: {:?}", e)),
+    }
+    tables.sort();
+    tables.dedup();
+
+    for specifier in &tables {
+        let table = match LootTable::load(specifier) {
+            Ok(table) => table,
+            Err(e) => {
+                problems.push(format!(
+                    "Failed to load loot table '{}': {:?}",
+                    specifier, e
+                ));
+                continue;
+            },
+        };
+        for spec in table.specs() {
+            match spec {
+                LootSpec::Item(item) | LootSpec::ItemQuantity(item, _, _) => {
+                    if ItemDef::load(item).is_err() {
+                        problems.push(format!(
+                            "Loot table '{}' references missing item '{}'",
+                            specifier, item
+                        ));
+                    }
+                },
+                LootSpec::LootTable(nested) => {
+                    if LootTable::load(nested).is_err() {
+                        problems.push(format!(
+                            "Loot table '{}' references missing loot table '{}'",
+                            specifier, nested
+                        ));
+                    }
+                },
+                LootSpec::Nothing => {},
+            }
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loot_tables() {
+        let problems = validate_loot_tables();
+        assert!(problems.is_empty(), "{}", problems.join("\n"));
+    }
+}
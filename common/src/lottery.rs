@@ -45,21 +45,3 @@ impl<T> Lottery<T> {
     pub fn iter(&self) -> impl Iterator<Item = &(f32, T)> { self.items.iter() }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{assets::Asset, comp::Item};
-
-    #[test]
-    fn test_loot_table() {
-        let test = Lottery::<String>::load_expect("common.loot_tables.loot_table");
-
-        for (_, item_asset_specifier) in test.iter() {
-            assert!(
-                Item::new_from_asset(item_asset_specifier).is_ok(),
-                "Invalid loot table item '{}'",
-                item_asset_specifier
-            );
-        }
-    }
-}
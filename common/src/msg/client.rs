@@ -3,7 +3,9 @@ use crate::{
     character::CharacterId,
     comp,
     comp::{Skill, SkillGroupType},
+    sync::Uid,
     terrain::block::Block,
+    trade::{SiteId, TradeAction, TradeId},
 };
 use serde::{Deserialize, Serialize};
 use vek::*;
@@ -76,6 +78,13 @@ pub enum ClientGeneral {
     UnlockSkill(Skill),
     RefundSkill(Skill),
     UnlockSkillGroup(SkillGroupType),
+    /// Asks another entity to trade
+    InitiateTrade(Uid),
+    UpdatePendingTrade(TradeId, TradeAction),
+    /// Asks for the economy of a site
+    RequestSiteInfo(SiteId),
+    /// Talks to an NPC, which may offer quests
+    Talk(Uid),
     //Always possible
     ChatMsg(String),
     Disconnect,
@@ -115,9 +124,11 @@ impl ClientMsg {
                         | ClientGeneral::TerrainChunkRequest { .. }
                         | ClientGeneral::UnlockSkill(_)
                         | ClientGeneral::RefundSkill(_)
-                        | ClientGeneral::UnlockSkillGroup(_) => {
-                            c_type == ClientType::Game && in_game.is_some()
-                        },
+                        | ClientGeneral::UnlockSkillGroup(_)
+                        | ClientGeneral::InitiateTrade(_)
+                        | ClientGeneral::UpdatePendingTrade(_, _)
+                        | ClientGeneral::RequestSiteInfo(_)
+                        | ClientGeneral::Talk(_) => c_type == ClientType::Game && in_game.is_some(),
                         //Always possible
                         ClientGeneral::ChatMsg(_)
                         | ClientGeneral::Disconnect
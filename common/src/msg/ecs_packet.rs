@@ -31,6 +31,7 @@ sum_type! {
         Ori(comp::Ori),
         Shockwave(comp::Shockwave),
         BeamSegment(comp::BeamSegment),
+        Buffs(comp::Buffs),
     }
 }
 // Automatically derive From<T> for EcsCompPhantom
@@ -60,6 +61,7 @@ sum_type! {
         Ori(PhantomData<comp::Ori>),
         Shockwave(PhantomData<comp::Shockwave>),
         BeamSegment(PhantomData<comp::BeamSegment>),
+        Buffs(PhantomData<comp::Buffs>),
     }
 }
 impl sync::CompPacket for EcsCompPacket {
@@ -89,6 +91,7 @@ impl sync::CompPacket for EcsCompPacket {
             EcsCompPacket::Ori(comp) => sync::handle_insert(comp, entity, world),
             EcsCompPacket::Shockwave(comp) => sync::handle_insert(comp, entity, world),
             EcsCompPacket::BeamSegment(comp) => sync::handle_insert(comp, entity, world),
+            EcsCompPacket::Buffs(comp) => sync::handle_insert(comp, entity, world),
         }
     }
 
@@ -116,6 +119,7 @@ impl sync::CompPacket for EcsCompPacket {
             EcsCompPacket::Ori(comp) => sync::handle_modify(comp, entity, world),
             EcsCompPacket::Shockwave(comp) => sync::handle_modify(comp, entity, world),
             EcsCompPacket::BeamSegment(comp) => sync::handle_modify(comp, entity, world),
+            EcsCompPacket::Buffs(comp) => sync::handle_modify(comp, entity, world),
         }
     }
 
@@ -147,6 +151,7 @@ impl sync::CompPacket for EcsCompPacket {
             EcsCompPhantom::Ori(_) => sync::handle_remove::<comp::Ori>(entity, world),
             EcsCompPhantom::Shockwave(_) => sync::handle_remove::<comp::Shockwave>(entity, world),
             EcsCompPhantom::BeamSegment(_) => sync::handle_remove::<comp::Ori>(entity, world),
+            EcsCompPhantom::Buffs(_) => sync::handle_remove::<comp::Buffs>(entity, world),
         }
     }
 }
@@ -7,6 +7,7 @@ use crate::{
     state, sync,
     sync::Uid,
     terrain::{Block, TerrainChunk},
+    trade::{EconomyInfo, PendingTrade, SitePrices, TradeId, TradeResult},
 };
 use authc::AuthClientError;
 use hashbrown::HashMap;
@@ -72,10 +73,12 @@ pub enum ServerGeneral {
     CharacterSuccess,
     //Ingame related
     GroupUpdate(comp::group::ChangeNotification<sync::Uid>),
-    /// Indicate to the client that they are invited to join a group
-    GroupInvite {
+    /// Indicate to the client that they are invited to join a group or to
+    /// trade
+    Invite {
         inviter: sync::Uid,
         timeout: std::time::Duration,
+        kind: comp::group::InviteKind,
     },
     /// Indicate to the client that their sent invite was not invalid and is
     /// currently pending
@@ -88,6 +91,7 @@ pub enum ServerGeneral {
     InviteComplete {
         target: sync::Uid,
         answer: InviteAnswer,
+        kind: comp::group::InviteKind,
     },
     /// Trigger cleanup for when the client goes back to the `Registered` state
     /// from an ingame state
@@ -101,6 +105,28 @@ pub enum ServerGeneral {
     SetViewDistance(u32),
     Outcomes(Vec<Outcome>),
     Knockback(Vec3<f32>),
+    /// The weather of the whole world, sent when joining and then periodically
+    WeatherUpdate(crate::weather::WeatherGrid),
+    /// The state of the trade the client is in, with the prices of the
+    /// merchant if it trades with one
+    UpdatePendingTrade {
+        id: TradeId,
+        trade: PendingTrade,
+        /// Items of the other party the client can see by their inventory
+        /// slot: the whole stock of a merchant, or what another player offers
+        counterparty_items: Vec<(usize, comp::Item)>,
+        prices: Option<SitePrices>,
+    },
+    FinishedTrade(TradeResult),
+    /// The economy of a site the client asked for
+    SiteEconomy(EconomyInfo),
+    /// The quests of the character, sent when it enters the game and whenever
+    /// they change
+    QuestLog(comp::QuestLog),
+    /// The character took on the quest with this asset specifier
+    QuestStarted(String),
+    /// The character completed the quest with this asset specifier
+    QuestCompleted(String),
     // Always possible
     PlayerListUpdate(PlayerListUpdate),
     /// A message to go into the client chat box. The client is responsible for
@@ -129,6 +155,7 @@ pub enum PlayerListUpdate {
     SelectedCharacter(Uid, CharacterInfo),
     LevelChange(Uid, u32),
     Admin(Uid, bool),
+    Pvp(Uid, bool),
     Remove(Uid),
     Alias(Uid, String),
 }
@@ -136,6 +163,9 @@ pub enum PlayerListUpdate {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerInfo {
     pub is_admin: bool,
+    /// Whether the player opted into PvP with `/pvp`
+    #[serde(default)]
+    pub pvp: bool,
     pub is_online: bool,
     pub player_alias: String,
     pub character: Option<CharacterInfo>,
@@ -173,7 +203,11 @@ pub enum DisconnectReason {
 pub enum RegisterError {
     AlreadyLoggedIn,
     AuthError(String),
-    Banned(String),
+    Banned {
+        reason: String,
+        /// `None` if the ban is permanent
+        remaining: Option<Duration>,
+    },
     InvalidCharacter,
     NotOnWhitelist,
     //TODO: InvalidAlias,
@@ -204,7 +238,7 @@ impl ServerMsg {
                         },
                         //Ingame related
                         ServerGeneral::GroupUpdate(_)
-                        | ServerGeneral::GroupInvite { .. }
+                        | ServerGeneral::Invite { .. }
                         | ServerGeneral::InvitePending(_)
                         | ServerGeneral::InviteComplete { .. }
                         | ServerGeneral::ExitInGameSuccess
@@ -213,7 +247,14 @@ impl ServerMsg {
                         | ServerGeneral::TerrainBlockUpdates(_)
                         | ServerGeneral::SetViewDistance(_)
                         | ServerGeneral::Outcomes(_)
-                        | ServerGeneral::Knockback(_) => {
+                        | ServerGeneral::Knockback(_)
+                        | ServerGeneral::WeatherUpdate(_)
+                        | ServerGeneral::UpdatePendingTrade { .. }
+                        | ServerGeneral::FinishedTrade(_)
+                        | ServerGeneral::SiteEconomy(_)
+                        | ServerGeneral::QuestLog(_)
+                        | ServerGeneral::QuestStarted(_)
+                        | ServerGeneral::QuestCompleted(_) => {
                             c_type == ClientType::Game && in_game.is_some()
                         },
                         // Always possible
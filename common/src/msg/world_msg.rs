@@ -1,3 +1,4 @@
+use crate::trade::SiteId;
 use serde::{Deserialize, Serialize};
 use vek::*;
 
@@ -120,4 +121,42 @@ pub struct WorldMapMsg {
     /// angles, or that we don't need as much precision as we currently have
     /// (256 possible angles).
     pub horizons: [(Vec<u8>, Vec<u8>); 2],
+    /// Towns, dungeons and castles of the world
+    pub sites: Vec<SiteInfo>,
+    /// Places of interest that aren't sites, like the entrances of caves
+    pub pois: Vec<PoiInfo>,
+    /// The tracks between sites, as lines through world positions
+    pub tracks: Vec<Vec<Vec2<i32>>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteInfo {
+    /// The id the economy of the site can be requested with
+    pub id: SiteId,
+    pub kind: SiteKind,
+    pub wpos: Vec2<i32>,
+    pub name: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SiteKind {
+    Town,
+    /// Difficulty goes up from 1 with the distance of the dungeon from the
+    /// centre of the world
+    Dungeon {
+        difficulty: u32,
+    },
+    Castle,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoiInfo {
+    pub kind: PoiKind,
+    pub wpos: Vec2<i32>,
+    pub name: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PoiKind {
+    Cave,
 }
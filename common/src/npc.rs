@@ -7,7 +7,7 @@ use rand::seq::SliceRandom;
 use serde::Deserialize;
 use std::{str::FromStr, sync::Arc};
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
 pub enum NpcKind {
     Humanoid,
     Wolf,
@@ -79,6 +79,46 @@ impl FromStr for NpcKind {
     }
 }
 
+impl NpcKind {
+    /// The kind of NPC an entity with this body counts as, if any. Kinds named
+    /// after a species only include that species, so that a tiger doesn't
+    /// count as a wolf.
+    pub fn from_body(body: &Body) -> Option<Self> {
+        use comp::{
+            biped_large, bird_medium, dragon, golem, quadruped_low, quadruped_medium,
+            quadruped_small, theropod,
+        };
+        match body {
+            Body::Humanoid(_) => Some(NpcKind::Humanoid),
+            Body::QuadrupedSmall(body) if body.species == quadruped_small::Species::Pig => {
+                Some(NpcKind::Pig)
+            },
+            Body::QuadrupedMedium(body) if body.species == quadruped_medium::Species::Wolf => {
+                Some(NpcKind::Wolf)
+            },
+            Body::BirdMedium(body) if body.species == bird_medium::Species::Duck => {
+                Some(NpcKind::Duck)
+            },
+            Body::BipedLarge(body) if body.species == biped_large::Species::Ogre => {
+                Some(NpcKind::Ogre)
+            },
+            Body::Theropod(body) if body.species == theropod::Species::Archaeos => {
+                Some(NpcKind::Archaeos)
+            },
+            Body::Golem(body) if body.species == golem::Species::StoneGolem => {
+                Some(NpcKind::StoneGolem)
+            },
+            Body::Dragon(body) if body.species == dragon::Species::Reddragon => {
+                Some(NpcKind::Reddragon)
+            },
+            Body::QuadrupedLow(body) if body.species == quadruped_low::Species::Crocodile => {
+                Some(NpcKind::Crocodile)
+            },
+            _ => None,
+        }
+    }
+}
+
 pub fn get_npc_name(npc_type: NpcKind) -> &'static str {
     let BodyNames { keyword, names } = &NPC_NAMES[npc_type];
 
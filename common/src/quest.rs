@@ -0,0 +1,218 @@
+//! Quests are defined in RON files under `common.quests`. Friendly NPCs of the
+//! kind and role that gives a quest offer it when talked to, and a character
+//! takes it on by accepting the offer. The quest is completed once all of its
+//! objectives are done. The server tracks the progress in
+//! the [`QuestLog`](crate::comp::QuestLog) of the character and sends it to
+//! the client.
+
+use crate::{
+    assets::{self, Asset},
+    msg::world_msg::SiteKind,
+    npc::NpcKind,
+};
+use serde::Deserialize;
+use std::{fs::File, io::BufReader};
+use tracing::warn;
+
+/// Asset specifier glob matching all quest definitions
+const QUESTS_GLOB: &str = "common.quests.*";
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct QuestDef {
+    pub title: String,
+    pub description: String,
+    /// Talking to a friendly NPC like this offers the quest
+    pub giver: QuestNpc,
+    /// All objectives have to be done to complete the quest, in any order
+    pub objectives: Vec<Objective>,
+}
+
+impl Asset for QuestDef {
+    const ENDINGS: &'static [&'static str] = &["ron"];
+
+    fn parse(buf_reader: BufReader<File>, _specifier: &str) -> Result<Self, assets::Error> {
+        ron::de::from_reader(buf_reader).map_err(assets::Error::parse_error)
+    }
+}
+
+/// What an NPC does in the world
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum NpcRole {
+    /// Trades with players in a town
+    Merchant,
+    /// Friendly NPC at a castle
+    Guard,
+    /// Friendly NPC in a town
+    Villager,
+    /// Friendly NPC away from the sites
+    Traveller,
+    /// Hostile NPC in a dungeon
+    Cultist,
+}
+
+/// An NPC as far as quests are concerned, which is how quests name the NPCs
+/// they are given by and the NPCs their objectives ask for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub struct QuestNpc {
+    pub kind: NpcKind,
+    /// What the NPC does. Any NPC of the kind matches if this is left out.
+    #[serde(default)]
+    pub role: Option<NpcRole>,
+}
+
+impl QuestNpc {
+    /// Whether `npc` is one of the NPCs this describes
+    pub fn matches(&self, npc: &QuestNpc) -> bool {
+        self.kind == npc.kind && self.role.map_or(true, |role| npc.role == Some(role))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub enum Objective {
+    /// Kill this many NPCs like this
+    Kill { npc: QuestNpc, count: u32 },
+    /// Hold this many of an item in the inventory, given by its asset
+    /// specifier
+    Collect { item: String, count: u32 },
+    /// Reach a site of a kind. Any dungeon of at least the given difficulty
+    /// counts.
+    Reach { site: SiteKind },
+    /// Talk to an NPC like this
+    Talk { npc: QuestNpc },
+}
+
+impl Objective {
+    /// The progress at which the objective is done
+    pub fn required(&self) -> u32 {
+        match self {
+            Objective::Kill { count, .. } | Objective::Collect { count, .. } => *count,
+            Objective::Reach { .. } | Objective::Talk { .. } => 1,
+        }
+    }
+
+    /// The progress on the objective after the event, given the progress
+    /// before it. Kills, reached sites and talks add up, while the progress
+    /// of collecting is always the amount held, so that dropping and picking
+    /// up the same item doesn't count twice.
+    pub fn progress_after(&self, done: u32, event: &QuestEvent) -> u32 {
+        let progress = match (self, event) {
+            (Objective::Kill { npc, .. }, QuestEvent::Killed(killed)) if npc.matches(killed) => {
+                done + 1
+            },
+            (Objective::Collect { item, .. }, QuestEvent::Holds { item: held, amount })
+                if item == held =>
+            {
+                *amount
+            },
+            (Objective::Reach { site }, QuestEvent::Reached(reached)) => {
+                let reaches = match (site, reached) {
+                    (SiteKind::Dungeon { difficulty: min }, SiteKind::Dungeon { difficulty }) => {
+                        difficulty >= min
+                    },
+                    (site, reached) => site == reached,
+                };
+                done + reaches as u32
+            },
+            (Objective::Talk { npc }, QuestEvent::Talked(talked)) if npc.matches(talked) => {
+                done + 1
+            },
+            _ => done,
+        };
+        progress.min(self.required())
+    }
+}
+
+/// Something a character did, which may advance the objectives of its quests
+#[derive(Clone, Debug)]
+pub enum QuestEvent<'a> {
+    Killed(QuestNpc),
+    /// The character holds this many of an item, given by its asset specifier
+    Holds {
+        item: &'a str,
+        amount: u32,
+    },
+    Reached(SiteKind),
+    Talked(QuestNpc),
+}
+
+/// Lists the asset specifiers of all quests, sorted so that quests are always
+/// offered in the same order
+pub fn all_quests() -> Vec<String> {
+    let mut quests = assets::get_glob_matches(QUESTS_GLOB).unwrap_or_else(|e| {
+        warn!(?e, "Could not list the quests");
+        Vec::new()
+    });
+    quests.sort();
+    quests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comp::item::ItemDef;
+
+    #[test]
+    fn test_quests() {
+        let quests = all_quests();
+        assert!(!quests.is_empty());
+        let mut givers = Vec::new();
+        for quest in quests {
+            let def = QuestDef::load(&quest)
+                .unwrap_or_else(|e| panic!("Could not load quest {}: {:?}", quest, e));
+            assert!(
+                !givers.contains(&def.giver),
+                "Quest {} has the same giver as another quest",
+                quest
+            );
+            givers.push(def.giver);
+            assert!(
+                !def.objectives.is_empty(),
+                "Quest {} has no objectives",
+                quest
+            );
+            for objective in &def.objectives {
+                assert!(
+                    objective.required() > 0,
+                    "Quest {} has an empty objective",
+                    quest
+                );
+                if let Objective::Collect { item, .. } = objective {
+                    assert!(
+                        ItemDef::load(item).is_ok(),
+                        "Quest {} asks for a missing item {}",
+                        quest,
+                        item
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn npcs_match_by_kind_and_role() {
+        let npc = |kind, role| QuestNpc { kind, role };
+        let merchant = npc(NpcKind::Humanoid, Some(NpcRole::Merchant));
+        assert!(npc(NpcKind::Humanoid, None).matches(&merchant));
+        assert!(merchant.matches(&merchant));
+        assert!(!npc(NpcKind::Humanoid, Some(NpcRole::Guard)).matches(&merchant));
+        assert!(!merchant.matches(&npc(NpcKind::Humanoid, None)));
+        assert!(!npc(NpcKind::Wolf, None).matches(&merchant));
+    }
+
+    #[test]
+    fn dungeons_need_the_minimum_difficulty() {
+        let objective = Objective::Reach {
+            site: SiteKind::Dungeon { difficulty: 3 },
+        };
+        let reach = |site| objective.progress_after(0, &QuestEvent::Reached(site));
+        assert_eq!(reach(SiteKind::Dungeon { difficulty: 2 }), 0);
+        assert_eq!(reach(SiteKind::Dungeon { difficulty: 3 }), 1);
+        assert_eq!(reach(SiteKind::Dungeon { difficulty: 5 }), 1);
+        assert_eq!(reach(SiteKind::Castle), 0);
+        // Reaching a site again doesn't go past done
+        assert_eq!(
+            objective.progress_after(1, &QuestEvent::Reached(SiteKind::Dungeon { difficulty: 4 })),
+            1
+        );
+    }
+}
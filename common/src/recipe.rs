@@ -1,15 +1,110 @@
 use crate::{
     assets::{self, Asset},
-    comp::{item::ItemDef, Inventory, Item},
+    comp::{
+        item::{ItemDef, ItemTag},
+        object, Body, Inventory, Item, Pos,
+    },
+    terrain::{SpriteKind, TerrainGrid},
+    vol::ReadVol,
 };
 use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
-use std::{fs::File, io::BufReader, sync::Arc};
+use specs::{Join, World, WorldExt};
+use std::{fs::File, io::BufReader, sync::Arc, time::Duration};
+use vek::*;
+
+/// Characters have to be this close to a crafting station to craft at it
+pub const MAX_STATION_RANGE: f32 = 5.0;
+
+/// Something recipes can require to be crafted next to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CraftingStation {
+    Anvil,
+    Cauldron,
+    Campfire,
+    CraftingBench,
+}
+
+impl CraftingStation {
+    /// Whether objects with this body are stations of this kind
+    pub fn is_object(self, body: object::Body) -> bool {
+        match self {
+            CraftingStation::Anvil => body == object::Body::Anvil,
+            CraftingStation::Cauldron => body == object::Body::Cauldron,
+            CraftingStation::Campfire => {
+                matches!(body, object::Body::Campfire | object::Body::CampfireLit)
+            },
+            CraftingStation::CraftingBench => body == object::Body::CraftingBench,
+        }
+    }
+
+    /// Whether blocks with this sprite are stations of this kind
+    pub fn is_sprite(self, sprite: SpriteKind) -> bool {
+        matches!((self, sprite), (CraftingStation::Cauldron, SpriteKind::Pot))
+    }
+
+    /// Whether a station of this kind is in range of a position, either as an
+    /// object or as a sprite in the terrain
+    pub fn is_near(self, ecs: &World, pos: Vec3<f32>) -> bool {
+        let max_dist_sqr = MAX_STATION_RANGE.powi(2);
+
+        let near_object = (&ecs.read_storage::<Pos>(), &ecs.read_storage::<Body>())
+            .join()
+            .any(|(station_pos, body)| match body {
+                Body::Object(body) => {
+                    self.is_object(*body) && station_pos.0.distance_squared(pos) < max_dist_sqr
+                },
+                _ => false,
+            });
+
+        near_object || {
+            let terrain = ecs.read_resource::<TerrainGrid>();
+            let range = MAX_STATION_RANGE.ceil() as i32;
+            let center = pos.map(|e| e.floor() as i32);
+            (-range..=range)
+                .flat_map(|x| {
+                    (-range..=range)
+                        .flat_map(move |y| (-range..=range).map(move |z| Vec3::new(x, y, z)))
+                })
+                .filter(|offs| offs.map(|e| e as f32).magnitude_squared() < max_dist_sqr)
+                .any(|offs| {
+                    terrain
+                        .get(center + offs)
+                        .ok()
+                        .and_then(|block| block.get_sprite())
+                        .map_or(false, |sprite| self.is_sprite(sprite))
+                })
+        }
+    }
+}
+
+/// What an input of a recipe accepts
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum RecipeInput {
+    /// Only this item
+    Item(Arc<ItemDef>),
+    /// Any item with this tag
+    Tag(ItemTag),
+}
+
+impl RecipeInput {
+    pub fn matches(&self, item: &Item) -> bool {
+        match self {
+            RecipeInput::Item(item_def) => item.is_same_item_def(item_def),
+            RecipeInput::Tag(tag) => item.has_tag(*tag),
+        }
+    }
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Recipe {
     pub output: (Arc<ItemDef>, u32),
-    pub inputs: Vec<(Arc<ItemDef>, u32)>,
+    pub inputs: Vec<(RecipeInput, u32)>,
+    /// The station the character has to be near to craft the recipe
+    pub station: Option<CraftingStation>,
+    /// How long crafting the recipe takes, recipes without a craft time are
+    /// crafted instantly
+    pub craft_time: Duration,
 }
 
 #[allow(clippy::type_complexity)]
@@ -18,7 +113,7 @@ impl Recipe {
     pub fn perform(
         &self,
         inv: &mut Inventory,
-    ) -> Result<Option<(Item, u32)>, Vec<(&ItemDef, u32)>> {
+    ) -> Result<Option<(Item, u32)>, Vec<(&RecipeInput, u32)>> {
         // Get ingredient cells from inventory,
         inv.contains_ingredients(self)?
             .into_iter()
@@ -39,10 +134,8 @@ impl Recipe {
         Ok(None)
     }
 
-    pub fn inputs(&self) -> impl ExactSizeIterator<Item = (&Arc<ItemDef>, u32)> {
-        self.inputs
-            .iter()
-            .map(|(item_def, amount)| (item_def, *amount))
+    pub fn inputs(&self) -> impl ExactSizeIterator<Item = (&RecipeInput, u32)> {
+        self.inputs.iter().map(|(input, amount)| (input, *amount))
     }
 }
 
@@ -65,36 +158,99 @@ impl RecipeBook {
     }
 }
 
+#[derive(Deserialize)]
+enum RawRecipeInput {
+    Item(String),
+    Tag(ItemTag),
+}
+
+#[derive(Deserialize)]
+struct RawRecipe {
+    output: (String, u32),
+    inputs: Vec<(RawRecipeInput, u32)>,
+    #[serde(default)]
+    station: Option<CraftingStation>,
+    /// In seconds
+    #[serde(default)]
+    craft_time: f32,
+}
+
 impl Asset for RecipeBook {
     const ENDINGS: &'static [&'static str] = &["ron"];
 
     fn parse(buf_reader: BufReader<File>, _specifier: &str) -> Result<Self, assets::Error> {
-        ron::de::from_reader::<
-            BufReader<File>,
-            HashMap<String, ((String, u32), Vec<(String, u32)>)>,
-        >(buf_reader)
-        .map_err(assets::Error::parse_error)
-        .and_then(|recipes| {
-            Ok(RecipeBook {
-                recipes: recipes
-                    .into_iter()
-                    .map::<Result<(String, Recipe), assets::Error>, _>(
-                        |(name, ((output, amount), inputs))| {
+        ron::de::from_reader::<BufReader<File>, HashMap<String, RawRecipe>>(buf_reader)
+            .map_err(assets::Error::parse_error)
+            .and_then(|recipes| {
+                Ok(RecipeBook {
+                    recipes: recipes
+                        .into_iter()
+                        .map::<Result<(String, Recipe), assets::Error>, _>(|(name, raw)| {
                             Ok((name, Recipe {
-                                output: (ItemDef::load(&output)?, amount),
-                                inputs: inputs
+                                output: (ItemDef::load(&raw.output.0)?, raw.output.1),
+                                inputs: raw
+                                    .inputs
                                     .into_iter()
-                                    .map::<Result<(Arc<ItemDef>, u32), assets::Error>, _>(
-                                        |(name, amount)| Ok((ItemDef::load(&name)?, amount)),
+                                    .map::<Result<(RecipeInput, u32), assets::Error>, _>(
+                                        |(input, amount)| {
+                                            Ok((
+                                                match input {
+                                                    RawRecipeInput::Item(name) => {
+                                                        RecipeInput::Item(ItemDef::load(&name)?)
+                                                    },
+                                                    RawRecipeInput::Tag(tag) => {
+                                                        RecipeInput::Tag(tag)
+                                                    },
+                                                },
+                                                amount,
+                                            ))
+                                        },
                                     )
                                     .collect::<Result<_, _>>()?,
+                                station: raw.station,
+                                craft_time: Duration::from_secs_f32(raw.craft_time.max(0.0)),
                             }))
-                        },
-                    )
-                    .collect::<Result<_, _>>()?,
+                        })
+                        .collect::<Result<_, _>>()?,
+                })
             })
-        })
     }
 }
 
 pub fn default_recipe_book() -> Arc<RecipeBook> { RecipeBook::load_expect("common.recipe_book") }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recipe_book() {
+        let recipe_book = RecipeBook::load("common.recipe_book")
+            .unwrap_or_else(|e| panic!("Could not load the recipe book: {:?}", e));
+        for (name, recipe) in recipe_book.iter() {
+            assert!(!recipe.inputs.is_empty(), "Recipe {} has no inputs", name);
+        }
+    }
+
+    #[test]
+    fn test_recipe_tags() {
+        // Tags are only worth asking for when more than one item has them
+        let items = ItemDef::load_glob("common.items.*")
+            .unwrap_or_else(|e| panic!("Could not load the items: {:?}", e));
+        let recipe_book = RecipeBook::load_expect("common.recipe_book");
+        for (name, recipe) in recipe_book.iter() {
+            for (input, _) in recipe.inputs() {
+                if let RecipeInput::Tag(tag) = input {
+                    let tagged = items.iter().filter(|item| item.has_tag(*tag)).count();
+                    assert!(
+                        tagged > 1,
+                        "Recipe {} asks for {:?}, which only {} items have",
+                        name,
+                        tag,
+                        tagged
+                    );
+                }
+            }
+        }
+    }
+}
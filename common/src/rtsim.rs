@@ -0,0 +1,16 @@
+//! Types shared by the real-time simulation of NPCs (rtsim), which lives in
+//! the world crate, and the server that loads its NPCs as entities
+
+use specs::Component;
+use specs_idvs::IdvStorage;
+
+/// Identifies an NPC simulated by rtsim
+pub type RtSimId = usize;
+
+/// Marks an entity as the loaded form of an NPC simulated by rtsim
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RtSimEntity(pub RtSimId);
+
+impl Component for RtSimEntity {
+    type Storage = IdvStorage<Self>;
+}
@@ -8,6 +8,7 @@ use crate::{
     terrain::{Block, TerrainChunk, TerrainGrid},
     time::DayPeriod,
     vol::{ReadVol, WriteVol},
+    weather::{Weather, WeatherGrid},
 };
 use hashbrown::{HashMap, HashSet};
 use rayon::{ThreadPool, ThreadPoolBuilder};
@@ -62,6 +63,12 @@ impl BlockChange {
     }
 
     pub fn clear(&mut self) { self.blocks.clear(); }
+
+    /// Whether a change to `pos` is already pending
+    pub fn contains(&self, pos: Vec3<i32>) -> bool { self.blocks.contains_key(&pos) }
+
+    /// Removes and returns all pending changes
+    pub fn drain(&mut self) -> impl Iterator<Item = (Vec3<i32>, Block)> + '_ { self.blocks.drain() }
 }
 
 #[derive(Default)]
@@ -112,6 +119,7 @@ impl State {
         ecs.register::<comp::Body>();
         ecs.register::<comp::Player>();
         ecs.register::<comp::Stats>();
+        ecs.register::<comp::Buffs>();
         ecs.register::<comp::Energy>();
         ecs.register::<comp::CanBuild>();
         ecs.register::<comp::LightEmitter>();
@@ -153,11 +161,14 @@ impl State {
         ecs.register::<comp::Last<comp::Ori>>();
         ecs.register::<comp::Alignment>();
         ecs.register::<comp::Agent>();
+        ecs.register::<comp::Merchant>();
         ecs.register::<comp::WaypointArea>();
         ecs.register::<comp::ForceUpdate>();
         ecs.register::<comp::InventoryUpdate>();
         ecs.register::<comp::Admin>();
+        ecs.register::<comp::PvpOptIn>();
         ecs.register::<comp::Waypoint>();
+        ecs.register::<comp::QuestLog>();
         ecs.register::<comp::Projectile>();
         ecs.register::<comp::Attacking>();
         ecs.register::<comp::ItemDrop>();
@@ -169,6 +180,7 @@ impl State {
 
         // Register synced resources used by the ECS.
         ecs.insert(TimeOfDay(0.0));
+        ecs.insert(WeatherGrid::default());
 
         // Register unsynced resources used by the ECS.
         ecs.insert(Time(0.0));
@@ -180,6 +192,7 @@ impl State {
         // TODO: only register on the server
         ecs.insert(EventBus::<ServerEvent>::default());
         ecs.insert(comp::group::GroupManager::default());
+        ecs.insert(comp::PvpRules::default());
         ecs.insert(RegionMap::new());
         ecs.insert(SysMetrics::default());
 
@@ -249,6 +262,14 @@ impl State {
     /// Get the current delta time.
     pub fn get_delta_time(&self) -> f32 { self.ecs.read_resource::<DeltaTime>().0 }
 
+    /// Get a reference to the weather of the world.
+    pub fn weather_grid(&self) -> Fetch<WeatherGrid> { self.ecs.read_resource() }
+
+    /// Get the weather at a position in the world.
+    pub fn weather_at(&self, wpos: Vec2<f32>) -> Weather {
+        self.weather_grid().get_interpolated(wpos)
+    }
+
     /// Get a reference to this state's terrain.
     pub fn terrain(&self) -> Fetch<TerrainGrid> { self.ecs.read_resource() }
 
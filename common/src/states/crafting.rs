@@ -0,0 +1,181 @@
+use super::utils::*;
+use crate::{
+    comp::{CharacterState, StateUpdate},
+    event::ServerEvent,
+    sys::character_behavior::{CharacterBehavior, JoinData},
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Separated out to condense update portions of character state
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StaticData {
+    /// Name of the recipe in the recipe book
+    pub recipe: String,
+    /// How long the recipe takes to craft
+    pub craft_time: Duration,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Data {
+    /// Struct containing data that does not change over the course of the
+    /// character state
+    pub static_data: StaticData,
+    /// Timer for the craft
+    pub timer: Duration,
+}
+
+impl CharacterBehavior for Data {
+    fn behavior(&self, data: &JoinData) -> StateUpdate {
+        let mut update = StateUpdate::from(data);
+
+        handle_wield(data, &mut update);
+        handle_jump(&data, &mut update);
+
+        // Moving or falling stops the craft, the ingredients are only used up once
+        // it's done
+        if !data.physics.on_ground || data.inputs.move_dir.magnitude_squared() > 0.0 {
+            update.character = CharacterState::Idle;
+        } else if self.timer < self.static_data.craft_time {
+            // Keep crafting
+            update.character = CharacterState::Crafting(Data {
+                static_data: self.static_data.clone(),
+                timer: self
+                    .timer
+                    .checked_add(Duration::from_secs_f32(data.dt.0))
+                    .unwrap_or_default(),
+            });
+        } else {
+            // Done
+            update.server_events.push_front(ServerEvent::FinishCraft {
+                entity: data.entity,
+                recipe: self.static_data.recipe.clone(),
+            });
+            update.character = CharacterState::Idle;
+        }
+
+        update
+    }
+
+    fn wield(&self, data: &JoinData) -> StateUpdate {
+        let mut update = StateUpdate::from(data);
+        attempt_wield(data, &mut update);
+        update
+    }
+
+    fn stand(&self, data: &JoinData) -> StateUpdate {
+        let mut update = StateUpdate::from(data);
+        update.character = CharacterState::Idle;
+        update
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        comp::{self, Body, Controller, Energy, Loadout, Ori, PhysicsState, Pos, Stats, Vel},
+        state::DeltaTime,
+        sync::Uid,
+        weather::WeatherGrid,
+    };
+    use specs::{LazyUpdate, WorldExt};
+    use vek::*;
+
+    fn craft(timer: Duration) -> Data {
+        Data {
+            static_data: StaticData {
+                recipe: "crafting_bench".to_owned(),
+                craft_time: Duration::from_secs(1),
+            },
+            timer,
+        }
+    }
+
+    /// Runs the crafting state for a tick of `dt` seconds
+    fn tick(craft: &Data, dt: f32, move_dir: Vec2<f32>, on_ground: bool) -> StateUpdate {
+        let world = specs::World::new();
+        let entity = world.entities().create();
+        let updater = world.read_resource::<LazyUpdate>();
+        let body = Body::Humanoid(comp::humanoid::Body::random());
+        let character = CharacterState::Crafting(craft.clone());
+        let mut controller = Controller::default();
+        controller.inputs.move_dir = move_dir;
+        let physics = PhysicsState {
+            on_ground,
+            ..PhysicsState::default()
+        };
+        let stats = Stats::new("Crafter".to_owned(), body);
+        let energy = Energy::new(1000);
+        let loadout = Loadout::default();
+        let weather = WeatherGrid::new(Vec2::one());
+        let data = JoinData {
+            entity,
+            uid: &Uid(1),
+            character: &character,
+            pos: &Pos::default(),
+            vel: &Vel::default(),
+            ori: &Ori::default(),
+            dt: &DeltaTime(dt),
+            controller: &controller,
+            inputs: &controller.inputs,
+            stats: &stats,
+            energy: &energy,
+            loadout: &loadout,
+            body: &body,
+            physics: &physics,
+            attacking: None,
+            buffs: None,
+            updater: &updater,
+            weather: &weather,
+        };
+        craft.behavior(&data)
+    }
+
+    fn finishes(update: &StateUpdate) -> bool {
+        matches!(
+            update.server_events.front(),
+            Some(ServerEvent::FinishCraft { recipe, .. }) if recipe == "crafting_bench"
+        )
+    }
+
+    #[test]
+    fn crafting_finishes_after_the_craft_time() {
+        let mut state = craft(Duration::default());
+        let mut ticks = 0;
+        loop {
+            let update = tick(&state, 0.25, Vec2::zero(), true);
+            ticks += 1;
+            match update.character {
+                CharacterState::Crafting(next) => {
+                    assert!(update.server_events.is_empty());
+                    assert!(next.timer > state.timer);
+                    state = next;
+                },
+                CharacterState::Idle => {
+                    assert!(finishes(&update));
+                    break;
+                },
+                other => panic!("Crafting turned into {:?}", other),
+            }
+            assert!(ticks < 10, "Crafting never finished");
+        }
+        // Four ticks to run out the time, and one to finish
+        assert_eq!(ticks, 5);
+        assert!(state.timer >= state.static_data.craft_time);
+    }
+
+    #[test]
+    fn moving_cancels_the_craft() {
+        let state = craft(Duration::from_millis(500));
+
+        let update = tick(&state, 0.25, Vec2::unit_x(), true);
+        assert_eq!(update.character, CharacterState::Idle);
+        assert!(update.server_events.is_empty());
+
+        // Falling cancels it too, even once the time ran out
+        let update = tick(&craft(Duration::from_secs(1)), 0.25, Vec2::zero(), false);
+        assert_eq!(update.character, CharacterState::Idle);
+        assert!(!finishes(&update));
+    }
+}
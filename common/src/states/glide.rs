@@ -5,11 +5,13 @@ use crate::{
     util::Dir,
 };
 use serde::{Deserialize, Serialize};
-use vek::Vec2;
+use vek::{Vec2, Vec3};
 // Gravity is 9.81 * 4, so this makes gravity equal to .15
 const GLIDE_ANTIGRAV: f32 = crate::sys::phys::GRAVITY * 0.90;
 const GLIDE_ACCEL: f32 = 12.0;
 const GLIDE_SPEED: f32 = 45.0;
+// How strongly the wind pushes a glider
+const GLIDE_WIND_ACCEL: f32 = 0.5;
 
 #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize, Eq, Hash)]
 pub struct Data;
@@ -47,6 +49,10 @@ impl CharacterBehavior for Data {
                 0.0
             };
 
+        // Drift with the wind
+        let wind = data.weather.get_interpolated(data.pos.0.xy()).wind;
+        update.vel.0 += Vec3::from(wind * GLIDE_WIND_ACCEL * data.dt.0);
+
         // Determine orientation vector from movement direction vector
         let ori_dir = Vec2::from(update.vel.0);
         update.ori.0 = Dir::slerp_to_vec3(update.ori.0, ori_dir.into(), 2.0 * data.dt.0);
@@ -72,3 +78,74 @@ impl CharacterBehavior for Data {
         update
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        comp::{self, Body, Controller, Energy, Item, Loadout, Ori, PhysicsState, Pos, Stats, Vel},
+        state::DeltaTime,
+        sync::Uid,
+        weather::WeatherGrid,
+    };
+    use specs::{LazyUpdate, WorldExt};
+
+    /// Glides for a tick of `dt` seconds in `wind`, starting at `vel`
+    fn glide(wind: Vec2<f32>, vel: Vec3<f32>, dt: f32) -> StateUpdate {
+        let world = specs::World::new();
+        let entity = world.entities().create();
+        let updater = world.read_resource::<LazyUpdate>();
+        let body = Body::Humanoid(comp::humanoid::Body::random());
+        let character = CharacterState::Glide;
+        let controller = Controller::default();
+        let physics = PhysicsState::default();
+        let stats = Stats::new("Glider".to_owned(), body);
+        let energy = Energy::new(1000);
+        let loadout = Loadout {
+            glider: Some(Item::new_from_asset_expect(
+                "common.items.glider.glider_blue",
+            )),
+            ..Loadout::default()
+        };
+        let mut weather = WeatherGrid::new(Vec2::one());
+        if let Some(cell) = weather.get_mut(Vec2::zero()) {
+            cell.wind = wind;
+        }
+        let data = JoinData {
+            entity,
+            uid: &Uid(1),
+            character: &character,
+            pos: &Pos::default(),
+            vel: &Vel(vel),
+            ori: &Ori::default(),
+            dt: &DeltaTime(dt),
+            controller: &controller,
+            inputs: &controller.inputs,
+            stats: &stats,
+            energy: &energy,
+            loadout: &loadout,
+            body: &body,
+            physics: &physics,
+            attacking: None,
+            buffs: None,
+            updater: &updater,
+            weather: &weather,
+        };
+        Data.behavior(&data)
+    }
+
+    #[test]
+    fn wind_pushes_gliders() {
+        let vel = Vec3::new(0.0, 10.0, -2.0);
+        let calm = glide(Vec2::zero(), vel, 0.5);
+        let windy = glide(Vec2::new(8.0, -4.0), vel, 0.5);
+        assert_eq!(calm.character, CharacterState::Glide);
+
+        let drift = windy.vel.0 - calm.vel.0;
+        let expected = Vec2::new(8.0, -4.0) * GLIDE_WIND_ACCEL * 0.5;
+        assert!((drift.x - expected.x).abs() < 1e-4);
+        assert!((drift.y - expected.y).abs() < 1e-4);
+        // Gliders turn to face the direction they drift in
+        assert!(windy.ori.0.x > calm.ori.0.x);
+    }
+}
@@ -7,6 +7,7 @@ pub mod charged_melee;
 pub mod charged_ranged;
 pub mod climb;
 pub mod combo_melee;
+pub mod crafting;
 pub mod dance;
 pub mod dash_melee;
 pub mod equipping;
@@ -84,8 +84,12 @@ fn basic_move(data: &JoinData, update: &mut StateUpdate, efficiency: f32) {
         BASE_HUMANOID_AIR_ACCEL
     };
 
-    update.vel.0 =
-        update.vel.0 + Vec2::broadcast(data.dt.0) * data.inputs.move_dir * accel * efficiency;
+    update.vel.0 = update.vel.0
+        + Vec2::broadcast(data.dt.0)
+            * data.inputs.move_dir
+            * accel
+            * efficiency
+            * speed_modifier(data);
 
     handle_orientation(data, update, data.body.base_ori_rate());
 }
@@ -100,11 +104,15 @@ pub fn forward_move(data: &JoinData, update: &mut StateUpdate, efficiency: f32,
 
     update.vel.0 += Vec2::broadcast(data.dt.0)
         * accel
+        * speed_modifier(data)
         * (data.inputs.move_dir * efficiency + (*update.ori.0).xy() * forward);
 
     handle_orientation(data, update, data.body.base_ori_rate() * efficiency);
 }
 
+/// Movement speed factor from buffs like slows
+fn speed_modifier(data: &JoinData) -> f32 { data.buffs.map_or(1.0, |b| b.speed_modifier()) }
+
 pub fn handle_orientation(data: &JoinData, update: &mut StateUpdate, rate: f32) {
     // Set direction based on move direction
     let ori_dir = if update.character.is_block() || update.character.is_attack() {
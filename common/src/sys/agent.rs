@@ -191,6 +191,7 @@ impl<'a> System<'a> for Sys {
             const SIGHT_DIST: f32 = 80.0;
             const MIN_ATTACK_DIST: f32 = 2.0;
             const MAX_FLEE_DIST: f32 = 20.0;
+            const TRAVEL_DIST: f32 = 4.0;
 
             let scale = scales.get(entity).map(|s| s.0).unwrap_or(1.0);
 
@@ -202,10 +203,19 @@ impl<'a> System<'a> for Sys {
             let slow_factor = body.map(|b| b.base_accel() / 250.0).unwrap_or(0.0).min(1.0);
 
             let mut do_idle = false;
+            let mut do_travel = false;
             let mut choose_target = false;
 
             'activity: {
                 match &mut agent.activity {
+                    Activity::Idle(_)
+                        if agent.travel_to.map_or(false, |travel_to| {
+                            travel_to.xy().distance_squared(pos.0.xy())
+                                > (TRAVEL_DIST * 2.0).powf(2.0)
+                        }) =>
+                    {
+                        do_travel = true;
+                    }
                     Activity::Idle(bearing) => {
                         *bearing += Vec2::new(
                             thread_rng().gen::<f32>() - 0.5,
@@ -257,6 +267,30 @@ impl<'a> System<'a> for Sys {
                             choose_target = true;
                         }
                     },
+                    Activity::Travel { chaser } => {
+                        if let Some((bearing, speed)) = agent.travel_to.and_then(|travel_to| {
+                            chaser.chase(&*terrain, pos.0, vel.0, travel_to, TraversalConfig {
+                                node_tolerance,
+                                slow_factor,
+                                on_ground: physics_state.on_ground,
+                                min_tgt_dist: TRAVEL_DIST,
+                            })
+                        }) {
+                            inputs.move_dir = bearing.xy().try_normalized().unwrap_or(Vec2::zero())
+                                * speed
+                                * 0.65;
+                            inputs.jump.set_state(bearing.z > 1.5);
+                            inputs.swimup.set_state(bearing.z > 0.5);
+                            inputs.swimdown.set_state(bearing.z < 0.5);
+                        } else {
+                            do_idle = true;
+                        }
+
+                        // Sometimes try searching for new targets
+                        if thread_rng().gen::<f32>() < 0.1 {
+                            choose_target = true;
+                        }
+                    },
                     Activity::Follow { target, chaser } => {
                         if let (Some(tgt_pos), _tgt_stats) =
                             (positions.get(*target), stats.get(*target))
@@ -550,6 +584,10 @@ impl<'a> System<'a> for Sys {
 
             if do_idle {
                 agent.activity = Activity::Idle(Vec2::zero());
+            } else if do_travel {
+                agent.activity = Activity::Travel {
+                    chaser: Chaser::default(),
+                };
             }
 
             // Choose a new target to attack: only go out of our way to attack targets we
@@ -1,7 +1,8 @@
 use crate::{
     comp::{
         group, Beam, BeamSegment, Body, CharacterState, Damage, DamageSource, Energy, EnergySource,
-        HealthChange, HealthSource, Last, Loadout, Ori, Pos, Scale, Stats,
+        HealthChange, HealthSource, Last, Loadout, Ori, Player, Pos, PvpOptIn, PvpRules, Scale,
+        Stats,
     },
     event::{EventBus, ServerEvent},
     state::{DeltaTime, Time},
@@ -23,6 +24,7 @@ impl<'a> System<'a> for Sys {
         Read<'a, Time>,
         Read<'a, DeltaTime>,
         Read<'a, UidAllocator>,
+        Read<'a, PvpRules>,
         ReadStorage<'a, Uid>,
         ReadStorage<'a, Pos>,
         ReadStorage<'a, Last<Pos>>,
@@ -33,6 +35,8 @@ impl<'a> System<'a> for Sys {
         ReadStorage<'a, Loadout>,
         ReadStorage<'a, group::Group>,
         ReadStorage<'a, CharacterState>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, PvpOptIn>,
         WriteStorage<'a, Energy>,
         WriteStorage<'a, BeamSegment>,
         WriteStorage<'a, Beam>,
@@ -46,6 +50,7 @@ impl<'a> System<'a> for Sys {
             time,
             dt,
             uid_allocator,
+            pvp_rules,
             uids,
             positions,
             last_positions,
@@ -56,6 +61,8 @@ impl<'a> System<'a> for Sys {
             loadouts,
             groups,
             character_states,
+            players,
+            pvp_opt_ins,
             mut energies,
             mut beam_segments,
             mut beams,
@@ -168,7 +175,18 @@ impl<'a> System<'a> for Sys {
                     }
                     // Don't heal if outside group
                     // Don't damage in the same group
-                    let is_damage = !same_group && (beam_segment.damage > 0);
+                    let is_damage = !same_group
+                        && (beam_segment.damage > 0)
+                        && beam_owner.map_or(true, |owner| {
+                            pvp_rules.allows_damage(
+                                owner,
+                                b,
+                                &players,
+                                &pvp_opt_ins,
+                                &positions,
+                                &groups,
+                            )
+                        });
                     let is_heal = same_group && (beam_segment.heal > 0);
                     if !is_heal && !is_damage {
                         continue;
@@ -0,0 +1,154 @@
+use crate::{
+    comp::{Buffs, HealthChange, HealthSource, Stats},
+    event::{EventBus, ServerEvent},
+    span,
+    state::DeltaTime,
+    sync::Uid,
+};
+use specs::{Join, Read, ReadStorage, System, WriteStorage};
+use std::time::Duration;
+
+/// This system ticks buffs, removes the ones that ran out and applies the
+/// health changes of buffs like poison or regeneration
+pub struct Sys;
+impl<'a> System<'a> for Sys {
+    type SystemData = (
+        Read<'a, DeltaTime>,
+        Read<'a, EventBus<ServerEvent>>,
+        ReadStorage<'a, Uid>,
+        ReadStorage<'a, Stats>,
+        WriteStorage<'a, Buffs>,
+    );
+
+    fn run(&mut self, (dt, server_bus, uids, stats, mut buffs): Self::SystemData) {
+        span!(_guard, "run", "buff::Sys::run");
+        let mut server_emitter = server_bus.emitter();
+        let dt_duration = Duration::from_secs_f32(dt.0);
+
+        for (uid, stats, mut buffs) in (&uids, &stats, &mut buffs.restrict_mut()).join() {
+            // Only flag the buffs as modified if they are going to change, permanent
+            // buffs without a health change just stay as they are
+            let changes = buffs.get_unchecked().buffs.iter().any(|buff| {
+                stats.is_dead
+                    || buff.time.is_some()
+                    || buff.kind.health_rate(buff.data.strength).is_some()
+            });
+            if !changes {
+                continue;
+            }
+            let buffs = buffs.get_mut_unchecked();
+
+            // Dead entities lose all of their buffs
+            if stats.is_dead {
+                buffs.buffs.clear();
+                continue;
+            }
+
+            for buff in buffs.buffs.iter_mut() {
+                if let Some(rate) = buff.kind.health_rate(buff.data.strength) {
+                    buff.accumulated += rate * dt.0;
+                    // Only whole points of health can be applied
+                    let amount = buff.accumulated.trunc();
+                    if amount != 0.0 {
+                        buff.accumulated -= amount;
+                        server_emitter.emit(ServerEvent::Damage {
+                            uid: *uid,
+                            change: HealthChange {
+                                amount: amount as i32,
+                                cause: HealthSource::Buff {
+                                    owner: buff.source.owner(),
+                                },
+                            },
+                        });
+                    }
+                }
+
+                if let Some(time) = buff.time.as_mut() {
+                    *time = time.checked_sub(dt_duration).unwrap_or_default();
+                }
+            }
+
+            buffs
+                .buffs
+                .retain(|buff| buff.time.map_or(true, |time| time > Duration::default()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comp::{humanoid, Body, Buff, BuffData, BuffKind, BuffSource};
+    use specs::{Builder, RunNow, World, WorldExt};
+
+    fn setup() -> World {
+        let mut world = World::new();
+        world.register::<Uid>();
+        world.register::<Stats>();
+        world.register::<Buffs>();
+        world.insert(DeltaTime(0.5));
+        world.insert(EventBus::<ServerEvent>::default());
+        world
+    }
+
+    fn buff(kind: BuffKind, strength: f32, duration: Option<Duration>) -> Buff {
+        Buff::new(kind, BuffData { strength, duration }, BuffSource::Unknown)
+    }
+
+    #[test]
+    fn buffs_tick_and_expire() {
+        let mut world = setup();
+        let mut buffs = Buffs::default();
+        buffs.add(buff(BuffKind::Poisoned, 3.0, Some(Duration::from_secs(1))));
+        buffs.add(buff(BuffKind::Protected, 0.5, None));
+        let entity = world
+            .create_entity()
+            .with(Uid(1))
+            .with(Stats::new(
+                "Test".to_owned(),
+                Body::Humanoid(humanoid::Body::random()),
+            ))
+            .with(buffs)
+            .build();
+
+        // Half a second of poison at 3 per second only applies whole points
+        Sys.run_now(&world);
+        let damage = world
+            .read_resource::<EventBus<ServerEvent>>()
+            .recv_all()
+            .map(|event| match event {
+                ServerEvent::Damage { uid, change } => {
+                    assert_eq!(uid, Uid(1));
+                    change.amount
+                },
+                _ => panic!("Unexpected event"),
+            })
+            .sum::<i32>();
+        assert_eq!(damage, -1);
+        assert_eq!(
+            world
+                .read_storage::<Buffs>()
+                .get(entity)
+                .unwrap()
+                .buffs
+                .len(),
+            2
+        );
+
+        // The poison runs out after a second, the permanent buff stays
+        Sys.run_now(&world);
+        let damage = world
+            .read_resource::<EventBus<ServerEvent>>()
+            .recv_all()
+            .map(|event| match event {
+                ServerEvent::Damage { change, .. } => change.amount,
+                _ => panic!("Unexpected event"),
+            })
+            .sum::<i32>();
+        assert_eq!(damage, -2);
+        let storage = world.read_storage::<Buffs>();
+        let buffs = &storage.get(entity).unwrap().buffs;
+        assert_eq!(buffs.len(), 1);
+        assert_eq!(buffs[0].kind, BuffKind::Protected);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     comp::{
-        Attacking, Beam, Body, CharacterState, ControlAction, Controller, ControllerInputs, Energy,
-        Loadout, Mounting, Ori, PhysicsState, Pos, StateUpdate, Stats, Vel,
+        Attacking, Beam, Body, Buffs, CharacterState, ControlAction, Controller, ControllerInputs,
+        Energy, Loadout, Mounting, Ori, PhysicsState, Pos, StateUpdate, Stats, Vel,
     },
     event::{EventBus, LocalEvent, ServerEvent},
     metrics::SysMetrics,
@@ -9,6 +9,7 @@ use crate::{
     state::DeltaTime,
     states,
     sync::{Uid, UidAllocator},
+    weather::WeatherGrid,
 };
 
 use specs::{
@@ -64,7 +65,9 @@ pub struct JoinData<'a> {
     pub body: &'a Body,
     pub physics: &'a PhysicsState,
     pub attacking: Option<&'a Attacking>,
+    pub buffs: Option<&'a Buffs>,
     pub updater: &'a LazyUpdate,
+    pub weather: &'a WeatherGrid,
 }
 
 type RestrictedMut<'a, C> = PairedStorage<
@@ -90,6 +93,7 @@ pub type JoinTuple<'a> = (
     &'a PhysicsState,
     Option<&'a Attacking>,
     Option<&'a Beam>,
+    Option<&'a Buffs>,
 );
 
 fn incorporate_update(tuple: &mut JoinTuple, state_update: StateUpdate) {
@@ -111,7 +115,12 @@ fn incorporate_update(tuple: &mut JoinTuple, state_update: StateUpdate) {
 }
 
 impl<'a> JoinData<'a> {
-    fn new(j: &'a JoinTuple<'a>, updater: &'a LazyUpdate, dt: &'a DeltaTime) -> Self {
+    fn new(
+        j: &'a JoinTuple<'a>,
+        updater: &'a LazyUpdate,
+        dt: &'a DeltaTime,
+        weather: &'a WeatherGrid,
+    ) -> Self {
         Self {
             entity: j.0,
             uid: j.1,
@@ -127,8 +136,10 @@ impl<'a> JoinData<'a> {
             body: j.10,
             physics: j.11,
             attacking: j.12,
+            buffs: j.14,
             updater,
             dt,
+            weather,
         }
     }
 }
@@ -147,6 +158,7 @@ impl<'a> System<'a> for Sys {
         Read<'a, EventBus<LocalEvent>>,
         Read<'a, DeltaTime>,
         Read<'a, LazyUpdate>,
+        Read<'a, WeatherGrid>,
         ReadExpect<'a, SysMetrics>,
         WriteStorage<'a, CharacterState>,
         WriteStorage<'a, Pos>,
@@ -160,6 +172,7 @@ impl<'a> System<'a> for Sys {
         ReadStorage<'a, PhysicsState>,
         ReadStorage<'a, Attacking>,
         ReadStorage<'a, Beam>,
+        ReadStorage<'a, Buffs>,
         ReadStorage<'a, Uid>,
         ReadStorage<'a, Mounting>,
     );
@@ -174,6 +187,7 @@ impl<'a> System<'a> for Sys {
             local_bus,
             dt,
             updater,
+            weather,
             sys_metrics,
             mut character_states,
             mut positions,
@@ -187,6 +201,7 @@ impl<'a> System<'a> for Sys {
             physics_states,
             attacking_storage,
             beam_storage,
+            buffs,
             uids,
             mountings,
         ): Self::SystemData,
@@ -211,6 +226,7 @@ impl<'a> System<'a> for Sys {
             &physics_states,
             attacking_storage.maybe(),
             beam_storage.maybe(),
+            buffs.maybe(),
         )
             .join()
         {
@@ -231,7 +247,7 @@ impl<'a> System<'a> for Sys {
 
             let actions = std::mem::replace(&mut tuple.8.actions, Vec::new());
             for action in actions {
-                let j = JoinData::new(&tuple, &updater, &dt);
+                let j = JoinData::new(&tuple, &updater, &dt, &weather);
                 let mut state_update = match j.character {
                     CharacterState::Idle => states::idle::Data.handle_event(&j, action),
                     CharacterState::Climb => states::climb::Data.handle_event(&j, action),
@@ -248,6 +264,7 @@ impl<'a> System<'a> for Sys {
                     CharacterState::Sneak => {
                         states::sneak::Data::handle_event(&states::sneak::Data, &j, action)
                     },
+                    CharacterState::Crafting(data) => data.handle_event(&j, action),
                     CharacterState::BasicBlock => {
                         states::basic_block::Data.handle_event(&j, action)
                     },
@@ -272,7 +289,7 @@ impl<'a> System<'a> for Sys {
                 incorporate_update(&mut tuple, state_update);
             }
 
-            let j = JoinData::new(&tuple, &updater, &dt);
+            let j = JoinData::new(&tuple, &updater, &dt, &weather);
 
             let mut state_update = match j.character {
                 CharacterState::Idle => states::idle::Data.behavior(&j),
@@ -282,6 +299,7 @@ impl<'a> System<'a> for Sys {
                 CharacterState::Sit => states::sit::Data::behavior(&states::sit::Data, &j),
                 CharacterState::Dance => states::dance::Data::behavior(&states::dance::Data, &j),
                 CharacterState::Sneak => states::sneak::Data::behavior(&states::sneak::Data, &j),
+                CharacterState::Crafting(data) => data.behavior(&j),
                 CharacterState::BasicBlock => states::basic_block::Data.behavior(&j),
                 CharacterState::Roll(data) => data.behavior(&j),
                 CharacterState::Wielding => states::wielding::Data.behavior(&j),
@@ -1,7 +1,7 @@
 use crate::{
     comp::{
         group, Attacking, Body, CharacterState, Damage, DamageSource, HealthChange, HealthSource,
-        Loadout, Ori, Pos, Scale, Stats,
+        Loadout, Ori, Player, Pos, PvpOptIn, PvpRules, Scale, Stats,
     },
     event::{EventBus, LocalEvent, ServerEvent},
     metrics::SysMetrics,
@@ -25,6 +25,7 @@ impl<'a> System<'a> for Sys {
         Read<'a, EventBus<ServerEvent>>,
         Read<'a, EventBus<LocalEvent>>,
         ReadExpect<'a, SysMetrics>,
+        Read<'a, PvpRules>,
         ReadStorage<'a, Uid>,
         ReadStorage<'a, Pos>,
         ReadStorage<'a, Ori>,
@@ -34,6 +35,8 @@ impl<'a> System<'a> for Sys {
         ReadStorage<'a, Loadout>,
         ReadStorage<'a, group::Group>,
         ReadStorage<'a, CharacterState>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, PvpOptIn>,
         WriteStorage<'a, Attacking>,
     );
 
@@ -44,6 +47,7 @@ impl<'a> System<'a> for Sys {
             server_bus,
             local_bus,
             sys_metrics,
+            pvp_rules,
             uids,
             positions,
             orientations,
@@ -53,6 +57,8 @@ impl<'a> System<'a> for Sys {
             loadouts,
             groups,
             character_states,
+            players,
+            pvp_opt_ins,
             mut attacking_storage,
         ): Self::SystemData,
     ) {
@@ -113,7 +119,16 @@ impl<'a> System<'a> for Sys {
                         .unwrap_or(false);
                     // Don't heal if outside group
                     // Don't damage in the same group
-                    let is_damage = !same_group && (attack.base_damage > 0);
+                    let is_damage = !same_group
+                        && (attack.base_damage > 0)
+                        && pvp_rules.allows_damage(
+                            entity,
+                            b,
+                            &players,
+                            &pvp_opt_ins,
+                            &positions,
+                            &groups,
+                        );
                     let is_heal = same_group && (attack.base_heal > 0);
                     if !is_heal && !is_damage {
                         continue;
@@ -1,7 +1,8 @@
 use crate::{
     comp::{
-        projectile, Damage, DamageSource, Energy, EnergySource, Group, HealthChange, HealthSource,
-        Loadout, Ori, PhysicsState, Pos, Projectile, Vel,
+        projectile, Buff, BuffChange, BuffSource, Damage, DamageSource, Energy, EnergySource,
+        Group, HealthChange, HealthSource, Loadout, Ori, PhysicsState, Player, Pos, Projectile,
+        PvpOptIn, PvpRules, Vel,
     },
     event::{EventBus, LocalEvent, ServerEvent},
     metrics::SysMetrics,
@@ -27,6 +28,7 @@ impl<'a> System<'a> for Sys {
         Read<'a, EventBus<LocalEvent>>,
         Read<'a, EventBus<ServerEvent>>,
         ReadExpect<'a, SysMetrics>,
+        Read<'a, PvpRules>,
         ReadStorage<'a, Pos>,
         ReadStorage<'a, PhysicsState>,
         ReadStorage<'a, Vel>,
@@ -35,6 +37,8 @@ impl<'a> System<'a> for Sys {
         WriteStorage<'a, Energy>,
         ReadStorage<'a, Loadout>,
         ReadStorage<'a, Group>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, PvpOptIn>,
     );
 
     fn run(
@@ -46,6 +50,7 @@ impl<'a> System<'a> for Sys {
             local_bus,
             server_bus,
             sys_metrics,
+            pvp_rules,
             positions,
             physics_states,
             velocities,
@@ -54,6 +59,8 @@ impl<'a> System<'a> for Sys {
             mut energies,
             loadouts,
             groups,
+            players,
+            pvp_opt_ins,
         ): Self::SystemData,
     ) {
         let start_time = std::time::Instant::now();
@@ -109,8 +116,22 @@ impl<'a> System<'a> for Sys {
                                 damage.modify_damage(false, loadout);
                             }
 
+                            let owner_entity =
+                                uid_allocator.retrieve_entity_internal(owner_uid.into());
+                            let pvp_allowed = match (owner_entity, other_entity) {
+                                (Some(owner), Some(other)) => pvp_rules.allows_damage(
+                                    owner,
+                                    other,
+                                    &players,
+                                    &pvp_opt_ins,
+                                    &positions,
+                                    &groups,
+                                ),
+                                _ => true,
+                            };
+
                             if other != owner_uid {
-                                if damage.healthchange < 0.0 {
+                                if damage.healthchange < 0.0 && pvp_allowed {
                                     server_emitter.emit(ServerEvent::Damage {
                                         uid: other,
                                         change: HealthChange {
@@ -166,6 +187,34 @@ impl<'a> System<'a> for Sys {
                             entity,
                             cause: HealthSource::World,
                         }),
+                        projectile::Effect::Buff { kind, data } => {
+                            let owner_entity = projectile
+                                .owner
+                                .and_then(|o| uid_allocator.retrieve_entity_internal(o.into()));
+                            let other_entity = uid_allocator.retrieve_entity_internal(other.into());
+                            // Harmful buffs follow the same PvP rules as damage
+                            let allowed = kind.is_buff()
+                                || match (owner_entity, other_entity) {
+                                    (Some(owner), Some(other)) => pvp_rules.allows_damage(
+                                        owner,
+                                        other,
+                                        &players,
+                                        &pvp_opt_ins,
+                                        &positions,
+                                        &groups,
+                                    ),
+                                    _ => true,
+                                };
+                            if allowed {
+                                let source = projectile
+                                    .owner
+                                    .map_or(BuffSource::Unknown, |by| BuffSource::Character { by });
+                                server_emitter.emit(ServerEvent::Buff {
+                                    uid: other,
+                                    buff_change: BuffChange::Add(Buff::new(kind, data, source)),
+                                });
+                            }
+                        },
                         projectile::Effect::Possess => {
                             if other != projectile.owner.unwrap() {
                                 if let Some(owner) = projectile.owner {
@@ -1,7 +1,8 @@
 use crate::{
     comp::{
         group, Body, CharacterState, Damage, DamageSource, HealthChange, HealthSource, Last,
-        Loadout, Ori, PhysicsState, Pos, Scale, Shockwave, ShockwaveHitEntities, Stats,
+        Loadout, Ori, PhysicsState, Player, Pos, PvpOptIn, PvpRules, Scale, Shockwave,
+        ShockwaveHitEntities, Stats,
     },
     event::{EventBus, LocalEvent, ServerEvent},
     state::{DeltaTime, Time},
@@ -25,6 +26,7 @@ impl<'a> System<'a> for Sys {
         Read<'a, Time>,
         Read<'a, DeltaTime>,
         Read<'a, UidAllocator>,
+        Read<'a, PvpRules>,
         ReadStorage<'a, Uid>,
         ReadStorage<'a, Pos>,
         ReadStorage<'a, Last<Pos>>,
@@ -36,6 +38,8 @@ impl<'a> System<'a> for Sys {
         ReadStorage<'a, group::Group>,
         ReadStorage<'a, CharacterState>,
         ReadStorage<'a, PhysicsState>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, PvpOptIn>,
         WriteStorage<'a, Shockwave>,
         WriteStorage<'a, ShockwaveHitEntities>,
     );
@@ -49,6 +53,7 @@ impl<'a> System<'a> for Sys {
             time,
             dt,
             uid_allocator,
+            pvp_rules,
             uids,
             positions,
             last_positions,
@@ -60,6 +65,8 @@ impl<'a> System<'a> for Sys {
             groups,
             character_states,
             physics_states,
+            players,
+            pvp_opt_ins,
             mut shockwaves,
             mut shockwave_hit_lists,
         ): Self::SystemData,
@@ -117,12 +124,13 @@ impl<'a> System<'a> for Sys {
                 end: frame_end_dist,
             };
 
+            let shockwave_owner = shockwave
+                .owner
+                .and_then(|uid| uid_allocator.retrieve_entity_internal(uid.into()));
+
             // Group to ignore collisions with
             // Might make this more nuanced if shockwaves are used for non damage effects
-            let group = shockwave
-                .owner
-                .and_then(|uid| uid_allocator.retrieve_entity_internal(uid.into()))
-                .and_then(|e| groups.get(e));
+            let group = shockwave_owner.and_then(|e| groups.get(e));
 
             // Go through all other effectable entities
             for (
@@ -190,7 +198,10 @@ impl<'a> System<'a> for Sys {
                     }
                     && (pos_b_ground - pos.0).angle_between(pos_b.0 - pos.0) < max_angle
                     && (!shockwave.requires_ground || physics_state_b.on_ground)
-                    && !same_group;
+                    && !same_group
+                    && shockwave_owner.map_or(true, |owner| {
+                        pvp_rules.allows_damage(owner, b, &players, &pvp_opt_ins, &positions, &groups)
+                    });
 
                 if hit {
                     let mut damage = Damage {
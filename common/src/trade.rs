@@ -0,0 +1,686 @@
+//! Trading between two parties. Each party offers items from its inventory,
+//! and the trade only goes through once both parties accepted the offers
+//! and then accepted them again after reviewing them. Merchants price the
+//! items they trade from the economy of their site. Players pick what they
+//! want to buy from the inventory of a merchant, and the merchant pays coins
+//! for what it buys.
+
+use crate::{
+    comp::{
+        item::{Item, Quality},
+        Inventory,
+    },
+    sync::Uid,
+};
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Item that trades are paid with
+pub const COINS: &str = "common.items.utility.coins";
+/// Coins an item with a value of 1 is sold for
+const COINS_PER_VALUE: f32 = 5.0;
+/// Share of the selling price that merchants pay for the items they buy
+const BUY_RATIO: f32 = 0.7;
+/// Parties have to stay this close to each other to trade (squared)
+pub const MAX_TRADE_RANGE_SQR: f32 = 100.0;
+/// Trades nobody acted on for this long are cancelled
+pub const TRADE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Items that stand for the goods of an economy
+const GOOD_ITEMS: &[(Good, &str)] = &[
+    (Good::Food, "common.items.food.apple"),
+    (Good::Food, "common.items.food.cheese"),
+    (Good::Food, "common.items.food.mushroom"),
+    (Good::Game, "common.items.crafting_ing.leather_scraps"),
+    (Good::Wood, "common.items.crafting_ing.twigs"),
+    (Good::Stone, "common.items.crafting_ing.stones"),
+];
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum Good {
+    Wheat = 0,
+    Flour = 1,
+    Meat = 2,
+    Fish = 3,
+    Game = 4,
+    Food = 5,
+    Logs = 6,
+    Wood = 7,
+    Rock = 8,
+    Stone = 9,
+}
+
+impl Default for Good {
+    fn default() -> Self {
+        Good::Rock // Arbitrary
+    }
+}
+
+impl Good {
+    pub fn list() -> &'static [Self] {
+        use Good::*;
+        static GOODS: [Good; 10] = [
+            Wheat, Flour, Meat, Fish, Game, Food, Logs, Wood, Rock, Stone,
+        ];
+
+        &GOODS
+    }
+
+    pub fn decay_rate(&self) -> f32 {
+        match self {
+            Good::Food => 0.2,
+            Good::Wheat => 0.1,
+            Good::Meat => 0.25,
+            Good::Fish => 0.2,
+            _ => 0.0,
+        }
+    }
+
+    /// The good an item stands for, if any
+    pub fn of_item(item: &Item) -> Option<Self> {
+        GOOD_ITEMS
+            .iter()
+            .find(|(_, asset)| *asset == item.item_definition_id())
+            .map(|(good, _)| *good)
+    }
+
+    /// Assets of the items that stand for this good
+    pub fn items(self) -> impl Iterator<Item = &'static str> {
+        GOOD_ITEMS
+            .iter()
+            .filter(move |(good, _)| *good == self)
+            .map(|(_, asset)| *asset)
+    }
+}
+
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub struct TradeId(u64);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TradePhase {
+    /// The offers can be changed
+    Mutate,
+    /// Both parties accepted the offers, which can no longer be changed
+    Review,
+    /// Both parties accepted the reviewed offers, and the items can be
+    /// exchanged
+    Complete,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TradeAction {
+    /// Offers `quantity` more of the item in inventory slot `slot`. If not
+    /// `ours`, the item is asked for from the inventory of the counterparty
+    /// instead, which only merchants allow.
+    AddItem {
+        slot: usize,
+        quantity: u32,
+        ours: bool,
+    },
+    /// Offers or asks for `quantity` less of the item in inventory slot `slot`
+    RemoveItem {
+        slot: usize,
+        quantity: u32,
+        ours: bool,
+    },
+    /// Accepts the trade as it is in the given phase. Naming the phase keeps
+    /// an accept that was sent before the phase changed from counting for the
+    /// next one.
+    Accept(TradePhase),
+    Decline,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TradeResult {
+    Completed,
+    Declined,
+    NotEnoughSpace,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PendingTrade {
+    /// The party that started the trade and the party it was started with
+    pub parties: [Uid; 2],
+    /// Quantity each party offers, by the inventory slot of the item
+    pub offers: [HashMap<usize, u32>; 2],
+    /// Item definition id of each offered item, by its inventory slot. The
+    /// trade is only performed if the slots still hold these items.
+    pub offered_items: [HashMap<usize, String>; 2],
+    pub phase: TradePhase,
+    pub accept_flags: [bool; 2],
+}
+
+impl PendingTrade {
+    pub fn new(party: Uid, counterparty: Uid) -> Self {
+        Self {
+            parties: [party, counterparty],
+            offers: [HashMap::new(), HashMap::new()],
+            offered_items: [HashMap::new(), HashMap::new()],
+            phase: TradePhase::Mutate,
+            accept_flags: [false, false],
+        }
+    }
+
+    /// Index of `party` in `parties`
+    pub fn which_party(&self, party: Uid) -> Option<usize> {
+        self.parties.iter().position(|p| *p == party)
+    }
+
+    /// Whether both parties accepted the reviewed offers
+    pub fn is_complete(&self) -> bool { self.phase == TradePhase::Complete }
+
+    /// Sets the quantity party `who` offers of the item in `slot` of its
+    /// `inventory`, taking back the accepts if the offer changed
+    pub fn set_offer(&mut self, who: usize, slot: usize, quantity: u32, inventory: &Inventory) {
+        let old = self.offers[who].get(&slot).copied().unwrap_or(0);
+        if self.phase != TradePhase::Mutate || old == quantity {
+            return;
+        }
+        match inventory.get(slot) {
+            Some(item) if quantity > 0 => {
+                self.offers[who].insert(slot, quantity);
+                self.offered_items[who].insert(slot, item.item_definition_id().to_owned());
+            },
+            _ => {
+                self.offers[who].remove(&slot);
+                self.offered_items[who].remove(&slot);
+            },
+        }
+        self.accept_flags = [false, false];
+    }
+
+    /// Applies an action of party `who`, with `inventories` being the
+    /// inventories of the parties. Actions that don't fit the phase of the
+    /// trade or the inventories are ignored. `Decline` has to be handled by
+    /// removing the trade.
+    pub fn process_action(
+        &mut self,
+        who: usize,
+        action: TradeAction,
+        inventories: [&Inventory; 2],
+    ) {
+        let offerer = |ours: bool| if ours { who } else { 1 - who };
+        match action {
+            TradeAction::AddItem {
+                slot,
+                quantity,
+                ours,
+            } => {
+                let offerer = offerer(ours);
+                if let Some(item) = inventories[offerer].get(slot) {
+                    let old = self.offers[offerer].get(&slot).copied().unwrap_or(0);
+                    self.set_offer(
+                        offerer,
+                        slot,
+                        old.saturating_add(quantity).min(item.amount()),
+                        inventories[offerer],
+                    );
+                }
+            },
+            TradeAction::RemoveItem {
+                slot,
+                quantity,
+                ours,
+            } => {
+                let offerer = offerer(ours);
+                let old = self.offers[offerer].get(&slot).copied().unwrap_or(0);
+                self.set_offer(
+                    offerer,
+                    slot,
+                    old.saturating_sub(quantity),
+                    inventories[offerer],
+                );
+            },
+            TradeAction::Accept(phase) => {
+                if self.phase == phase && phase != TradePhase::Complete {
+                    self.accept_flags[who] = true;
+                }
+                if self.accept_flags == [true, true] {
+                    self.phase = match self.phase {
+                        TradePhase::Mutate => TradePhase::Review,
+                        TradePhase::Review | TradePhase::Complete => TradePhase::Complete,
+                    };
+                    self.accept_flags = [false, false];
+                }
+            },
+            TradeAction::Decline => {},
+        }
+    }
+}
+
+/// All trades that are going on. A party can only be in one trade at a time.
+#[derive(Default)]
+pub struct Trades {
+    next_id: TradeId,
+    pub trades: HashMap<TradeId, PendingTrade>,
+    entity_trades: HashMap<Uid, TradeId>,
+    /// When a party last acted on each trade
+    last_actions: HashMap<TradeId, Instant>,
+}
+
+impl Trades {
+    /// Starts a trade between two parties that aren't trading yet
+    pub fn begin_trade(&mut self, party: Uid, counterparty: Uid) -> Option<TradeId> {
+        if party == counterparty || self.in_trade(party) || self.in_trade(counterparty) {
+            return None;
+        }
+        let id = self.next_id;
+        self.next_id = TradeId(id.0.wrapping_add(1));
+        self.trades
+            .insert(id, PendingTrade::new(party, counterparty));
+        self.entity_trades.insert(party, id);
+        self.entity_trades.insert(counterparty, id);
+        self.last_actions.insert(id, Instant::now());
+        Some(id)
+    }
+
+    /// Applies an action of `who` to the trade `id`, if it is one of its
+    /// parties. `inventories` are the inventories of the parties of the trade.
+    pub fn process_trade_action(
+        &mut self,
+        id: TradeId,
+        who: Uid,
+        action: TradeAction,
+        inventories: [&Inventory; 2],
+    ) {
+        if let Some(trade) = self.trades.get_mut(&id) {
+            if let Some(party) = trade.which_party(who) {
+                trade.process_action(party, action, inventories);
+                self.last_actions.insert(id, Instant::now());
+            } else {
+                warn!(?who, ?id, "Party tried to change a trade it is not part of");
+            }
+        }
+    }
+
+    /// Ends the trade `id` because `who` declined it, returning the other
+    /// party
+    pub fn decline_trade(&mut self, id: TradeId, who: Uid) -> Option<Uid> {
+        let trade = self.trades.get(&id)?;
+        let party = trade.which_party(who)?;
+        let counterparty = trade.parties[1 - party];
+        self.remove(id);
+        Some(counterparty)
+    }
+
+    /// Ends the trade `id`, returning it
+    pub fn remove(&mut self, id: TradeId) -> Option<PendingTrade> {
+        let trade = self.trades.remove(&id)?;
+        self.last_actions.remove(&id);
+        for party in trade.parties.iter() {
+            self.entity_trades.remove(party);
+        }
+        Some(trade)
+    }
+
+    /// The trade a party is in
+    pub fn trade_of(&self, party: Uid) -> Option<TradeId> {
+        self.entity_trades.get(&party).copied()
+    }
+
+    pub fn in_trade(&self, party: Uid) -> bool { self.entity_trades.contains_key(&party) }
+
+    /// Trades none of the parties acted on for [`TRADE_TIMEOUT`] at `now`
+    pub fn stale_trades(&self, now: Instant) -> Vec<TradeId> {
+        self.last_actions
+            .iter()
+            .filter(|(_, last_action)| now.saturating_duration_since(**last_action) > TRADE_TIMEOUT)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Whether a party is in a trade whose offers can no longer change, in
+    /// which case its inventory must not change either
+    pub fn in_immutable_trade(&self, party: Uid) -> bool {
+        self.trade_of(party)
+            .and_then(|id| self.trades.get(&id))
+            .map_or(false, |trade| trade.phase != TradePhase::Mutate)
+    }
+}
+
+/// Index of a site in the world
+pub type SiteId = u64;
+
+/// Snapshot of the economy of a site that is sent to clients
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EconomyInfo {
+    pub id: SiteId,
+    pub population: u32,
+    /// Amount of each good the site has in stock
+    pub stock: HashMap<Good, f32>,
+    /// Value of each good, for the goods that have one
+    pub values: HashMap<Good, f32>,
+}
+
+/// Prices a merchant trades at, from the values of the goods in the economy
+/// of its site
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SitePrices {
+    pub values: HashMap<Good, f32>,
+}
+
+impl SitePrices {
+    /// Coins the merchant sells one of the item for
+    pub fn sell_price(&self, item: &Item) -> f32 {
+        if item.item_definition_id() == COINS {
+            return 1.0;
+        }
+        let value = match Good::of_item(item) {
+            Some(good) => self.values.get(&good).copied().unwrap_or(1.0),
+            // Items that aren't goods of the economy are priced by how rare they are
+            None => match item.quality() {
+                Quality::Low => 1.0,
+                Quality::Common => 2.0,
+                Quality::Moderate => 4.0,
+                Quality::High => 8.0,
+                Quality::Epic => 16.0,
+                Quality::Legendary => 32.0,
+                Quality::Artifact => 64.0,
+                Quality::Debug => 0.0,
+            },
+        };
+        value * COINS_PER_VALUE
+    }
+
+    /// Coins the merchant pays for one of the item
+    pub fn buy_price(&self, item: &Item) -> f32 {
+        if item.item_definition_id() == COINS {
+            1.0
+        } else {
+            self.sell_price(item) * BUY_RATIO
+        }
+    }
+
+    /// Value of what party `who` offers, in coins
+    fn offer_value(
+        trade: &PendingTrade,
+        inventory: &Inventory,
+        who: usize,
+        price: impl Fn(&Item) -> f32,
+    ) -> f32 {
+        trade.offers[who]
+            .iter()
+            .filter_map(|(slot, quantity)| {
+                inventory
+                    .get(*slot)
+                    .map(|item| price(item) * *quantity as f32)
+            })
+            .sum()
+    }
+
+    /// Coins the merchant, party `merchant` of the trade, owes the other party
+    /// for the items it buys, after subtracting the price of the items it
+    /// sells. Fractions of coins are kept by the merchant.
+    pub fn coins_owed(
+        &self,
+        trade: &PendingTrade,
+        inventories: [&Inventory; 2],
+        merchant: usize,
+    ) -> u32 {
+        let received = Self::offer_value(trade, inventories[1 - merchant], 1 - merchant, |item| {
+            self.buy_price(item)
+        });
+        let given = Self::offer_value(trade, inventories[merchant], merchant, |item| {
+            if item.item_definition_id() == COINS {
+                0.0
+            } else {
+                self.sell_price(item)
+            }
+        });
+        (received - given).max(0.0).floor() as u32
+    }
+
+    /// Whether the merchant, party `merchant` of the trade, gets at least as
+    /// much as it gives. The merchant has to give something, so that it
+    /// doesn't accept items for nothing.
+    pub fn is_profitable(
+        &self,
+        trade: &PendingTrade,
+        inventories: [&Inventory; 2],
+        merchant: usize,
+    ) -> bool {
+        let given = Self::offer_value(trade, inventories[merchant], merchant, |item| {
+            self.sell_price(item)
+        });
+        let received = Self::offer_value(trade, inventories[1 - merchant], 1 - merchant, |item| {
+            self.buy_price(item)
+        });
+        !trade.offers[merchant].is_empty() && given > 0.0 && received >= given
+    }
+}
+
+/// What the merchant of a site trades, from the economy of the site
+#[derive(Clone, Debug, Default)]
+pub struct TradingInformation {
+    pub prices: SitePrices,
+    /// Amount of each good the merchant has for sale
+    pub stock: HashMap<Good, u32>,
+    pub coins: u32,
+}
+
+impl TradingInformation {
+    /// The items the merchant starts out with
+    pub fn items(&self) -> Vec<Item> {
+        let mut items = Vec::new();
+        for (good, amount) in self.stock.iter() {
+            let assets = good.items().collect::<Vec<_>>();
+            for (i, asset) in assets.iter().enumerate() {
+                // Split the stock evenly between the items of the good
+                let amount = amount / assets.len() as u32
+                    + if (i as u32) < amount % assets.len() as u32 {
+                        1
+                    } else {
+                        0
+                    };
+                if amount > 0 {
+                    let mut item = Item::new_from_asset_expect(asset);
+                    if item.set_amount(amount).is_ok() {
+                        items.push(item);
+                    }
+                }
+            }
+        }
+        if self.coins > 0 {
+            let mut coins = Item::new_from_asset_expect(COINS);
+            if coins.set_amount(self.coins).is_ok() {
+                items.push(coins);
+            }
+        }
+        items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(asset: &str, amount: u32) -> Item {
+        let mut item = Item::new_from_asset_expect(asset);
+        item.set_amount(amount).unwrap();
+        item
+    }
+
+    fn inventory() -> Inventory {
+        let mut inventory = Inventory::new_empty();
+        inventory.push(item("common.items.food.apple", 5));
+        inventory
+    }
+
+    /// A merchant with 10 apples in slot 0 and 100 coins in slot 1
+    fn merchant_inventory() -> Inventory {
+        let mut inventory = Inventory::new_empty();
+        inventory.push(item("common.items.food.apple", 10));
+        inventory.push(item(COINS, 100));
+        inventory
+    }
+
+    /// Apples are sold for 10 coins and bought for 7
+    fn prices() -> SitePrices {
+        let mut values = HashMap::new();
+        values.insert(Good::Food, 2.0);
+        SitePrices { values }
+    }
+
+    fn add(slot: usize, quantity: u32) -> TradeAction {
+        TradeAction::AddItem {
+            slot,
+            quantity,
+            ours: true,
+        }
+    }
+
+    fn ask(slot: usize, quantity: u32) -> TradeAction {
+        TradeAction::AddItem {
+            slot,
+            quantity,
+            ours: false,
+        }
+    }
+
+    #[test]
+    fn test_trade_phases() {
+        let inv = inventory();
+        let invs = [&inv, &inv];
+        let mut trade = PendingTrade::new(Uid(1), Uid(2));
+        trade.process_action(0, add(0, 10), invs);
+        // Offers are limited to what is in the inventory
+        assert_eq!(trade.offers[0].get(&0), Some(&5));
+        // Along with the item they are of
+        assert_eq!(
+            trade.offered_items[0].get(&0).map(String::as_str),
+            Some("common.items.food.apple")
+        );
+        // Empty slots can't be offered
+        trade.process_action(1, add(3, 1), invs);
+        assert!(trade.offers[1].is_empty());
+
+        trade.process_action(0, TradeAction::Accept(TradePhase::Mutate), invs);
+        // Changing the offer takes back the accepts
+        let remove = TradeAction::RemoveItem {
+            slot: 0,
+            quantity: 2,
+            ours: true,
+        };
+        trade.process_action(0, remove, invs);
+        assert_eq!(trade.offers[0].get(&0), Some(&3));
+        assert_eq!(trade.accept_flags, [false, false]);
+
+        trade.process_action(0, TradeAction::Accept(TradePhase::Mutate), invs);
+        trade.process_action(1, TradeAction::Accept(TradePhase::Mutate), invs);
+        assert_eq!(trade.phase, TradePhase::Review);
+
+        // Offers can't change while reviewing, and old accepts don't count
+        trade.process_action(0, add(0, 1), invs);
+        assert_eq!(trade.offers[0].get(&0), Some(&3));
+        trade.process_action(0, TradeAction::Accept(TradePhase::Mutate), invs);
+        assert_eq!(trade.accept_flags, [false, false]);
+
+        trade.process_action(0, TradeAction::Accept(TradePhase::Review), invs);
+        assert!(!trade.is_complete());
+        trade.process_action(1, TradeAction::Accept(TradePhase::Review), invs);
+        assert!(trade.is_complete());
+    }
+
+    #[test]
+    fn test_asking_for_items() {
+        let player = inventory();
+        let merchant = merchant_inventory();
+        let mut trade = PendingTrade::new(Uid(1), Uid(2));
+        // The player asks for items from the inventory of the merchant
+        trade.process_action(0, ask(0, 20), [&player, &merchant]);
+        assert!(trade.offers[0].is_empty());
+        assert_eq!(trade.offers[1].get(&0), Some(&10));
+        let remove = TradeAction::RemoveItem {
+            slot: 0,
+            quantity: 4,
+            ours: false,
+        };
+        trade.process_action(0, remove, [&player, &merchant]);
+        assert_eq!(trade.offers[1].get(&0), Some(&6));
+    }
+
+    #[test]
+    fn test_buying() {
+        let mut player = Inventory::new_empty();
+        player.push(item(COINS, 25));
+        let merchant = merchant_inventory();
+        let invs = [&player, &merchant];
+        let prices = prices();
+        let mut trade = PendingTrade::new(Uid(1), Uid(2));
+
+        trade.process_action(0, ask(0, 2), invs);
+        assert!(!prices.is_profitable(&trade, invs, 1));
+        trade.set_offer(0, 0, 15, &player);
+        assert!(!prices.is_profitable(&trade, invs, 1));
+        trade.set_offer(0, 0, 20, &player);
+        assert!(prices.is_profitable(&trade, invs, 1));
+        assert_eq!(prices.coins_owed(&trade, invs, 1), 0);
+        // Paying too much is answered with change
+        trade.set_offer(0, 0, 25, &player);
+        assert_eq!(prices.coins_owed(&trade, invs, 1), 5);
+    }
+
+    #[test]
+    fn test_selling() {
+        let player = inventory();
+        let merchant = merchant_inventory();
+        let invs = [&player, &merchant];
+        let prices = prices();
+        let mut trade = PendingTrade::new(Uid(1), Uid(2));
+
+        // Nothing is traded
+        assert!(!prices.is_profitable(&trade, invs, 1));
+
+        trade.process_action(0, add(0, 5), invs);
+        // The merchant doesn't take the items without paying for them
+        assert!(!prices.is_profitable(&trade, invs, 1));
+        let owed = prices.coins_owed(&trade, invs, 1);
+        assert_eq!(owed, 35);
+        trade.set_offer(1, 1, owed, &merchant);
+        assert!(prices.is_profitable(&trade, invs, 1));
+        // But doesn't overpay either
+        trade.set_offer(1, 1, owed + 1, &merchant);
+        assert!(!prices.is_profitable(&trade, invs, 1));
+    }
+
+    #[test]
+    fn test_trades() {
+        let inv = inventory();
+        let mut trades = Trades::default();
+        let id = trades.begin_trade(Uid(1), Uid(2)).unwrap();
+        // Parties can only be in one trade
+        assert!(trades.begin_trade(Uid(2), Uid(3)).is_none());
+        // Only parties can change the trade
+        trades.process_trade_action(id, Uid(3), add(0, 1), [&inv, &inv]);
+        assert!(
+            trades.trades[&id]
+                .offers
+                .iter()
+                .all(|offer| offer.is_empty())
+        );
+
+        assert_eq!(trades.decline_trade(id, Uid(2)), Some(Uid(1)));
+        assert!(!trades.in_trade(Uid(1)) && !trades.in_trade(Uid(2)));
+    }
+
+    #[test]
+    fn test_stale_trades() {
+        let inv = inventory();
+        let mut trades = Trades::default();
+        let id = trades.begin_trade(Uid(1), Uid(2)).unwrap();
+        let later = Instant::now() + TRADE_TIMEOUT * 2;
+        assert!(trades.stale_trades(Instant::now()).is_empty());
+        assert_eq!(trades.stale_trades(later), vec![id]);
+
+        // Acting on the trade keeps it alive, a trade that ended isn't stale
+        trades.process_trade_action(id, Uid(1), add(0, 1), [&inv, &inv]);
+        assert!(trades.stale_trades(Instant::now()).is_empty());
+        trades.remove(id);
+        assert!(trades.stale_trades(later).is_empty());
+    }
+}
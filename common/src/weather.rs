@@ -0,0 +1,142 @@
+use crate::{terrain::TerrainChunkSize, vol::RectVolSize};
+use serde::{Deserialize, Serialize};
+use vek::*;
+
+/// Size of a weather cell in chunks. Weather is simulated by the server at
+/// this resolution and interpolated in between.
+pub const CELL_SIZE: u32 = 32;
+
+/// The weather at a point in the world
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Weather {
+    /// Cloud cover, from 0 (clear sky) to 1 (overcast)
+    pub cloud: f32,
+    /// Rain intensity, from 0 to 1
+    pub rain: f32,
+    /// Snow intensity, from 0 to 1
+    pub snow: f32,
+    /// Wind velocity in blocks per second
+    pub wind: Vec2<f32>,
+}
+
+impl Weather {
+    /// Intensity of rain or snow, whichever is falling
+    pub fn precipitation(&self) -> f32 { self.rain.max(self.snow) }
+
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        Self {
+            cloud: Lerp::lerp(a.cloud, b.cloud, t),
+            rain: Lerp::lerp(a.rain, b.rain, t),
+            snow: Lerp::lerp(a.snow, b.snow, t),
+            wind: Lerp::lerp(a.wind, b.wind, t),
+        }
+    }
+}
+
+/// The weather of the whole world, one `Weather` per cell of `CELL_SIZE`
+/// chunks. This is a resource synced to clients.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WeatherGrid {
+    size: Vec2<u32>,
+    cells: Vec<Weather>,
+}
+
+impl WeatherGrid {
+    /// Creates a calm grid covering a world of `world_size` chunks
+    pub fn new(world_size: Vec2<u32>) -> Self {
+        let size = world_size.map(|e| ((e + CELL_SIZE - 1) / CELL_SIZE).max(1));
+        Self {
+            size,
+            cells: vec![Weather::default(); (size.x * size.y) as usize],
+        }
+    }
+
+    /// Size of a cell in blocks
+    pub fn cell_size() -> Vec2<f32> { TerrainChunkSize::RECT_SIZE.map(|e| (e * CELL_SIZE) as f32) }
+
+    /// Size of the grid in cells
+    pub fn size(&self) -> Vec2<u32> { self.size }
+
+    fn idx(&self, cell: Vec2<u32>) -> Option<usize> {
+        if cell.x < self.size.x && cell.y < self.size.y {
+            Some((cell.y * self.size.x + cell.x) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// The weather of a cell, or calm weather outside of the grid
+    pub fn get(&self, cell: Vec2<u32>) -> Weather {
+        self.idx(cell)
+            .and_then(|idx| self.cells.get(idx))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn get_mut(&mut self, cell: Vec2<u32>) -> Option<&mut Weather> {
+        let idx = self.idx(cell)?;
+        self.cells.get_mut(idx)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Vec2<u32>, &Weather)> {
+        let width = self.size.x.max(1);
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, weather)| (Vec2::new(i as u32 % width, i as u32 / width), weather))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Vec2<u32>, &mut Weather)> {
+        let width = self.size.x.max(1);
+        self.cells
+            .iter_mut()
+            .enumerate()
+            .map(move |(i, weather)| (Vec2::new(i as u32 % width, i as u32 / width), weather))
+    }
+
+    /// The weather at a world position, interpolated between the centres of
+    /// the surrounding cells
+    pub fn get_interpolated(&self, wpos: Vec2<f32>) -> Weather {
+        if self.cells.is_empty() {
+            return Weather::default();
+        }
+        let max = self.size.map(|e| e as f32 - 1.0);
+        let pos = (wpos / Self::cell_size() - 0.5).clamped(Vec2::zero(), max);
+        let min_cell = pos.map(|e| e.floor() as u32);
+        let max_cell = (min_cell + 1).map2(self.size, |e, sz| e.min(sz - 1));
+        let t = pos - pos.floor();
+
+        let below = Weather::lerp(
+            self.get(min_cell),
+            self.get(Vec2::new(max_cell.x, min_cell.y)),
+            t.x,
+        );
+        let above = Weather::lerp(
+            self.get(Vec2::new(min_cell.x, max_cell.y)),
+            self.get(max_cell),
+            t.x,
+        );
+        Weather::lerp(below, above, t.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_interpolated() {
+        let mut grid = WeatherGrid::new(Vec2::new(CELL_SIZE * 2, CELL_SIZE));
+        assert_eq!(grid.size(), Vec2::new(2, 1));
+        grid.get_mut(Vec2::new(1, 0)).unwrap().rain = 1.0;
+
+        let cell_size = WeatherGrid::cell_size();
+        let rain_at = |x: f32| grid.get_interpolated(Vec2::new(x, 0.0) * cell_size).rain;
+        assert!(rain_at(0.0).abs() < 0.001);
+        assert!((rain_at(1.0) - 0.5).abs() < 0.001);
+        assert!((rain_at(1.5) - 1.0).abs() < 0.001);
+        // Positions outside of the world use the weather at its edge
+        assert!((rain_at(10.0) - 1.0).abs() < 0.001);
+        assert!(rain_at(-10.0).abs() < 0.001);
+    }
+}
@@ -3,8 +3,9 @@
 //!
 //! (cd network/examples/async_recv && RUST_BACKTRACE=1 cargo run)
 use crate::{
-    message::{partial_eq_bincode, IncomingMessage, Message, OutgoingMessage},
-    participant::{A2bStreamOpen, S2bShutdownBparticipant},
+    crypto::StreamCipher,
+    message::{partial_eq_bincode, Message, OutgoingMessage},
+    participant::{A2bStreamOpen, B2aMsgRecv, S2bShutdownBparticipant},
     scheduler::Scheduler,
     types::{Mid, Pid, Prio, Promises, Sid},
 };
@@ -77,8 +78,9 @@ pub struct Stream {
     promises: Promises,
     send_closed: Arc<AtomicBool>,
     a2b_msg_s: crossbeam_channel::Sender<(Prio, Sid, OutgoingMessage)>,
-    b2a_msg_recv_r: Option<mpsc::UnboundedReceiver<IncomingMessage>>,
+    b2a_msg_recv_r: Option<mpsc::UnboundedReceiver<B2aMsgRecv>>,
     a2b_close_stream_s: Option<mpsc::UnboundedSender<Sid>>,
+    cipher: Option<Arc<StreamCipher>>,
 }
 
 /// Error type thrown by [`Networks`](Network) methods
@@ -111,6 +113,9 @@ pub enum StreamError {
     #[cfg(feature = "compression")]
     Compression(DecodeError),
     Deserialize(bincode::Error),
+    /// The message didn't match its checksum on a Stream with
+    /// [`Promises::CONSISTENCY`] and was dropped. The `Stream` stays usable.
+    Corrupted,
 }
 
 /// Use the `Network` to create connections to other [`Participants`]
@@ -656,8 +661,9 @@ impl Stream {
         promises: Promises,
         send_closed: Arc<AtomicBool>,
         a2b_msg_s: crossbeam_channel::Sender<(Prio, Sid, OutgoingMessage)>,
-        b2a_msg_recv_r: mpsc::UnboundedReceiver<IncomingMessage>,
+        b2a_msg_recv_r: mpsc::UnboundedReceiver<B2aMsgRecv>,
         a2b_close_stream_s: mpsc::UnboundedSender<Sid>,
+        cipher: Option<Arc<StreamCipher>>,
     ) -> Self {
         Self {
             pid,
@@ -669,6 +675,7 @@ impl Stream {
             a2b_msg_s,
             b2a_msg_recv_r: Some(b2a_msg_recv_r),
             a2b_close_stream_s: Some(a2b_close_stream_s),
+            cipher,
         }
     }
 
@@ -788,6 +795,12 @@ impl Stream {
             cursor: 0,
             mid: self.mid,
             sid: self.sid,
+            cipher: self.cipher.as_ref().map(Arc::clone),
+            checksum: if self.promises.contains(Promises::CONSISTENCY) {
+                crc32fast::hash(&message.buffer.data)
+            } else {
+                0
+            },
         }))?;
         self.mid += 1;
         Ok(())
@@ -870,7 +883,8 @@ impl Stream {
         match &mut self.b2a_msg_recv_r {
             Some(b2a_msg_recv_r) => {
                 match b2a_msg_recv_r.next().await {
-                    Some(msg) => Ok(Message {
+                    Some(Err(e)) => Err(e),
+                    Some(Ok(msg)) => Ok(Message {
                         buffer: Arc::new(msg.buffer),
                         #[cfg(feature = "compression")]
                         compressed: self.promises.contains(Promises::COMPRESSED),
@@ -927,7 +941,8 @@ impl Stream {
                     self.b2a_msg_recv_r = None; //prevent panic
                     Err(StreamError::StreamClosed)
                 },
-                Ok(Some(msg)) => Ok(Some(
+                Ok(Some(Err(e))) => Err(e),
+                Ok(Some(Ok(msg))) => Ok(Some(
                     Message {
                         buffer: Arc::new(msg.buffer),
                         #[cfg(feature = "compression")]
@@ -1107,6 +1122,7 @@ impl core::fmt::Display for StreamError {
             #[cfg(feature = "compression")]
             StreamError::Compression(err) => write!(f, "compression error on message: {}", err),
             StreamError::Deserialize(err) => write!(f, "deserialize error on message: {}", err),
+            StreamError::Corrupted => write!(f, "message didn't match its checksum"),
         }
     }
 }
@@ -1141,6 +1157,7 @@ impl core::cmp::PartialEq for StreamError {
                 #[cfg(feature = "compression")]
                 StreamError::Compression(_) => false,
                 StreamError::Deserialize(_) => false,
+                StreamError::Corrupted => false,
             },
             #[cfg(feature = "compression")]
             StreamError::Compression(err) => match other {
@@ -1148,12 +1165,21 @@ impl core::cmp::PartialEq for StreamError {
                 #[cfg(feature = "compression")]
                 StreamError::Compression(other_err) => err == other_err,
                 StreamError::Deserialize(_) => false,
+                StreamError::Corrupted => false,
             },
             StreamError::Deserialize(err) => match other {
                 StreamError::StreamClosed => false,
                 #[cfg(feature = "compression")]
                 StreamError::Compression(_) => false,
                 StreamError::Deserialize(other_err) => partial_eq_bincode(err, other_err),
+                StreamError::Corrupted => false,
+            },
+            StreamError::Corrupted => match other {
+                StreamError::StreamClosed => false,
+                #[cfg(feature = "compression")]
+                StreamError::Compression(_) => false,
+                StreamError::Deserialize(_) => false,
+                StreamError::Corrupted => true,
             },
         }
     }
@@ -1,6 +1,7 @@
 #[cfg(feature = "metrics")]
 use crate::metrics::NetworkMetrics;
 use crate::{
+    crypto::{KeyExchange, SessionKeys},
     participant::C2pFrame,
     protocols::Protocols,
     types::{
@@ -82,6 +83,7 @@ pub(crate) struct Handshake {
     local_pid: Pid,
     secret: u128,
     init_handshake: bool,
+    key_exchange: KeyExchange,
     #[cfg(feature = "metrics")]
     metrics: Arc<NetworkMetrics>,
 }
@@ -111,10 +113,15 @@ impl Handshake {
             #[cfg(feature = "metrics")]
             metrics,
             init_handshake,
+            key_exchange: KeyExchange::new(),
         }
     }
 
-    pub async fn setup(self, protocol: &Protocols) -> Result<(Pid, Sid, u128, Vec<C2pFrame>), ()> {
+    #[allow(clippy::type_complexity)]
+    pub async fn setup(
+        self,
+        protocol: &Protocols,
+    ) -> Result<(Pid, Sid, u128, SessionKeys, Vec<C2pFrame>), ()> {
         let (c2w_frame_s, c2w_frame_r) = mpsc::unbounded::<Frame>();
         let (mut w2c_cid_frame_s, mut w2c_cid_frame_r) = mpsc::unbounded::<C2pFrame>();
 
@@ -154,7 +161,7 @@ impl Handshake {
                          bparticipant as leftover_frames"
                     );
                 }
-                Ok((res.0, res.1, res.2, leftover_frames))
+                Ok((res.0, res.1, res.2, res.3, leftover_frames))
             },
             Err(()) => Err(()),
         }
@@ -165,7 +172,7 @@ impl Handshake {
         w2c_cid_frame_r: &mut mpsc::UnboundedReceiver<C2pFrame>,
         mut c2w_frame_s: mpsc::UnboundedSender<Frame>,
         read_stop_sender: oneshot::Sender<()>,
-    ) -> Result<(Pid, Sid, u128), ()> {
+    ) -> Result<(Pid, Sid, u128, SessionKeys), ()> {
         const ERR_S: &str = "Got A Raw Message, these are usually Debug Messages indicating that \
                              something went wrong on network layer and connection will be closed";
         #[cfg(feature = "metrics")]
@@ -256,7 +263,11 @@ impl Handshake {
 
         let frame = w2c_cid_frame_r.next().await.map(|(_cid, frame)| frame);
         let r = match frame {
-            Some(Ok(Frame::Init { pid, secret })) => {
+            Some(Ok(Frame::Init {
+                pid,
+                secret,
+                public_key,
+            })) => {
                 debug!(?pid, "Participant send their ID");
                 #[cfg(feature = "metrics")]
                 self.metrics
@@ -269,8 +280,11 @@ impl Handshake {
                     self.send_init(&mut c2w_frame_s).await;
                     STREAM_ID_OFFSET2
                 };
+                let session_keys = self
+                    .key_exchange
+                    .derive_session_keys(public_key, self.init_handshake);
                 info!(?pid, "This Handshake is now configured!");
-                Ok((pid, stream_id_offset, secret))
+                Ok((pid, stream_id_offset, secret, session_keys))
             },
             Some(Ok(frame)) => {
                 #[cfg(feature = "metrics")]
@@ -329,6 +343,7 @@ impl Handshake {
             .send(Frame::Init {
                 pid: self.local_pid,
                 secret: self.secret,
+                public_key: self.key_exchange.public_key(),
             })
             .await
             .unwrap();
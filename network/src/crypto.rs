@@ -0,0 +1,282 @@
+//! Key exchange and authenticated encryption for [`Streams`] opened with
+//! [`Promises::ENCRYPTED`].
+//!
+//! Every [`Handshake`] generates a fresh x25519 key pair and sends the public
+//! half with its `Frame::Init`. Both sides then derive a pair of directional
+//! session keys from the shared secret. Each encrypted `Stream` derives its
+//! own ChaCha20-Poly1305 key from those, so the nonce of a `Frame::Data` only
+//! needs to be unique within a single `Stream` and is built from the message
+//! id and the frame's offset into the message.
+//!
+//! This protects against passive eavesdropping and detects any modification
+//! of encrypted frames. The remote side is not authenticated, so it does not
+//! protect against an active man-in-the-middle.
+//!
+//! [`Streams`]: crate::api::Stream
+//! [`Promises::ENCRYPTED`]: crate::types::Promises::ENCRYPTED
+//! [`Handshake`]: crate::channel::Handshake
+use crate::types::{Mid, Sid};
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+pub(crate) const PUBLIC_KEY_LEN: usize = 32;
+/// Every encrypted `Frame::Data` grows by the size of its authentication tag
+pub(crate) const TAG_LEN: usize = 16;
+
+const SESSION_KEY_SALT: &[u8] = b"veloren_network session";
+const INITIATOR_TO_RESPONDER: &[u8] = b"initiator to responder";
+const RESPONDER_TO_INITIATOR: &[u8] = b"responder to initiator";
+const STREAM_KEY_INFO: &[u8] = b"veloren_network stream";
+
+/// One side of a x25519 key exchange, created freshly for every
+/// [`Handshake`](crate::channel::Handshake)
+pub(crate) struct KeyExchange {
+    secret: StaticSecret,
+    public: PublicKey,
+}
+
+/// Directional keys shared between two participants after a successful
+/// [`KeyExchange`]
+#[derive(Clone)]
+pub(crate) struct SessionKeys {
+    send: [u8; 32],
+    recv: [u8; 32],
+}
+
+/// Encrypts or decrypts the `Frame::Data` of a single [`Stream`] in a
+/// single direction
+///
+/// [`Stream`]: crate::api::Stream
+pub(crate) struct StreamCipher {
+    cipher: ChaCha20Poly1305,
+}
+
+impl KeyExchange {
+    pub fn new() -> Self {
+        let secret = StaticSecret::new(rand::rngs::OsRng);
+        let public = PublicKey::from(&secret);
+        Self { secret, public }
+    }
+
+    pub fn public_key(&self) -> [u8; PUBLIC_KEY_LEN] { *self.public.as_bytes() }
+
+    /// `initiator` needs to be `true` on exactly one side of the connection,
+    /// so that both sides agree on which key is used in which direction.
+    pub fn derive_session_keys(
+        &self,
+        remote_public_key: [u8; PUBLIC_KEY_LEN],
+        initiator: bool,
+    ) -> SessionKeys {
+        let remote_public = PublicKey::from(remote_public_key);
+        let shared = self.secret.diffie_hellman(&remote_public);
+
+        // bind the keys to this exact exchange, in a order both sides agree on
+        let (initiator_public, responder_public) = if initiator {
+            (self.public.as_bytes(), remote_public.as_bytes())
+        } else {
+            (remote_public.as_bytes(), self.public.as_bytes())
+        };
+        let mut salt = SESSION_KEY_SALT.to_vec();
+        salt.extend_from_slice(initiator_public);
+        salt.extend_from_slice(responder_public);
+
+        let hkdf = Hkdf::<Sha256>::new(Some(&salt), shared.as_bytes());
+        let mut i2r = [0u8; 32];
+        let mut r2i = [0u8; 32];
+        hkdf.expand(INITIATOR_TO_RESPONDER, &mut i2r)
+            .expect("32 bytes is a valid output length for sha256");
+        hkdf.expand(RESPONDER_TO_INITIATOR, &mut r2i)
+            .expect("32 bytes is a valid output length for sha256");
+
+        if initiator {
+            SessionKeys {
+                send: i2r,
+                recv: r2i,
+            }
+        } else {
+            SessionKeys {
+                send: r2i,
+                recv: i2r,
+            }
+        }
+    }
+}
+
+impl SessionKeys {
+    /// returns the `(send, recv)` ciphers for the [`Stream`] with this `sid`
+    ///
+    /// [`Stream`]: crate::api::Stream
+    pub fn stream_ciphers(&self, sid: Sid) -> (StreamCipher, StreamCipher) {
+        (
+            StreamCipher::derive(&self.send, sid),
+            StreamCipher::derive(&self.recv, sid),
+        )
+    }
+}
+
+impl StreamCipher {
+    fn derive(session_key: &[u8; 32], sid: Sid) -> Self {
+        let mut info = STREAM_KEY_INFO.to_vec();
+        info.extend_from_slice(&sid.to_le_bytes());
+        let hkdf = Hkdf::<Sha256>::from_prk(session_key)
+            .expect("session keys are always a valid pseudorandom key");
+        let mut key = [0u8; 32];
+        hkdf.expand(&info, &mut key)
+            .expect("32 bytes is a valid output length for sha256");
+        Self {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+        }
+    }
+
+    /// nonces are unique as long as a single message doesn't exceed 4 GiB
+    fn nonce(mid: Mid, start: u64) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        nonce[0..8].copy_from_slice(&mid.to_le_bytes());
+        nonce[8..12].copy_from_slice(&(start as u32).to_le_bytes());
+        nonce
+    }
+
+    pub fn encrypt(&self, mid: Mid, start: u64, data: &[u8]) -> Vec<u8> {
+        self.cipher
+            .encrypt(Nonce::from_slice(&Self::nonce(mid, start)), data)
+            .expect("encryption only fails for messages far larger than a frame")
+    }
+
+    /// returns `Err` if the data was altered, or wasn't encrypted for this
+    /// `mid` and `start`
+    pub fn decrypt(&self, mid: Mid, start: u64, data: &[u8]) -> Result<Vec<u8>, ()> {
+        self.cipher
+            .decrypt(Nonce::from_slice(&Self::nonce(mid, start)), data)
+            .map_err(|_| ())
+    }
+}
+
+impl std::fmt::Debug for KeyExchange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "KeyExchange({:X?})", &self.public.as_bytes()[..4])
+    }
+}
+
+impl std::fmt::Debug for SessionKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SessionKeys(..)")
+    }
+}
+
+impl std::fmt::Debug for StreamCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "StreamCipher(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        crypto::*,
+        message::{MessageBuffer, OutgoingMessage},
+        types::Frame,
+    };
+    use futures::{channel::mpsc, executor::block_on, sink::SinkExt, stream::StreamExt};
+    use std::sync::Arc;
+
+    fn session_keys() -> (SessionKeys, SessionKeys) {
+        let initiator = KeyExchange::new();
+        let responder = KeyExchange::new();
+        (
+            initiator.derive_session_keys(responder.public_key(), true),
+            responder.derive_session_keys(initiator.public_key(), false),
+        )
+    }
+
+    fn encrypted_frames(data: Vec<u8>, cipher: StreamCipher) -> Vec<(Sid, Frame)> {
+        let sid = Sid::new(42);
+        let mut msg = OutgoingMessage {
+            buffer: Arc::new(MessageBuffer { data }),
+            cursor: 0,
+            mid: 7,
+            sid,
+            cipher: Some(Arc::new(cipher)),
+            checksum: 0,
+        };
+        let mut frames = Vec::new();
+        while !msg.fill_next(sid, &mut frames) {}
+        frames
+    }
+
+    #[test]
+    fn both_sides_derive_matching_keys() {
+        let (a, b) = session_keys();
+        assert_eq!(a.send, b.recv);
+        assert_eq!(a.recv, b.send);
+        assert_ne!(a.send, a.recv);
+    }
+
+    #[test]
+    fn ciphertext_on_the_wire() {
+        let (a, b) = session_keys();
+        let (a_send, _) = a.stream_ciphers(Sid::new(42));
+        let (_, b_recv) = b.stream_ciphers(Sid::new(42));
+        let plaintext = b"auth token: 0123456789abcdef".repeat(100);
+
+        // simulate the wire between two participants
+        let (mut wire_s, mut wire_r) = mpsc::unbounded::<Frame>();
+        block_on(async {
+            for (_, frame) in encrypted_frames(plaintext.clone(), a_send) {
+                wire_s.send(frame).await.unwrap();
+            }
+            drop(wire_s);
+
+            let mut received = Vec::new();
+            while let Some(frame) = wire_r.next().await {
+                match frame {
+                    Frame::DataHeader { length, .. } => assert_eq!(length, plaintext.len() as u64),
+                    Frame::Data { mid, start, data } => {
+                        assert!(!data.windows(11).any(|w| w == b"auth token:"));
+                        received.extend(b_recv.decrypt(mid, start, &data).unwrap());
+                    },
+                    frame => panic!("unexpected frame {:?}", frame),
+                }
+            }
+            assert_eq!(received, plaintext);
+        });
+    }
+
+    #[test]
+    fn tampered_frame_is_detected() {
+        let (a, b) = session_keys();
+        let (a_send, _) = a.stream_ciphers(Sid::new(42));
+        let (_, b_recv) = b.stream_ciphers(Sid::new(42));
+
+        let frames = encrypted_frames(b"Hello World".to_vec(), a_send);
+        if let (_, Frame::Data { mid, start, data }) = &frames[1] {
+            assert_eq!(data.len(), 11 + TAG_LEN);
+            let mut tampered = data.clone();
+            tampered[3] ^= 0b0000_0100;
+            assert!(b_recv.decrypt(*mid, *start, &tampered).is_err());
+            // replaying the frame as part of another message fails, too
+            assert!(b_recv.decrypt(*mid + 1, *start, data).is_err());
+            assert!(b_recv.decrypt(*mid, *start, data).is_ok());
+        } else {
+            panic!("expected a data frame");
+        }
+    }
+
+    #[test]
+    fn other_stream_cannot_decrypt() {
+        let (a, b) = session_keys();
+        let (a_send, _) = a.stream_ciphers(Sid::new(42));
+        let (_, b_recv_other) = b.stream_ciphers(Sid::new(43));
+
+        let frames = encrypted_frames(b"Hello World".to_vec(), a_send);
+        if let (_, Frame::Data { mid, start, data }) = &frames[1] {
+            assert!(b_recv_other.decrypt(*mid, *start, data).is_err());
+        } else {
+            panic!("expected a data frame");
+        }
+    }
+}
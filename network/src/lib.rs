@@ -101,6 +101,7 @@
 
 mod api;
 mod channel;
+mod crypto;
 mod message;
 #[cfg(feature = "metrics")] mod metrics;
 mod participant;
@@ -4,6 +4,7 @@ use serde::{de::DeserializeOwned, Serialize};
 use crate::types::Promises;
 use crate::{
     api::{Stream, StreamError},
+    crypto::StreamCipher,
     types::{Frame, Mid, Sid},
 };
 use std::{io, sync::Arc};
@@ -36,6 +37,12 @@ pub(crate) struct OutgoingMessage {
     pub cursor: u64,
     pub mid: Mid,
     pub sid: Sid,
+    /// set for [`Streams`](crate::api::Stream) with
+    /// [`Promises::ENCRYPTED`](crate::types::Promises::ENCRYPTED)
+    pub cipher: Option<Arc<StreamCipher>>,
+    /// crc32 of the message, only set for [`Streams`](crate::api::Stream) with
+    /// [`Promises::CONSISTENCY`](crate::types::Promises::CONSISTENCY)
+    pub checksum: u32,
 }
 
 #[derive(Debug)]
@@ -44,6 +51,7 @@ pub(crate) struct IncomingMessage {
     pub length: u64,
     pub mid: Mid,
     pub sid: Sid,
+    pub checksum: u32,
 }
 
 impl Message {
@@ -189,12 +197,18 @@ impl OutgoingMessage {
                     mid: self.mid,
                     sid: self.sid,
                     length: self.buffer.data.len() as u64,
+                    checksum: self.checksum,
                 })));
             }
+            let data = &self.buffer.data[self.cursor as usize..][..to_send as usize];
+            let data = match &self.cipher {
+                Some(cipher) => cipher.encrypt(self.mid, self.cursor, data),
+                None => data.to_vec(),
+            };
             frames.extend(std::iter::once((msg_sid, Frame::Data {
                 mid: self.mid,
                 start: self.cursor,
-                data: self.buffer.data[self.cursor as usize..][..to_send as usize].to_vec(),
+                data,
             })));
         };
         self.cursor += to_send;
@@ -285,6 +299,7 @@ mod tests {
             a2b_msg_s,
             b2a_msg_recv_r,
             a2b_close_stream_s,
+            None,
         )
     }
 
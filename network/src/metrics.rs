@@ -24,6 +24,8 @@ pub struct NetworkMetrics {
     // opened streams, seperated by PARTICIPANT
     pub streams_opened_total: IntCounterVec,
     pub streams_closed_total: IntCounterVec,
+    // messages failing their checksum on `Promises::CONSISTENCY` streams, seperated by PARTICIPANT
+    pub corrupted_messages_total: IntCounterVec,
     pub network_info: IntGauge,
     // Frames counted a channel level, seperated by CHANNEL (and PARTICIPANT) AND FRAME TYPE,
     pub frames_out_total: IntCounterVec,
@@ -110,6 +112,13 @@ impl NetworkMetrics {
             ),
             &["participant"],
         )?;
+        let corrupted_messages_total = IntCounterVec::new(
+            Opts::new(
+                "corrupted_messages_total",
+                "Number of received messages that failed their checksum on the network",
+            ),
+            &["participant"],
+        )?;
         let opts = Opts::new("network_info", "Static Network information")
             .const_label(
                 "version",
@@ -219,6 +228,7 @@ impl NetworkMetrics {
             channels_disconnected_total,
             streams_opened_total,
             streams_closed_total,
+            corrupted_messages_total,
             network_info,
             frames_out_total,
             frames_in_total,
@@ -245,6 +255,7 @@ impl NetworkMetrics {
         registry.register(Box::new(self.channels_disconnected_total.clone()))?;
         registry.register(Box::new(self.streams_opened_total.clone()))?;
         registry.register(Box::new(self.streams_closed_total.clone()))?;
+        registry.register(Box::new(self.corrupted_messages_total.clone()))?;
         registry.register(Box::new(self.network_info.clone()))?;
         registry.register(Box::new(self.frames_out_total.clone()))?;
         registry.register(Box::new(self.frames_in_total.clone()))?;
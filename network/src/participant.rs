@@ -1,12 +1,13 @@
 #[cfg(feature = "metrics")]
 use crate::metrics::{MultiCidFrameCache, NetworkMetrics};
 use crate::{
-    api::{ParticipantError, Stream},
+    api::{ParticipantError, Stream, StreamError},
     channel::Channel,
+    crypto::{SessionKeys, StreamCipher},
     message::{IncomingMessage, MessageBuffer, OutgoingMessage},
     prios::PrioManager,
     protocols::Protocols,
-    types::{Cid, Frame, Pid, Prio, Promises, Sid},
+    types::{Cid, Frame, Mid, Pid, Prio, Promises, Sid},
 };
 use async_std::sync::{Mutex, RwLock};
 use futures::{
@@ -32,6 +33,7 @@ pub(crate) type C2pFrame = (Cid, Result<Frame, ()>);
 pub(crate) type S2bCreateChannel = (Cid, Sid, Protocols, Vec<C2pFrame>, oneshot::Sender<()>);
 pub(crate) type S2bShutdownBparticipant = oneshot::Sender<Result<(), ParticipantError>>;
 pub(crate) type B2sPrioStatistic = (Pid, u64, u64);
+pub(crate) type B2aMsgRecv = Result<IncomingMessage, StreamError>;
 
 #[derive(Debug)]
 struct ChannelInfo {
@@ -46,7 +48,9 @@ struct StreamInfo {
     prio: Prio,
     promises: Promises,
     send_closed: Arc<AtomicBool>,
-    b2a_msg_recv_s: Mutex<mpsc::UnboundedSender<IncomingMessage>>,
+    b2a_msg_recv_s: Mutex<mpsc::UnboundedSender<B2aMsgRecv>>,
+    /// decrypts incoming data if the stream has `Promises::ENCRYPTED`
+    cipher: Option<StreamCipher>,
 }
 
 #[derive(Debug)]
@@ -71,6 +75,7 @@ pub struct BParticipant {
     remote_pid: Pid,
     remote_pid_string: String, //optimisation
     offset_sid: Sid,
+    session_keys: SessionKeys,
     channels: Arc<RwLock<HashMap<Cid, Mutex<ChannelInfo>>>>,
     streams: RwLock<HashMap<Sid, StreamInfo>>,
     running_mgr: AtomicUsize,
@@ -86,6 +91,7 @@ impl BParticipant {
     pub(crate) fn new(
         remote_pid: Pid,
         offset_sid: Sid,
+        session_keys: SessionKeys,
         #[cfg(feature = "metrics")] metrics: Arc<NetworkMetrics>,
     ) -> (
         Self,
@@ -120,6 +126,7 @@ impl BParticipant {
                 remote_pid,
                 remote_pid_string: remote_pid.to_string(),
                 offset_sid,
+                session_keys,
                 channels: Arc::new(RwLock::new(HashMap::new())),
                 streams: RwLock::new(HashMap::new()),
                 running_mgr: AtomicUsize::new(0),
@@ -136,6 +143,24 @@ impl BParticipant {
         )
     }
 
+    /// Checks a fully received message against its checksum if its stream
+    /// has `Promises::CONSISTENCY`, counting the messages that don't match
+    fn verify_checksum(&self, promises: Promises, imsg: IncomingMessage) -> B2aMsgRecv {
+        if promises.contains(Promises::CONSISTENCY)
+            && crc32fast::hash(&imsg.buffer.data) != imsg.checksum
+        {
+            warn!(mid = ?imsg.mid, "Message doesn't match its checksum, dropping it");
+            #[cfg(feature = "metrics")]
+            self.metrics
+                .corrupted_messages_total
+                .with_label_values(&[&self.remote_pid_string])
+                .inc();
+            Err(StreamError::Corrupted)
+        } else {
+            Ok(imsg)
+        }
+    }
+
     pub async fn run(mut self, b2s_prio_statistic_s: mpsc::UnboundedSender<B2sPrioStatistic>) {
         //those managers that listen on api::Participant need an additional oneshot for
         // shutdown scenario, those handled by scheduler will be closed by it.
@@ -376,20 +401,39 @@ impl BParticipant {
                     )
                     .await;
                 },
-                Frame::DataHeader { mid, sid, length } => {
+                Frame::DataHeader {
+                    mid,
+                    sid,
+                    length,
+                    checksum,
+                } => {
                     let imsg = IncomingMessage {
                         buffer: MessageBuffer { data: Vec::new() },
                         length,
                         mid,
                         sid,
+                        checksum,
                     };
                     messages.insert(mid, imsg);
                 },
-                Frame::Data {
-                    mid,
-                    start: _,
-                    mut data,
-                } => {
+                Frame::Data { mid, start, data } => {
+                    let data = match messages.get(&mid).map(|imsg: &IncomingMessage| imsg.sid) {
+                        Some(sid) => self.decrypt_data(sid, mid, start, data).await,
+                        None => Ok(data),
+                    };
+                    let mut data = match data {
+                        Ok(data) => data,
+                        Err(()) => {
+                            error!(
+                                ?mid,
+                                "Encrypted message was altered on the way, closing participant"
+                            );
+                            messages.remove(&mid);
+                            self.close_api(Some(ParticipantError::ProtocolFailedUnrecoverable))
+                                .await;
+                            continue;
+                        },
+                    };
                     let finished = if let Some(imsg) = messages.get_mut(&mid) {
                         imsg.buffer.data.append(&mut data);
                         imsg.buffer.data.len() as u64 == imsg.length
@@ -400,7 +444,8 @@ impl BParticipant {
                         //trace!(?mid, "finished receiving message");
                         let imsg = messages.remove(&mid).unwrap();
                         if let Some(si) = self.streams.read().await.get(&imsg.sid) {
-                            if let Err(e) = si.b2a_msg_recv_s.lock().await.send(imsg).await {
+                            let msg = self.verify_checksum(si.promises, imsg);
+                            if let Err(e) = si.b2a_msg_recv_s.lock().await.send(msg).await {
                                 warn!(
                                     ?e,
                                     ?mid,
@@ -814,6 +859,27 @@ impl BParticipant {
         .await;
     }
 
+    /// decrypts `data` if it belongs to a stream with `Promises::ENCRYPTED`,
+    /// returns `Err` if it was tampered with
+    async fn decrypt_data(
+        &self,
+        sid: Sid,
+        mid: Mid,
+        start: u64,
+        data: Vec<u8>,
+    ) -> Result<Vec<u8>, ()> {
+        match self
+            .streams
+            .read()
+            .await
+            .get(&sid)
+            .and_then(|si| si.cipher.as_ref())
+        {
+            Some(cipher) => cipher.decrypt(mid, start, &data),
+            None => Ok(data),
+        }
+    }
+
     async fn create_stream(
         &self,
         sid: Sid,
@@ -822,13 +888,20 @@ impl BParticipant {
         a2p_msg_s: crossbeam_channel::Sender<(Prio, Sid, OutgoingMessage)>,
         a2b_close_stream_s: &mpsc::UnboundedSender<Sid>,
     ) -> Stream {
-        let (b2a_msg_recv_s, b2a_msg_recv_r) = mpsc::unbounded::<IncomingMessage>();
+        let (b2a_msg_recv_s, b2a_msg_recv_r) = mpsc::unbounded::<B2aMsgRecv>();
         let send_closed = Arc::new(AtomicBool::new(false));
+        let (send_cipher, recv_cipher) = if promises.contains(Promises::ENCRYPTED) {
+            let (send, recv) = self.session_keys.stream_ciphers(sid);
+            (Some(Arc::new(send)), Some(recv))
+        } else {
+            (None, None)
+        };
         self.streams.write().await.insert(sid, StreamInfo {
             prio,
             promises,
             send_closed: Arc::clone(&send_closed),
             b2a_msg_recv_s: Mutex::new(b2a_msg_recv_s),
+            cipher: recv_cipher,
         });
         #[cfg(feature = "metrics")]
         self.metrics
@@ -844,6 +917,7 @@ impl BParticipant {
             a2p_msg_s,
             b2a_msg_recv_r,
             a2b_close_stream_s.clone(),
+            send_cipher,
         )
     }
 
@@ -873,3 +947,69 @@ impl BParticipant {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyExchange;
+
+    fn bparticipant(remote_pid: Pid) -> BParticipant {
+        let initiator = KeyExchange::new();
+        let responder = KeyExchange::new();
+        let (bparticipant, ..) = BParticipant::new(
+            remote_pid,
+            Sid::new(0),
+            initiator.derive_session_keys(responder.public_key(), true),
+            #[cfg(feature = "metrics")]
+            Arc::new(NetworkMetrics::new(&Pid::fake(0)).unwrap()),
+        );
+        bparticipant
+    }
+
+    fn incoming(data: Vec<u8>, checksum: u32) -> IncomingMessage {
+        IncomingMessage {
+            length: data.len() as u64,
+            buffer: MessageBuffer { data },
+            mid: 7,
+            sid: Sid::new(3),
+            checksum,
+        }
+    }
+
+    #[test]
+    fn corrupted_message_is_dropped() {
+        let remote_pid = Pid::fake(1);
+        let bparticipant = bparticipant(remote_pid);
+        let data = vec![1u8, 2, 3, 4, 5];
+        let checksum = crc32fast::hash(&data);
+        let mut corrupted = data.clone();
+        corrupted[2] ^= 0x10;
+
+        assert!(
+            bparticipant
+                .verify_checksum(Promises::CONSISTENCY, incoming(data, checksum))
+                .is_ok()
+        );
+        assert!(matches!(
+            bparticipant
+                .verify_checksum(Promises::CONSISTENCY, incoming(corrupted.clone(), checksum)),
+            Err(StreamError::Corrupted)
+        ));
+        // Streams without the promise don't check it
+        assert!(
+            bparticipant
+                .verify_checksum(Promises::ORDERED, incoming(corrupted, checksum))
+                .is_ok()
+        );
+
+        #[cfg(feature = "metrics")]
+        assert_eq!(
+            bparticipant
+                .metrics
+                .corrupted_messages_total
+                .with_label_values(&[&remote_pid.to_string()])
+                .get(),
+            1
+        );
+    }
+}
@@ -318,6 +318,8 @@ mod tests {
             cursor: 0,
             mid: 1,
             sid,
+            cipher: None,
+            checksum: 0,
         })
     }
 
@@ -331,6 +333,8 @@ mod tests {
             cursor: 0,
             mid: 1,
             sid,
+            cipher: None,
+            checksum: 0,
         })
     }
 
@@ -339,7 +343,10 @@ mod tests {
             .pop_front()
             .expect("Frames vecdeque doesn't contain enough frames!")
             .1;
-        if let Frame::DataHeader { mid, sid, length } = frame {
+        if let Frame::DataHeader {
+            mid, sid, length, ..
+        } = frame
+        {
             assert_eq!(mid, 1);
             assert_eq!(sid, Sid::new(f_sid));
             assert_eq!(length, f_length);
@@ -586,6 +593,8 @@ mod tests {
                 cursor: 0,
                 mid: 1,
                 sid,
+                cipher: None,
+                checksum: 0,
             }))
             .unwrap();
 
@@ -615,6 +624,8 @@ mod tests {
                 cursor: 0,
                 mid: 1,
                 sid,
+                cipher: None,
+                checksum: 0,
             }))
             .unwrap();
         msg_tx.send(mock_out(16, 8)).unwrap();
@@ -647,6 +658,8 @@ mod tests {
                 cursor: 0,
                 mid: 1,
                 sid,
+                cipher: None,
+                checksum: 0,
             }))
             .unwrap();
         msg_tx.send(mock_out(20, 8)).unwrap();
@@ -17,7 +17,13 @@ use futures::{
     sink::SinkExt,
     stream::StreamExt,
 };
-use std::{convert::TryFrom, net::SocketAddr, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tracing::*;
 
 // Reserving bytes 0, 10, 13 as i have enough space and want to make it easy to
@@ -34,6 +40,19 @@ const FRAME_RAW: u8 = 8;
 //const FRAME_RESERVED_2: u8 = 10;
 //const FRAME_RESERVED_3: u8 = 13;
 
+// Every udp datagram starts with a header of kind, seq, ack and ack_bits
+const UDP_DATAGRAM_FRAME: u8 = 0;
+const UDP_DATAGRAM_ACK: u8 = 1;
+const UDP_HEADER_LEN: usize = 21;
+/// frames that arrive further ahead than this are dropped and retransmitted
+const UDP_REORDER_WINDOW: u64 = 1024;
+const UDP_ACK_INTERVAL: Duration = Duration::from_millis(10);
+const UDP_MIN_RETRANSMIT: Duration = Duration::from_millis(100);
+/// the retransmit timeout doubles with every retry, up to 2^UDP_MAX_BACKOFF
+const UDP_MAX_BACKOFF: u32 = 4;
+/// after this many retries of a single datagram the remote is considered gone
+const UDP_MAX_RETRIES: u32 = 20;
+
 #[derive(Debug)]
 pub(crate) enum Protocols {
     Tcp(TcpProtocol),
@@ -48,13 +67,48 @@ pub(crate) struct TcpProtocol {
     metrics: Arc<NetworkMetrics>,
 }
 
+/// UDP doesn't guarantee delivery nor order, so every frame is sent in its own
+/// datagram with a sequence number. The remote side acknowledges received
+/// datagrams, lost ones get retransmitted and out-of-order ones are held back
+/// until all previous frames arrived. This way the participant gets the same
+/// guarantees as with TCP.
 #[derive(Debug)]
 pub(crate) struct UdpProtocol {
-    socket: Arc<UdpSocket>,
-    remote_addr: SocketAddr,
+    sink: UdpSink,
     #[cfg(feature = "metrics")]
     metrics: Arc<NetworkMetrics>,
     data_in: Mutex<mpsc::UnboundedReceiver<Vec<u8>>>,
+    reliability: Mutex<UdpReliability>,
+    /// notifies `read_from_wire` when the remote stopped acknowledging
+    failed_s: mpsc::UnboundedSender<()>,
+    failed_r: Mutex<mpsc::UnboundedReceiver<()>>,
+}
+
+#[derive(Debug)]
+enum UdpSink {
+    Socket {
+        socket: Arc<UdpSocket>,
+        remote_addr: SocketAddr,
+    },
+    #[cfg(test)]
+    Mpsc(mpsc::UnboundedSender<Vec<u8>>),
+}
+
+#[derive(Debug, Default)]
+struct UdpReliability {
+    next_seq: u64,
+    unacked: BTreeMap<u64, UnackedDatagram>,
+    /// the lowest sequence number we didn't receive yet
+    next_expected: u64,
+    reorder: BTreeMap<u64, Frame>,
+    ack_pending: bool,
+}
+
+#[derive(Debug)]
+struct UnackedDatagram {
+    frame: Vec<u8>,
+    last_sent: Instant,
+    retries: u32,
 }
 
 //TODO: PERFORMACE: Use BufWriter and BufReader from std::io!
@@ -98,7 +152,7 @@ impl TcpProtocol {
                 Ok(Frame::gen_handshake(bytes))
             },
             FRAME_INIT => {
-                let mut bytes = [0u8; 32];
+                let mut bytes = [0u8; 64];
                 handle(r.read_exact(&mut bytes).await)?;
                 Ok(Frame::gen_init(bytes))
             },
@@ -114,7 +168,7 @@ impl TcpProtocol {
                 Ok(Frame::gen_close_stream(bytes))
             },
             FRAME_DATA_HEADER => {
-                let mut bytes = [0u8; 24];
+                let mut bytes = [0u8; 28];
                 handle(r.read_exact(&mut bytes).await)?;
                 Ok(Frame::gen_data_header(bytes))
             },
@@ -224,10 +278,15 @@ impl TcpProtocol {
                 w.write_all(&version[1].to_le_bytes()).await?;
                 w.write_all(&version[2].to_le_bytes()).await?;
             },
-            Frame::Init { pid, secret } => {
+            Frame::Init {
+                pid,
+                secret,
+                public_key,
+            } => {
                 w.write_all(&FRAME_INIT.to_be_bytes()).await?;
                 w.write_all(&pid.to_le_bytes()).await?;
                 w.write_all(&secret.to_le_bytes()).await?;
+                w.write_all(&public_key).await?;
             },
             Frame::Shutdown => {
                 w.write_all(&FRAME_SHUTDOWN.to_be_bytes()).await?;
@@ -246,11 +305,17 @@ impl TcpProtocol {
                 w.write_all(&FRAME_CLOSE_STREAM.to_be_bytes()).await?;
                 w.write_all(&sid.to_le_bytes()).await?;
             },
-            Frame::DataHeader { mid, sid, length } => {
+            Frame::DataHeader {
+                mid,
+                sid,
+                length,
+                checksum,
+            } => {
                 w.write_all(&FRAME_DATA_HEADER.to_be_bytes()).await?;
                 w.write_all(&mid.to_le_bytes()).await?;
                 w.write_all(&sid.to_le_bytes()).await?;
                 w.write_all(&length.to_le_bytes()).await?;
+                w.write_all(&checksum.to_le_bytes()).await?;
             },
             Frame::Data { mid, start, data } => {
                 w.write_all(&FRAME_DATA.to_be_bytes()).await?;
@@ -307,6 +372,110 @@ impl TcpProtocol {
     }
 }
 
+impl UdpSink {
+    async fn send(&self, datagram: &[u8]) {
+        match self {
+            UdpSink::Socket {
+                socket,
+                remote_addr,
+            } => {
+                if let Err(e) = socket.send_to(datagram, *remote_addr).await {
+                    // the datagram counts as lost and will be retransmitted
+                    debug!(?e, "Error sending udp datagram");
+                }
+            },
+            #[cfg(test)]
+            UdpSink::Mpsc(sender) => {
+                let _ = sender.unbounded_send(datagram.to_vec());
+            },
+        }
+    }
+}
+
+impl UdpReliability {
+    /// Assigns the next sequence number to an encoded frame and remembers it
+    /// until the remote side acknowledges it
+    fn register_sent(&mut self, frame: Vec<u8>, now: Instant) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.unacked.insert(seq, UnackedDatagram {
+            frame,
+            last_sent: now,
+            retries: 0,
+        });
+        seq
+    }
+
+    /// `ack` is the next sequence number the remote side expects, bit `i` of
+    /// `ack_bits` is set if `ack + 1 + i` was received out of order. Acks for
+    /// sequence numbers we never sent are ignored.
+    fn handle_ack(&mut self, ack: u64, ack_bits: u32) {
+        if ack > self.next_seq {
+            debug!(?ack, "Ignoring udp ack for a datagram that was never sent");
+            return;
+        }
+        self.unacked = self.unacked.split_off(&ack);
+        for i in 0..32 {
+            if ack_bits & (1 << i) == 0 {
+                continue;
+            }
+            match ack.checked_add(1 + i) {
+                Some(seq) if seq < self.next_seq => {
+                    self.unacked.remove(&seq);
+                },
+                _ => break,
+            }
+        }
+    }
+
+    /// Returns the acknowledgement for everything received so far
+    fn ack_header(&mut self) -> (u64, u32) {
+        self.ack_pending = false;
+        let ack_bits = self
+            .reorder
+            .range(self.next_expected + 1..self.next_expected + 33)
+            .fold(0u32, |bits, (seq, _)| {
+                bits | 1 << (seq - self.next_expected - 1)
+            });
+        (self.next_expected, ack_bits)
+    }
+
+    /// Returns all frames which are now ready to be handed to the
+    /// participant, in the order they were sent
+    fn receive(&mut self, seq: u64, frame: Frame) -> Vec<Frame> {
+        self.ack_pending = true;
+        if seq < self.next_expected || seq >= self.next_expected + UDP_REORDER_WINDOW {
+            // duplicate, or too far ahead. The latter will be retransmitted
+            return Vec::new();
+        }
+        self.reorder.insert(seq, frame);
+        let mut frames = Vec::new();
+        while let Some(frame) = self.reorder.remove(&self.next_expected) {
+            frames.push(frame);
+            self.next_expected += 1;
+        }
+        frames
+    }
+
+    /// Returns all datagrams whose retransmit timer expired, or `Err` if one
+    /// of them exceeded [`UDP_MAX_RETRIES`]
+    fn due_retransmits(&mut self, now: Instant) -> Result<Vec<(u64, Vec<u8>)>, ()> {
+        let mut due = Vec::new();
+        for (seq, datagram) in self.unacked.iter_mut() {
+            let timeout = UDP_MIN_RETRANSMIT * (1u32 << datagram.retries.min(UDP_MAX_BACKOFF));
+            if now.duration_since(datagram.last_sent) >= timeout {
+                if datagram.retries >= UDP_MAX_RETRIES {
+                    return Err(());
+                }
+                datagram.retries += 1;
+                datagram.last_sent = now;
+                due.push((*seq, datagram.frame.clone()));
+            }
+        }
+        Ok(due)
+    }
+}
+
 impl UdpProtocol {
     pub(crate) fn new(
         socket: Arc<UdpSocket>,
@@ -314,15 +483,191 @@ impl UdpProtocol {
         #[cfg(feature = "metrics")] metrics: Arc<NetworkMetrics>,
         data_in: mpsc::UnboundedReceiver<Vec<u8>>,
     ) -> Self {
+        let (failed_s, failed_r) = mpsc::unbounded();
         Self {
-            socket,
-            remote_addr,
+            sink: UdpSink::Socket {
+                socket,
+                remote_addr,
+            },
             #[cfg(feature = "metrics")]
             metrics,
             data_in: Mutex::new(data_in),
+            reliability: Mutex::new(UdpReliability::default()),
+            failed_s,
+            failed_r: Mutex::new(failed_r),
         }
     }
 
+    /// Creates a `UdpProtocol` which doesn't use a socket, but sends all
+    /// datagrams to `sink`, e.g. to simulate a lossy connection
+    #[cfg(test)]
+    pub(crate) fn new_mpsc(
+        sink: mpsc::UnboundedSender<Vec<u8>>,
+        #[cfg(feature = "metrics")] metrics: Arc<NetworkMetrics>,
+        data_in: mpsc::UnboundedReceiver<Vec<u8>>,
+    ) -> Self {
+        let (failed_s, failed_r) = mpsc::unbounded();
+        Self {
+            sink: UdpSink::Mpsc(sink),
+            #[cfg(feature = "metrics")]
+            metrics,
+            data_in: Mutex::new(data_in),
+            reliability: Mutex::new(UdpReliability::default()),
+            failed_s,
+            failed_r: Mutex::new(failed_r),
+        }
+    }
+
+    fn decode_frame(bytes: &[u8]) -> Frame {
+        macro_rules! fixed {
+            ($range:expr) => {
+                match bytes.get($range).and_then(|b| TryFrom::try_from(b).ok()) {
+                    Some(buf) => buf,
+                    None => return Frame::Raw(bytes.to_vec()),
+                }
+            };
+        }
+        match bytes.first() {
+            Some(&FRAME_HANDSHAKE) => Frame::gen_handshake(fixed!(1..20)),
+            Some(&FRAME_INIT) => Frame::gen_init(fixed!(1..65)),
+            Some(&FRAME_SHUTDOWN) => Frame::Shutdown,
+            Some(&FRAME_OPEN_STREAM) => Frame::gen_open_stream(fixed!(1..11)),
+            Some(&FRAME_CLOSE_STREAM) => Frame::gen_close_stream(fixed!(1..9)),
+            Some(&FRAME_DATA_HEADER) => Frame::gen_data_header(fixed!(1..29)),
+            Some(&FRAME_DATA) => {
+                let (mid, start, length) = Frame::gen_data(fixed!(1..19));
+                match bytes.get(19..19 + length as usize) {
+                    Some(data) => Frame::Data {
+                        mid,
+                        start,
+                        data: data.to_vec(),
+                    },
+                    None => Frame::Raw(bytes.to_vec()),
+                }
+            },
+            Some(&FRAME_RAW) => {
+                let length = Frame::gen_raw(fixed!(1..3));
+                match bytes.get(3..3 + length as usize) {
+                    Some(data) => Frame::Raw(data.to_vec()),
+                    None => Frame::Raw(bytes.to_vec()),
+                }
+            },
+            _ => Frame::Raw(bytes.to_vec()),
+        }
+    }
+
+    /// returns the length of the encoded frame in `buffer`
+    fn encode_frame(frame: Frame, buffer: &mut [u8]) -> usize {
+        match frame {
+            Frame::Handshake {
+                magic_number,
+                version,
+            } => {
+                let x = FRAME_HANDSHAKE.to_be_bytes();
+                buffer[0] = x[0];
+                buffer[1..8].copy_from_slice(&magic_number);
+                buffer[8..12].copy_from_slice(&version[0].to_le_bytes());
+                buffer[12..16].copy_from_slice(&version[1].to_le_bytes());
+                buffer[16..20].copy_from_slice(&version[2].to_le_bytes());
+                20
+            },
+            Frame::Init {
+                pid,
+                secret,
+                public_key,
+            } => {
+                buffer[0] = FRAME_INIT.to_be_bytes()[0];
+                buffer[1..17].copy_from_slice(&pid.to_le_bytes());
+                buffer[17..33].copy_from_slice(&secret.to_le_bytes());
+                buffer[33..65].copy_from_slice(&public_key);
+                65
+            },
+            Frame::Shutdown => {
+                buffer[0] = FRAME_SHUTDOWN.to_be_bytes()[0];
+                1
+            },
+            Frame::OpenStream {
+                sid,
+                prio,
+                promises,
+            } => {
+                buffer[0] = FRAME_OPEN_STREAM.to_be_bytes()[0];
+                buffer[1..9].copy_from_slice(&sid.to_le_bytes());
+                buffer[9] = prio.to_le_bytes()[0];
+                buffer[10] = promises.to_le_bytes()[0];
+                11
+            },
+            Frame::CloseStream { sid } => {
+                buffer[0] = FRAME_CLOSE_STREAM.to_be_bytes()[0];
+                buffer[1..9].copy_from_slice(&sid.to_le_bytes());
+                9
+            },
+            Frame::DataHeader {
+                mid,
+                sid,
+                length,
+                checksum,
+            } => {
+                buffer[0] = FRAME_DATA_HEADER.to_be_bytes()[0];
+                buffer[1..9].copy_from_slice(&mid.to_le_bytes());
+                buffer[9..17].copy_from_slice(&sid.to_le_bytes());
+                buffer[17..25].copy_from_slice(&length.to_le_bytes());
+                buffer[25..29].copy_from_slice(&checksum.to_le_bytes());
+                29
+            },
+            Frame::Data { mid, start, data } => {
+                buffer[0] = FRAME_DATA.to_be_bytes()[0];
+                buffer[1..9].copy_from_slice(&mid.to_le_bytes());
+                buffer[9..17].copy_from_slice(&start.to_le_bytes());
+                buffer[17..19].copy_from_slice(&(data.len() as u16).to_le_bytes());
+                buffer[19..(data.len() + 19)].clone_from_slice(&data[..]);
+                19 + data.len()
+            },
+            Frame::Raw(data) => {
+                buffer[0] = FRAME_RAW.to_be_bytes()[0];
+                buffer[1..3].copy_from_slice(&(data.len() as u16).to_le_bytes());
+                buffer[3..(data.len() + 3)].clone_from_slice(&data[..]);
+                3 + data.len()
+            },
+        }
+    }
+
+    async fn send_datagram(&self, kind: u8, seq: u64, (ack, ack_bits): (u64, u32), frame: &[u8]) {
+        let mut datagram = Vec::with_capacity(UDP_HEADER_LEN + frame.len());
+        datagram.push(kind);
+        datagram.extend_from_slice(&seq.to_le_bytes());
+        datagram.extend_from_slice(&ack.to_le_bytes());
+        datagram.extend_from_slice(&ack_bits.to_le_bytes());
+        datagram.extend_from_slice(frame);
+        self.sink.send(&datagram).await;
+    }
+
+    /// Retransmits lost datagrams and sends pending acknowledgements. Returns
+    /// `Err` if the remote side stopped acknowledging our datagrams.
+    async fn maintain(&self) -> Result<(), ()> {
+        let (retransmits, ack) = {
+            let mut reliability = self.reliability.lock().await;
+            let retransmits = reliability.due_retransmits(Instant::now())?;
+            let ack = if reliability.ack_pending || !retransmits.is_empty() {
+                Some(reliability.ack_header())
+            } else {
+                None
+            };
+            (retransmits, ack)
+        };
+        if let Some(ack) = ack {
+            if retransmits.is_empty() {
+                self.send_datagram(UDP_DATAGRAM_ACK, 0, ack, &[]).await;
+            }
+            for (seq, frame) in retransmits {
+                trace!(?seq, "Retransmitting udp datagram");
+                self.send_datagram(UDP_DATAGRAM_FRAME, seq, ack, &frame)
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
     pub async fn read_from_wire(
         &self,
         cid: Cid,
@@ -338,6 +683,7 @@ impl UdpProtocol {
             .wire_in_throughput
             .with_label_values(&[&cid.to_string()]);
         let mut data_in = self.data_in.lock().await;
+        let mut failed_r = self.failed_r.lock().await;
         let mut end_r = end_r.fuse();
         while let Some(bytes) = select! {
             r = data_in.next().fuse() => match r {
@@ -348,45 +694,50 @@ impl UdpProtocol {
                     None
                 }
             },
+            _ = failed_r.next().fuse() => {
+                w2c_cid_frame_s.send((cid, Err(()))).await.expect("Channel or Participant seems no longer to exist");
+                None
+            },
             _ = end_r => None,
         } {
             trace!("Got raw UDP message with len: {}", bytes.len());
-            let frame_no = bytes[0];
-            let frame = match frame_no {
-                FRAME_HANDSHAKE => {
-                    Frame::gen_handshake(*<&[u8; 19]>::try_from(&bytes[1..20]).unwrap())
-                },
-                FRAME_INIT => Frame::gen_init(*<&[u8; 32]>::try_from(&bytes[1..33]).unwrap()),
-                FRAME_SHUTDOWN => Frame::Shutdown,
-                FRAME_OPEN_STREAM => {
-                    Frame::gen_open_stream(*<&[u8; 10]>::try_from(&bytes[1..11]).unwrap())
-                },
-                FRAME_CLOSE_STREAM => {
-                    Frame::gen_close_stream(*<&[u8; 8]>::try_from(&bytes[1..9]).unwrap())
-                },
-                FRAME_DATA_HEADER => {
-                    Frame::gen_data_header(*<&[u8; 24]>::try_from(&bytes[1..25]).unwrap())
-                },
-                FRAME_DATA => {
-                    let (mid, start, length) =
-                        Frame::gen_data(*<&[u8; 18]>::try_from(&bytes[1..19]).unwrap());
-                    let mut data = vec![0; length as usize];
-                    #[cfg(feature = "metrics")]
-                    throughput_cache.inc_by(length as i64);
-                    data.copy_from_slice(&bytes[19..]);
-                    Frame::Data { mid, start, data }
-                },
-                FRAME_RAW => {
-                    let length = Frame::gen_raw(*<&[u8; 2]>::try_from(&bytes[1..3]).unwrap());
-                    let mut data = vec![0; length as usize];
-                    data.copy_from_slice(&bytes[3..]);
-                    Frame::Raw(data)
-                },
-                _ => Frame::Raw(bytes),
+            if bytes.len() < UDP_HEADER_LEN {
+                // not even a header, pass it on as it's probably a debug message
+                w2c_cid_frame_s
+                    .send((cid, Ok(Frame::Raw(bytes))))
+                    .await
+                    .unwrap();
+                continue;
+            }
+            let kind = bytes[0];
+            let seq = u64::from_le_bytes(*<&[u8; 8]>::try_from(&bytes[1..9]).unwrap());
+            let ack = u64::from_le_bytes(*<&[u8; 8]>::try_from(&bytes[9..17]).unwrap());
+            let ack_bits = u32::from_le_bytes(*<&[u8; 4]>::try_from(&bytes[17..21]).unwrap());
+            let frames = {
+                let mut reliability = self.reliability.lock().await;
+                match kind {
+                    UDP_DATAGRAM_FRAME => {
+                        reliability.handle_ack(ack, ack_bits);
+                        let frame = Self::decode_frame(&bytes[UDP_HEADER_LEN..]);
+                        reliability.receive(seq, frame)
+                    },
+                    UDP_DATAGRAM_ACK => {
+                        reliability.handle_ack(ack, ack_bits);
+                        Vec::new()
+                    },
+                    _ => vec![Frame::Raw(bytes)],
+                }
             };
-            #[cfg(feature = "metrics")]
-            metrics_cache.with_label_values(&frame).inc();
-            w2c_cid_frame_s.send((cid, Ok(frame))).await.unwrap();
+            for frame in frames {
+                #[cfg(feature = "metrics")]
+                {
+                    metrics_cache.with_label_values(&frame).inc();
+                    if let Frame::Data { ref data, .. } = frame {
+                        throughput_cache.inc_by(data.len() as i64);
+                    }
+                }
+                w2c_cid_frame_s.send((cid, Ok(frame))).await.unwrap();
+            }
         }
         trace!("Shutting down udp read()");
     }
@@ -403,93 +754,44 @@ impl UdpProtocol {
             .with_label_values(&[&cid.to_string()]);
         #[cfg(not(feature = "metrics"))]
         let _cid = cid;
-        while let Some(frame) = c2w_frame_r.next().await {
-            #[cfg(feature = "metrics")]
-            metrics_cache.with_label_values(&frame).inc();
-            let len = match frame {
-                Frame::Handshake {
-                    magic_number,
-                    version,
-                } => {
-                    let x = FRAME_HANDSHAKE.to_be_bytes();
-                    buffer[0] = x[0];
-                    buffer[1..8].copy_from_slice(&magic_number);
-                    buffer[8..12].copy_from_slice(&version[0].to_le_bytes());
-                    buffer[12..16].copy_from_slice(&version[1].to_le_bytes());
-                    buffer[16..20].copy_from_slice(&version[2].to_le_bytes());
-                    20
-                },
-                Frame::Init { pid, secret } => {
-                    buffer[0] = FRAME_INIT.to_be_bytes()[0];
-                    buffer[1..17].copy_from_slice(&pid.to_le_bytes());
-                    buffer[17..33].copy_from_slice(&secret.to_le_bytes());
-                    33
-                },
-                Frame::Shutdown => {
-                    buffer[0] = FRAME_SHUTDOWN.to_be_bytes()[0];
-                    1
-                },
-                Frame::OpenStream {
-                    sid,
-                    prio,
-                    promises,
-                } => {
-                    buffer[0] = FRAME_OPEN_STREAM.to_be_bytes()[0];
-                    buffer[1..9].copy_from_slice(&sid.to_le_bytes());
-                    buffer[9] = prio.to_le_bytes()[0];
-                    buffer[10] = promises.to_le_bytes()[0];
-                    11
-                },
-                Frame::CloseStream { sid } => {
-                    buffer[0] = FRAME_CLOSE_STREAM.to_be_bytes()[0];
-                    buffer[1..9].copy_from_slice(&sid.to_le_bytes());
-                    9
-                },
-                Frame::DataHeader { mid, sid, length } => {
-                    buffer[0] = FRAME_DATA_HEADER.to_be_bytes()[0];
-                    buffer[1..9].copy_from_slice(&mid.to_le_bytes());
-                    buffer[9..17].copy_from_slice(&sid.to_le_bytes());
-                    buffer[17..25].copy_from_slice(&length.to_le_bytes());
-                    25
-                },
-                Frame::Data { mid, start, data } => {
-                    buffer[0] = FRAME_DATA.to_be_bytes()[0];
-                    buffer[1..9].copy_from_slice(&mid.to_le_bytes());
-                    buffer[9..17].copy_from_slice(&start.to_le_bytes());
-                    buffer[17..19].copy_from_slice(&(data.len() as u16).to_le_bytes());
-                    buffer[19..(data.len() + 19)].clone_from_slice(&data[..]);
-                    #[cfg(feature = "metrics")]
-                    throughput_cache.inc_by(data.len() as i64);
-                    19 + data.len()
-                },
-                Frame::Raw(data) => {
-                    buffer[0] = FRAME_RAW.to_be_bytes()[0];
-                    buffer[1..3].copy_from_slice(&(data.len() as u16).to_le_bytes());
-                    buffer[3..(data.len() + 3)].clone_from_slice(&data[..]);
-                    3 + data.len()
-                },
-            };
-            let mut start = 0;
-            while start < len {
-                trace!(?start, ?len, "Splitting up udp frame in multiple packages");
-                match self
-                    .socket
-                    .send_to(&buffer[start..len], self.remote_addr)
-                    .await
-                {
-                    Ok(n) => {
-                        start += n;
-                        if n != len {
-                            error!(
-                                "THIS DOESN'T WORK, as RECEIVER CURRENTLY ONLY HANDLES 1 FRAME \
-                                 per UDP message. splitting up will fail!"
-                            );
+        let mut last_maintenance = Instant::now();
+        loop {
+            select! {
+                frame = c2w_frame_r.next().fuse() => match frame {
+                    Some(frame) => {
+                        #[cfg(feature = "metrics")]
+                        {
+                            metrics_cache.with_label_values(&frame).inc();
+                            if let Frame::Data { ref data, .. } = frame {
+                                throughput_cache.inc_by(data.len() as i64);
+                            }
                         }
+                        let len = Self::encode_frame(frame, &mut buffer);
+                        let frame = buffer[..len].to_vec();
+                        let (seq, ack) = {
+                            let mut reliability = self.reliability.lock().await;
+                            let seq = reliability.register_sent(frame.clone(), Instant::now());
+                            (seq, reliability.ack_header())
+                        };
+                        self.send_datagram(UDP_DATAGRAM_FRAME, seq, ack, &frame).await;
                     },
-                    Err(e) => error!(?e, "Need to handle that error!"),
+                    None => break,
+                },
+                _ = async_std::task::sleep(UDP_ACK_INTERVAL).fuse() => {},
+            }
+            if last_maintenance.elapsed() >= UDP_ACK_INTERVAL {
+                last_maintenance = Instant::now();
+                if self.maintain().await.is_err() {
+                    info!("Remote stopped acknowledging udp datagrams, closing the channel");
+                    let _ = self.failed_s.unbounded_send(());
+                    break;
                 }
             }
         }
+        // Datagrams which are still unacknowledged stay in `reliability`, so they
+        // get retransmitted if this protocol continues to be used after the
+        // handshake
+        let _ = self.maintain().await;
         trace!("Shutting down udp write()");
     }
 }
@@ -497,7 +799,10 @@ impl UdpProtocol {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{metrics::NetworkMetrics, types::Pid};
+    use crate::{
+        metrics::NetworkMetrics,
+        types::{Pid, Sid},
+    };
     use async_std::net;
     use futures::{executor::block_on, stream::StreamExt};
     use std::sync::Arc;
@@ -593,4 +898,148 @@ mod tests {
             t.join().unwrap();
         });
     }
+
+    #[test]
+    fn udp_data_header_keeps_checksum() {
+        let mut buffer = [0u8; 2000];
+        let len = UdpProtocol::encode_frame(
+            Frame::DataHeader {
+                mid: 12,
+                sid: Sid::new(3),
+                length: 1337,
+                checksum: 0xDEAD_BEEF,
+            },
+            &mut buffer,
+        );
+        match UdpProtocol::decode_frame(&buffer[..len]) {
+            Frame::DataHeader {
+                mid,
+                sid,
+                length,
+                checksum,
+            } => {
+                assert_eq!(mid, 12);
+                assert_eq!(sid, Sid::new(3));
+                assert_eq!(length, 1337);
+                assert_eq!(checksum, 0xDEAD_BEEF);
+            },
+            frame => panic!("unexpected frame {:?}", frame),
+        }
+        // a truncated header must not be mistaken for a valid one
+        assert!(matches!(
+            UdpProtocol::decode_frame(&buffer[..len - 1]),
+            Frame::Raw(_)
+        ));
+    }
+
+    /// forwards datagrams, but loses every 7th and delays every 5th until
+    /// after the next one
+    fn lossy_link(mut from: mpsc::UnboundedReceiver<Vec<u8>>, to: mpsc::UnboundedSender<Vec<u8>>) {
+        block_on(async {
+            let mut held = None;
+            let mut i = 0u32;
+            while let Some(datagram) = from.next().await {
+                i += 1;
+                if i % 7 == 0 {
+                    continue;
+                }
+                if i % 5 == 0 && held.is_none() {
+                    held = Some(datagram);
+                    continue;
+                }
+                let _ = to.unbounded_send(datagram);
+                if let Some(held) = held.take() {
+                    let _ = to.unbounded_send(held);
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn udp_ignores_acks_for_unsent_datagrams() {
+        let now = Instant::now();
+        let mut reliability = UdpReliability::default();
+        for _ in 0..4 {
+            reliability.register_sent(Vec::new(), now);
+        }
+
+        reliability.handle_ack(u64::MAX, u32::MAX);
+        assert_eq!(reliability.unacked.len(), 4);
+        reliability.handle_ack(5, 0);
+        assert_eq!(reliability.unacked.len(), 4);
+
+        // 0 and 2 arrived, 1 and 3 are missing, bits beyond `next_seq` are ignored
+        reliability.handle_ack(1, u32::MAX - 2);
+        assert_eq!(
+            reliability.unacked.keys().copied().collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        reliability.handle_ack(4, 0);
+        assert!(reliability.unacked.is_empty());
+    }
+
+    #[test]
+    fn udp_lossy_link_delivers_in_order() {
+        let pid = Pid::new();
+        let metrics = Arc::new(NetworkMetrics::new(&pid).unwrap());
+        let (a_out_s, a_out_r) = mpsc::unbounded();
+        let (a_in_s, a_in_r) = mpsc::unbounded();
+        let (b_out_s, b_out_r) = mpsc::unbounded();
+        let (b_in_s, b_in_r) = mpsc::unbounded();
+        let a = UdpProtocol::new_mpsc(a_out_s, Arc::clone(&metrics), a_in_r);
+        let b = UdpProtocol::new_mpsc(b_out_s, metrics, b_in_r);
+        let link_ab = std::thread::spawn(move || lossy_link(a_out_r, b_in_s));
+        let link_ba = std::thread::spawn(move || lossy_link(b_out_r, a_in_s));
+
+        const FRAMES: u64 = 200;
+        let (a_frames_s, a_frames_r) = mpsc::unbounded::<Frame>();
+        let (b_frames_s, b_frames_r) = mpsc::unbounded::<Frame>();
+        let (mut a_w2c_s, _a_w2c_r) = mpsc::unbounded::<C2pFrame>();
+        let (mut b_w2c_s, mut b_w2c_r) = mpsc::unbounded::<C2pFrame>();
+        let (a_stop_s, a_stop_r) = oneshot::channel();
+        let (b_stop_s, b_stop_r) = oneshot::channel();
+        for mid in 0..FRAMES {
+            a_frames_s
+                .unbounded_send(Frame::Data {
+                    mid,
+                    start: 0,
+                    data: vec![mid as u8; 100],
+                })
+                .unwrap();
+        }
+
+        let check = async {
+            for i in 0..FRAMES {
+                match b_w2c_r.next().await {
+                    Some((2, Ok(Frame::Data { mid, start, data }))) => {
+                        assert_eq!(mid, i);
+                        assert_eq!(start, 0);
+                        assert_eq!(data, vec![i as u8; 100]);
+                    },
+                    frame => panic!("unexpected frame {:?}", frame),
+                }
+            }
+            // also wait till `a` knows that everything arrived
+            while !a.reliability.lock().await.unacked.is_empty() {
+                async_std::task::sleep(UDP_ACK_INTERVAL).await;
+            }
+            drop(a_frames_s);
+            drop(b_frames_s);
+            a_stop_s.send(()).unwrap();
+            b_stop_s.send(()).unwrap();
+        };
+        block_on(async {
+            futures::join!(
+                a.read_from_wire(1, &mut a_w2c_s, a_stop_r),
+                a.write_to_wire(1, a_frames_r),
+                b.read_from_wire(2, &mut b_w2c_s, b_stop_r),
+                b.write_to_wire(2, b_frames_r),
+                check,
+            );
+        });
+        assert!(b.reliability.into_inner().reorder.is_empty());
+        drop(a);
+        link_ab.join().unwrap();
+        link_ba.join().unwrap();
+    }
 }
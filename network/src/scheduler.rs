@@ -524,7 +524,7 @@ impl Scheduler {
                     .instrument(tracing::info_span!("handshake", ?cid))
                     .await
                 {
-                    Ok((pid, sid, secret, leftover_cid_frame)) => {
+                    Ok((pid, sid, secret, session_keys, leftover_cid_frame)) => {
                         trace!(
                             ?cid,
                             ?pid,
@@ -542,6 +542,7 @@ impl Scheduler {
                             ) = BParticipant::new(
                                 pid,
                                 sid,
+                                session_keys,
                                 #[cfg(feature = "metrics")]
                                 Arc::clone(&metrics),
                             );
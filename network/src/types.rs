@@ -1,3 +1,4 @@
+use crate::crypto::PUBLIC_KEY_LEN;
 use bitflags::bitflags;
 use rand::Rng;
 use std::convert::TryFrom;
@@ -16,7 +17,10 @@ bitflags! {
         /// is the same when received on the other.
         const ORDERED = 0b00000001;
         /// this will guarantee that messages received haven't been altered by errors,
-        /// like bit flips, this is done with a checksum.
+        /// like bit flips, this is done with a checksum. Messages that don't match
+        /// their checksum are dropped and reported as [`StreamError::Corrupted`].
+        ///
+        /// [`StreamError::Corrupted`]: crate::api::StreamError::Corrupted
         const CONSISTENCY = 0b00000010;
         /// this will guarantee that the other side will receive every message exactly
         /// once no messages are dropped
@@ -26,7 +30,11 @@ bitflags! {
         #[cfg(feature = "compression")]
         const COMPRESSED = 0b00001000;
         /// this will enable the internal encryption on this
-        /// [`Stream`](crate::api::Stream)
+        /// [`Stream`](crate::api::Stream). Every message is encrypted and
+        /// authenticated with keys negotiated during the handshake; a message
+        /// which was altered on the way closes the [`Participant`].
+        ///
+        /// [`Participant`]: crate::api::Participant
         const ENCRYPTED = 0b00010000;
     }
 }
@@ -36,7 +44,7 @@ impl Promises {
 }
 
 pub(crate) const VELOREN_MAGIC_NUMBER: [u8; 7] = [86, 69, 76, 79, 82, 69, 78]; //VELOREN
-pub const VELOREN_NETWORK_VERSION: [u32; 3] = [0, 5, 0];
+pub const VELOREN_NETWORK_VERSION: [u32; 3] = [0, 8, 0];
 pub(crate) const STREAM_ID_OFFSET1: Sid = Sid::new(0);
 pub(crate) const STREAM_ID_OFFSET2: Sid = Sid::new(u64::MAX / 2);
 
@@ -65,6 +73,7 @@ pub(crate) enum Frame {
     Init {
         pid: Pid,
         secret: u128,
+        public_key: [u8; PUBLIC_KEY_LEN],
     },
     Shutdown, /* Shutdown this channel gracefully, if all channels are shutdown, Participant
                * is deleted */
@@ -80,6 +89,9 @@ pub(crate) enum Frame {
         mid: Mid,
         sid: Sid,
         length: u64,
+        /// crc32 of the whole message, only checked for streams with
+        /// `Promises::CONSISTENCY`
+        checksum: u32,
     },
     Data {
         mid: Mid,
@@ -139,10 +151,11 @@ impl Frame {
         }
     }
 
-    pub fn gen_init(buf: [u8; 32]) -> Self {
+    pub fn gen_init(buf: [u8; 64]) -> Self {
         Frame::Init {
             pid: Pid::from_le_bytes(*<&[u8; 16]>::try_from(&buf[0..16]).unwrap()),
             secret: u128::from_le_bytes(*<&[u8; 16]>::try_from(&buf[16..32]).unwrap()),
+            public_key: *<&[u8; PUBLIC_KEY_LEN]>::try_from(&buf[32..64]).unwrap(),
         }
     }
 
@@ -160,11 +173,12 @@ impl Frame {
         }
     }
 
-    pub fn gen_data_header(buf: [u8; 24]) -> Self {
+    pub fn gen_data_header(buf: [u8; 28]) -> Self {
         Frame::DataHeader {
             mid: Mid::from_le_bytes(*<&[u8; 8]>::try_from(&buf[0..8]).unwrap()),
             sid: Sid::from_le_bytes(*<&[u8; 8]>::try_from(&buf[8..16]).unwrap()),
             length: u64::from_le_bytes(*<&[u8; 8]>::try_from(&buf[16..24]).unwrap()),
+            checksum: u32::from_le_bytes(*<&[u8; 4]>::try_from(&buf[24..28]).unwrap()),
         }
     }
 
@@ -46,6 +46,36 @@ fn stream_simple_3msg() {
     assert_eq!(block_on(s1_b.recv()), Ok("3rdMessage".to_string()));
 }
 
+#[test]
+fn stream_encrypted() {
+    let (_, _) = helper::setup(false, 0);
+    let (n_a, f_a) = Network::new(Pid::fake(0));
+    std::thread::spawn(f_a);
+    let (n_b, f_b) = Network::new(Pid::fake(1));
+    std::thread::spawn(f_b);
+    let addr = tcp();
+    block_on(async {
+        n_a.listen(addr.clone()).await.unwrap();
+        let p_b = n_b.connect(addr).await.unwrap();
+        let p_a = n_a.connected().await.unwrap();
+
+        let mut s_a = p_a
+            .open(16, Promises::ORDERED | Promises::ENCRYPTED)
+            .await
+            .unwrap();
+        let mut s_b = p_b.opened().await.unwrap();
+
+        let big = vec![42u8; 5000];
+        s_a.send("Hello World").unwrap();
+        s_a.send(&big).unwrap();
+        assert_eq!(s_b.recv().await, Ok("Hello World".to_string()));
+        assert_eq!(s_b.recv().await, Ok(big));
+
+        s_b.send(1337u32).unwrap();
+        assert_eq!(s_a.recv().await, Ok(1337u32));
+    });
+}
+
 #[test]
 fn stream_simple_udp() {
     let (_, _) = helper::setup(false, 0);
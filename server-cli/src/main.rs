@@ -144,6 +144,8 @@ fn main() -> io::Result<()> {
     // TODO: consider integrating this into Clock::start?
     clock.tick(Duration::from_millis(1000 / TPS));
 
+    let mut player_count = 0;
+
     loop {
         // Terminate the server if instructed to do so by the shutdown coordinator
         if shutdown_coordinator.check(&mut server, &settings) {
@@ -154,11 +156,19 @@ fn main() -> io::Result<()> {
             .tick(Input::default(), clock.get_last_delta())
             .expect("Failed to tick server");
 
+        let mut players_left = false;
         for event in events {
             match event {
                 Event::ClientConnected { entity: _ } => info!("Client connected!"),
-                Event::ClientDisconnected { entity: _ } => info!("Client disconnected!"),
+                Event::ClientDisconnected { entity: _ } => {
+                    info!("Client disconnected!");
+                    players_left = true;
+                },
                 Event::Chat { entity: _, msg } => info!("[Client] {}", msg),
+                Event::ShutdownRequested {
+                    grace_period,
+                    reason,
+                } => shutdown_coordinator.initiate_shutdown(&mut server, grace_period, reason),
             }
         }
 
@@ -168,6 +178,14 @@ fn main() -> io::Result<()> {
         common::util::tracy_client::finish_continuous_frame!();
 
         if let Some(tui) = tui.as_ref() {
+            // Players only show up once they registered, so their number is checked
+            // as well
+            let new_player_count = server.number_of_registered_players();
+            if players_left || new_player_count != player_count {
+                player_count = new_player_count;
+                tui.set_player_names(server.player_aliases());
+            }
+
             match tui.msg_r.try_recv() {
                 Ok(msg) => match msg {
                     Message::AbortShutdown => shutdown_coordinator.abort_shutdown(&mut server),
@@ -188,6 +206,10 @@ fn main() -> io::Result<()> {
                     Message::RemoveAdmin(username) => {
                         server.remove_admin(&username);
                     },
+                    Message::Command(cmd) => {
+                        info!("> {}", cmd);
+                        server.process_console_cmd(&cmd);
+                    },
                 },
                 Err(mpsc::TryRecvError::Empty) | Err(mpsc::TryRecvError::Disconnected) => {},
             }
@@ -1,4 +1,5 @@
 use crate::logging::LOG;
+use common::cmd::{self, ChatCommand};
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
@@ -8,7 +9,7 @@ use std::{
     io::{self, Write},
     sync::{
         atomic::{AtomicBool, Ordering},
-        mpsc, Arc,
+        mpsc, Arc, Mutex,
     },
     time::Duration,
 };
@@ -24,10 +25,14 @@ use tui::{
 #[derive(Debug, Clone)]
 pub enum Message {
     AbortShutdown,
-    Shutdown { grace_period: Duration },
+    Shutdown {
+        grace_period: Duration,
+    },
     Quit,
     AddAdmin(String),
     RemoveAdmin(String),
+    /// A chat command to execute as the server console
+    Command(String),
 }
 
 pub struct Command<'a> {
@@ -99,6 +104,10 @@ pub const COMMANDS: [Command; 5] = [
             for command in COMMANDS.iter() {
                 info!("{} - {}", command.name, command.description)
             }
+            info!(
+                "Chat commands like kick or tp can be used as well, '/help' lists them. A leading \
+                 '/' runs the chat command even if there is a console command of the same name."
+            );
             info!("================");
         },
     },
@@ -109,10 +118,15 @@ pub struct Tui {
     background: Option<std::thread::JoinHandle<()>>,
     basic: bool,
     running: Arc<AtomicBool>,
+    player_names: Arc<Mutex<Vec<String>>>,
 }
 
 impl Tui {
-    fn handle_events(input: &mut String, msg_s: &mut mpsc::Sender<Message>) {
+    fn handle_events(
+        input: &mut String,
+        msg_s: &mut mpsc::Sender<Message>,
+        player_names: &Mutex<Vec<String>>,
+    ) {
         use crossterm::event::*;
         if let Event::Key(event) = read().unwrap() {
             match event.code {
@@ -127,6 +141,7 @@ impl Tui {
                 KeyCode::Backspace => {
                     input.pop();
                 },
+                KeyCode::Tab => complete(input, &player_names.lock().unwrap()),
                 KeyCode::Enter => {
                     debug!(?input, "tui mode: command entered");
                     parse_command(input, msg_s);
@@ -142,6 +157,8 @@ impl Tui {
         let (mut msg_s, msg_r) = mpsc::channel();
         let running = Arc::new(AtomicBool::new(true));
         let running2 = Arc::clone(&running);
+        let player_names = Arc::new(Mutex::new(Vec::new()));
+        let player_names2 = Arc::clone(&player_names);
 
         let background = if basic {
             std::thread::spawn(move || {
@@ -231,7 +248,7 @@ impl Tui {
                         warn!(?e, "couldn't draw frame");
                     };
                     if crossterm::event::poll(Duration::from_millis(100)).unwrap() {
-                        Self::handle_events(&mut input, &mut msg_s);
+                        Self::handle_events(&mut input, &mut msg_s, &player_names2);
                     };
                 }
             }))
@@ -242,9 +259,15 @@ impl Tui {
             background,
             basic,
             running,
+            player_names,
         }
     }
 
+    /// Updates the aliases of the players online, used for tab completion
+    pub fn set_player_names(&self, names: Vec<String>) {
+        *self.player_names.lock().unwrap() = names;
+    }
+
     pub fn shutdown(basic: bool) {
         if !basic {
             let mut stdout = io::stdout();
@@ -263,6 +286,12 @@ impl Drop for Tui {
 }
 
 fn parse_command(input: &str, msg_s: &mut mpsc::Sender<Message>) {
+    let input = input.trim();
+    if input.starts_with('/') {
+        msg_s.send(Message::Command(input.to_owned())).unwrap();
+        return;
+    }
+
     let mut args = input.split_whitespace();
 
     if let Some(cmd_name) = args.next() {
@@ -294,8 +323,67 @@ fn parse_command(input: &str, msg_s: &mut mpsc::Sender<Message>) {
                     cmd(args, msg_s)
                 },
             }
+        } else if cmd_name.parse::<ChatCommand>().is_ok() {
+            msg_s.send(Message::Command(input.to_owned())).unwrap();
         } else {
             error!("{} not found", cmd_name);
         }
     }
 }
+
+/// Completes the last word of `input` with the console commands, or the
+/// arguments of the chat command being typed. If there are several
+/// completions, they are logged and the word is completed as far as they
+/// agree.
+fn complete(input: &mut String, player_names: &[String]) {
+    let mut completions = if input.starts_with('/') {
+        cmd::complete(input, player_names)
+    } else {
+        // Chat commands don't need the leading '/' in the console
+        let mut completions = cmd::complete(&format!("/{}", input), player_names)
+            .into_iter()
+            .map(|completion| match completion.strip_prefix('/') {
+                Some(completion) => completion.to_owned(),
+                None => completion,
+            })
+            .collect::<Vec<_>>();
+        if !input.contains(char::is_whitespace) {
+            completions.extend(
+                COMMANDS
+                    .iter()
+                    .map(|cmd| cmd.name)
+                    .filter(|name| name.starts_with(input.as_str()))
+                    .map(str::to_owned),
+            );
+        }
+        completions
+    };
+    completions.sort();
+    completions.dedup();
+
+    let word_start = input.rfind(char::is_whitespace).map_or(0, |i| {
+        i + input[i..].chars().next().map_or(1, char::len_utf8)
+    });
+    match completions.as_slice() {
+        [] => {},
+        [completion] => {
+            input.truncate(word_start);
+            input.push_str(completion);
+            input.push(' ');
+        },
+        [first, rest @ ..] => {
+            let common_len = rest.iter().fold(first.len(), |len, completion| {
+                first[..len]
+                    .char_indices()
+                    .zip(completion.chars())
+                    .find(|((_, a), b)| a != b)
+                    .map_or(len.min(completion.len()), |((i, _), _)| i)
+            });
+            if common_len > input.len() - word_start {
+                input.truncate(word_start);
+                input.push_str(&first[..common_len]);
+            }
+            info!("{}", completions.join("  "));
+        },
+    }
+}
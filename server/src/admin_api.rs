@@ -0,0 +1,431 @@
+//! HTTP/JSON API for administrating the server without a game client. It is
+//! served on `Settings::admin_address`, which only accepts local connections
+//! by default, and only enabled when `Settings::admin_api_token` is set. Every
+//! request needs the token in an `Authorization: Bearer <token>` header.
+//!
+//! The API doesn't share the metrics listener, because that one is usually
+//! exposed to the network for collecting metrics. With its own listener the
+//! API stays local even if the token leaks.
+//!
+//! - `GET /admin/players`
+//! - `POST /admin/kick` with `{"player": "name", "reason": "..."}`
+//! - `POST /admin/ban` with `{"player": "name", "duration": "7d", "reason":
+//!   "..."}`
+//! - `POST /admin/broadcast` with `{"message": "..."}`
+//! - `POST /admin/reload_settings`
+//! - `POST /admin/shutdown` with `{"grace_period": 60, "reason": "..."}`
+
+use crate::{cmd, settings::EditableSettings, Event, Server};
+use common::comp::{self, ChatType};
+use crossbeam::channel;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use specs::{Join, WorldExt};
+use std::{
+    io::Read,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+use tiny_http::{Header, Method, Request, Response, StatusCode};
+use tracing::{debug, error, info, warn};
+
+/// How long the HTTP thread waits for the server to answer a request
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Requests with a larger body are rejected
+const MAX_BODY_SIZE: u64 = 64 * 1024;
+
+#[derive(Debug, Deserialize)]
+pub struct Kick {
+    pub player: String,
+    #[serde(default)]
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Ban {
+    pub player: String,
+    /// Duration of the ban like `30m`, `12h` or `7d`, the ban is permanent if
+    /// there is none
+    #[serde(default)]
+    pub duration: Option<String>,
+    #[serde(default)]
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Broadcast {
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Shutdown {
+    /// Seconds to wait before shutting down
+    pub grace_period: u64,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum AdminRequest {
+    Players,
+    Kick(Kick),
+    Ban(Ban),
+    Broadcast(Broadcast),
+    ReloadSettings,
+    Shutdown(Shutdown),
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlayerInfo {
+    pub alias: String,
+    pub uuid: String,
+    pub admin: bool,
+    /// Position of the player's character, if they are in game
+    pub pos: Option<[f32; 3]>,
+}
+
+/// The result of a request, serialized as the response body. Errors are
+/// answered with `{"error": "..."}`.
+pub type AdminResponse = Result<Value, String>;
+
+pub type PendingRequest = (AdminRequest, channel::Sender<AdminResponse>);
+
+/// The admin API's HTTP thread, which is stopped when this is dropped
+pub struct AdminApi {
+    requests: channel::Receiver<PendingRequest>,
+    running: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl AdminApi {
+    /// Serves the admin API on `addr`
+    pub fn run(addr: SocketAddr, token: String) -> Result<Self, String> {
+        if !addr.ip().is_loopback() {
+            warn!(
+                ?addr,
+                "The admin API accepts connections from other machines, it should only be \
+                 reachable through an encrypted connection"
+            );
+        }
+        let server = tiny_http::Server::http(addr)
+            .map_err(|e| format!("Failed to serve the admin API on {}: {}", addr, e))?;
+        let (requests_s, requests_r) = channel::unbounded();
+        let listener = AdminApiListener {
+            token,
+            requests: requests_s,
+        };
+        let running = Arc::new(AtomicBool::new(true));
+        let running2 = Arc::clone(&running);
+
+        let handle = thread::spawn(move || {
+            const TIMEOUT: Duration = Duration::from_secs(1);
+            debug!("starting tiny_http server to serve the admin API");
+            while running2.load(Ordering::Relaxed) {
+                match server.recv_timeout(TIMEOUT) {
+                    Ok(Some(request)) => listener.handle(request),
+                    Ok(None) => continue,
+                    Err(e) => {
+                        error!(?e, "admin API http server error");
+                        break;
+                    },
+                }
+            }
+            debug!("stopping tiny_http server to serve the admin API");
+        });
+
+        Ok(Self {
+            requests: requests_r,
+            running,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for AdminApi {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                error!("Error shutting down the admin API");
+            }
+        }
+    }
+}
+
+/// Answers admin API requests on the HTTP thread, by passing them on to the
+/// server and waiting for the result
+struct AdminApiListener {
+    token: String,
+    requests: channel::Sender<PendingRequest>,
+}
+
+impl AdminApiListener {
+    fn handle(&self, mut request: Request) {
+        let (status, body) = self.answer(&mut request);
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(
+                "Content-Type: application/json"
+                    .parse::<Header>()
+                    .expect("Invalid header"),
+            );
+        if let Err(e) = request.respond(response) {
+            error!(?e, "The admin API had encountered an error with answering");
+        }
+    }
+
+    fn answer(&self, request: &mut Request) -> (StatusCode, Value) {
+        let authorization = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Authorization"))
+            .map(|header| header.value.as_str());
+        if !self.is_authorized(authorization) {
+            warn!(
+                url = ?request.url(),
+                remote_addr = ?request.remote_addr(),
+                "Unauthorized admin API request"
+            );
+            return error_response(401, "Unauthorized");
+        }
+
+        let mut body = String::new();
+        if let Err(e) = request
+            .as_reader()
+            .take(MAX_BODY_SIZE)
+            .read_to_string(&mut body)
+        {
+            return error_response(400, &format!("Failed to read the request body: {}", e));
+        }
+
+        let admin_request = match parse_request(request.method(), request.url(), &body) {
+            Ok(Some(admin_request)) => admin_request,
+            Ok(None) => return error_response(404, "Not found"),
+            Err(e) => return error_response(400, &e),
+        };
+        info!(?admin_request, "Admin API request");
+
+        let (response_s, response_r) = channel::bounded(1);
+        if self.requests.send((admin_request, response_s)).is_err() {
+            return error_response(503, "The server is shutting down");
+        }
+        match response_r.recv_timeout(RESPONSE_TIMEOUT) {
+            Ok(Ok(value)) => (StatusCode(200), value),
+            Ok(Err(e)) => error_response(400, &e),
+            Err(_) => error_response(503, "The server didn't answer in time"),
+        }
+    }
+
+    /// Checks the value of the `Authorization` header of a request
+    fn is_authorized(&self, authorization: Option<&str>) -> bool {
+        authorization
+            .and_then(|authorization| authorization.strip_prefix("Bearer "))
+            .map_or(false, |token| constant_time_eq(token.trim(), &self.token))
+    }
+}
+
+fn error_response(status: u16, error: &str) -> (StatusCode, Value) {
+    (StatusCode(status), json!({ "error": error }))
+}
+
+/// Compares the tokens without leaking how much of them matched through the
+/// response time
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Parses a request to `url`, returning `None` if there is no such endpoint
+fn parse_request(method: &Method, url: &str, body: &str) -> Result<Option<AdminRequest>, String> {
+    fn parse_body<T: DeserializeOwned>(body: &str) -> Result<T, String> {
+        serde_json::from_str(body).map_err(|e| format!("Invalid request body: {}", e))
+    }
+
+    let path = url.split('?').next().unwrap_or(url).trim_end_matches('/');
+    Ok(Some(match (method, path) {
+        (Method::Get, "/admin/players") => AdminRequest::Players,
+        (Method::Post, "/admin/kick") => AdminRequest::Kick(parse_body(body)?),
+        (Method::Post, "/admin/ban") => AdminRequest::Ban(parse_body(body)?),
+        (Method::Post, "/admin/broadcast") => AdminRequest::Broadcast(parse_body(body)?),
+        (Method::Post, "/admin/reload_settings") => AdminRequest::ReloadSettings,
+        (Method::Post, "/admin/shutdown") => AdminRequest::Shutdown(parse_body(body)?),
+        _ => return Ok(None),
+    }))
+}
+
+impl Server {
+    /// Answers the requests received by the admin API since the last tick
+    pub(crate) fn handle_admin_requests(&mut self, frontend_events: &mut Vec<Event>) {
+        let requests = match &self.admin_api {
+            Some(admin_api) => admin_api.requests.try_iter().collect::<Vec<_>>(),
+            None => return,
+        };
+        for (request, response_s) in requests {
+            let response = self.handle_admin_request(request, frontend_events);
+            // The HTTP thread might have given up on waiting
+            let _ = response_s.send(response);
+        }
+    }
+
+    fn handle_admin_request(
+        &mut self,
+        request: AdminRequest,
+        frontend_events: &mut Vec<Event>,
+    ) -> AdminResponse {
+        match request {
+            AdminRequest::Players => {
+                let ecs = self.state.ecs();
+                let positions = ecs.read_storage::<comp::Pos>();
+                let admins = ecs.read_storage::<comp::Admin>();
+                let players = (
+                    &ecs.read_storage::<comp::Player>(),
+                    positions.maybe(),
+                    admins.maybe(),
+                )
+                    .join()
+                    .map(|(player, pos, admin)| PlayerInfo {
+                        alias: player.alias.clone(),
+                        uuid: player.uuid().to_string(),
+                        admin: admin.is_some(),
+                        pos: pos.map(|pos| pos.0.into_array()),
+                    })
+                    .collect::<Vec<_>>();
+                serde_json::to_value(players).map_err(|e| e.to_string())
+            },
+            AdminRequest::Kick(Kick { player, reason }) => {
+                cmd::kick(self, &player, &reason).map(|message| json!({ "message": message }))
+            },
+            AdminRequest::Ban(Ban {
+                player,
+                duration,
+                reason,
+            }) => {
+                let duration = match duration {
+                    Some(duration) => Some((
+                        cmd::parse_ban_duration(&duration)
+                            .ok_or_else(|| format!("Invalid ban duration '{}'", duration))?,
+                        duration,
+                    )),
+                    None => None,
+                };
+                cmd::ban(self, &player, duration, &reason, None)
+                    .map(|message| json!({ "message": message }))
+            },
+            AdminRequest::Broadcast(Broadcast { message }) => {
+                self.notify_registered_clients(
+                    ChatType::Meta.server_msg(format!("[Server] {}", message)),
+                );
+                Ok(json!({ "message": "Message sent" }))
+            },
+            AdminRequest::ReloadSettings => {
+                // Unlike on startup, files with errors are not replaced by the defaults, as
+                // that would e.g. lift all bans
+                let editable_settings = EditableSettings::try_load(&self.data_dir().path)?;
+                *self.editable_settings_mut() = editable_settings;
+                Ok(json!({ "message": "Reloaded the editable settings" }))
+            },
+            AdminRequest::Shutdown(Shutdown {
+                grace_period,
+                reason,
+            }) => {
+                frontend_events.push(Event::ShutdownRequested {
+                    grace_period: Duration::from_secs(grace_period),
+                    reason: reason.unwrap_or_else(|| "The server is shutting down".to_owned()),
+                });
+                Ok(json!({ "message": "Shutdown requested" }))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listener(token: &str) -> AdminApiListener {
+        AdminApiListener {
+            token: token.to_owned(),
+            requests: channel::unbounded().0,
+        }
+    }
+
+    #[test]
+    fn tokens_are_compared() {
+        assert!(constant_time_eq("secret", "secret"));
+        assert!(constant_time_eq("", ""));
+        assert!(!constant_time_eq("secret", "secreT"));
+        assert!(!constant_time_eq("secret", "secret2"));
+        assert!(!constant_time_eq("", "secret"));
+    }
+
+    #[test]
+    fn authorization() {
+        let listener = listener("secret");
+        assert!(listener.is_authorized(Some("Bearer secret")));
+        assert!(listener.is_authorized(Some("Bearer secret ")));
+        assert!(!listener.is_authorized(None));
+        assert!(!listener.is_authorized(Some("secret")));
+        assert!(!listener.is_authorized(Some("Basic secret")));
+        assert!(!listener.is_authorized(Some("Bearer wrong")));
+        assert!(!listener.is_authorized(Some("Bearer ")));
+    }
+
+    #[test]
+    fn requests_are_parsed() {
+        assert!(matches!(
+            parse_request(&Method::Get, "/admin/players", ""),
+            Ok(Some(AdminRequest::Players))
+        ));
+        assert!(matches!(
+            parse_request(&Method::Get, "/admin/players/?verbose", ""),
+            Ok(Some(AdminRequest::Players))
+        ));
+        match parse_request(
+            &Method::Post,
+            "/admin/ban",
+            r#"{"player": "griefer", "duration": "7d"}"#,
+        ) {
+            Ok(Some(AdminRequest::Ban(ban))) => {
+                assert_eq!(ban.player, "griefer");
+                assert_eq!(ban.duration.as_deref(), Some("7d"));
+                assert_eq!(ban.reason, "");
+            },
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(matches!(
+            parse_request(&Method::Post, "/admin/shutdown", r#"{"grace_period": 60}"#),
+            Ok(Some(AdminRequest::Shutdown(Shutdown {
+                grace_period: 60,
+                reason: None,
+            })))
+        ));
+    }
+
+    #[test]
+    fn invalid_requests() {
+        // Missing fields and invalid JSON
+        assert!(parse_request(&Method::Post, "/admin/kick", "{}").is_err());
+        assert!(parse_request(&Method::Post, "/admin/broadcast", "hello").is_err());
+        // Unknown endpoints and wrong methods
+        assert!(matches!(
+            parse_request(&Method::Get, "/admin/unknown", ""),
+            Ok(None)
+        ));
+        assert!(matches!(
+            parse_request(&Method::Get, "/admin/kick", ""),
+            Ok(None)
+        ));
+        assert!(matches!(
+            parse_request(&Method::Post, "/admin/players", ""),
+            Ok(None)
+        ));
+    }
+}
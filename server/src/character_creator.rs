@@ -1,6 +1,6 @@
 use crate::persistence::character_loader::CharacterLoader;
 use common::{
-    comp::{Body, Inventory, Stats},
+    comp::{Body, Inventory, QuestLog, Stats},
     loadout_builder::LoadoutBuilder,
 };
 use specs::{Entity, ReadExpect};
@@ -28,6 +28,14 @@ pub fn create_character(
         entity,
         player_uuid,
         character_alias,
-        (body, stats, inventory, loadout),
+        (
+            body,
+            stats,
+            inventory,
+            loadout,
+            None,
+            None,
+            QuestLog::default(),
+        ),
     );
 }
@@ -69,7 +69,7 @@ impl Client {
                     | ServerGeneral::CharacterSuccess => &mut self.character_screen_stream,
                     //Ingame related
                     ServerGeneral::GroupUpdate(_)
-                    | ServerGeneral::GroupInvite { .. }
+                    | ServerGeneral::Invite { .. }
                     | ServerGeneral::InvitePending(_)
                     | ServerGeneral::InviteComplete { .. }
                     | ServerGeneral::ExitInGameSuccess
@@ -78,7 +78,14 @@ impl Client {
                     | ServerGeneral::TerrainBlockUpdates(_)
                     | ServerGeneral::SetViewDistance(_)
                     | ServerGeneral::Outcomes(_)
-                    | ServerGeneral::Knockback(_) => &mut self.in_game_stream,
+                    | ServerGeneral::Knockback(_)
+                    | ServerGeneral::WeatherUpdate(_)
+                    | ServerGeneral::UpdatePendingTrade { .. }
+                    | ServerGeneral::FinishedTrade(_)
+                    | ServerGeneral::SiteEconomy(_)
+                    | ServerGeneral::QuestLog(_)
+                    | ServerGeneral::QuestStarted(_)
+                    | ServerGeneral::QuestCompleted(_) => &mut self.in_game_stream,
                     // Always possible
                     ServerGeneral::PlayerListUpdate(_)
                     | ServerGeneral::ChatMsg(_)
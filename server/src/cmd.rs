@@ -4,9 +4,10 @@
 
 use crate::{
     client::Client,
-    settings::{BanRecord, EditableSetting},
+    settings::{unix_timestamp, BanAction, BanRecord, EditableSetting},
     Server, StateExt,
 };
+use authc::Uuid;
 use chrono::{NaiveTime, Timelike};
 use common::{
     cmd::{ChatCommand, CHAT_COMMANDS, CHAT_SHORTCUTS},
@@ -95,6 +96,7 @@ fn get_handler(cmd: &ChatCommand) -> CommandHandler {
         ChatCommand::Motd => handle_motd,
         ChatCommand::Object => handle_object,
         ChatCommand::Players => handle_players,
+        ChatCommand::Pvp => handle_pvp,
         ChatCommand::Region => handle_region,
         ChatCommand::RemoveLights => handle_remove_lights,
         ChatCommand::Say => handle_say,
@@ -597,10 +599,16 @@ fn handle_spawn(
         String
     ) {
         (Some(opt_align), Some(npc::NpcBody(id, mut body)), opt_amount, opt_ai) => {
-            let uid = server
-                .state
-                .read_component_copied(target)
-                .expect("Expected player to have a UID");
+            let uid = match server.state.read_component_copied(target) {
+                Some(uid) => uid,
+                None => {
+                    server.notify_client(
+                        client,
+                        ChatType::CommandError.server_msg("No target to spawn for!"),
+                    );
+                    return;
+                },
+            };
             if let Some(alignment) = parse_alignment(uid, &opt_align) {
                 let amount = opt_amount
                     .and_then(|a| a.parse().ok())
@@ -823,6 +831,46 @@ fn handle_players(
     );
 }
 
+fn handle_pvp(
+    server: &mut Server,
+    client: EcsEntity,
+    target: EcsEntity,
+    _args: String,
+    _action: &ChatCommand,
+) {
+    let mode = server.state.ecs().fetch::<comp::PvpRules>().mode;
+    let ecs = server.state.ecs();
+    let opted_in = if ecs.read_storage::<comp::PvpOptIn>().contains(target) {
+        ecs.write_storage::<comp::PvpOptIn>().remove(target);
+        false
+    } else {
+        ecs.write_storage().insert(target, comp::PvpOptIn).is_ok()
+    };
+
+    let mode_note = match mode {
+        comp::PvpMode::OptIn => "",
+        comp::PvpMode::Off => " PvP is disabled on this server, so this has no effect.",
+        comp::PvpMode::On => " PvP is always enabled on this server, so this has no effect.",
+        comp::PvpMode::Zones => {
+            " PvP on this server is enabled outside of towns, so this has no effect."
+        },
+    };
+    server.notify_client(
+        client,
+        ChatType::CommandInfo.server_msg(format!(
+            "PvP {}.{}",
+            if opted_in { "enabled" } else { "disabled" },
+            mode_note
+        )),
+    );
+
+    // Update player list so others can see who is open to PvP
+    if let Some(uid) = server.state.ecs().read_storage::<Uid>().get(target) {
+        let msg = ServerGeneral::PlayerListUpdate(PlayerListUpdate::Pvp(*uid, opted_in));
+        server.state.notify_registered_clients(msg);
+    }
+}
+
 fn handle_build(
     server: &mut Server,
     client: EcsEntity,
@@ -1259,10 +1307,18 @@ fn handle_tell(
                 );
                 return;
             }
-            let client_uid = *ecs
-                .read_storage()
-                .get(client)
-                .expect("Player must have uid");
+            let client_uid = match ecs.read_storage::<Uid>().get(client) {
+                Some(uid) => *uid,
+                None => {
+                    // The server console has no uid to send a tell from
+                    let msg = message_opt.unwrap_or_default();
+                    server.notify_client(
+                        player,
+                        ChatType::CommandInfo.server_msg(format!("[Server] {}", msg)),
+                    );
+                    return;
+                },
+            };
             let player_uid = *ecs
                 .read_storage()
                 .get(player)
@@ -1433,6 +1489,11 @@ fn handle_world(
     if !msg.is_empty() {
         if let Some(uid) = server.state.ecs().read_storage().get(client) {
             server.state.send_chat(mode.new_message(*uid, msg));
+        } else {
+            // The server console broadcasts to everyone
+            server
+                .state
+                .notify_registered_clients(ChatType::Meta.server_msg(format!("[Server] {}", msg)));
         }
     }
 }
@@ -1661,34 +1722,34 @@ fn handle_set_level(
         let mut error_msg = None;
 
         match target {
-            Ok(player) => {
-                let uid = *server
-                    .state
-                    .ecs()
-                    .read_storage::<Uid>()
-                    .get(player)
-                    .expect("Failed to get uid for player");
-                server
-                    .state
-                    .notify_registered_clients(ServerGeneral::PlayerListUpdate(
-                        PlayerListUpdate::LevelChange(uid, lvl),
-                    ));
-
-                if let Some(stats) = server
-                    .state
-                    .ecs_mut()
-                    .write_storage::<comp::Stats>()
-                    .get_mut(player)
-                {
-                    stats.level.set_level(lvl);
+            // The server console has no uid and no stats
+            Ok(player) => match server.state.read_component_copied::<Uid>(player) {
+                Some(uid) => {
+                    server
+                        .state
+                        .notify_registered_clients(ServerGeneral::PlayerListUpdate(
+                            PlayerListUpdate::LevelChange(uid, lvl),
+                        ));
 
-                    stats.update_max_hp(stats.body_type);
-                    stats
-                        .health
-                        .set_to(stats.health.maximum(), comp::HealthSource::LevelUp);
-                } else {
-                    error_msg = Some(ChatType::CommandError.server_msg("Player has no stats!"));
-                }
+                    if let Some(stats) = server
+                        .state
+                        .ecs_mut()
+                        .write_storage::<comp::Stats>()
+                        .get_mut(player)
+                    {
+                        stats.level.set_level(lvl);
+
+                        stats.update_max_hp(stats.body_type);
+                        stats
+                            .health
+                            .set_to(stats.health.maximum(), comp::HealthSource::LevelUp);
+                    } else {
+                        error_msg = Some(ChatType::CommandError.server_msg("Player has no stats!"));
+                    }
+                },
+                None => {
+                    error_msg = Some(ChatType::CommandError.server_msg("No target given!"));
+                },
             },
             Err(e) => {
                 error_msg = Some(e);
@@ -1914,6 +1975,26 @@ fn kick_player(server: &mut Server, target_player: EcsEntity, reason: &str) {
     );
 }
 
+/// Kicks the player with `alias`, returning the message for whoever kicked
+/// them
+pub(crate) fn kick(server: &mut Server, alias: &str, reason: &str) -> Result<String, String> {
+    let ecs = server.state.ecs();
+    let target_player_opt = (&ecs.entities(), &ecs.read_storage::<comp::Player>())
+        .join()
+        .find(|(_, player)| player.alias == alias)
+        .map(|(entity, _)| entity);
+
+    if let Some(target_player) = target_player_opt {
+        kick_player(server, target_player, reason);
+        Ok(format!(
+            "Kicked {} from the server with reason: {}",
+            alias, reason
+        ))
+    } else {
+        Err(format!("Player with alias {} not found", alias))
+    }
+}
+
 fn handle_kick(
     server: &mut Server,
     client: EcsEntity,
@@ -1925,27 +2006,9 @@ fn handle_kick(
         scan_fmt_some!(&args, &action.arg_fmt(), String, String)
     {
         let reason = reason_opt.unwrap_or_default();
-        let ecs = server.state.ecs();
-        let target_player_opt = (&ecs.entities(), &ecs.read_storage::<comp::Player>())
-            .join()
-            .find(|(_, player)| player.alias == target_alias)
-            .map(|(entity, _)| entity);
-
-        if let Some(target_player) = target_player_opt {
-            kick_player(server, target_player, &reason);
-            server.notify_client(
-                client,
-                ChatType::CommandInfo.server_msg(format!(
-                    "Kicked {} from the server with reason: {}",
-                    target_alias, reason
-                )),
-            );
-        } else {
-            server.notify_client(
-                client,
-                ChatType::CommandError
-                    .server_msg(format!("Player with alias {} not found", target_alias)),
-            )
+        match kick(server, &target_alias, &reason) {
+            Ok(msg) => server.notify_client(client, ChatType::CommandInfo.server_msg(msg)),
+            Err(msg) => server.notify_client(client, ChatType::CommandError.server_msg(msg)),
         }
     } else {
         server.notify_client(
@@ -1955,6 +2018,92 @@ fn handle_kick(
     }
 }
 
+/// Parses ban durations like `30m`, `12h` or `7d`. Durations of zero and
+/// durations whose expiry can't be stored are rejected.
+pub(crate) fn parse_ban_duration(duration: &str) -> Option<std::time::Duration> {
+    let unit = match duration.chars().last()? {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    let amount = duration[..duration.len() - 1]
+        .parse::<u64>()
+        .ok()
+        .filter(|amount| *amount > 0)?;
+    let secs = amount.checked_mul(unit)?;
+    unix_timestamp().checked_add(secs)?;
+    Some(std::time::Duration::from_secs(secs))
+}
+
+/// Bans the player with `alias`, for a duration given as parsed and as typed
+/// or permanently, and kicks them if they are online. Returns the message for
+/// whoever issued the ban.
+pub(crate) fn ban(
+    server: &mut Server,
+    alias: &str,
+    duration: Option<(std::time::Duration, String)>,
+    reason: &str,
+    issued_by: Option<Uuid>,
+) -> Result<String, String> {
+    let uuid = server
+        .state
+        .ecs()
+        .read_resource::<LoginProvider>()
+        .username_to_uuid(alias)
+        .map_err(|_| format!("Unable to determine UUID for username \"{}\"", alias))?;
+
+    if server
+        .editable_settings()
+        .banlist
+        .get(&uuid)
+        .map_or(false, |record| !record.is_expired())
+    {
+        return Err(format!("{} is already on the banlist", alias));
+    }
+
+    let record = BanRecord::new(
+        alias.to_owned(),
+        reason.to_owned(),
+        issued_by,
+        duration.as_ref().map(|(d, _)| *d),
+    );
+    server
+        .editable_settings_mut()
+        .ban_history
+        .edit(server.data_dir().as_ref(), |h| {
+            h.append(uuid, BanAction::Ban(record.clone()))
+        });
+    server
+        .editable_settings_mut()
+        .banlist
+        .edit(server.data_dir().as_ref(), |b| {
+            b.remove_expired();
+            b.insert(uuid, record);
+        });
+
+    // If the player is online kick them
+    let ecs = server.state.ecs();
+    let target_player_opt = (&ecs.entities(), &ecs.read_storage::<comp::Player>())
+        .join()
+        .find(|(_, player)| player.alias == alias)
+        .map(|(entity, _)| entity);
+    if let Some(target_player) = target_player_opt {
+        kick_player(server, target_player, reason);
+    }
+
+    let duration_msg = match duration {
+        Some((_, duration_str)) => format!("for {}", duration_str),
+        None => "permanently".to_owned(),
+    };
+    Ok(format!(
+        "Added {} to the banlist {} with reason: {}",
+        alias, duration_msg, reason
+    ))
+}
+
 fn handle_ban(
     server: &mut Server,
     client: EcsEntity,
@@ -1962,59 +2111,33 @@ fn handle_ban(
     args: String,
     action: &ChatCommand,
 ) {
-    if let (Some(target_alias), reason_opt) =
-        scan_fmt_some!(&args, &action.arg_fmt(), String, String)
+    if let (Some(target_alias), duration_opt, reason_opt) =
+        scan_fmt_some!(&args, &action.arg_fmt(), String, String, String)
     {
-        let reason = reason_opt.unwrap_or_default();
-        let uuid_result = server
+        // The duration is optional, so the first word might already be the reason
+        let (duration, reason) = match duration_opt.as_deref().map(parse_ban_duration) {
+            Some(Some(duration)) => (
+                Some((duration, duration_opt.unwrap_or_default())),
+                reason_opt.unwrap_or_default(),
+            ),
+            _ => (
+                None,
+                duration_opt
+                    .into_iter()
+                    .chain(reason_opt)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+        };
+        let issued_by = server
             .state
             .ecs()
-            .read_resource::<LoginProvider>()
-            .username_to_uuid(&target_alias);
-
-        if let Ok(uuid) = uuid_result {
-            if server.editable_settings().banlist.contains_key(&uuid) {
-                server.notify_client(
-                    client,
-                    ChatType::CommandError
-                        .server_msg(format!("{} is already on the banlist", target_alias)),
-                )
-            } else {
-                server
-                    .editable_settings_mut()
-                    .banlist
-                    .edit(server.data_dir().as_ref(), |b| {
-                        b.insert(uuid, BanRecord {
-                            username_when_banned: target_alias.clone(),
-                            reason: reason.clone(),
-                        });
-                    });
-                server.notify_client(
-                    client,
-                    ChatType::CommandInfo.server_msg(format!(
-                        "Added {} to the banlist with reason: {}",
-                        target_alias, reason
-                    )),
-                );
-
-                // If the player is online kick them
-                let ecs = server.state.ecs();
-                let target_player_opt = (&ecs.entities(), &ecs.read_storage::<comp::Player>())
-                    .join()
-                    .find(|(_, player)| player.alias == target_alias)
-                    .map(|(entity, _)| entity);
-                if let Some(target_player) = target_player_opt {
-                    kick_player(server, target_player, &reason);
-                }
-            }
-        } else {
-            server.notify_client(
-                client,
-                ChatType::CommandError.server_msg(format!(
-                    "Unable to determine UUID for username \"{}\"",
-                    target_alias
-                )),
-            )
+            .read_storage::<comp::Player>()
+            .get(client)
+            .map(|player| player.uuid());
+        match ban(server, &target_alias, duration, &reason, issued_by) {
+            Ok(msg) => server.notify_client(client, ChatType::CommandInfo.server_msg(msg)),
+            Err(msg) => server.notify_client(client, ChatType::CommandError.server_msg(msg)),
         }
     } else {
         server.notify_client(
@@ -2039,12 +2162,27 @@ fn handle_unban(
             .username_to_uuid(&username);
 
         if let Ok(uuid) = uuid_result {
-            server
+            let issued_by = server
+                .state
+                .ecs()
+                .read_storage::<comp::Player>()
+                .get(client)
+                .map(|player| player.uuid());
+            let was_banned = server
                 .editable_settings_mut()
                 .banlist
-                .edit(server.data_dir().as_ref(), |b| {
-                    b.remove(&uuid);
-                });
+                .edit(server.data_dir().as_ref(), |b| b.remove(&uuid).is_some());
+            if was_banned {
+                server
+                    .editable_settings_mut()
+                    .ban_history
+                    .edit(server.data_dir().as_ref(), |h| {
+                        h.append(uuid, BanAction::Unban {
+                            issued_by,
+                            issued_at: unix_timestamp(),
+                        })
+                    });
+            }
             server.notify_client(
                 client,
                 ChatType::CommandInfo.server_msg(format!("{} was successfully unbanned", username)),
@@ -2065,3 +2203,45 @@ fn handle_unban(
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn ban_durations() {
+        assert_eq!(parse_ban_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(
+            parse_ban_duration("30m"),
+            Some(Duration::from_secs(30 * 60))
+        );
+        assert_eq!(
+            parse_ban_duration("12h"),
+            Some(Duration::from_secs(12 * 60 * 60))
+        );
+        assert_eq!(
+            parse_ban_duration("7d"),
+            Some(Duration::from_secs(7 * 24 * 60 * 60))
+        );
+        assert_eq!(
+            parse_ban_duration("2w"),
+            Some(Duration::from_secs(14 * 24 * 60 * 60))
+        );
+    }
+
+    #[test]
+    fn invalid_ban_durations() {
+        assert_eq!(parse_ban_duration(""), None);
+        assert_eq!(parse_ban_duration("m"), None);
+        assert_eq!(parse_ban_duration("0m"), None);
+        assert_eq!(parse_ban_duration("-5m"), None);
+        assert_eq!(parse_ban_duration("5y"), None);
+        assert_eq!(parse_ban_duration("5ü"), None);
+        assert_eq!(parse_ban_duration("griefing"), None);
+        // Overflows the multiplication
+        assert_eq!(parse_ban_duration(&format!("{}w", u64::MAX / 2)), None);
+        // Overflows the expiry timestamp
+        assert_eq!(parse_ban_duration(&format!("{}s", u64::MAX)), None);
+    }
+}
@@ -101,10 +101,12 @@ impl ConnectionHandler {
 
         let reliable = Promises::ORDERED | Promises::CONSISTENCY;
         let reliablec = reliable | Promises::COMPRESSED;
+        // the register stream carries auth tokens
+        let reliablece = reliablec | Promises::ENCRYPTED;
 
         let general_stream = participant.open(10, reliablec).await?;
         let ping_stream = participant.open(5, reliable).await?;
-        let mut register_stream = participant.open(10, reliablec).await?;
+        let mut register_stream = participant.open(10, reliablece).await?;
         let character_screen_stream = participant.open(10, reliablec).await?;
         let in_game_stream = participant.open(10, reliablec).await?;
 
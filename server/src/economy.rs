@@ -0,0 +1,108 @@
+//! The economies of the sites keep running while the server is up. Every
+//! economy tick advances them by one step of the simulated history, after the
+//! goods that players brought in since the last tick were added to the stocks
+//! of the sites closest to where they got them.
+
+use common::trade::Good;
+use std::{
+    cmp::Ordering,
+    time::{Duration, Instant},
+};
+use vek::*;
+use world::{index::Index, sim2};
+
+/// Goods count towards a site up to this many times its radius away from it
+const SITE_RANGE_FACTOR: f32 = 2.0;
+/// Goods a site gains for every animal players hunt or fish near it
+pub const GOODS_PER_KILL: f32 = 1.0;
+
+pub struct EconomySim {
+    period: Duration,
+    last_tick: Instant,
+    /// Time of the economies, going on from the end of the simulated history
+    time: f32,
+    /// Goods players brought in since the last tick, with where they got them
+    contributions: Vec<(Vec2<f32>, Good, f32)>,
+}
+
+impl EconomySim {
+    pub fn new(time: f32, period: Duration) -> Self {
+        Self {
+            period,
+            last_tick: Instant::now(),
+            time,
+            contributions: Vec::new(),
+        }
+    }
+
+    /// Time of the economies, which is saved with them
+    pub fn time(&self) -> f32 { self.time }
+
+    /// Adds goods to the stock of the site closest to `wpos` on the next tick
+    pub fn contribute(&mut self, wpos: Vec2<f32>, good: Good, amount: f32) {
+        // Without a generated world there are no sites that could use them
+        if cfg!(feature = "worldgen") && amount > 0.0 {
+            self.contributions.push((wpos, good, amount));
+        }
+    }
+
+    /// Advances the economies once the tick period has passed, returning
+    /// whether they were advanced
+    pub fn tick(&mut self, index: &Index) -> bool {
+        if self.last_tick.elapsed() < self.period {
+            return false;
+        }
+        self.last_tick = Instant::now();
+
+        for (wpos, good, amount) in self.contributions.drain(..) {
+            let sites = index
+                .sites
+                .values()
+                .map(|site| (site, site.get_origin().map(|e| e as f32), site.radius()));
+            if let Some(site) = receiving_site(sites, wpos) {
+                site.economy_mut().stocks[good] += amount;
+            }
+        }
+
+        sim2::tick_sites(index, self.time, sim2::TICK_PERIOD);
+        self.time += sim2::TICK_PERIOD;
+        true
+    }
+}
+
+/// The closest of the sites, given with their position and radius, that goods
+/// brought in at `wpos` count towards
+fn receiving_site<S>(
+    sites: impl Iterator<Item = (S, Vec2<f32>, f32)>,
+    wpos: Vec2<f32>,
+) -> Option<S> {
+    sites
+        .map(|(site, origin, radius)| (site, origin.distance(wpos), radius))
+        .filter(|(_, dist, radius)| *dist < radius * SITE_RANGE_FACTOR)
+        .min_by(|(_, a, _), (_, b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        .map(|(site, _, _)| site)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small site at the origin and a large one 100 blocks to the east
+    fn sites() -> impl Iterator<Item = (usize, Vec2<f32>, f32)> {
+        vec![(0, Vec2::zero(), 10.0), (1, Vec2::new(100.0, 0.0), 40.0)].into_iter()
+    }
+
+    #[test]
+    fn goods_go_to_the_closest_site() {
+        assert_eq!(receiving_site(sites(), Vec2::new(5.0, 0.0)), Some(0));
+        assert_eq!(receiving_site(sites(), Vec2::new(60.0, 0.0)), Some(1));
+        // Closer to the small site, but only in range of the large one
+        assert_eq!(receiving_site(sites(), Vec2::new(40.0, 0.0)), Some(1));
+    }
+
+    #[test]
+    fn goods_out_of_range_are_dropped() {
+        assert_eq!(receiving_site(sites(), Vec2::new(0.0, 50.0)), None);
+        assert_eq!(receiving_site(sites(), Vec2::new(300.0, 0.0)), None);
+    }
+}
@@ -1,16 +1,24 @@
-use crate::{sys, Server, StateExt};
+use crate::{
+    persistence::{terrain::TerrainPersistence, PersistedComponents},
+    sys, Server, StateExt,
+};
 use common::{
     character::CharacterId,
     comp::{
         self, beam, humanoid::DEFAULT_HUMANOID_EYE_HEIGHT, shockwave, Agent, Alignment, Body,
-        Gravity, Item, ItemDrop, LightEmitter, Loadout, Ori, Pos, Projectile, Scale, Stats, Vel,
-        WaypointArea,
+        Gravity, Inventory, ItemDrop, LightEmitter, Loadout, Merchant, Ori, Pos, Projectile, Scale,
+        Stats, Vel, WaypointArea,
     },
+    loot::LootSpec,
     outcome::Outcome,
+    rtsim::RtSimEntity,
+    trade::TradingInformation,
     util::Dir,
+    vol::ReadVol,
 };
 use comp::group;
 use specs::{Builder, Entity as EcsEntity, WorldExt};
+use tracing::warn;
 use vek::{Rgb, Vec3};
 
 pub fn handle_initialize_character(
@@ -24,14 +32,89 @@ pub fn handle_initialize_character(
 pub fn handle_loaded_character_data(
     server: &mut Server,
     entity: EcsEntity,
-    loaded_components: (comp::Body, comp::Stats, comp::Inventory, comp::Loadout),
+    loaded_components: PersistedComponents,
 ) {
-    server
-        .state
-        .update_character_data(entity, loaded_components);
+    let (body, stats, inventory, loadout, pos, waypoint, quest_log) = loaded_components;
+
+    // The world may have changed since the character was saved, so don't put
+    // them inside solid terrain
+    let waypoint = waypoint.filter(|waypoint| is_free_position(server, waypoint.get_pos()));
+    let pos = pos
+        .filter(|pos| is_free_position(server, pos.0))
+        .or_else(|| {
+            if pos.is_some() {
+                warn!(
+                    ?entity,
+                    "Saved position is obstructed, moving character to a safe spot"
+                );
+            }
+            waypoint.map(|waypoint| comp::Pos(waypoint.get_pos()))
+        });
+
+    server.state.update_character_data(
+        entity,
+        (body, stats, inventory, loadout, pos, waypoint, quest_log),
+    );
     sys::subscription::initialize_region_subscription(server.state.ecs(), entity);
 }
 
+/// Checks that a character at `pos` wouldn't be stuck inside solid terrain,
+/// the loaded terrain includes changes made by players so it's preferred over
+/// the generated world
+fn is_free_position(server: &Server, pos: Vec3<f32>) -> bool {
+    let wpos = pos.map(|e| e.floor() as i32);
+    let terrain = server.state.terrain();
+    if terrain.get_key(terrain.pos_key(wpos)).is_some() {
+        // Check the blocks at the character's feet and head
+        (0..2).all(|z| {
+            terrain
+                .get(wpos + Vec3::unit_z() * z)
+                .map_or(true, |block| !block.is_solid())
+        })
+    } else {
+        let persistence = server.state.ecs().read_resource::<TerrainPersistence>();
+        is_free_generated_position(server, &persistence, wpos)
+    }
+}
+
+/// Checks the generated world for solid blocks, with the changes made by
+/// players applied on top of it
+#[cfg(feature = "worldgen")]
+fn is_free_generated_position(
+    server: &Server,
+    persistence: &TerrainPersistence,
+    wpos: Vec3<i32>,
+) -> bool {
+    let index = server.index.as_index_ref();
+    let mut block_sampler = server.world.sample_blocks();
+    // Positions outside of the world don't have a z cache
+    let z_cache = match block_sampler.get_z_cache(wpos.xy(), index) {
+        Some(z_cache) => z_cache,
+        None => return false,
+    };
+    // Check the blocks at the character's feet and head
+    (0..2).all(|z| {
+        let pos = wpos + Vec3::unit_z() * z;
+        persistence
+            .block_at(pos)
+            .or_else(|| block_sampler.get_with_z_cache(pos, Some(&z_cache), false, index))
+            .map_or(true, |block| !block.is_solid())
+    })
+}
+
+#[cfg(not(feature = "worldgen"))]
+fn is_free_generated_position(
+    _server: &Server,
+    persistence: &TerrainPersistence,
+    wpos: Vec3<i32>,
+) -> bool {
+    (0..2).all(|z| {
+        persistence
+            .block_at(wpos + Vec3::unit_z() * z)
+            .map_or(true, |block| !block.is_solid())
+    })
+}
+
 #[allow(clippy::too_many_arguments)] // TODO: Pending review in #587
 pub fn handle_create_npc(
     server: &mut Server,
@@ -42,7 +125,9 @@ pub fn handle_create_npc(
     agent: impl Into<Option<Agent>>,
     alignment: Alignment,
     scale: Scale,
-    drop_item: Option<Item>,
+    drop_item: Option<LootSpec>,
+    trading_information: Option<TradingInformation>,
+    rtsim_entity: Option<RtSimEntity>,
 ) {
     let group = match alignment {
         Alignment::Wild => None,
@@ -77,6 +162,25 @@ pub fn handle_create_npc(
         entity
     };
 
+    // Merchants carry the goods they sell
+    let entity = if let Some(trading_information) = trading_information {
+        let mut inventory = Inventory::new_empty();
+        if let Err(error) = inventory.push_all(trading_information.items().into_iter()) {
+            warn!(?error, "Merchant stock doesn't fit into its inventory");
+        }
+        entity.with(inventory).with(Merchant {
+            prices: trading_information.prices,
+        })
+    } else {
+        entity
+    };
+
+    let entity = if let Some(rtsim_entity) = rtsim_entity {
+        entity.with(rtsim_entity)
+    } else {
+        entity
+    };
+
     entity.build();
 }
 
@@ -1,42 +1,81 @@
 use crate::{
     client::Client,
-    comp::{biped_large, quadruped_medium, quadruped_small},
-    Server, SpawnPoint, StateExt,
+    economy::{EconomySim, GOODS_PER_KILL},
+    quest, Server, SpawnPoint, StateExt,
 };
 use common::{
-    assets::Asset,
     comp::{
         self,
         chat::{KillSource, KillType},
-        object, Alignment, Body, Damage, DamageSource, Group, HealthChange, HealthSource, Item,
-        Player, Pos, Stats,
+        object, Alignment, Body, Damage, DamageSource, Group, HealthChange, HealthSource, Player,
+        Pos, Stats,
     },
-    lottery::Lottery,
+    loot::{LootSpec, LOOT_CONFIG},
     msg::{PlayerListUpdate, ServerGeneral},
     outcome::Outcome,
+    quest::QuestEvent,
+    rtsim::RtSimEntity,
     state::BlockChange,
     sync::{Uid, UidAllocator, WorldSyncExt},
     sys::combat::BLOCK_ANGLE,
     terrain::{Block, TerrainGrid},
+    trade::Good,
     vol::ReadVol,
     Explosion,
 };
 use comp::item::Reagent;
-use rand::prelude::*;
 use specs::{join::Join, saveload::MarkerAllocator, Entity as EcsEntity, WorldExt};
 use tracing::error;
 use vek::Vec3;
+use world::rtsim::RtSim;
 
 pub fn handle_damage(server: &Server, uid: Uid, change: HealthChange) {
     let state = &server.state;
     let ecs = state.ecs();
     if let Some(entity) = ecs.entity_from_uid(uid.into()) {
+        let change = protect(ecs.read_storage::<comp::Buffs>().get(entity), change);
         if let Some(stats) = ecs.write_storage::<Stats>().get_mut(entity) {
             stats.health.change_by(change);
         }
     }
 }
 
+/// Protective buffs reduce damage dealt in combat, but not e.g. falling,
+/// suicide or damage from commands
+fn protect(buffs: Option<&comp::Buffs>, mut change: HealthChange) -> HealthChange {
+    let protectable = matches!(
+        change.cause,
+        HealthSource::Attack { .. }
+            | HealthSource::Projectile { .. }
+            | HealthSource::Explosion { .. }
+            | HealthSource::Buff { .. }
+    );
+    if change.amount < 0 && protectable {
+        if let Some(buffs) = buffs {
+            change.amount = (change.amount as f32 * buffs.damage_modifier()).round() as i32;
+        }
+    }
+    change
+}
+
+pub fn handle_buff(server: &Server, uid: Uid, buff_change: comp::BuffChange) {
+    let ecs = server.state.ecs();
+    if let Some(entity) = ecs.entity_from_uid(uid.into()) {
+        let is_dead = ecs
+            .read_storage::<Stats>()
+            .get(entity)
+            .map_or(true, |stats| stats.is_dead);
+        if is_dead {
+            return;
+        }
+        if let Ok(entry) = ecs.write_storage::<comp::Buffs>().entry(entity) {
+            entry
+                .or_insert_with(comp::Buffs::default)
+                .apply_change(buff_change);
+        }
+    }
+}
+
 pub fn handle_knockback(server: &Server, entity: EcsEntity, impulse: Vec3<f32>) {
     let state = &server.state;
     let mut velocities = state.ecs().write_storage::<comp::Vel>();
@@ -175,6 +214,7 @@ pub fn handle_destroy(server: &mut Server, entity: EcsEntity, cause: HealthSourc
                 | HealthSource::LevelUp
                 | HealthSource::Item
                 | HealthSource::Healing { by: _ }
+                | HealthSource::Buff { owner: _ }
                 | HealthSource::Unknown => KillSource::Other,
             };
             state.notify_registered_clients(
@@ -189,7 +229,8 @@ pub fn handle_destroy(server: &mut Server, entity: EcsEntity, cause: HealthSourc
         let by = if let HealthSource::Attack { by }
         | HealthSource::Projectile { owner: Some(by) }
         | HealthSource::Energy { owner: Some(by) }
-        | HealthSource::Explosion { owner: Some(by) } = cause
+        | HealthSource::Explosion { owner: Some(by) }
+        | HealthSource::Buff { owner: Some(by) } = cause
         {
             by
         } else {
@@ -268,6 +309,39 @@ pub fn handle_destroy(server: &mut Server, entity: EcsEntity, cause: HealthSourc
         }
     })();
 
+    // Animals that players hunt feed the economy of the closest site
+    if let HealthSource::Attack { by }
+    | HealthSource::Projectile { owner: Some(by) }
+    | HealthSource::Energy { owner: Some(by) }
+    | HealthSource::Explosion { owner: Some(by) }
+    | HealthSource::Buff { owner: Some(by) } = cause
+    {
+        let ecs = state.ecs();
+        let by_player = ecs.entity_from_uid(by.into()).map_or(false, |attacker| {
+            ecs.read_storage::<Player>().contains(attacker)
+        });
+        let good = match ecs.read_storage::<Body>().get(entity) {
+            Some(Body::QuadrupedSmall(_))
+            | Some(Body::QuadrupedMedium(_))
+            | Some(Body::QuadrupedLow(_))
+            | Some(Body::BirdMedium(_))
+            | Some(Body::BirdSmall(_)) => Some(Good::Game),
+            Some(Body::FishMedium(_)) | Some(Body::FishSmall(_)) => Some(Good::Fish),
+            _ => None,
+        };
+        let pos = ecs.read_storage::<Pos>().get(entity).map(|pos| pos.0.xy());
+        if let (true, Some(good), Some(pos)) = (by_player, good, pos) {
+            ecs.write_resource::<EconomySim>()
+                .contribute(pos, good, GOODS_PER_KILL);
+        }
+
+        // Killing NPCs counts for the quests of the killer
+        let killed = quest::quest_npc(ecs, entity);
+        if let (Some(attacker), Some(killed)) = (ecs.entity_from_uid(by.into()), killed) {
+            quest::record_for(ecs, attacker, &QuestEvent::Killed(killed));
+        }
+    }
+
     if state
         .ecs()
         .write_storage::<Client>()
@@ -302,106 +376,47 @@ pub fn handle_destroy(server: &mut Server, entity: EcsEntity, cause: HealthSourc
     } else if state.ecs().read_storage::<comp::Agent>().contains(entity) {
         use specs::Builder;
 
+        // NPCs simulated by rtsim come back to life in their home
+        if let Some(rtsim_entity) = state.ecs().read_storage::<RtSimEntity>().get(entity) {
+            state
+                .ecs()
+                .write_resource::<RtSim>()
+                .respawn(rtsim_entity.0);
+        }
+
         // Decide for a loot drop before turning into a lootbag
         let old_body = state.ecs().write_storage::<Body>().remove(entity);
-        let mut rng = rand::thread_rng();
-        let mut lottery = || {
-            Lottery::<String>::load_expect(match old_body {
-                Some(common::comp::Body::Humanoid(_)) => match rng.gen_range(0, 4) {
-                    0 => "common.loot_tables.loot_table_humanoids",
-                    1 => "common.loot_tables.loot_table_armor_light",
-                    2 => "common.loot_tables.loot_table_armor_cloth",
-                    3 => "common.loot_tables.loot_table_weapon_common",
-                    4 => "common.loots_tables.loot_table_armor_misc",
-                    _ => "common.loot_tables.loot_table_humanoids",
-                },
-                Some(common::comp::Body::QuadrupedSmall(quadruped_small)) => {
-                    match quadruped_small.species {
-                        quadruped_small::Species::Dodarock => match rng.gen_range(0, 6) {
-                            1 => "common.loot_tables.loot_table_rocks",
-                            _ => "common.loot_tables.loot_table_rocks",
-                        },
-                        _ => match rng.gen_range(0, 4) {
-                            0 => "common.loot_tables.loot_table_food",
-                            2 => "common.loot_tables.loot_table_animal_parts",
-                            _ => "common.loot_tables.loot_table_animal_parts",
-                        },
-                    }
-                },
-                Some(common::comp::Body::QuadrupedMedium(quadruped_medium)) => {
-                    match quadruped_medium.species {
-                        quadruped_medium::Species::Frostfang
-                        | quadruped_medium::Species::Roshwalr => {
-                            "common.loot_tables.loot_table_animal_ice"
-                        },
-                        _ => match rng.gen_range(0, 4) {
-                            0 => "common.loot_tables.loot_table_food",
-                            2 => "common.loot_tables.loot_table_animal_parts",
-                            _ => "common.loot_tables.loot_table_animal_parts",
-                        },
-                    }
-                },
-                Some(common::comp::Body::BirdMedium(_)) => match rng.gen_range(0, 3) {
-                    0 => "common.loot_tables.loot_table_food",
-                    _ => "common.loot_tables.loot_table",
-                },
-                Some(common::comp::Body::BipedLarge(biped_large)) => match biped_large.species {
-                    biped_large::Species::Wendigo => match rng.gen_range(0, 7) {
-                        0 => "common.loot_tables.loot_table_food",
-                        1 => "common.loot_tables.loot_table_wendigo",
-                        3 => "common.loot_tables.loot_table_armor_heavy",
-                        5 => "common.loot_tables.loot_table_weapon_uncommon",
-                        6 => "common.loot_tables.loot_table_weapon_rare",
-                        _ => "common.loot_tables.loot_table_cave_large",
-                    },
-                    _ => match rng.gen_range(0, 8) {
-                        0 => "common.loot_tables.loot_table_food",
-                        1 => "common.loot_tables.loot_table_armor_nature",
-                        3 => "common.loot_tables.loot_table_armor_heavy",
-                        5 => "common.loot_tables.loot_table_weapon_uncommon",
-                        6 => "common.loot_tables.loot_table_weapon_rare",
-                        _ => "common.loot_tables.loot_table_cave_large",
-                    },
-                },
-                Some(common::comp::Body::Golem(_)) => match rng.gen_range(0, 9) {
-                    0 => "common.loot_tables.loot_table_food",
-                    2 => "common.loot_tables.loot_table_armor_light",
-                    3 => "common.loot_tables.loot_table_armor_heavy",
-                    5 => "common.loot_tables.loot_table_weapon_common",
-                    6 => "common.loot_tables.loot_table_weapon_uncommon",
-                    7 => "common.loot_tables.loot_table_weapon_rare",
-                    _ => "common.loot_tables.loot_table",
-                },
-                Some(common::comp::Body::Theropod(_)) => {
-                    "common.loot_tables.loot_table_animal_parts"
-                },
-                Some(common::comp::Body::Dragon(_)) => "common.loot_tables.loot_table_weapon_rare",
-                Some(common::comp::Body::QuadrupedLow(_)) => match rng.gen_range(0, 3) {
-                    0 => "common.loot_tables.loot_table_food",
-                    1 => "common.loot_tables.loot_table_animal_parts",
-                    _ => "common.loot_tables.loot_table",
-                },
-                _ => "common.loot_tables.loot_table",
-            })
-        };
-
-        let item = {
-            let mut item_drops = state.ecs().write_storage::<comp::ItemDrop>();
-            item_drops.remove(entity).map_or_else(
-                || Item::new_from_asset_expect(lottery().choose()),
-                |item_drop| item_drop.0,
-            )
-        };
+        let loot = state
+            .ecs()
+            .write_storage::<comp::ItemDrop>()
+            .remove(entity)
+            .map(|item_drop| item_drop.0)
+            .unwrap_or_else(|| {
+                LootSpec::LootTable(
+                    old_body
+                        .as_ref()
+                        .map_or(LOOT_CONFIG.default.as_str(), |body| {
+                            LOOT_CONFIG.body_table(body)
+                        })
+                        .to_owned(),
+                )
+            });
+        let items = loot.roll(&mut rand::thread_rng());
 
         let pos = state.ecs().read_storage::<comp::Pos>().get(entity).cloned();
         if let Some(pos) = pos {
-            let _ = state
-                .create_object(
-                    comp::Pos(pos.0 + Vec3::unit_z() * 0.25),
-                    object::Body::Pouch,
-                )
-                .with(item)
-                .build();
+            for (i, item) in items.into_iter().enumerate() {
+                // Spread out the bags so they don't end up inside each other
+                let angle = i as f32 * 2.4;
+                let offset = Vec3::new(angle.cos(), angle.sin(), 0.0) * (i as f32).sqrt() * 0.5;
+                let _ = state
+                    .create_object(
+                        comp::Pos(pos.0 + offset + Vec3::unit_z() * 0.25),
+                        object::Body::Pouch,
+                    )
+                    .with(item)
+                    .build();
+            }
         } else {
             error!(
                 ?entity,
@@ -518,10 +533,15 @@ pub fn handle_explosion(
             .retrieve_entity_internal(uid.into())
     });
     let groups = ecs.read_storage::<comp::Group>();
+    let positions = ecs.read_storage::<comp::Pos>();
+    let players = ecs.read_storage::<comp::Player>();
+    let pvp_opt_ins = ecs.read_storage::<comp::PvpOptIn>();
+    let pvp_rules = ecs.read_resource::<comp::PvpRules>();
+    let buffs = ecs.read_storage::<comp::Buffs>();
 
     for (entity_b, pos_b, ori_b, character_b, stats_b, loadout_b) in (
         &ecs.entities(),
-        &ecs.read_storage::<comp::Pos>(),
+        &positions,
         &ecs.read_storage::<comp::Ori>(),
         ecs.read_storage::<comp::CharacterState>().maybe(),
         &mut ecs.write_storage::<comp::Stats>(),
@@ -546,7 +566,20 @@ pub fn handle_explosion(
             }
             // Don't heal if outside group
             // Don't damage in the same group
-            let is_damage = (friendly_damage || !same_group) && explosion.max_damage > 0;
+            // Only hit other players if the PvP rules allow it
+            let is_damage = (friendly_damage || !same_group)
+                && explosion.max_damage > 0
+                && (same_group
+                    || owner_entity.map_or(true, |owner| {
+                        pvp_rules.allows_damage(
+                            owner,
+                            entity_b,
+                            &players,
+                            &pvp_opt_ins,
+                            &positions,
+                            &groups,
+                        )
+                    }));
             let is_heal = same_group && explosion.max_heal > 0 && !friendly_damage;
             if !is_heal && !is_damage {
                 continue;
@@ -585,10 +618,12 @@ pub fn handle_explosion(
                 } else {
                     HealthSource::Explosion { owner }
                 };
-                stats_b.health.change_by(HealthChange {
-                    amount: damage.healthchange as i32,
-                    cause,
-                });
+                stats_b
+                    .health
+                    .change_by(protect(buffs.get(entity_b), HealthChange {
+                        amount: damage.healthchange as i32,
+                        cause,
+                    }));
                 if let Some(owner) = owner_entity {
                     if let Some(energy) = ecs.write_storage::<comp::Energy>().get_mut(owner) {
                         energy
@@ -2,7 +2,7 @@ use crate::{client::Client, Server};
 use common::{
     comp::{
         self,
-        group::{self, Group, GroupManager, Invite, PendingInvites},
+        group::{Group, GroupManager, Invite, InviteKind, PendingInvites},
         ChatType, GroupManip,
     },
     msg::{InviteAnswer, ServerGeneral},
@@ -14,9 +14,9 @@ use std::time::{Duration, Instant};
 use tracing::{error, warn};
 
 /// Time before invite times out
-const INVITE_TIMEOUT_DUR: Duration = Duration::from_secs(31);
+pub const INVITE_TIMEOUT_DUR: Duration = Duration::from_secs(31);
 /// Reduced duration shown to the client to help alleviate latency issues
-const PRESENTED_INVITE_TIMEOUT_DUR: Duration = Duration::from_secs(30);
+pub const PRESENTED_INVITE_TIMEOUT_DUR: Duration = Duration::from_secs(30);
 
 // TODO: turn chat messages into enums
 pub fn handle_group(server: &mut Server, entity: specs::Entity, manip: GroupManip) {
@@ -88,7 +88,11 @@ pub fn handle_group(server: &mut Server, entity: specs::Entity, manip: GroupMani
                         .map(|i| i.num_members)
                 })
                 .unwrap_or(1) as usize
-                + pending_invites.get(entity).map_or(0, |p| p.0.len())
+                + pending_invites.get(entity).map_or(0, |p| {
+                    p.0.iter()
+                        .filter(|(_, kind, _)| *kind == InviteKind::Group)
+                        .count()
+                })
                 >= max_group_size as usize;
             if group_size_limit_reached {
                 // Inform inviter that they have reached the group size limit
@@ -121,7 +125,10 @@ pub fn handle_group(server: &mut Server, entity: specs::Entity, manip: GroupMani
             let mut invite_sent = false;
             // Returns true if insertion was succesful
             let mut send_invite = || {
-                match invites.insert(invitee, group::Invite(entity)) {
+                match invites.insert(invitee, Invite {
+                    inviter: entity,
+                    kind: InviteKind::Group,
+                }) {
                     Err(err) => {
                         error!("Failed to insert Invite component: {:?}", err);
                         false
@@ -129,10 +136,11 @@ pub fn handle_group(server: &mut Server, entity: specs::Entity, manip: GroupMani
                     Ok(_) => {
                         match pending_invites.entry(entity) {
                             Ok(entry) => {
-                                entry
-                                    .or_insert_with(|| PendingInvites(Vec::new()))
-                                    .0
-                                    .push((invitee, Instant::now() + INVITE_TIMEOUT_DUR));
+                                entry.or_insert_with(|| PendingInvites(Vec::new())).0.push((
+                                    invitee,
+                                    InviteKind::Group,
+                                    Instant::now() + INVITE_TIMEOUT_DUR,
+                                ));
                                 invite_sent = true;
                                 true
                             },
@@ -155,9 +163,10 @@ pub fn handle_group(server: &mut Server, entity: specs::Entity, manip: GroupMani
                 (clients.get_mut(invitee), uids.get(entity).copied())
             {
                 if send_invite() {
-                    client.send_msg(ServerGeneral::GroupInvite {
+                    client.send_msg(ServerGeneral::Invite {
                         inviter,
                         timeout: PRESENTED_INVITE_TIMEOUT_DUR,
+                        kind: InviteKind::Group,
                     });
                 }
             } else if agents.contains(invitee) {
@@ -176,80 +185,42 @@ pub fn handle_group(server: &mut Server, entity: specs::Entity, manip: GroupMani
             }
         },
         GroupManip::Accept => {
+            let inviter = match take_invite(state.ecs(), entity, InviteAnswer::Accepted) {
+                Some((inviter, InviteKind::Group)) => inviter,
+                Some((inviter, InviteKind::Trade)) => {
+                    super::trade::begin_player_trade(server, inviter, entity);
+                    return;
+                },
+                Some((inviter, InviteKind::Quest)) => {
+                    super::quest::start_offered_quest(state.ecs(), entity, inviter);
+                    return;
+                },
+                None => return,
+            };
             let mut clients = state.ecs().write_storage::<Client>();
             let uids = state.ecs().read_storage::<sync::Uid>();
-            let mut invites = state.ecs().write_storage::<Invite>();
-            if let Some(inviter) = invites.remove(entity).and_then(|invite| {
-                let inviter = invite.0;
-                let mut pending_invites = state.ecs().write_storage::<PendingInvites>();
-                let pending = &mut pending_invites.get_mut(inviter)?.0;
-                // Check that inviter has a pending invite and remove it from the list
-                let invite_index = pending.iter().position(|p| p.0 == entity)?;
-                pending.swap_remove(invite_index);
-                // If no pending invites remain remove the component
-                if pending.is_empty() {
-                    pending_invites.remove(inviter);
-                }
-
-                Some(inviter)
-            }) {
-                if let (Some(client), Some(target)) =
-                    (clients.get_mut(inviter), uids.get(entity).copied())
-                {
-                    client.send_msg(ServerGeneral::InviteComplete {
-                        target,
-                        answer: InviteAnswer::Accepted,
-                    })
-                }
-                let mut group_manager = state.ecs().write_resource::<GroupManager>();
-                group_manager.add_group_member(
-                    inviter,
-                    entity,
-                    &state.ecs().entities(),
-                    &mut state.ecs().write_storage(),
-                    &state.ecs().read_storage(),
-                    &uids,
-                    |entity, group_change| {
-                        clients
-                            .get_mut(entity)
-                            .and_then(|c| {
-                                group_change
-                                    .try_map(|e| uids.get(e).copied())
-                                    .map(|g| (g, c))
-                            })
-                            .map(|(g, c)| c.send_msg(ServerGeneral::GroupUpdate(g)));
-                    },
-                );
-            }
+            let mut group_manager = state.ecs().write_resource::<GroupManager>();
+            group_manager.add_group_member(
+                inviter,
+                entity,
+                &state.ecs().entities(),
+                &mut state.ecs().write_storage(),
+                &state.ecs().read_storage(),
+                &uids,
+                |entity, group_change| {
+                    clients
+                        .get_mut(entity)
+                        .and_then(|c| {
+                            group_change
+                                .try_map(|e| uids.get(e).copied())
+                                .map(|g| (g, c))
+                        })
+                        .map(|(g, c)| c.send_msg(ServerGeneral::GroupUpdate(g)));
+                },
+            );
         },
         GroupManip::Decline => {
-            let mut clients = state.ecs().write_storage::<Client>();
-            let uids = state.ecs().read_storage::<sync::Uid>();
-            let mut invites = state.ecs().write_storage::<Invite>();
-            if let Some(inviter) = invites.remove(entity).and_then(|invite| {
-                let inviter = invite.0;
-                let mut pending_invites = state.ecs().write_storage::<PendingInvites>();
-                let pending = &mut pending_invites.get_mut(inviter)?.0;
-                // Check that inviter has a pending invite and remove it from the list
-                let invite_index = pending.iter().position(|p| p.0 == entity)?;
-                pending.swap_remove(invite_index);
-                // If no pending invites remain remove the component
-                if pending.is_empty() {
-                    pending_invites.remove(inviter);
-                }
-
-                Some(inviter)
-            }) {
-                // Inform inviter of rejection
-                if let (Some(client), Some(target)) =
-                    (clients.get_mut(inviter), uids.get(entity).copied())
-                {
-                    client.send_msg(ServerGeneral::InviteComplete {
-                        target,
-                        answer: InviteAnswer::Declined,
-                    })
-                }
-            }
+            take_invite(state.ecs(), entity, InviteAnswer::Declined);
         },
         GroupManip::Leave => {
             let mut clients = state.ecs().write_storage::<Client>();
@@ -450,3 +421,143 @@ pub fn handle_group(server: &mut Server, entity: specs::Entity, manip: GroupMani
         },
     }
 }
+
+/// Invites an entity to something other than a group, returning whether the
+/// invite was sent
+pub(super) fn send_invite(
+    ecs: &specs::World,
+    inviter: specs::Entity,
+    invitee: specs::Entity,
+    kind: InviteKind,
+) -> bool {
+    let inviter_uid = match ecs.read_storage::<sync::Uid>().get(inviter).copied() {
+        Some(uid) => uid,
+        None => return false,
+    };
+    let mut clients = ecs.write_storage::<Client>();
+    let mut invites = ecs.write_storage::<Invite>();
+    if invites.contains(invitee) {
+        if let Some(client) = clients.get_mut(inviter) {
+            client.send_msg(
+                ChatType::Meta.server_msg("This player already has a pending invite.".to_owned()),
+            );
+        }
+        return false;
+    }
+    let mut pending_invites = ecs.write_storage::<PendingInvites>();
+    let inserted = invites.insert(invitee, Invite { inviter, kind }).is_ok();
+    let pending_inserted = inserted
+        && pending_invites
+            .entry(inviter)
+            .map(|entry| {
+                entry.or_insert_with(|| PendingInvites(Vec::new())).0.push((
+                    invitee,
+                    kind,
+                    Instant::now() + INVITE_TIMEOUT_DUR,
+                ))
+            })
+            .is_ok();
+    if !pending_inserted {
+        invites.remove(invitee);
+        return false;
+    }
+    if let Some(client) = clients.get_mut(invitee) {
+        client.send_msg(ServerGeneral::Invite {
+            inviter: inviter_uid,
+            timeout: PRESENTED_INVITE_TIMEOUT_DUR,
+            kind,
+        });
+    }
+    true
+}
+
+/// Removes the invite `invitee` answered and tells the inviter about the
+/// answer, returning the inviter and the kind of invite
+fn take_invite(
+    ecs: &specs::World,
+    invitee: specs::Entity,
+    answer: InviteAnswer,
+) -> Option<(specs::Entity, InviteKind)> {
+    let Invite { inviter, kind } = ecs.write_storage::<Invite>().remove(invitee)?;
+    let mut pending_invites = ecs.write_storage::<PendingInvites>();
+    let pending = &mut pending_invites.get_mut(inviter)?.0;
+    // Check that inviter has a pending invite and remove it from the list
+    let invite_index = pending.iter().position(|p| p.0 == invitee)?;
+    pending.swap_remove(invite_index);
+    // If no pending invites remain remove the component
+    if pending.is_empty() {
+        pending_invites.remove(inviter);
+    }
+
+    if let (Some(client), Some(target)) = (
+        ecs.write_storage::<Client>().get_mut(inviter),
+        ecs.read_storage::<sync::Uid>().get(invitee).copied(),
+    ) {
+        client.send_msg(ServerGeneral::InviteComplete {
+            target,
+            answer,
+            kind,
+        })
+    }
+
+    Some((inviter, kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sys::{invite_timeout, InviteTimeoutTimer};
+    use specs::{Builder, RunNow};
+
+    /// An inviter and an invitee with an open invite of `kind`, which times out
+    /// at `timeout`
+    fn setup(kind: InviteKind, timeout: Instant) -> (specs::World, specs::Entity, specs::Entity) {
+        let mut world = specs::World::new();
+        world.register::<sync::Uid>();
+        world.register::<Client>();
+        world.register::<Invite>();
+        world.register::<PendingInvites>();
+        world.insert(InviteTimeoutTimer::default());
+        let inviter = world.create_entity().with(sync::Uid(1)).build();
+        let invitee = world
+            .create_entity()
+            .with(sync::Uid(2))
+            .with(Invite { inviter, kind })
+            .build();
+        world
+            .write_storage()
+            .insert(inviter, PendingInvites(vec![(invitee, kind, timeout)]))
+            .unwrap();
+        (world, inviter, invitee)
+    }
+
+    #[test]
+    fn answering_takes_the_invite() {
+        let (world, inviter, invitee) =
+            setup(InviteKind::Trade, Instant::now() + INVITE_TIMEOUT_DUR);
+
+        assert_eq!(
+            take_invite(&world, invitee, InviteAnswer::Accepted),
+            Some((inviter, InviteKind::Trade))
+        );
+        assert!(!world.read_storage::<Invite>().contains(invitee));
+        assert!(!world.read_storage::<PendingInvites>().contains(inviter));
+
+        // An invite can only be answered once
+        assert_eq!(take_invite(&world, invitee, InviteAnswer::Declined), None);
+        assert_eq!(take_invite(&world, inviter, InviteAnswer::Accepted), None);
+    }
+
+    #[test]
+    fn invites_time_out() {
+        let (world, _, invitee) = setup(InviteKind::Group, Instant::now() + INVITE_TIMEOUT_DUR);
+        invite_timeout::Sys.run_now(&world);
+        assert!(world.read_storage::<Invite>().contains(invitee));
+
+        let (world, inviter, invitee) = setup(InviteKind::Trade, Instant::now());
+        invite_timeout::Sys.run_now(&world);
+        assert!(!world.read_storage::<Invite>().contains(invitee));
+        assert!(!world.read_storage::<PendingInvites>().contains(inviter));
+        assert_eq!(take_invite(&world, invitee, InviteAnswer::Accepted), None);
+    }
+}
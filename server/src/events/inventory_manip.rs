@@ -1,4 +1,4 @@
-use crate::{client::Client, Server, StateExt};
+use crate::{client::Client, economy::EconomySim, quest, Server, StateExt};
 use common::{
     comp::{
         self, item,
@@ -6,13 +6,17 @@ use common::{
         Pos, MAX_PICKUP_RANGE_SQR,
     },
     msg::ServerGeneral,
-    recipe::default_recipe_book,
+    recipe::{default_recipe_book, Recipe},
+    state::State,
+    states::crafting,
     sync::{Uid, WorldSyncExt},
+    trade::{Good, PendingTrade, TradeResult, Trades},
     vol::ReadVol,
 };
 use comp::LightEmitter;
 use rand::Rng;
 use specs::{join::Join, world::WorldExt, Builder, Entity as EcsEntity, WriteStorage};
+use std::time::Duration;
 use tracing::{debug, error};
 use vek::{Rgb, Vec3};
 
@@ -38,6 +42,11 @@ pub fn handle_inventory(server: &mut Server, entity: EcsEntity, manip: comp::Inv
     let mut dropped_items = Vec::new();
     let mut thrown_items = Vec::new();
 
+    // The items of a trade that is being reviewed must stay where they are
+    if in_immutable_trade(state, entity) {
+        return;
+    }
+
     match manip {
         comp::InventoryManip::Pickup(uid) => {
             let picked_up_item: Option<comp::Item>;
@@ -85,6 +94,7 @@ pub fn handle_inventory(server: &mut Server, entity: EcsEntity, manip: comp::Inv
                     // player's inventory but also left on the ground
                     panic!("Failed to delete picked up item entity: {:?}", err);
                 }
+                quest::record_held(state.ecs(), entity);
                 comp::InventoryUpdate::new(comp::InventoryUpdateEvent::Collected(
                     picked_up_item.unwrap(),
                 ))
@@ -101,6 +111,7 @@ pub fn handle_inventory(server: &mut Server, entity: EcsEntity, manip: comp::Inv
             if let Some(block) = block {
                 if block.is_collectible() && state.can_set_block(pos) {
                     if let Some(item) = comp::Item::try_reclaim_from_block(block) {
+                        let good = Good::of_item(&item);
                         let (event, item_was_added) = if let Some(inv) = state
                             .ecs()
                             .write_storage::<comp::Inventory>()
@@ -131,7 +142,17 @@ pub fn handle_inventory(server: &mut Server, entity: EcsEntity, manip: comp::Inv
                             state.write_component(entity, event);
                             if item_was_added {
                                 // we made sure earlier the block was not already modified this tick
-                                state.set_block(pos, block.into_vacant())
+                                state.set_block(pos, block.into_vacant());
+                                // Gathered and mined goods feed the economy of the closest
+                                // site
+                                if let Some(good) = good {
+                                    state.ecs().write_resource::<EconomySim>().contribute(
+                                        pos.xy().map(|e| e as f32),
+                                        good,
+                                        1.0,
+                                    );
+                                }
+                                quest::record_held(state.ecs(), entity);
                             };
                         }
                     } else {
@@ -389,53 +410,35 @@ pub fn handle_inventory(server: &mut Server, entity: EcsEntity, manip: comp::Inv
         },
 
         comp::InventoryManip::CraftRecipe(recipe) => {
-            if let Some(inv) = state
-                .ecs()
-                .write_storage::<comp::Inventory>()
-                .get_mut(entity)
-            {
-                let recipe_book = default_recipe_book();
-                let craft_result = recipe_book.get(&recipe).and_then(|r| r.perform(inv).ok());
-
-                // FIXME: We should really require the drop and write to be atomic!
-                if craft_result.is_some() {
-                    let _ = state.ecs().write_storage().insert(
-                        entity,
-                        comp::InventoryUpdate::new(comp::InventoryUpdateEvent::Craft),
-                    );
-                }
-
-                // Drop the item if there wasn't enough space
-                if let Some(Some((item, amount))) = craft_result {
-                    for _ in 0..amount {
-                        dropped_items.push((
-                            state
-                                .read_component_copied::<comp::Pos>(entity)
-                                .unwrap_or_default(),
-                            state
-                                .read_component_copied::<comp::Ori>(entity)
-                                .unwrap_or_default(),
-                            item.clone(),
-                        ));
+            let craft_time = default_recipe_book()
+                .get(&recipe)
+                .filter(|r| can_craft(state, entity, r))
+                .map(|r| r.craft_time);
+            match craft_time {
+                Some(craft_time) if craft_time == Duration::default() => {
+                    craft(state, entity, &recipe, &mut dropped_items);
+                },
+                // The ingredients are only used up once the craft time ran out
+                Some(craft_time) => {
+                    if let Some(character_state) = state
+                        .ecs()
+                        .write_storage::<comp::CharacterState>()
+                        .get_mut(entity)
+                    {
+                        if !matches!(character_state, comp::CharacterState::Crafting(_)) {
+                            *character_state = comp::CharacterState::Crafting(crafting::Data {
+                                static_data: crafting::StaticData { recipe, craft_time },
+                                timer: Duration::default(),
+                            });
+                        }
                     }
-                }
+                },
+                None => debug!(?entity, ?recipe, "Rejected crafting a recipe"),
             }
         },
     }
 
-    // Drop items
-    for (pos, ori, item) in dropped_items {
-        let vel = *ori.0 * 5.0
-            + Vec3::unit_z() * 10.0
-            + Vec3::<f32>::zero().map(|_| rand::thread_rng().gen::<f32>() - 0.5) * 4.0;
-
-        state
-            .create_object(Default::default(), comp::object::Body::Pouch)
-            .with(comp::Pos(pos.0 + Vec3::unit_z() * 0.25))
-            .with(item)
-            .with(comp::Vel(vel))
-            .build();
-    }
+    drop_items(state, dropped_items);
 
     let mut rng = rand::thread_rng();
 
@@ -501,6 +504,188 @@ pub fn handle_inventory(server: &mut Server, entity: EcsEntity, manip: comp::Inv
     }
 }
 
+/// Whether the character is near the station of the recipe and has its
+/// ingredients
+fn can_craft(state: &State, entity: EcsEntity, recipe: &Recipe) -> bool {
+    let near_station = recipe.station.map_or(true, |station| {
+        state
+            .read_component_copied::<Pos>(entity)
+            .map_or(false, |pos| station.is_near(state.ecs(), pos.0))
+    });
+    near_station
+        && state
+            .ecs()
+            .read_storage::<comp::Inventory>()
+            .get(entity)
+            .map_or(false, |inv| inv.contains_ingredients(recipe).is_ok())
+}
+
+/// Crafts a recipe the character can craft, the items that don't fit into the
+/// inventory are dropped
+fn craft(
+    state: &State,
+    entity: EcsEntity,
+    recipe: &str,
+    dropped_items: &mut Vec<(Pos, comp::Ori, comp::Item)>,
+) {
+    if let Some(inv) = state
+        .ecs()
+        .write_storage::<comp::Inventory>()
+        .get_mut(entity)
+    {
+        let recipe_book = default_recipe_book();
+        let craft_result = recipe_book.get(recipe).and_then(|r| r.perform(inv).ok());
+
+        // FIXME: We should really require the drop and write to be atomic!
+        if craft_result.is_some() {
+            let _ = state.ecs().write_storage().insert(
+                entity,
+                comp::InventoryUpdate::new(comp::InventoryUpdateEvent::Craft),
+            );
+        }
+
+        // Drop the item if there wasn't enough space
+        if let Some(Some((item, amount))) = craft_result {
+            for _ in 0..amount {
+                dropped_items.push((
+                    state
+                        .read_component_copied::<comp::Pos>(entity)
+                        .unwrap_or_default(),
+                    state
+                        .read_component_copied::<comp::Ori>(entity)
+                        .unwrap_or_default(),
+                    item.clone(),
+                ));
+            }
+        }
+    }
+}
+
+/// Crafts a recipe once its craft time ran out, if the character is still near
+/// the station and has the ingredients
+pub fn handle_finish_craft(server: &mut Server, entity: EcsEntity, recipe: String) {
+    let state = server.state_mut();
+    if in_immutable_trade(state, entity) {
+        return;
+    }
+
+    let mut dropped_items = Vec::new();
+    if default_recipe_book()
+        .get(&recipe)
+        .map_or(false, |r| can_craft(state, entity, r))
+    {
+        craft(state, entity, &recipe, &mut dropped_items);
+    } else {
+        debug!(?entity, ?recipe, "Could not finish crafting a recipe");
+    }
+
+    drop_items(state, dropped_items);
+}
+
+/// Whether the character is in a trade being reviewed, in which case its
+/// items must stay where they are
+fn in_immutable_trade(state: &State, entity: EcsEntity) -> bool {
+    state
+        .read_component_copied::<Uid>(entity)
+        .map_or(false, |uid| {
+            let in_trade = state
+                .ecs()
+                .read_resource::<Trades>()
+                .in_immutable_trade(uid);
+            if in_trade {
+                debug!(?uid, "Ignoring inventory change during a trade");
+            }
+            in_trade
+        })
+}
+
+fn drop_items(state: &mut State, dropped_items: Vec<(Pos, comp::Ori, comp::Item)>) {
+    for (pos, ori, item) in dropped_items {
+        let vel = *ori.0 * 5.0
+            + Vec3::unit_z() * 10.0
+            + Vec3::<f32>::zero().map(|_| rand::thread_rng().gen::<f32>() - 0.5) * 4.0;
+
+        state
+            .create_object(Default::default(), comp::object::Body::Pouch)
+            .with(comp::Pos(pos.0 + Vec3::unit_z() * 0.25))
+            .with(item)
+            .with(comp::Vel(vel))
+            .build();
+    }
+}
+
+/// Exchanges the items the parties of a completed trade offered. This either
+/// exchanges all of them or, if an offer is no longer in the inventory, was
+/// replaced by another item or the items don't fit, changes nothing.
+pub fn perform_trade(
+    inventories: &mut WriteStorage<comp::Inventory>,
+    trade: &PendingTrade,
+    entities: [EcsEntity; 2],
+) -> TradeResult {
+    let mut new_inventories = match (inventories.get(entities[0]), inventories.get(entities[1])) {
+        (Some(a), Some(b)) => [a.clone(), b.clone()],
+        _ => return TradeResult::Declined,
+    };
+
+    let mut items = [Vec::new(), Vec::new()];
+    for (who, offer) in trade.offers.iter().enumerate() {
+        for (slot, quantity) in offer.iter() {
+            let taken = trade.offered_items[who].get(slot).and_then(|item_id| {
+                take_amount(&mut new_inventories[who], *slot, item_id, *quantity)
+            });
+            match taken {
+                Some(mut item) => {
+                    // The item now belongs to another character
+                    item.put_in_world();
+                    items[who].push(item);
+                },
+                None => {
+                    debug!(
+                        ?slot,
+                        ?quantity,
+                        "Offered items are no longer in the inventory"
+                    );
+                    return TradeResult::Declined;
+                },
+            }
+        }
+    }
+    for (who, inventory) in new_inventories.iter_mut().enumerate() {
+        let received = std::mem::take(&mut items[1 - who]);
+        if inventory.push_all(received.into_iter()).is_err() {
+            return TradeResult::NotEnoughSpace;
+        }
+    }
+
+    let [a, b] = new_inventories;
+    let _ = inventories.insert(entities[0], a);
+    let _ = inventories.insert(entities[1], b);
+    TradeResult::Completed
+}
+
+/// Takes `amount` of the item in a slot, or nothing if there are fewer or the
+/// slot holds another item than `item_id`
+fn take_amount(
+    inventory: &mut comp::Inventory,
+    slot: usize,
+    item_id: &str,
+    amount: u32,
+) -> Option<comp::Item> {
+    let item = inventory.get(slot)?;
+    if item.item_definition_id() != item_id || item.amount() < amount {
+        return None;
+    }
+    let mut item = inventory.remove(slot)?;
+    if item.amount() == amount {
+        return Some(item);
+    }
+    let mut taken = item.duplicate();
+    taken.set_amount(amount).ok()?;
+    item.decrease_amount(amount).ok()?;
+    inventory.insert(slot, item).ok()?;
+    Some(taken)
+}
+
 fn within_pickup_range(player_position: Option<&Pos>, item_position: Option<&Pos>) -> bool {
     match (player_position, item_position) {
         (Some(ppos), Some(ipos)) => ppos.0.distance_squared(ipos.0) < MAX_PICKUP_RANGE_SQR,
@@ -511,9 +696,12 @@ fn within_pickup_range(player_position: Option<&Pos>, item_position: Option<&Pos
 #[cfg(test)]
 mod tests {
     use super::*;
-    use common::comp::Pos;
+    use common::{comp::Pos, trade::COINS};
     use vek::Vec3;
 
+    const APPLE: &str = "common.items.food.apple";
+    const CHEESE: &str = "common.items.food.cheese";
+
     #[test]
     fn pickup_distance_within_range() {
         let player_position = Pos(Vec3::zero());
@@ -535,4 +723,103 @@ mod tests {
             false
         );
     }
+
+    fn item(asset: &str, amount: u32) -> comp::Item {
+        let mut item = comp::Item::new_from_asset_expect(asset);
+        item.set_amount(amount).unwrap();
+        item
+    }
+
+    fn count(inventory: &comp::Inventory, asset: &str) -> u32 {
+        inventory
+            .slots()
+            .iter()
+            .flatten()
+            .filter(|item| item.item_definition_id() == asset)
+            .map(|item| item.amount())
+            .sum()
+    }
+
+    /// Two entities, the first one with 5 apples in slot 0
+    fn setup(inventory: comp::Inventory) -> (specs::World, [EcsEntity; 2]) {
+        let mut world = specs::World::new();
+        world.register::<comp::Inventory>();
+        let mut apples = comp::Inventory::new_empty();
+        apples.push(item(APPLE, 5));
+        let a = world.create_entity().with(apples).build();
+        let b = world.create_entity().with(inventory).build();
+        (world, [a, b])
+    }
+
+    /// Lets party `who` offer `quantity` of the item in slot 0
+    fn offer(trade: &mut PendingTrade, who: usize, asset: &str, quantity: u32) {
+        trade.offers[who].insert(0, quantity);
+        trade.offered_items[who].insert(0, asset.to_owned());
+    }
+
+    #[test]
+    fn trade_exchanges_offers() {
+        let mut coins = comp::Inventory::new_empty();
+        coins.push(item(COINS, 50));
+        let (world, [a, b]) = setup(coins);
+        let mut trade = PendingTrade::new(Uid(1), Uid(2));
+        offer(&mut trade, 0, APPLE, 3);
+        offer(&mut trade, 1, COINS, 20);
+
+        assert_eq!(
+            perform_trade(&mut world.write_storage(), &trade, [a, b]),
+            TradeResult::Completed
+        );
+        let inventories = world.read_storage::<comp::Inventory>();
+        assert_eq!(count(inventories.get(a).unwrap(), APPLE), 2);
+        assert_eq!(count(inventories.get(a).unwrap(), COINS), 20);
+        assert_eq!(count(inventories.get(b).unwrap(), APPLE), 3);
+        assert_eq!(count(inventories.get(b).unwrap(), COINS), 30);
+    }
+
+    #[test]
+    fn trade_without_space_changes_nothing() {
+        let mut full = comp::Inventory::new_empty();
+        for slot in 0..full.len() {
+            full.insert(slot, item(CHEESE, 1)).unwrap();
+        }
+        let slots = full.len() as u32;
+        let (world, [a, b]) = setup(full);
+        let mut trade = PendingTrade::new(Uid(1), Uid(2));
+        offer(&mut trade, 0, APPLE, 3);
+
+        assert_eq!(
+            perform_trade(&mut world.write_storage(), &trade, [a, b]),
+            TradeResult::NotEnoughSpace
+        );
+        let inventories = world.read_storage::<comp::Inventory>();
+        assert_eq!(count(inventories.get(a).unwrap(), APPLE), 5);
+        assert_eq!(count(inventories.get(b).unwrap(), APPLE), 0);
+        assert_eq!(count(inventories.get(b).unwrap(), CHEESE), slots);
+    }
+
+    #[test]
+    fn trade_of_replaced_items_changes_nothing() {
+        let mut coins = comp::Inventory::new_empty();
+        coins.push(item(COINS, 50));
+        let (world, [a, b]) = setup(coins);
+        let mut trade = PendingTrade::new(Uid(1), Uid(2));
+        offer(&mut trade, 0, APPLE, 3);
+        offer(&mut trade, 1, COINS, 20);
+        // The apples were swapped for cheese after they were offered
+        world
+            .write_storage::<comp::Inventory>()
+            .get_mut(a)
+            .unwrap()
+            .insert(0, item(CHEESE, 5))
+            .unwrap();
+
+        assert_eq!(
+            perform_trade(&mut world.write_storage(), &trade, [a, b]),
+            TradeResult::Declined
+        );
+        let inventories = world.read_storage::<comp::Inventory>();
+        assert_eq!(count(inventories.get(a).unwrap(), CHEESE), 5);
+        assert_eq!(count(inventories.get(b).unwrap(), COINS), 50);
+    }
 }
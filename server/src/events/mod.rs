@@ -8,14 +8,17 @@ use entity_creation::{
     handle_loaded_character_data, handle_shockwave, handle_shoot,
 };
 use entity_manipulation::{
-    handle_damage, handle_destroy, handle_explosion, handle_knockback, handle_land_on_ground,
-    handle_level_up, handle_respawn,
+    handle_buff, handle_damage, handle_destroy, handle_explosion, handle_knockback,
+    handle_land_on_ground, handle_level_up, handle_respawn,
 };
 use group_manip::handle_group;
 use interaction::{handle_lantern, handle_mount, handle_possess, handle_unmount};
-use inventory_manip::handle_inventory;
+use inventory_manip::{handle_finish_craft, handle_inventory};
 use player::{handle_client_disconnect, handle_exit_ingame};
+use quest::handle_talk;
 use specs::{Entity as EcsEntity, WorldExt};
+use std::time::Duration;
+use trade::{handle_initiate_trade, handle_process_trade_action, handle_site_info};
 
 mod entity_creation;
 mod entity_manipulation;
@@ -23,6 +26,8 @@ mod group_manip;
 mod interaction;
 mod inventory_manip;
 mod player;
+mod quest;
+mod trade;
 
 pub enum Event {
     ClientConnected {
@@ -35,6 +40,11 @@ pub enum Event {
         entity: Option<EcsEntity>,
         msg: String,
     },
+    /// A graceful shutdown was requested through the admin API
+    ShutdownRequested {
+        grace_period: Duration,
+        reason: String,
+    },
 }
 
 impl Server {
@@ -84,8 +94,20 @@ impl Server {
                     handle_knockback(&self, entity, impulse)
                 },
                 ServerEvent::Damage { uid, change } => handle_damage(&self, uid, change),
+                ServerEvent::Buff { uid, buff_change } => handle_buff(&self, uid, buff_change),
                 ServerEvent::Destroy { entity, cause } => handle_destroy(self, entity, cause),
                 ServerEvent::InventoryManip(entity, manip) => handle_inventory(self, entity, manip),
+                ServerEvent::FinishCraft { entity, recipe } => {
+                    handle_finish_craft(self, entity, recipe)
+                },
+                ServerEvent::InitiateTrade(entity, counterparty) => {
+                    handle_initiate_trade(&self, entity, counterparty)
+                },
+                ServerEvent::ProcessTradeAction(entity, trade_id, action) => {
+                    handle_process_trade_action(&self, entity, trade_id, action)
+                },
+                ServerEvent::RequestSiteInfo { entity, id } => handle_site_info(&self, entity, id),
+                ServerEvent::Talk { entity, npc } => handle_talk(&self, entity, npc),
                 ServerEvent::GroupManip(entity, manip) => handle_group(self, entity, manip),
                 ServerEvent::Respawn(entity) => handle_respawn(&self, entity),
                 ServerEvent::LandOnGround { entity, vel } => {
@@ -116,8 +138,20 @@ impl Server {
                     alignment,
                     scale,
                     drop_item,
+                    trading_information,
+                    rtsim_entity,
                 } => handle_create_npc(
-                    self, pos, stats, loadout, body, agent, alignment, scale, drop_item,
+                    self,
+                    pos,
+                    stats,
+                    loadout,
+                    body,
+                    agent,
+                    alignment,
+                    scale,
+                    drop_item,
+                    trading_information,
+                    rtsim_entity,
                 ),
                 ServerEvent::CreateWaypoint(pos) => handle_create_waypoint(self, pos),
                 ServerEvent::ClientDisconnect(entity) => {
@@ -1,4 +1,4 @@
-use super::Event;
+use super::{trade::cancel_trade, Event};
 use crate::{
     client::Client, login_provider::LoginProvider, persistence, state_ext::StateExt, Server,
 };
@@ -15,6 +15,7 @@ use tracing::{debug, error, trace, warn};
 
 pub fn handle_exit_ingame(server: &mut Server, entity: EcsEntity) {
     span!(_guard, "handle_exit_ingame");
+    cancel_trade(server, entity);
     let state = server.state_mut();
 
     // Create new entity with just `Client`, `Uid`, and `Player` components
@@ -89,6 +90,7 @@ pub fn handle_exit_ingame(server: &mut Server, entity: EcsEntity) {
 
 pub fn handle_client_disconnect(server: &mut Server, entity: EcsEntity) -> Event {
     span!(_guard, "handle_client_disconnect");
+    cancel_trade(server, entity);
     if let Some(client) = server
         .state()
         .ecs()
@@ -151,7 +153,15 @@ pub fn handle_client_disconnect(server: &mut Server, entity: EcsEntity) -> Event
             .read_resource::<persistence::character_updater::CharacterUpdater>(),
     ) {
         if let Some(character_id) = player.character_id {
-            updater.update(character_id, stats, inventory, loadout);
+            updater.update(
+                character_id,
+                stats,
+                inventory,
+                loadout,
+                state.read_storage::<comp::Pos>().get(entity),
+                state.read_storage::<comp::Waypoint>().get(entity),
+                state.read_storage::<comp::QuestLog>().get(entity),
+            );
         }
     }
 
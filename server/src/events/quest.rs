@@ -0,0 +1,93 @@
+use super::group_manip::send_invite;
+use crate::{client::Client, quest, Server};
+use common::{
+    assets::Asset,
+    comp::{self, group::InviteKind, QuestLog},
+    msg::ServerGeneral,
+    quest::{QuestDef, QuestEvent, QuestNpc},
+    sync::{Uid, WorldSyncExt},
+};
+use specs::{Entity as EcsEntity, WorldExt};
+use tracing::debug;
+
+/// Characters have to be this close to an NPC to talk to it (squared)
+const MAX_TALK_RANGE_SQR: f32 = 100.0;
+
+/// Talking to an NPC counts for the objectives that ask for it, and the NPC
+/// offers the first quest it gives the character didn't take on yet. The
+/// quest only starts once the character accepts the offer.
+pub fn handle_talk(server: &Server, entity: EcsEntity, npc: Uid) {
+    let ecs = server.state.ecs();
+    let npc_entity = match ecs.entity_from_uid(npc.0) {
+        Some(npc_entity) => npc_entity,
+        None => return,
+    };
+    // Players don't hand out quests
+    if ecs.read_storage::<Client>().contains(npc_entity) {
+        return;
+    }
+    // Neither do cultists and bandits, who would rather fight
+    let hostile = ecs
+        .read_storage::<comp::Alignment>()
+        .get(npc_entity)
+        .map_or(false, |alignment| *alignment == comp::Alignment::Enemy);
+    let positions = ecs.read_storage::<comp::Pos>();
+    let in_range = match (positions.get(entity), positions.get(npc_entity)) {
+        (Some(a), Some(b)) => a.0.distance_squared(b.0) < MAX_TALK_RANGE_SQR,
+        _ => false,
+    };
+    let quest_npc = match quest::quest_npc(ecs, npc_entity) {
+        Some(quest_npc) if in_range && !hostile => quest_npc,
+        _ => {
+            debug!(?entity, ?npc, "Rejected talking to an NPC");
+            return;
+        },
+    };
+
+    let offers_quest = {
+        let mut quest_logs = ecs.write_storage::<QuestLog>();
+        let mut clients = ecs.write_storage::<Client>();
+        let (log, client) = match (quest_logs.get_mut(entity), clients.get_mut(entity)) {
+            (Some(log), Some(client)) => (log, client),
+            _ => return,
+        };
+
+        quest::record(log, client, &QuestEvent::Talked(quest_npc));
+        offered_quest(log, &quest_npc).is_some()
+    };
+    if offers_quest {
+        send_invite(ecs, npc_entity, entity, InviteKind::Quest);
+    }
+}
+
+/// Starts the quest an NPC offered, once the character accepted the offer
+pub fn start_offered_quest(ecs: &specs::World, entity: EcsEntity, npc: EcsEntity) {
+    let quest_npc = match quest::quest_npc(ecs, npc) {
+        Some(quest_npc) => quest_npc,
+        None => return,
+    };
+    let mut quest_logs = ecs.write_storage::<QuestLog>();
+    let mut clients = ecs.write_storage::<Client>();
+    let (log, client) = match (quest_logs.get_mut(entity), clients.get_mut(entity)) {
+        (Some(log), Some(client)) => (log, client),
+        _ => return,
+    };
+
+    if let Some(quest) = offered_quest(log, &quest_npc) {
+        if log.start(&quest) {
+            client.send_msg(ServerGeneral::QuestStarted(quest));
+            client.send_msg(ServerGeneral::QuestLog(log.clone()));
+            // Items the character holds already count for the new quest
+            if let Some(inventory) = ecs.read_storage::<comp::Inventory>().get(entity) {
+                quest::record_inventory(log, client, inventory);
+            }
+        }
+    }
+}
+
+/// The first quest an NPC gives, which the character didn't take on yet
+fn offered_quest(log: &QuestLog, npc: &QuestNpc) -> Option<String> {
+    common::quest::all_quests().into_iter().find(|quest| {
+        !log.has_quest(quest) && QuestDef::load(quest).map_or(false, |def| def.giver.matches(npc))
+    })
+}
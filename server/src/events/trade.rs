@@ -0,0 +1,486 @@
+use super::{group_manip::send_invite, inventory_manip::perform_trade};
+use crate::{client::Client, economy::EconomySim, Server};
+use common::{
+    comp::{self, group::InviteKind, Merchant},
+    msg::ServerGeneral,
+    sync::{Uid, WorldSyncExt},
+    trade::{
+        Good, PendingTrade, SiteId, TradeAction, TradeId, TradeResult, Trades, COINS,
+        MAX_TRADE_RANGE_SQR,
+    },
+};
+use specs::{Entity as EcsEntity, WorldExt};
+use tracing::{debug, warn};
+use vek::*;
+
+/// Starts a trade with a merchant right away, and invites other players to
+/// trade, which starts the trade once they accept
+pub fn handle_initiate_trade(server: &Server, entity: EcsEntity, counterparty: Uid) {
+    let ecs = server.state.ecs();
+    let uid = match ecs.read_storage::<Uid>().get(entity).copied() {
+        Some(uid) => uid,
+        None => return,
+    };
+    let counterparty_entity = match ecs.entity_from_uid(counterparty.0) {
+        Some(counterparty_entity) => counterparty_entity,
+        None => return,
+    };
+    let in_range = in_trade_range(ecs, entity, counterparty_entity);
+    let is_merchant = ecs.read_storage::<Merchant>().contains(counterparty_entity);
+    let is_player = ecs.read_storage::<Client>().contains(counterparty_entity);
+
+    if in_range && is_player && uid != counterparty {
+        if send_invite(ecs, entity, counterparty_entity, InviteKind::Trade) {
+            return;
+        }
+    } else if in_range && is_merchant {
+        let trade_id = ecs
+            .write_resource::<Trades>()
+            .begin_trade(uid, counterparty);
+        if let Some(trade_id) = trade_id {
+            send_trade_update(server, trade_id);
+            return;
+        }
+    }
+    debug!(?uid, ?counterparty, "Rejected trade request");
+    send_to(
+        server,
+        entity,
+        ServerGeneral::FinishedTrade(TradeResult::Declined),
+    );
+}
+
+/// Starts the trade between two players once the invitee accepted the invite
+/// to trade
+pub fn begin_player_trade(server: &Server, inviter: EcsEntity, invitee: EcsEntity) {
+    match start_player_trade(server.state.ecs(), inviter, invitee) {
+        Some(trade_id) => send_trade_update(server, trade_id),
+        None => {
+            for entity in [inviter, invitee].iter() {
+                send_to(
+                    server,
+                    *entity,
+                    ServerGeneral::FinishedTrade(TradeResult::Declined),
+                );
+            }
+        },
+    }
+}
+
+/// Begins the trade between two players, unless one of them is in another
+/// trade or they walked away from each other while the invite was open
+fn start_player_trade(
+    ecs: &specs::World,
+    inviter: EcsEntity,
+    invitee: EcsEntity,
+) -> Option<TradeId> {
+    let uids = ecs.read_storage::<Uid>();
+    let (a, b) = (uids.get(inviter)?, uids.get(invitee)?);
+    if in_trade_range(ecs, inviter, invitee) {
+        ecs.write_resource::<Trades>().begin_trade(*a, *b)
+    } else {
+        None
+    }
+}
+
+pub fn handle_process_trade_action(
+    server: &Server,
+    entity: EcsEntity,
+    trade_id: TradeId,
+    action: TradeAction,
+) {
+    let ecs = server.state.ecs();
+    let uid = match ecs.read_storage::<Uid>().get(entity).copied() {
+        Some(uid) => uid,
+        None => return,
+    };
+
+    if let TradeAction::Decline = action {
+        let counterparty = ecs.write_resource::<Trades>().decline_trade(trade_id, uid);
+        if let Some(counterparty) = counterparty {
+            let counterparty_entity = ecs.entity_from_uid(counterparty.0);
+            send_to(
+                server,
+                entity,
+                ServerGeneral::FinishedTrade(TradeResult::Declined),
+            );
+            if let Some(counterparty_entity) = counterparty_entity {
+                send_to(
+                    server,
+                    counterparty_entity,
+                    ServerGeneral::FinishedTrade(TradeResult::Declined),
+                );
+            }
+        }
+        return;
+    }
+
+    // Parties that walked away from each other can't trade anymore
+    let out_of_range = ecs
+        .read_resource::<Trades>()
+        .trades
+        .get(&trade_id)
+        .map_or(false, |trade| match party_entities(server, trade) {
+            [Some(a), Some(b)] => !in_trade_range(ecs, a, b),
+            _ => true,
+        });
+    if out_of_range {
+        handle_process_trade_action(server, entity, trade_id, TradeAction::Decline);
+        return;
+    }
+
+    {
+        let mut trades = ecs.write_resource::<Trades>();
+        if trades.trade_of(uid) != Some(trade_id) {
+            debug!(
+                ?uid,
+                ?trade_id,
+                "Entity tried to change a trade it is not part of"
+            );
+            return;
+        }
+        let trade = match trades.trades.get(&trade_id) {
+            Some(trade) => trade,
+            None => return,
+        };
+        let entities = party_entities(server, trade);
+        // Only merchants let others pick from their inventory
+        let asks_for_items = matches!(
+            action,
+            TradeAction::AddItem { ours: false, .. } | TradeAction::RemoveItem { ours: false, .. }
+        );
+        let counterparty_is_merchant = trade
+            .which_party(uid)
+            .and_then(|who| entities[1 - who])
+            .map_or(false, |counterparty| {
+                ecs.read_storage::<Merchant>().contains(counterparty)
+            });
+        if asks_for_items && !counterparty_is_merchant {
+            debug!(?uid, ?trade_id, "Entity asked for items from a player");
+            return;
+        }
+        let inventories = ecs.read_storage::<comp::Inventory>();
+        if let [Some(a), Some(b)] = [
+            entities[0].and_then(|entity| inventories.get(entity)),
+            entities[1].and_then(|entity| inventories.get(entity)),
+        ] {
+            trades.process_trade_action(trade_id, uid, action, [a, b]);
+        }
+    }
+    merchant_react(server, trade_id);
+
+    let completed = {
+        let trades = ecs.read_resource::<Trades>();
+        trades
+            .trades
+            .get(&trade_id)
+            .map_or(false, PendingTrade::is_complete)
+    };
+    if completed {
+        finish_trade(server, trade_id);
+    } else {
+        send_trade_update(server, trade_id);
+    }
+}
+
+/// Tells a client about the economy of a site
+pub fn handle_site_info(server: &Server, entity: EcsEntity, id: SiteId) {
+    #[cfg(feature = "worldgen")]
+    let info = server
+        .index
+        .sites
+        .iter()
+        .find(|(site_id, _)| site_id.id() == id)
+        .map(|(_, site)| site.economy().get_information(id));
+    #[cfg(not(feature = "worldgen"))]
+    let info: Option<common::trade::EconomyInfo> = None;
+
+    match info {
+        Some(info) => send_to(server, entity, ServerGeneral::SiteEconomy(info)),
+        None => debug!(?id, "Client asked for the economy of an unknown site"),
+    }
+}
+
+/// Ends the trade of an entity that leaves the game
+pub fn cancel_trade(server: &Server, entity: EcsEntity) {
+    let ecs = server.state.ecs();
+    let uid = match ecs.read_storage::<Uid>().get(entity).copied() {
+        Some(uid) => uid,
+        None => return,
+    };
+    let trade_id = ecs.read_resource::<Trades>().trade_of(uid);
+    if let Some(trade_id) = trade_id {
+        handle_process_trade_action(server, entity, trade_id, TradeAction::Decline);
+    }
+}
+
+/// Lets a merchant in the trade pay for what it is offered, and accept the
+/// trade if it is getting a good deal
+fn merchant_react(server: &Server, trade_id: TradeId) {
+    let ecs = server.state.ecs();
+    let mut trades = ecs.write_resource::<Trades>();
+    let trade = match trades.trades.get_mut(&trade_id) {
+        Some(trade) => trade,
+        None => return,
+    };
+    let entities = party_entities(server, trade);
+    let merchants = ecs.read_storage::<Merchant>();
+    let inventory_storage = ecs.read_storage::<comp::Inventory>();
+    let inventories = match (
+        entities[0].and_then(|entity| inventory_storage.get(entity)),
+        entities[1].and_then(|entity| inventory_storage.get(entity)),
+    ) {
+        (Some(a), Some(b)) => [a, b],
+        _ => return,
+    };
+    for (who, entity) in entities.iter().copied().enumerate() {
+        if let Some(merchant) = entity.and_then(|entity| merchants.get(entity)) {
+            // Pay for the items the other party offers with coins, or give change
+            let coin_slot = inventories[who].slots().iter().position(|item| {
+                item.as_ref()
+                    .map_or(false, |item| item.item_definition_id() == COINS)
+            });
+            if let Some(coin_slot) = coin_slot {
+                let coins = inventories[who]
+                    .get(coin_slot)
+                    .map_or(0, |coins| coins.amount());
+                let owed = merchant.prices.coins_owed(trade, inventories, who);
+                trade.set_offer(who, coin_slot, owed.min(coins), inventories[who]);
+            }
+            if merchant.prices.is_profitable(trade, inventories, who) {
+                let phase = trade.phase;
+                trade.process_action(who, TradeAction::Accept(phase), inventories);
+            }
+        }
+    }
+}
+
+/// Exchanges the items of a trade both parties accepted and tells them how
+/// it went
+fn finish_trade(server: &Server, trade_id: TradeId) {
+    let ecs = server.state.ecs();
+    let trade = match ecs.write_resource::<Trades>().remove(trade_id) {
+        Some(trade) => trade,
+        None => return,
+    };
+    let entities = party_entities(server, &trade);
+    let result = match entities {
+        [Some(a), Some(b)] => {
+            let sold = goods_sold_to_merchant(server, &trade, [a, b]);
+            let result = perform_trade(&mut ecs.write_storage(), &trade, [a, b]);
+            if result == TradeResult::Completed {
+                // Goods players sell end up in the stock of the merchant's site
+                if let Some((wpos, goods)) = sold {
+                    let mut economy = ecs.write_resource::<EconomySim>();
+                    for (good, amount) in goods {
+                        economy.contribute(wpos, good, amount);
+                    }
+                }
+                let mut updates = ecs.write_storage();
+                for entity in [a, b].iter() {
+                    let _ = updates.insert(
+                        *entity,
+                        comp::InventoryUpdate::new(comp::InventoryUpdateEvent::Given),
+                    );
+                }
+            }
+            result
+        },
+        _ => {
+            warn!(?trade_id, "Party of a completed trade is gone");
+            TradeResult::Declined
+        },
+    };
+    for entity in entities.iter().flatten() {
+        send_to(server, *entity, ServerGeneral::FinishedTrade(result));
+    }
+}
+
+/// The goods a player offers to a merchant in a trade, along with where the
+/// merchant is
+fn goods_sold_to_merchant(
+    server: &Server,
+    trade: &PendingTrade,
+    entities: [EcsEntity; 2],
+) -> Option<(Vec2<f32>, Vec<(Good, f32)>)> {
+    let ecs = server.state.ecs();
+    let merchants = ecs.read_storage::<Merchant>();
+    let merchant = entities
+        .iter()
+        .position(|entity| merchants.contains(*entity))?;
+    let seller = entities[1 - merchant];
+    if merchants.contains(seller) {
+        return None;
+    }
+    let wpos = ecs
+        .read_storage::<comp::Pos>()
+        .get(entities[merchant])?
+        .0
+        .xy();
+    let inventories = ecs.read_storage::<comp::Inventory>();
+    let inventory = inventories.get(seller)?;
+    let goods = trade.offers[1 - merchant]
+        .iter()
+        .filter_map(|(slot, quantity)| {
+            let good = Good::of_item(inventory.get(*slot)?)?;
+            Some((good, *quantity as f32))
+        })
+        .collect();
+    Some((wpos, goods))
+}
+
+/// Sends the state of the trade to its parties, along with the items of the
+/// other party they can see and the prices of the merchant they trade with
+fn send_trade_update(server: &Server, trade_id: TradeId) {
+    let ecs = server.state.ecs();
+    let trade = match ecs.read_resource::<Trades>().trades.get(&trade_id) {
+        Some(trade) => trade.clone(),
+        None => return,
+    };
+    let entities = party_entities(server, &trade);
+    let merchants = ecs.read_storage::<Merchant>();
+    let inventories = ecs.read_storage::<comp::Inventory>();
+    let prices = entities
+        .iter()
+        .flatten()
+        .find_map(|entity| merchants.get(*entity))
+        .map(|merchant| merchant.prices.clone());
+    for (who, entity) in entities.iter().enumerate() {
+        let counterparty = entities[1 - who];
+        // The whole stock of a merchant is on display, players only show what
+        // they offer
+        let counterparty_items = match counterparty.and_then(|e| inventories.get(e)) {
+            Some(inventory) if counterparty.map_or(false, |e| merchants.contains(e)) => inventory
+                .slots()
+                .iter()
+                .enumerate()
+                .filter_map(|(slot, item)| Some((slot, item.clone()?)))
+                .collect(),
+            Some(inventory) => trade.offers[1 - who]
+                .keys()
+                .filter_map(|slot| Some((*slot, inventory.get(*slot)?.clone())))
+                .collect(),
+            None => Vec::new(),
+        };
+        if let Some(entity) = entity {
+            send_to(server, *entity, ServerGeneral::UpdatePendingTrade {
+                id: trade_id,
+                trade: trade.clone(),
+                counterparty_items,
+                prices: prices.clone(),
+            });
+        }
+    }
+}
+
+fn in_trade_range(ecs: &specs::World, a: EcsEntity, b: EcsEntity) -> bool {
+    let positions = ecs.read_storage::<comp::Pos>();
+    match (positions.get(a), positions.get(b)) {
+        (Some(a), Some(b)) => a.0.distance_squared(b.0) < MAX_TRADE_RANGE_SQR,
+        _ => false,
+    }
+}
+
+fn party_entities(server: &Server, trade: &PendingTrade) -> [Option<EcsEntity>; 2] {
+    let ecs = server.state.ecs();
+    [
+        ecs.entity_from_uid(trade.parties[0].0),
+        ecs.entity_from_uid(trade.parties[1].0),
+    ]
+}
+
+fn send_to(server: &Server, entity: EcsEntity, msg: ServerGeneral) {
+    if let Some(client) = server.state.ecs().write_storage::<Client>().get_mut(entity) {
+        client.send_msg(msg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sys::{trade_timeout, TradeTimeoutTimer};
+    use specs::{Builder, RunNow};
+
+    /// Three players, the first two next to each other and the third one far
+    /// away
+    fn setup() -> (specs::World, [EcsEntity; 3]) {
+        let mut world = specs::World::new();
+        world.register::<Uid>();
+        world.register::<comp::Pos>();
+        world.insert(Trades::default());
+        let mut player = |uid, pos| {
+            world
+                .create_entity()
+                .with(Uid(uid))
+                .with(comp::Pos(pos))
+                .build()
+        };
+        let players = [
+            player(1, Vec3::zero()),
+            player(2, Vec3::new(5.0, 0.0, 0.0)),
+            player(3, Vec3::new(50.0, 0.0, 0.0)),
+        ];
+        (world, players)
+    }
+
+    #[test]
+    fn players_in_range_start_trading() {
+        let (world, players) = setup();
+        let trade_id = start_player_trade(&world, players[0], players[1]).unwrap();
+        let trades = world.read_resource::<Trades>();
+        assert_eq!(trades.trade_of(Uid(1)), Some(trade_id));
+        assert_eq!(trades.trade_of(Uid(2)), Some(trade_id));
+    }
+
+    #[test]
+    fn players_out_of_range_dont_trade() {
+        let (world, players) = setup();
+        assert_eq!(start_player_trade(&world, players[0], players[2]), None);
+        assert!(!world.read_resource::<Trades>().in_trade(Uid(1)));
+    }
+
+    #[test]
+    fn players_in_another_trade_dont_trade() {
+        let (world, players) = setup();
+        world
+            .write_resource::<Trades>()
+            .begin_trade(Uid(2), Uid(3))
+            .unwrap();
+        assert_eq!(start_player_trade(&world, players[0], players[1]), None);
+        assert!(!world.read_resource::<Trades>().in_trade(Uid(1)));
+    }
+
+    #[test]
+    fn trades_end_once_the_parties_walk_apart() {
+        let mut world = specs::World::new();
+        world.register_sync_marker();
+        world.register::<comp::Pos>();
+        world.register::<Client>();
+        world.insert(Trades::default());
+        world.insert(TradeTimeoutTimer::default());
+        let player = world
+            .create_entity_synced()
+            .with(comp::Pos(Vec3::zero()))
+            .build();
+        let merchant = world
+            .create_entity_synced()
+            .with(comp::Pos(Vec3::new(5.0, 0.0, 0.0)))
+            .build();
+        let (a, b) = (
+            world.uid_from_entity(player).unwrap(),
+            world.uid_from_entity(merchant).unwrap(),
+        );
+        world.write_resource::<Trades>().begin_trade(a, b).unwrap();
+
+        trade_timeout::Sys.run_now(&world);
+        assert!(world.read_resource::<Trades>().in_trade(a));
+
+        world
+            .write_storage()
+            .insert(player, comp::Pos(Vec3::new(50.0, 0.0, 0.0)))
+            .unwrap();
+        trade_timeout::Sys.run_now(&world);
+        assert!(!world.read_resource::<Trades>().in_trade(a));
+        assert!(!world.read_resource::<Trades>().in_trade(b));
+    }
+}
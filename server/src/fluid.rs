@@ -0,0 +1,316 @@
+//! Flowing water. Water blocks next to a changed block become active, and
+//! active water falls into the air below it, flows off ledges and is pushed
+//! sideways by the water above it until it has settled. Water is only ever
+//! moved, so the amount of water stays the same.
+//!
+//! Every step of flowing water is a block change, so instead of saving each of
+//! them the positions the water flowed through are collected until it has
+//! settled, and only their final blocks are saved.
+
+use common::{
+    state::BlockChange,
+    terrain::{Block, SpriteKind},
+    vol::ReadVol,
+};
+use hashbrown::{HashMap, HashSet};
+use std::{collections::BTreeSet, mem};
+use vek::*;
+
+/// Most water blocks that are updated in a tick, the others wait for the next
+/// ones
+pub const MAX_FLUID_UPDATES: usize = 1024;
+
+const HORIZONTAL: [Vec3<i32>; 4] = [
+    Vec3::new(1, 0, 0),
+    Vec3::new(0, 1, 0),
+    Vec3::new(-1, 0, 0),
+    Vec3::new(0, -1, 0),
+];
+
+#[derive(Default)]
+pub struct FluidSim {
+    /// Positions that might hold water that can move, stored as `(z, y, x)`
+    /// so that lower water is updated first
+    active: BTreeSet<(i32, i32, i32)>,
+    /// Rotates the order in which the sides are tried, so that water doesn't
+    /// drift in one direction
+    ticks: usize,
+    /// Blocks set by the last tick
+    moves: HashMap<Vec3<i32>, Block>,
+    /// Positions the water flowed through since it last settled
+    flowed: HashSet<Vec3<i32>>,
+}
+
+impl FluidSim {
+    /// Wakes up the water around blocks that were changed
+    pub fn activate_changes<'a>(&mut self, changes: impl IntoIterator<Item = &'a Vec3<i32>>) {
+        for pos in changes {
+            // The block itself and the water that can flow into it, directly or off a
+            // ledge
+            for offset in std::iter::once(Vec3::zero())
+                .chain(HORIZONTAL.iter().copied())
+                .chain(std::iter::once(Vec3::unit_z()))
+                .chain(std::iter::once(-Vec3::unit_z()))
+                .chain(HORIZONTAL.iter().map(|dir| dir + Vec3::unit_z()))
+            {
+                let pos = pos + offset;
+                self.active.insert((pos.z, pos.y, pos.x));
+            }
+        }
+    }
+
+    /// Number of blocks waiting to be updated
+    pub fn active_count(&self) -> usize { self.active.len() }
+
+    /// Whether setting `pos` to `block` was a move of water made by the last
+    /// tick
+    pub fn is_move(&self, pos: &Vec3<i32>, block: &Block) -> bool {
+        self.moves.get(pos) == Some(block)
+    }
+
+    /// Once all water has settled, returns the positions it flowed through
+    /// since it last did
+    pub fn take_settled(&mut self) -> Option<HashSet<Vec3<i32>>> {
+        if self.active.is_empty() && !self.flowed.is_empty() {
+            Some(mem::take(&mut self.flowed))
+        } else {
+            None
+        }
+    }
+
+    /// Moves up to `budget` active water blocks. The moves are written to
+    /// `block_change`, and once they are applied the changed blocks should be
+    /// passed to `activate_changes` to keep the water flowing.
+    pub fn tick<V: ReadVol<Vox = Block>>(
+        &mut self,
+        terrain: &V,
+        block_change: &mut BlockChange,
+        budget: usize,
+    ) {
+        self.ticks = self.ticks.wrapping_add(1);
+        self.moves.clear();
+        let sides = (0..HORIZONTAL.len())
+            .map(|i| HORIZONTAL[(i + self.ticks) % HORIZONTAL.len()])
+            .collect::<Vec<_>>();
+
+        let is_water = |pos: Vec3<i32>| terrain.get(pos).map_or(false, |block| block.is_liquid());
+        // Water only flows into empty air, so that it doesn't wash away sprites
+        let is_empty = |pos: Vec3<i32>| {
+            terrain.get(pos).map_or(false, |block| {
+                block.is_air() && block.get_sprite() == Some(SpriteKind::Empty)
+            })
+        };
+
+        for _ in 0..budget {
+            let pos = match self.active.iter().next().copied() {
+                Some((z, y, x)) => {
+                    self.active.remove(&(z, y, x));
+                    Vec3::new(x, y, z)
+                },
+                None => break,
+            };
+            let movable = terrain.get(pos).map_or(false, |block| {
+                block.is_liquid() && block.get_sprite() == Some(SpriteKind::Empty)
+            });
+            if !movable {
+                continue;
+            }
+
+            let below = pos - Vec3::unit_z();
+            let target = if is_empty(below) {
+                Some(below)
+            } else {
+                sides
+                    .iter()
+                    .map(|side| pos + side)
+                    .find(|side| is_empty(*side) && is_empty(side - Vec3::unit_z()))
+                    .or_else(|| {
+                        // Pushed sideways by the water above, but not under other water
+                        // which would just fall into the gap that is left
+                        if is_water(pos + Vec3::unit_z()) {
+                            sides
+                                .iter()
+                                .map(|side| pos + side)
+                                .find(|side| is_empty(*side) && !is_water(side + Vec3::unit_z()))
+                        } else {
+                            None
+                        }
+                    })
+            };
+
+            // Another block may already have flowed into the target this tick, and
+            // changes other systems made to the water itself take precedence
+            if let Some(target) = target {
+                if !block_change.contains(pos)
+                    && block_change
+                        .try_set(target, Block::water(SpriteKind::Empty))
+                        .is_some()
+                {
+                    block_change.set(pos, Block::air(SpriteKind::Empty));
+                    self.moves.insert(target, Block::water(SpriteKind::Empty));
+                    self.moves.insert(pos, Block::air(SpriteKind::Empty));
+                    self.flowed.insert(target);
+                    self.flowed.insert(pos);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{
+        terrain::{BlockKind, TerrainChunk, TerrainChunkMeta, TerrainGrid},
+        vol::{RectVolSize, WriteVol},
+    };
+    use std::sync::Arc;
+
+    const FLOOR: i32 = 10;
+
+    /// A chunk of rock up to `FLOOR`, with a pool of water 4 blocks deep held
+    /// by a wall
+    fn pool() -> TerrainGrid {
+        let mut terrain = TerrainGrid::new().unwrap();
+        terrain.insert(
+            Vec2::zero(),
+            Arc::new(TerrainChunk::new(
+                FLOOR,
+                Block::new(BlockKind::Rock, Rgb::zero()),
+                Block::air(SpriteKind::Empty),
+                TerrainChunkMeta::void(),
+            )),
+        );
+        let rock = Block::new(BlockKind::Rock, Rgb::zero());
+        let size = TerrainChunk::RECT_SIZE.map(|e| e as i32);
+        for x in 0..size.x {
+            for y in 0..size.y {
+                for z in FLOOR..FLOOR + 4 {
+                    let block = if x == 0 || y == 0 || x == size.x - 1 || y == size.y - 1 {
+                        rock
+                    } else if x < 4 {
+                        Block::water(SpriteKind::Empty)
+                    } else if x == 4 {
+                        rock
+                    } else {
+                        continue;
+                    };
+                    terrain.set(Vec3::new(x, y, z), block).unwrap();
+                }
+            }
+        }
+        terrain
+    }
+
+    fn water(terrain: &TerrainGrid) -> Vec<Vec3<i32>> {
+        let size = TerrainChunk::RECT_SIZE.map(|e| e as i32);
+        let mut water = Vec::new();
+        for x in 0..size.x {
+            for y in 0..size.y {
+                for z in FLOOR..FLOOR + 8 {
+                    let pos = Vec3::new(x, y, z);
+                    if terrain.get(pos).map_or(false, |block| block.is_liquid()) {
+                        water.push(pos);
+                    }
+                }
+            }
+        }
+        water
+    }
+
+    /// Breaks the wall of the pool and lets the water flow until it settled,
+    /// returning the number of ticks it took
+    fn break_wall(terrain: &mut TerrainGrid, budget: usize) -> usize {
+        let mut sim = FluidSim::default();
+        let mut block_change = BlockChange::default();
+        let size = TerrainChunk::RECT_SIZE.map(|e| e as i32);
+        for y in 1..size.y - 1 {
+            for z in FLOOR..FLOOR + 4 {
+                block_change.set(Vec3::new(4, y, z), Block::air(SpriteKind::Empty));
+            }
+        }
+
+        for tick in 0..10_000 {
+            let changes = block_change.drain().collect::<Vec<_>>();
+            assert!(tick == 0 || changes.len() <= budget * 2);
+            for (pos, block) in &changes {
+                terrain.set(*pos, *block).unwrap();
+            }
+            sim.activate_changes(changes.iter().map(|(pos, _)| pos));
+            if changes.is_empty() && sim.active_count() == 0 {
+                // The water flowed through the wall
+                let settled = sim.take_settled().expect("The water never moved");
+                assert!(settled.contains(&Vec3::new(4, 1, FLOOR)));
+                return tick;
+            }
+            // Flowing water isn't saved yet
+            assert!(sim.take_settled().is_none());
+            sim.tick(&*terrain, &mut block_change, budget);
+        }
+        panic!("The water did not settle");
+    }
+
+    #[test]
+    fn test_dam_break() {
+        let mut terrain = pool();
+        let before = water(&terrain);
+        break_wall(&mut terrain, MAX_FLUID_UPDATES);
+        let after = water(&terrain);
+
+        // No water was lost or created
+        assert_eq!(before.len(), after.len());
+        // The water spread out over the floor and no longer stands as a wall
+        assert!(after.iter().any(|pos| pos.x > 4));
+        let top = |water: &[Vec3<i32>]| water.iter().map(|pos| pos.z).max().unwrap();
+        assert!(top(&after) < top(&before));
+        // All water rests on something
+        assert!(after.iter().all(|pos| {
+            terrain
+                .get(pos - Vec3::unit_z())
+                .map_or(true, |block| !block.is_air())
+        }));
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let mut a = pool();
+        let mut b = pool();
+        // The same flow always ends up the same way
+        let ticks_a = break_wall(&mut a, 64);
+        let ticks_b = break_wall(&mut b, 64);
+        assert_eq!(ticks_a, ticks_b);
+        assert_eq!(water(&a), water(&b));
+    }
+
+    #[test]
+    fn test_queued_changes_take_precedence() {
+        let mut terrain = pool();
+        let mut sim = FluidSim::default();
+        let mut block_change = BlockChange::default();
+        let rock = Block::new(BlockKind::Rock, Rgb::zero());
+        let wall = Vec3::new(4, 5, FLOOR + 3);
+        let water = Vec3::new(3, 5, FLOOR + 3);
+        // Break a hole into the wall
+        let hole = (FLOOR..FLOOR + 4)
+            .map(|z| Vec3::new(wall.x, wall.y, z))
+            .collect::<Vec<_>>();
+        for pos in &hole {
+            terrain.set(*pos, Block::air(SpriteKind::Empty)).unwrap();
+        }
+        sim.activate_changes(&hole);
+
+        // Another system turns the water next to the hole into rock this tick
+        block_change.set(water, rock);
+        sim.tick(&terrain, &mut block_change, MAX_FLUID_UPDATES);
+
+        let changes = block_change.drain().collect::<HashMap<_, _>>();
+        // The water below did flow into the hole
+        assert_eq!(
+            changes.get(&(wall - Vec3::unit_z())),
+            Some(&Block::water(SpriteKind::Empty))
+        );
+        assert_eq!(changes.get(&water), Some(&rock));
+        assert_eq!(changes.get(&wall), None);
+        assert!(!sim.is_move(&water, &rock));
+    }
+}
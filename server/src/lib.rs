@@ -4,6 +4,7 @@
 #![feature(bool_to_option, drain_filter, option_zip)]
 #![cfg_attr(not(feature = "worldgen"), feature(const_panic))]
 
+pub mod admin_api;
 pub mod alias_validator;
 mod character_creator;
 pub mod chunk_generator;
@@ -11,16 +12,21 @@ pub mod client;
 pub mod cmd;
 pub mod connection_handler;
 mod data_dir;
+pub mod economy;
 pub mod error;
 pub mod events;
+pub mod fluid;
 pub mod input;
 pub mod login_provider;
 pub mod metrics;
+pub mod movement_validation;
 pub mod persistence;
+pub mod quest;
 pub mod settings;
 pub mod state_ext;
 pub mod sys;
 #[cfg(not(feature = "worldgen"))] mod test_world;
+pub mod weather;
 
 // Reexports
 pub use crate::{
@@ -38,9 +44,12 @@ use crate::{
     cmd::ChatCommandExt,
     connection_handler::ConnectionHandler,
     data_dir::DataDir,
+    economy::EconomySim,
     login_provider::LoginProvider,
+    settings::{Banlist, EditableSetting},
     state_ext::StateExt,
     sys::sentinel::{DeletedEntities, TrackedComps},
+    weather::{Climate, WeatherSim},
 };
 use common::{
     cmd::ChatCommand,
@@ -51,10 +60,13 @@ use common::{
     },
     outcome::Outcome,
     recipe::default_recipe_book,
+    rtsim::RtSimEntity,
     state::{State, TimeOfDay},
     sync::WorldSyncExt,
     terrain::TerrainChunkSize,
+    trade::Trades,
     vol::{ReadVol, RectVolSize},
+    weather::WeatherGrid,
 };
 use futures_executor::block_on;
 use metrics::{ServerMetrics, StateTickMetrics, TickMetrics};
@@ -62,6 +74,8 @@ use network::{Network, Pid, ProtocolAddr};
 use persistence::{
     character_loader::{CharacterLoader, CharacterLoaderResponseType},
     character_updater::CharacterUpdater,
+    economy::EconomyPersistence,
+    terrain::TerrainPersistence,
 };
 use specs::{join::Join, Builder, Entity as EcsEntity, RunNow, SystemData, WorldExt};
 use std::{
@@ -72,9 +86,10 @@ use std::{
 };
 #[cfg(not(feature = "worldgen"))]
 use test_world::{IndexOwned, World};
-use tracing::{debug, error, info, trace};
+use tracing::{debug, error, info, trace, warn};
 use uvth::{ThreadPool, ThreadPoolBuilder};
 use vek::*;
+use world::rtsim::RtSim;
 #[cfg(feature = "worldgen")]
 use world::{
     civ::SiteKind,
@@ -106,6 +121,11 @@ pub struct Server {
     metrics: ServerMetrics,
     tick_metrics: TickMetrics,
     state_tick_metrics: StateTickMetrics,
+
+    /// Entity that chat commands from the server console are executed as
+    console: EcsEntity,
+    /// The admin API, if it is enabled
+    admin_api: Option<admin_api::AdminApi>,
 }
 
 impl Server {
@@ -114,7 +134,7 @@ impl Server {
     #[allow(clippy::needless_update)] // TODO: Pending review in #587
     pub fn new(
         settings: Settings,
-        editable_settings: EditableSettings,
+        mut editable_settings: EditableSettings,
         data_dir: &std::path::Path,
     ) -> Result<Self, Error> {
         info!("Server is data dir is: {}", data_dir.display());
@@ -132,6 +152,14 @@ impl Server {
             panic!("Migration error: {:?}", e);
         }
 
+        // Temporary bans that ran out are still kept in the ban history
+        if editable_settings.banlist.has_expired() {
+            let removed = editable_settings
+                .banlist
+                .edit(data_dir, Banlist::remove_expired);
+            info!(?removed, "Removed expired bans from the banlist");
+        }
+
         let (chunk_gen_metrics, registry_chunk) = metrics::ChunkGenMetrics::new().unwrap();
         let (network_request_metrics, registry_network) =
             metrics::NetworkRequestMetrics::new().unwrap();
@@ -159,7 +187,11 @@ impl Server {
         state
             .ecs_mut()
             .insert(CharacterLoader::new(&persistence_db_dir)?);
+        state
+            .ecs_mut()
+            .insert(TerrainPersistence::new(&persistence_db_dir)?);
         state.ecs_mut().insert(Vec::<Outcome>::new());
+        state.ecs_mut().insert(Trades::default());
 
         // System timers for performance monitoring
         state.ecs_mut().insert(sys::EntitySyncTimer::default());
@@ -170,16 +202,32 @@ impl Server {
         state.ecs_mut().insert(sys::TerrainTimer::default());
         state.ecs_mut().insert(sys::WaypointTimer::default());
         state.ecs_mut().insert(sys::InviteTimeoutTimer::default());
+        state.ecs_mut().insert(sys::TradeTimeoutTimer::default());
         state.ecs_mut().insert(sys::PersistenceTimer::default());
+        state.ecs_mut().insert(sys::WeatherTimer::default());
+        state.ecs_mut().insert(sys::FluidTimer::default());
+        state.ecs_mut().insert(sys::RtSimTimer::default());
+        state.ecs_mut().insert(sys::QuestTimer::default());
 
         // System schedulers to control execution of systems
         state
             .ecs_mut()
             .insert(sys::PersistenceScheduler::every(Duration::from_secs(10)));
+        state
+            .ecs_mut()
+            .insert(sys::WeatherScheduler::every(sys::weather::WEATHER_TICK));
+        state
+            .ecs_mut()
+            .insert(sys::QuestScheduler::every(sys::quest::QUEST_TICK));
 
         // Server-only components
         state.ecs_mut().register::<RegionSubscription>();
         state.ecs_mut().register::<Client>();
+        state
+            .ecs_mut()
+            .register::<movement_validation::MovementReference>();
+        state.ecs_mut().register::<weather::Extinguished>();
+        state.ecs_mut().register::<RtSimEntity>();
 
         //Alias validator
         let banned_words_paths = &settings.banned_words_files;
@@ -236,6 +284,9 @@ impl Server {
             horizons: [(vec![0], vec![0]), (vec![0], vec![0])],
             sea_level: 0.0,
             alt: vec![30],
+            sites: Vec::new(),
+            pois: Vec::new(),
+            tracks: Vec::new(),
         };
 
         #[cfg(feature = "worldgen")]
@@ -302,6 +353,68 @@ impl Server {
         #[cfg(not(feature = "worldgen"))]
         let spawn_point = Vec3::new(0.0, 0.0, 256.0);
 
+        // Towns are safe zones when PvP is restricted to the wilderness
+        #[cfg(feature = "worldgen")]
+        let safe_zones = if settings.pvp_mode == comp::PvpMode::Zones {
+            world
+                .civs()
+                .sites()
+                .filter(|site| matches!(site.kind, SiteKind::Settlement))
+                .map(|site| {
+                    let center = site.center.map2(TerrainChunkSize::RECT_SIZE, |e, sz| {
+                        e as i32 * sz as i32 + sz as i32 / 2
+                    });
+                    Aabr {
+                        min: center - settings.pvp_safe_zone_radius,
+                        max: center + settings.pvp_safe_zone_radius,
+                    }
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        #[cfg(not(feature = "worldgen"))]
+        let safe_zones = Vec::new();
+
+        state.ecs_mut().insert(comp::PvpRules {
+            mode: settings.pvp_mode,
+            safe_zones,
+        });
+
+        // Simulate the weather in the climate of each region of the world
+        #[cfg(feature = "worldgen")]
+        let weather = WeatherGrid::new(world.sim().get_size());
+        #[cfg(feature = "worldgen")]
+        let weather_sim = WeatherSim::new(&weather, settings.world_seed, |cell| {
+            // Average the climate of some chunks spread over the cell
+            const SAMPLES: u32 = 4;
+            let step = common::weather::CELL_SIZE / SAMPLES;
+            let chunks = (0..SAMPLES * SAMPLES)
+                .filter_map(|i| {
+                    let offset = Vec2::new(i % SAMPLES, i / SAMPLES) * step + step / 2;
+                    let chunk_pos = cell * common::weather::CELL_SIZE + offset;
+                    world.sim().get(chunk_pos.map(|e| e as i32))
+                })
+                .collect::<Vec<_>>();
+            if chunks.is_empty() {
+                return Climate::default();
+            }
+            Climate {
+                temp: chunks.iter().map(|chunk| chunk.temp).sum::<f32>() / chunks.len() as f32,
+                humidity: chunks.iter().map(|chunk| chunk.humidity).sum::<f32>()
+                    / chunks.len() as f32,
+            }
+        });
+
+        #[cfg(not(feature = "worldgen"))]
+        let weather = WeatherGrid::new(Vec2::one());
+        #[cfg(not(feature = "worldgen"))]
+        let weather_sim = WeatherSim::new(&weather, settings.world_seed, |_| Climate::default());
+
+        state.ecs_mut().insert(weather);
+        state.ecs_mut().insert(weather_sim);
+
         // set the spawn point we calculated above
         state.ecs_mut().insert(SpawnPoint(spawn_point));
 
@@ -313,6 +426,35 @@ impl Server {
 
         state.ecs_mut().insert(DeletedEntities::default());
 
+        state.ecs_mut().insert(fluid::FluidSim::default());
+
+        // Go on with the economies of the sites where the server left them
+        #[allow(unused_mut)]
+        let mut economy_persistence = EconomyPersistence::new(&persistence_db_dir)?;
+        #[cfg(feature = "worldgen")]
+        economy_persistence.restore(&index);
+        #[cfg(feature = "worldgen")]
+        let economy_time = economy_persistence.saved_time().unwrap_or(index.time);
+        #[cfg(not(feature = "worldgen"))]
+        let economy_time = 0.0;
+        state.ecs_mut().insert(economy_persistence);
+        state
+            .ecs_mut()
+            .insert(EconomySim::new(economy_time, settings.economy_tick_period));
+
+        // Settle the NPCs that are simulated while nobody is around
+        #[cfg(feature = "worldgen")]
+        let rtsim = RtSim::generate(&world, settings.world_seed);
+        #[cfg(not(feature = "worldgen"))]
+        let rtsim = RtSim::default();
+        state.ecs_mut().insert(rtsim);
+
+        // Sites characters can be sent to by their quests
+        state.ecs_mut().insert(quest::QuestSites(map.sites.clone()));
+
+        // The console has admin privileges, but no body or client
+        let console = state.ecs_mut().create_entity().with(comp::Admin).build();
+
         let mut metrics = ServerMetrics::new();
         // register all metrics submodules here
         let (tick_metrics, registry_tick) = TickMetrics::new(metrics.tick_clone())
@@ -332,6 +474,12 @@ impl Server {
         metrics
             .run(settings.metrics_address)
             .expect("Failed to initialize server metrics submodule.");
+        let admin_api = settings
+            .admin_api_token
+            .clone()
+            .map(|token| admin_api::AdminApi::run(settings.admin_address, token))
+            .transpose()
+            .map_err(Error::Other)?;
         thread_pool.execute(f);
         block_on(network.listen(ProtocolAddr::Tcp(settings.gameserver_address)))?;
         let connection_handler = ConnectionHandler::new(network);
@@ -349,6 +497,9 @@ impl Server {
             metrics,
             tick_metrics,
             state_tick_metrics,
+
+            console,
+            admin_api,
         };
 
         debug!(?settings, "created veloren server with");
@@ -471,6 +622,7 @@ impl Server {
 
         // Handle game events
         frontend_events.append(&mut self.handle_events());
+        self.handle_admin_requests(&mut frontend_events);
 
         let before_update_terrain_and_regions = Instant::now();
 
@@ -481,6 +633,31 @@ impl Server {
         self.state.update_region_map();
         self.state.apply_terrain_changes();
 
+        {
+            let ecs = self.state.ecs();
+            let terrain_changes = self.state.terrain_changes();
+            let mut terrain_persistence = ecs.write_resource::<TerrainPersistence>();
+            let mut fluid_sim = ecs.write_resource::<fluid::FluidSim>();
+            // Record block changes so that they can be re-applied when their chunk is
+            // regenerated, flowing water is only recorded once it has settled
+            terrain_persistence.record_changes(
+                terrain_changes
+                    .modified_blocks
+                    .iter()
+                    .filter(|(pos, block)| !fluid_sim.is_move(pos, block)),
+            );
+            // Let the water around the changed blocks flow
+            fluid_sim.activate_changes(terrain_changes.modified_blocks.keys());
+            if let Some(settled) = fluid_sim.take_settled() {
+                let terrain = self.state.terrain();
+                let settled = settled
+                    .into_iter()
+                    .filter_map(|pos| terrain.get(pos).ok().map(|block| (pos, *block)))
+                    .collect::<Vec<_>>();
+                terrain_persistence.record_changes(settled.iter().map(|(pos, block)| (pos, block)));
+            }
+        }
+
         let before_sync = Instant::now();
 
         // 6) Synchronise clients with the new state of the world.
@@ -491,6 +668,17 @@ impl Server {
         // Tick the world
         self.world.tick(dt);
 
+        // Run the economies of the sites, which are saved after every tick
+        #[cfg(feature = "worldgen")]
+        {
+            let ecs = self.state.ecs();
+            let mut economy_sim = ecs.write_resource::<EconomySim>();
+            if economy_sim.tick(&self.index) {
+                ecs.read_resource::<EconomyPersistence>()
+                    .save(&self.index, economy_sim.time());
+            }
+        }
+
         let before_entity_cleanup = Instant::now();
 
         // Remove NPCs that are outside the view distances of all players
@@ -626,13 +814,28 @@ impl Server {
             .ecs()
             .read_resource::<sys::InviteTimeoutTimer>()
             .nanos as i64;
+        let trade_timeout_nanos = self
+            .state
+            .ecs()
+            .read_resource::<sys::TradeTimeoutTimer>()
+            .nanos as i64;
         let stats_persistence_nanos = self
             .state
             .ecs()
             .read_resource::<sys::PersistenceTimer>()
             .nanos as i64;
-        let total_sys_ran_in_dispatcher_nanos =
-            terrain_nanos + waypoint_nanos + invite_timeout_nanos;
+        let weather_nanos = self.state.ecs().read_resource::<sys::WeatherTimer>().nanos as i64;
+        let fluid_nanos = self.state.ecs().read_resource::<sys::FluidTimer>().nanos as i64;
+        let rtsim_nanos = self.state.ecs().read_resource::<sys::RtSimTimer>().nanos as i64;
+        let quest_nanos = self.state.ecs().read_resource::<sys::QuestTimer>().nanos as i64;
+        let total_sys_ran_in_dispatcher_nanos = terrain_nanos
+            + waypoint_nanos
+            + invite_timeout_nanos
+            + trade_timeout_nanos
+            + weather_nanos
+            + fluid_nanos
+            + rtsim_nanos
+            + quest_nanos;
 
         // Report timing info
         self.tick_metrics
@@ -698,10 +901,30 @@ impl Server {
             .tick_time
             .with_label_values(&["invite timeout"])
             .set(invite_timeout_nanos);
+        self.tick_metrics
+            .tick_time
+            .with_label_values(&["trade timeout"])
+            .set(trade_timeout_nanos);
         self.tick_metrics
             .tick_time
             .with_label_values(&["persistence:stats"])
             .set(stats_persistence_nanos);
+        self.tick_metrics
+            .tick_time
+            .with_label_values(&["weather"])
+            .set(weather_nanos);
+        self.tick_metrics
+            .tick_time
+            .with_label_values(&["fluid"])
+            .set(fluid_nanos);
+        self.tick_metrics
+            .tick_time
+            .with_label_values(&["rtsim"])
+            .set(rtsim_nanos);
+        self.tick_metrics
+            .tick_time
+            .with_label_values(&["quest"])
+            .set(quest_nanos);
 
         //detailed state metrics
         {
@@ -859,7 +1082,15 @@ impl Server {
     where
         S: Into<ServerMsg>,
     {
-        if let Some(client) = self.state.ecs().write_storage::<Client>().get_mut(entity) {
+        if entity == self.console {
+            // Command output for the console goes to the log
+            if let ServerMsg::General(ServerGeneral::ChatMsg(msg)) = msg.into() {
+                match msg.chat_type {
+                    ChatType::CommandError => warn!("{}", msg.message),
+                    _ => info!("{}", msg.message),
+                }
+            }
+        } else if let Some(client) = self.state.ecs().write_storage::<Client>().get_mut(entity) {
             client.send_msg(msg.into())
         }
     }
@@ -903,6 +1134,36 @@ impl Server {
         }
     }
 
+    /// Executes a chat command, with or without the leading `/`, as the server
+    /// console. The console has admin privileges and the output of the
+    /// command is logged.
+    pub fn process_console_cmd(&mut self, cmd: &str) {
+        let cmd = cmd.trim();
+        let cmd = cmd.strip_prefix('/').unwrap_or(cmd);
+        if !cmd.is_empty() {
+            self.process_chat_cmd(self.console, cmd.to_owned());
+        }
+    }
+
+    /// Number of players online that have registered an alias
+    pub fn number_of_registered_players(&self) -> usize {
+        self.state
+            .ecs()
+            .read_storage::<comp::Player>()
+            .join()
+            .count()
+    }
+
+    /// Aliases of the players online
+    pub fn player_aliases(&self) -> Vec<String> {
+        self.state
+            .ecs()
+            .read_storage::<comp::Player>()
+            .join()
+            .map(|player| player.alias.clone())
+            .collect()
+    }
+
     fn entity_is_admin(&self, entity: EcsEntity) -> bool {
         self.state
             .read_storage::<comp::Admin>()
@@ -62,10 +62,13 @@ impl LoginProvider {
             .query(username_or_token)
             // if found, check name against whitelist or if user is admin
             .and_then(|(username, uuid)| {
-                // user cannot join if they are listed on the banlist
-                if let Some(ban_record) = banlist.get(&uuid) {
+                // user cannot join if they are listed on the banlist, unless their ban expired
+                if let Some(ban_record) = banlist.get(&uuid).filter(|r| !r.is_expired()) {
                     // Pull reason string out of ban record and send a copy of it
-                    return Err(RegisterError::Banned(ban_record.reason.clone()));
+                    return Err(RegisterError::Banned {
+                        reason: ban_record.reason.clone(),
+                        remaining: ban_record.remaining(),
+                    });
                 }
 
                 // user can only join if he is admin, the whitelist is empty (everyone can join)
@@ -28,6 +28,7 @@ pub struct PlayerMetrics {
     pub clients_connected: IntCounter,
     pub players_connected: IntCounter,
     pub clients_disconnected: IntCounterVec, // timeout, network_error, gracefully
+    pub movement_violations: IntCounterVec,  // speed, rise, terrain
 }
 
 pub struct NetworkRequestMetrics {
@@ -131,15 +132,25 @@ impl PlayerMetrics {
             ),
             &["reason"],
         )?;
+        let movement_violations = IntCounterVec::new(
+            Opts::new(
+                "movement_violations",
+                "shows the number of physics updates from clients that were rejected and the \
+                 reason",
+            ),
+            &["reason"],
+        )?;
 
         let clients_connected_clone = clients_connected.clone();
         let players_connected_clone = players_connected.clone();
         let clients_disconnected_clone = clients_disconnected.clone();
+        let movement_violations_clone = movement_violations.clone();
 
         let f = |registry: &Registry| {
             registry.register(Box::new(clients_connected_clone))?;
             registry.register(Box::new(players_connected_clone))?;
             registry.register(Box::new(clients_disconnected_clone))?;
+            registry.register(Box::new(movement_violations_clone))?;
             Ok(())
         };
 
@@ -148,6 +159,7 @@ impl PlayerMetrics {
                 clients_connected,
                 players_connected,
                 clients_disconnected,
+                movement_violations,
             },
             Box::new(f),
         ))
@@ -0,0 +1,2 @@
+DROP INDEX idx_terrain_block_chunk;
+DROP TABLE terrain_block;
@@ -0,0 +1,16 @@
+-- Stores blocks that have been changed by players (or other game events) so
+-- that the changes can be re-applied whenever a chunk is regenerated.
+
+CREATE TABLE terrain_block
+(
+    x          INTEGER NOT NULL,
+    y          INTEGER NOT NULL,
+    z          INTEGER NOT NULL,
+    chunk_x    INTEGER NOT NULL,
+    chunk_y    INTEGER NOT NULL,
+    block_data TEXT NOT NULL,
+    PRIMARY KEY (x, y, z)
+);
+
+CREATE INDEX idx_terrain_block_chunk
+    ON terrain_block(chunk_x, chunk_y);
@@ -0,0 +1,2 @@
+DROP TABLE skill;
+DROP TABLE skill_group;
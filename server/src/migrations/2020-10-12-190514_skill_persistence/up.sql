@@ -0,0 +1,20 @@
+-- Stores the skill groups a character has unlocked, along with the exp and
+-- unspent skill points in each group, and the skills the character learned.
+
+CREATE TABLE skill_group
+(
+    character_id INT NOT NULL
+        REFERENCES character(character_id),
+    skill_group_type TEXT NOT NULL,
+    exp INT NOT NULL,
+    available_sp INT NOT NULL,
+    PRIMARY KEY (character_id, skill_group_type)
+);
+
+CREATE TABLE skill
+(
+    character_id INT NOT NULL
+        REFERENCES character(character_id),
+    skill_type TEXT NOT NULL,
+    PRIMARY KEY (character_id, skill_type)
+);
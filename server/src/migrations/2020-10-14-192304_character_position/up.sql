@@ -0,0 +1,15 @@
+-- Stores where a character was when they were last saved, and the waypoint
+-- they respawn at.
+
+CREATE TABLE character_position
+(
+    character_id INT NOT NULL
+        PRIMARY KEY
+        REFERENCES character(character_id),
+    pos_x REAL NOT NULL,
+    pos_y REAL NOT NULL,
+    pos_z REAL NOT NULL,
+    waypoint_x REAL,
+    waypoint_y REAL,
+    waypoint_z REAL
+);
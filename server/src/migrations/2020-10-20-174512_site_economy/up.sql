@@ -0,0 +1,8 @@
+-- Stores the economies of the sites, which keep changing while the server is
+-- running, so that they survive a restart.
+
+CREATE TABLE site_economy
+(
+    site_id      INTEGER NOT NULL PRIMARY KEY,
+    economy_data TEXT NOT NULL
+);
@@ -0,0 +1,10 @@
+-- Stores the quests each character took on and completed, with the progress
+-- on the active ones.
+
+CREATE TABLE quest_log
+(
+    character_id   INT NOT NULL
+        PRIMARY KEY
+        REFERENCES character(character_id),
+    quest_log_data TEXT NOT NULL
+);
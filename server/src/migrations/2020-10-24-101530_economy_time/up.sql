@@ -0,0 +1,9 @@
+-- Stores how far the economies of the sites got, so that they go on from there
+-- after a restart instead of from the end of the simulated history. The table
+-- only ever has the row with id 0.
+
+CREATE TABLE economy_time
+(
+    id   INTEGER NOT NULL PRIMARY KEY,
+    time REAL NOT NULL
+);
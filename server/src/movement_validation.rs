@@ -0,0 +1,319 @@
+//! Sanity checks for the physics updates clients send for their own character,
+//! so that a modified client can't teleport, fly or move through terrain
+
+use common::{
+    comp::{CharacterState, ForceUpdate, PhysicsState, Pos},
+    state::Time,
+    terrain::{Block, TerrainGrid},
+    vol::ReadVol,
+};
+use serde::{Deserialize, Serialize};
+use specs::{
+    shred::ResourceId, Component, Entity as EcsEntity, Read, ReadExpect, ReadStorage, SystemData,
+    World, WriteStorage,
+};
+use specs_idvs::IdvStorage;
+use vek::*;
+
+/// Updates arriving after a longer gap than this (in seconds) are validated as
+/// if they arrived this long after the previous one, so that a client can't
+/// save up distance by not sending updates for a while
+const MAX_UPDATE_GAP: f64 = 1.0;
+/// Height above the feet at which movement is checked against terrain
+const TERRAIN_CHECK_HEIGHT: f32 = 1.0;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MovementValidationSettings {
+    pub enabled: bool,
+    /// Maximum horizontal speed in blocks per second
+    pub max_speed: f32,
+    /// Maximum horizontal speed while rolling, dashing, gliding or using other
+    /// abilities that move the character
+    pub max_ability_speed: f32,
+    /// Maximum upwards speed while not climbing, gliding or swimming, which
+    /// covers jumping
+    pub max_rise_speed: f32,
+    /// Distance in blocks an update may be off on top of the speed limits, to
+    /// account for latency and knockback
+    pub tolerance: f32,
+}
+
+impl Default for MovementValidationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_speed: 25.0,
+            max_ability_speed: 60.0,
+            max_rise_speed: 20.0,
+            tolerance: 3.0,
+        }
+    }
+}
+
+/// Why a physics update was rejected
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MovementViolation {
+    /// The character moved further than it could have
+    Speed,
+    /// The character moved upwards faster than it could have
+    Rise,
+    /// The character moved into or through solid terrain
+    Terrain,
+}
+
+impl MovementViolation {
+    /// Label of the violation in the metrics
+    pub fn label(self) -> &'static str {
+        match self {
+            MovementViolation::Speed => "speed",
+            MovementViolation::Rise => "rise",
+            MovementViolation::Terrain => "terrain",
+        }
+    }
+}
+
+/// The last position of an entity that was validated at the start of a tick,
+/// movement is measured from here. Removed whenever the server moves the
+/// entity with a `ForceUpdate`.
+#[derive(Copy, Clone, Debug)]
+pub struct MovementReference {
+    pos: Vec3<f32>,
+    time: f64,
+}
+
+impl Component for MovementReference {
+    type Storage = IdvStorage<Self>;
+}
+
+#[derive(SystemData)]
+pub struct MovementValidation<'a> {
+    time: Read<'a, Time>,
+    terrain: ReadExpect<'a, TerrainGrid>,
+    character_states: ReadStorage<'a, CharacterState>,
+    physics_states: ReadStorage<'a, PhysicsState>,
+    references: WriteStorage<'a, MovementReference>,
+    pub force_updates: WriteStorage<'a, ForceUpdate>,
+}
+
+impl<'a> MovementValidation<'a> {
+    /// Checks whether the client controlling `entity` could have moved it from
+    /// `old_pos`, the position the server knows of, to `new_pos`
+    pub fn validate(
+        &mut self,
+        settings: &MovementValidationSettings,
+        entity: EcsEntity,
+        old_pos: Option<&Pos>,
+        new_pos: &Pos,
+    ) -> Result<(), MovementViolation> {
+        if !settings.enabled {
+            return Ok(());
+        }
+        let old_pos = match old_pos {
+            Some(old_pos) => old_pos.0,
+            None => return Ok(()),
+        };
+        let now = self.time.0;
+        let reference = match self.references.entry(entity) {
+            Ok(entry) => *entry.or_insert(MovementReference {
+                pos: old_pos,
+                time: now,
+            }),
+            // The entity has been deleted
+            Err(_) => return Ok(()),
+        };
+        let elapsed = (now - reference.time).min(MAX_UPDATE_GAP) as f32;
+
+        let character_state = self.character_states.get(entity);
+        let physics_state = self.physics_states.get(entity);
+        let uses_ability = matches!(
+            character_state,
+            Some(CharacterState::Roll(_))
+                | Some(CharacterState::Boost(_))
+                | Some(CharacterState::DashMelee(_))
+                | Some(CharacterState::LeapMelee(_))
+                | Some(CharacterState::SpinMelee(_))
+                | Some(CharacterState::ComboMelee(_))
+                | Some(CharacterState::Glide)
+        );
+        let can_rise = uses_ability
+            || matches!(character_state, Some(CharacterState::Climb))
+            || physics_state.map_or(false, |physics| {
+                physics.in_fluid.is_some() || physics.on_wall.is_some()
+            });
+
+        let max_speed = if uses_ability {
+            settings.max_ability_speed
+        } else {
+            settings.max_speed
+        };
+        let max_rise_speed = if can_rise {
+            settings.max_ability_speed
+        } else {
+            settings.max_rise_speed
+        };
+
+        let moved = new_pos.0 - reference.pos;
+        if moved.xy().magnitude() > max_speed * elapsed + settings.tolerance {
+            return Err(MovementViolation::Speed);
+        }
+        // Falling is limited by physics on the client anyway
+        if moved.z > max_rise_speed * elapsed + settings.tolerance {
+            return Err(MovementViolation::Rise);
+        }
+
+        // Check the whole way from the reference, so that moving through terrain
+        // can't be split up into several updates
+        let from = reference.pos + Vec3::unit_z() * TERRAIN_CHECK_HEIGHT;
+        let to = new_pos.0 + Vec3::unit_z() * TERRAIN_CHECK_HEIGHT;
+        if from.distance_squared(to) > 0.0001 {
+            let (dist, hit) = self
+                .terrain
+                .ray(from, to)
+                .until(Block::is_solid)
+                .ignore_error()
+                .cast();
+            // Don't blame the client if the server's position is already inside terrain
+            if dist > 0.0 && matches!(hit, Ok(Some(_))) {
+                return Err(MovementViolation::Terrain);
+            }
+        }
+
+        // Measure from here in the following ticks
+        if now > reference.time {
+            if let Some(reference) = self.references.get_mut(entity) {
+                *reference = MovementReference {
+                    pos: new_pos.0,
+                    time: now,
+                };
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{
+        terrain::{BlockKind, SpriteKind, TerrainChunk, TerrainChunkMeta},
+        vol::WriteVol,
+    };
+    use specs::{Builder, WorldExt};
+    use std::sync::Arc;
+
+    const WALL_X: i32 = 10;
+
+    fn setup() -> (World, EcsEntity) {
+        let mut world = World::new();
+        world.register::<CharacterState>();
+        world.register::<PhysicsState>();
+        world.register::<MovementReference>();
+        world.register::<ForceUpdate>();
+        world.insert(Time(0.0));
+
+        let mut chunk = TerrainChunk::new(
+            0,
+            Block::new(BlockKind::Rock, Rgb::zero()),
+            Block::air(SpriteKind::Empty),
+            TerrainChunkMeta::void(),
+        );
+        for y in 0..8 {
+            for z in 0..4 {
+                chunk
+                    .set(
+                        Vec3::new(WALL_X, y, z),
+                        Block::new(BlockKind::Rock, Rgb::zero()),
+                    )
+                    .unwrap();
+            }
+        }
+        let mut terrain = TerrainGrid::new().unwrap();
+        terrain.insert(Vec2::zero(), Arc::new(chunk));
+        world.insert(terrain);
+
+        let entity = world.create_entity().build();
+        (world, entity)
+    }
+
+    fn validate(
+        world: &World,
+        entity: EcsEntity,
+        old_pos: Vec3<f32>,
+        new_pos: Vec3<f32>,
+    ) -> Result<(), MovementViolation> {
+        MovementValidation::fetch(world).validate(
+            &MovementValidationSettings::default(),
+            entity,
+            Some(&Pos(old_pos)),
+            &Pos(new_pos),
+        )
+    }
+
+    #[test]
+    fn speed_violations() {
+        let (mut world, entity) = setup();
+        let start = Vec3::new(2.0, 2.0, 0.0);
+        let far = Vec3::new(2.0, 20.0, 0.0);
+
+        // Small moves are within the tolerance
+        assert_eq!(
+            validate(&world, entity, start, start + Vec3::unit_y()),
+            Ok(())
+        );
+        assert_eq!(
+            validate(&world, entity, start, far),
+            Err(MovementViolation::Speed)
+        );
+        assert_eq!(
+            validate(&world, entity, start, start + Vec3::unit_z() * 10.0),
+            Err(MovementViolation::Rise)
+        );
+
+        // Given a second the same distance can be covered
+        world.insert(Time(1.0));
+        assert_eq!(validate(&world, entity, start, far), Ok(()));
+    }
+
+    #[test]
+    fn moving_through_walls() {
+        let (mut world, entity) = setup();
+        let start = Vec3::new(8.0, 2.0, 0.0);
+        let close = Vec3::new(9.5, 2.0, 0.0);
+        let behind = Vec3::new(12.0, 2.0, 0.0);
+
+        assert_eq!(validate(&world, entity, start, close), Ok(()));
+        world.insert(Time(1.0));
+        assert_eq!(
+            validate(&world, entity, close, behind),
+            Err(MovementViolation::Terrain)
+        );
+        // Splitting the move up doesn't get the character through either
+        assert_eq!(validate(&world, entity, close, close), Ok(()));
+        assert_eq!(
+            validate(&world, entity, close, behind),
+            Err(MovementViolation::Terrain)
+        );
+    }
+
+    #[test]
+    fn teleports_are_exempt() {
+        let (world, entity) = setup();
+        let start = Vec3::new(2.0, 2.0, 0.0);
+        let teleported = Vec3::new(20.0, 25.0, 0.0);
+
+        assert_eq!(validate(&world, entity, start, start), Ok(()));
+        assert_eq!(
+            validate(&world, entity, teleported, teleported),
+            Err(MovementViolation::Speed)
+        );
+
+        // Moving an entity with a `ForceUpdate` resets its reference
+        world.write_storage::<MovementReference>().remove(entity);
+        assert_eq!(
+            validate(&world, entity, teleported, teleported + Vec3::unit_x()),
+            Ok(())
+        );
+    }
+}
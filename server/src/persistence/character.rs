@@ -14,6 +14,9 @@ use crate::{
             convert_body_from_database, convert_body_to_database_json,
             convert_character_from_database, convert_inventory_from_database_items,
             convert_items_to_database_items, convert_loadout_from_database_items,
+            convert_position_from_database, convert_position_to_database,
+            convert_quest_log_from_database, convert_quest_log_to_database,
+            convert_skill_set_from_database, convert_skill_set_to_database,
             convert_stats_from_database, convert_stats_to_database,
         },
         character_loader::{CharacterDataResult, CharacterListResult},
@@ -83,11 +86,44 @@ pub fn load_character_data(
         .filter(schema::body::dsl::body_id.eq(char_id))
         .first::<Body>(&*connection)?;
 
+    let skill_group_data = schema::skill_group::table
+        .filter(schema::skill_group::dsl::character_id.eq(char_id))
+        .load::<SkillGroup>(&*connection)?;
+
+    let skill_data = schema::skill::table
+        .filter(schema::skill::dsl::character_id.eq(char_id))
+        .load::<Skill>(&*connection)?;
+
+    let position_data = schema::character_position::table
+        .filter(schema::character_position::dsl::character_id.eq(char_id))
+        .first::<CharacterPosition>(&*connection)
+        .optional()?;
+    let (char_pos, char_waypoint) = match position_data {
+        Some(position) => {
+            let (pos, waypoint) = convert_position_from_database(&position);
+            (Some(pos), waypoint)
+        },
+        None => (None, None),
+    };
+
+    let quest_log = schema::quest_log::table
+        .filter(schema::quest_log::dsl::character_id.eq(char_id))
+        .first::<QuestLog>(&*connection)
+        .optional()?
+        .map(|quest_log| convert_quest_log_from_database(&quest_log))
+        .unwrap_or_default();
+
+    let mut char_stats = convert_stats_from_database(&stats_data, character_data.alias);
+    char_stats.skill_set = convert_skill_set_from_database(&skill_group_data, &skill_data);
+
     Ok((
         convert_body_from_database(&char_body)?,
-        convert_stats_from_database(&stats_data, character_data.alias),
+        char_stats,
         convert_inventory_from_database_items(&inventory_items)?,
         convert_loadout_from_database_items(&loadout_items)?,
+        char_pos,
+        char_waypoint,
+        quest_log,
     ))
 }
 
@@ -157,7 +193,9 @@ pub fn create_character(
 
     use schema::{body, character, stats};
 
-    let (body, stats, inventory, loadout) = persisted_components;
+    // New characters don't have a position yet, they start at the spawn point,
+    // and haven't taken on any quests
+    let (body, stats, inventory, loadout, _, _, _) = persisted_components;
 
     // Fetch new entity IDs for character, inventory and loadout
     let mut new_entity_ids = get_new_entity_ids(connection, |next_id| next_id + 3)?;
@@ -248,6 +286,9 @@ pub fn create_character(
         )));
     }
 
+    // Insert skill group and skill records
+    update_skill_set(character_id, &stats.skill_set, connection)?;
+
     // Insert default inventory and loadout item records
     let mut inserts = Vec::new();
 
@@ -300,6 +341,24 @@ pub fn delete_character(
         )
         .first::<Character>(&*connection)?;
 
+    // Delete the saved position, quest log, skills and skill groups, which
+    // reference the character
+    diesel::delete(
+        schema::character_position::table
+            .filter(schema::character_position::dsl::character_id.eq(char_id)),
+    )
+    .execute(&*connection)?;
+    diesel::delete(
+        schema::quest_log::table.filter(schema::quest_log::dsl::character_id.eq(char_id)),
+    )
+    .execute(&*connection)?;
+    diesel::delete(schema::skill::table.filter(schema::skill::dsl::character_id.eq(char_id)))
+        .execute(&*connection)?;
+    diesel::delete(
+        schema::skill_group::table.filter(schema::skill_group::dsl::character_id.eq(char_id)),
+    )
+    .execute(&*connection)?;
+
     // Delete character
     let character_count = diesel::delete(
         character
@@ -510,6 +569,9 @@ pub fn update(
     char_stats: comp::Stats,
     inventory: comp::Inventory,
     loadout: comp::Loadout,
+    pos: Option<comp::Pos>,
+    waypoint: Option<comp::Waypoint>,
+    quest_log: Option<comp::QuestLog>,
     connection: VelorenTransaction,
 ) -> Result<Vec<Arc<common::comp::item::ItemId>>, Error> {
     use super::schema::{item::dsl::*, stats::dsl::*};
@@ -587,5 +649,209 @@ pub fn update(
         )));
     }
 
+    update_skill_set(char_id, &char_stats.skill_set, connection)?;
+
+    if let Some(pos) = pos {
+        let db_position = convert_position_to_database(char_id, &pos, waypoint.as_ref());
+        diesel::replace_into(schema::character_position::table)
+            .values(&db_position)
+            .execute(&*connection)?;
+    }
+
+    if let Some(quest_log) = quest_log {
+        let db_quest_log = convert_quest_log_to_database(char_id, &quest_log)?;
+        diesel::replace_into(schema::quest_log::table)
+            .values(&db_quest_log)
+            .execute(&*connection)?;
+    }
+
     Ok(upserted_comps)
 }
+
+/// Replaces the stored skill groups and skills of a character
+fn update_skill_set(
+    char_id: CharacterId,
+    skill_set: &comp::skills::SkillSet,
+    connection: VelorenTransaction,
+) -> Result<(), Error> {
+    use schema::{skill, skill_group};
+
+    let (skill_groups, skills) = convert_skill_set_to_database(char_id, skill_set)?;
+
+    diesel::delete(skill_group::table.filter(skill_group::dsl::character_id.eq(char_id)))
+        .execute(&*connection)?;
+    diesel::delete(skill::table.filter(skill::dsl::character_id.eq(char_id)))
+        .execute(&*connection)?;
+
+    let skill_group_count = if skill_groups.is_empty() {
+        0
+    } else {
+        diesel::insert_into(skill_group::table)
+            .values(&skill_groups)
+            .execute(&*connection)?
+    };
+    let skill_count = if skills.is_empty() {
+        0
+    } else {
+        diesel::insert_into(skill::table)
+            .values(&skills)
+            .execute(&*connection)?
+    };
+
+    if skill_group_count != skill_groups.len() || skill_count != skills.len() {
+        return Err(Error::OtherError(format!(
+            "Expected to insert {} skill groups and {} skills, actually inserted {} and {}, for \
+             char_id {}",
+            skill_groups.len(),
+            skills.len(),
+            skill_group_count,
+            skill_count,
+            char_id
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::{establish_connection, run_migrations, VelorenConnection};
+    use common::{
+        comp::skills::{Skill, SkillGroupType},
+        loadout_builder::LoadoutBuilder,
+    };
+    use std::fs;
+    use vek::Vec3;
+
+    const UUID: &str = "00000000-0000-0000-0000-000000000000";
+
+    /// Creates a character in a fresh database in `db_dir`
+    fn setup(db_dir: &std::path::Path) -> (CharacterId, PersistedComponents) {
+        let _ = fs::remove_dir_all(db_dir);
+        fs::create_dir_all(db_dir).unwrap();
+        run_migrations(db_dir).unwrap();
+
+        let body = comp::Body::Humanoid(comp::humanoid::Body::random());
+        let components = (
+            body,
+            comp::Stats::new("Tester".to_owned(), body),
+            comp::Inventory::new_empty(),
+            LoadoutBuilder::new().build(),
+            None,
+            None,
+            comp::QuestLog::default(),
+        );
+        let characters = establish_connection(db_dir)
+            .unwrap()
+            .transaction(|txn| create_character(UUID, "Tester", components.clone(), txn))
+            .unwrap();
+        let char_id = characters[0].character.id.unwrap();
+        (char_id, components)
+    }
+
+    #[test]
+    fn test_position_round_trip() {
+        let db_dir = std::env::temp_dir().join(format!(
+            "veloren-character-position-test-{}",
+            std::process::id()
+        ));
+        let (char_id, (_, stats, inventory, loadout, _, _, _)) = setup(&db_dir);
+        let mut connection = establish_connection(&db_dir).unwrap();
+        let load = |connection: &mut VelorenConnection| {
+            let (_, _, _, _, pos, waypoint, _) = connection
+                .transaction(|txn| load_character_data(UUID.to_owned(), char_id, txn))
+                .unwrap();
+            (pos, waypoint.map(|waypoint| waypoint.get_pos()))
+        };
+
+        // New characters start at the spawn point
+        assert_eq!(load(&mut connection), (None, None));
+
+        let pos = comp::Pos(Vec3::new(1.5, -2.25, 300.0));
+        let waypoint = comp::Waypoint::new(Vec3::new(10.0, 20.0, 30.0), common::state::Time(5.0));
+        connection
+            .transaction(|txn| {
+                update(
+                    char_id,
+                    stats.clone(),
+                    inventory.clone(),
+                    loadout.clone(),
+                    Some(pos),
+                    Some(waypoint),
+                    None,
+                    txn,
+                )
+            })
+            .unwrap();
+        assert_eq!(load(&mut connection), (Some(pos), Some(waypoint.get_pos())));
+
+        // Characters without a waypoint keep their position
+        connection
+            .transaction(|txn| {
+                update(
+                    char_id,
+                    stats.clone(),
+                    inventory.clone(),
+                    loadout.clone(),
+                    Some(pos),
+                    None,
+                    None,
+                    txn,
+                )
+            })
+            .unwrap();
+        assert_eq!(load(&mut connection), (Some(pos), None));
+
+        drop(connection);
+        let _ = fs::remove_dir_all(&db_dir);
+    }
+
+    #[test]
+    fn test_skill_set_round_trip() {
+        let db_dir = std::env::temp_dir().join(format!(
+            "veloren-character-skills-test-{}",
+            std::process::id()
+        ));
+        let (char_id, (_, mut stats, inventory, loadout, _, _, _)) = setup(&db_dir);
+        let mut connection = establish_connection(&db_dir).unwrap();
+
+        let skill_set = &mut stats.skill_set;
+        skill_set.unlock_skill_group(SkillGroupType::Axes);
+        skill_set.unlock_skill_group(SkillGroupType::Swords);
+        skill_set.add_skill_points(SkillGroupType::Axes, 3);
+        skill_set.unlock_skill(Skill::TestAxeSkill2);
+        skill_set.unlock_skill(Skill::TestAxeSkill3);
+        skill_set.skill_groups[0].exp = 40;
+        connection
+            .transaction(|txn| {
+                update(
+                    char_id,
+                    stats.clone(),
+                    inventory.clone(),
+                    loadout.clone(),
+                    None,
+                    None,
+                    None,
+                    txn,
+                )
+            })
+            .unwrap();
+
+        let (_, loaded, _, _, _, _, _) = connection
+            .transaction(|txn| load_character_data(UUID.to_owned(), char_id, txn))
+            .unwrap();
+        let (saved, loaded) = (&stats.skill_set, &loaded.skill_set);
+        assert_eq!(loaded.skills, saved.skills);
+        assert_eq!(loaded.skill_groups.len(), saved.skill_groups.len());
+        assert!(
+            saved
+                .skill_groups
+                .iter()
+                .all(|group| loaded.skill_groups.contains(group))
+        );
+
+        drop(connection);
+        let _ = fs::remove_dir_all(&db_dir);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::persistence::{
     character::EntityId,
-    models::{Body, Character, Item, Stats},
+    models::{Body, Character, CharacterPosition, Item, QuestLog, Skill, SkillGroup, Stats},
 };
 
 use crate::persistence::{error::Error, json_models::HumanoidBody};
@@ -11,7 +11,10 @@ use common::{
 };
 use core::{convert::TryFrom, num::NonZeroU64};
 use itertools::{Either, Itertools};
+use serde::{de::DeserializeOwned, Serialize};
 use std::sync::Arc;
+use tracing::warn;
+use vek::Vec3;
 
 pub struct ItemModelPair {
     pub comp: Arc<common::comp::item::ItemId>,
@@ -176,6 +179,179 @@ pub fn convert_stats_to_database(character_id: CharacterId, stats: &common::comp
     }
 }
 
+/// Skills and skill groups are stored by their enum variant name
+fn enum_to_database_string<T: Serialize>(value: &T) -> Result<String, Error> {
+    match serde_json::to_value(value)? {
+        serde_json::Value::String(name) => Ok(name),
+        other => Err(Error::ConversionError(format!(
+            "Expected a unit variant, got {}",
+            other
+        ))),
+    }
+}
+
+fn enum_from_database_string<T: DeserializeOwned>(name: &str) -> Option<T> {
+    serde_json::from_value(serde_json::Value::String(name.to_owned())).ok()
+}
+
+pub fn convert_skill_set_to_database(
+    character_id: CharacterId,
+    skill_set: &skills::SkillSet,
+) -> Result<(Vec<SkillGroup>, Vec<Skill>), Error> {
+    let skill_groups = skill_set
+        .skill_groups
+        .iter()
+        .map(|group| {
+            Ok(SkillGroup {
+                character_id,
+                skill_group_type: enum_to_database_string(&group.skill_group_type)?,
+                exp: group.exp as i32,
+                available_sp: group.available_sp as i32,
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let skills = skill_set
+        .skills
+        .iter()
+        .map(|skill| {
+            Ok(Skill {
+                character_id,
+                skill_type: enum_to_database_string(skill)?,
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok((skill_groups, skills))
+}
+
+/// Skill groups and skills that no longer exist are dropped, as are skills
+/// whose skill group or prerequisites are missing. The skill points spent on
+/// dropped skills are refunded.
+pub fn convert_skill_set_from_database(
+    db_skill_groups: &[SkillGroup],
+    db_skills: &[Skill],
+) -> skills::SkillSet {
+    let mut skill_set = skills::SkillSet::new();
+
+    for db_group in db_skill_groups {
+        match enum_from_database_string::<skills::SkillGroupType>(&db_group.skill_group_type) {
+            Some(skill_group_type) => skill_set.skill_groups.push(skills::SkillGroup {
+                skill_group_type,
+                exp: db_group.exp as u32,
+                available_sp: db_group.available_sp as u8,
+            }),
+            None => warn!(?db_group, "Dropping unknown skill group"),
+        }
+    }
+
+    for db_skill in db_skills {
+        match enum_from_database_string::<skills::Skill>(&db_skill.skill_type) {
+            Some(skill) => {
+                skill_set.skills.insert(skill);
+            },
+            None => warn!(?db_skill, "Dropping unknown skill"),
+        }
+    }
+
+    // Remove skills until every remaining skill has its group and prerequisites
+    loop {
+        let invalid = skill_set.skills.iter().copied().find(|skill| {
+            let has_group = skills::SkillSet::get_skill_group_type_for_skill(skill).map_or(
+                false,
+                |group_type| {
+                    skill_set
+                        .skill_groups
+                        .iter()
+                        .any(|g| g.skill_group_type == group_type)
+                },
+            );
+            let has_prerequisites = skills::SKILL_PREREQUISITES
+                .get(skill)
+                .map_or(true, |prereqs| {
+                    prereqs.iter().all(|p| skill_set.skills.contains(p))
+                });
+            !has_group || !has_prerequisites
+        });
+        let skill = match invalid {
+            Some(skill) => skill,
+            None => break,
+        };
+
+        warn!(
+            ?skill,
+            "Dropping skill with missing skill group or prerequisites"
+        );
+        skill_set.skills.remove(&skill);
+        if let Some(group_type) = skills::SkillSet::get_skill_group_type_for_skill(&skill) {
+            if let Some(group) = skill_set
+                .skill_groups
+                .iter_mut()
+                .find(|g| g.skill_group_type == group_type)
+            {
+                group.available_sp = group.available_sp.saturating_add(1);
+            }
+        }
+    }
+
+    skill_set
+}
+
+pub fn convert_position_to_database(
+    character_id: CharacterId,
+    pos: &Pos,
+    waypoint: Option<&Waypoint>,
+) -> CharacterPosition {
+    let waypoint = waypoint.map(|waypoint| waypoint.get_pos());
+    CharacterPosition {
+        character_id,
+        pos_x: pos.0.x,
+        pos_y: pos.0.y,
+        pos_z: pos.0.z,
+        waypoint_x: waypoint.map(|w| w.x),
+        waypoint_y: waypoint.map(|w| w.y),
+        waypoint_z: waypoint.map(|w| w.z),
+    }
+}
+
+pub fn convert_position_from_database(position: &CharacterPosition) -> (Pos, Option<Waypoint>) {
+    let pos = Pos(Vec3::new(position.pos_x, position.pos_y, position.pos_z));
+    let waypoint = match (
+        position.waypoint_x,
+        position.waypoint_y,
+        position.waypoint_z,
+    ) {
+        (Some(x), Some(y), Some(z)) => {
+            Some(Waypoint::new(Vec3::new(x, y, z), common::state::Time(0.0)))
+        },
+        _ => None,
+    };
+    (pos, waypoint)
+}
+
+pub fn convert_quest_log_to_database(
+    character_id: CharacterId,
+    quest_log: &common::comp::QuestLog,
+) -> Result<QuestLog, Error> {
+    Ok(QuestLog {
+        character_id,
+        quest_log_data: serde_json::to_string(quest_log)?,
+    })
+}
+
+/// A quest log that can't be read anymore is dropped, rather than keeping the
+/// character from being loaded
+pub fn convert_quest_log_from_database(quest_log: &QuestLog) -> common::comp::QuestLog {
+    serde_json::from_str(&quest_log.quest_log_data).unwrap_or_else(|e| {
+        warn!(
+            ?e,
+            character_id = quest_log.character_id,
+            "Dropping a saved quest log that could not be deserialized"
+        );
+        common::comp::QuestLog::default()
+    })
+}
+
 pub fn convert_inventory_from_database_items(database_items: &[Item]) -> Result<Inventory, Error> {
     let mut inventory = Inventory::new_empty();
     for db_item in database_items.iter() {
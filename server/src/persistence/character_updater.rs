@@ -6,7 +6,14 @@ use crossbeam::channel;
 use std::{path::Path, sync::Arc};
 use tracing::{error, trace};
 
-pub type CharacterUpdateData = (comp::Stats, comp::Inventory, comp::Loadout);
+pub type CharacterUpdateData = (
+    comp::Stats,
+    comp::Inventory,
+    comp::Loadout,
+    Option<comp::Pos>,
+    Option<comp::Waypoint>,
+    Option<comp::QuestLog>,
+);
 
 /// A unidirectional messaging resource for saving characters in a
 /// background thread.
@@ -48,17 +55,29 @@ impl CharacterUpdater {
                 &'a comp::Stats,
                 &'a comp::Inventory,
                 &'a comp::Loadout,
+                Option<&'a comp::Pos>,
+                Option<&'a comp::Waypoint>,
+                Option<&'a comp::QuestLog>,
             ),
         >,
     ) {
         let updates = updates
-            .map(|(character_id, stats, inventory, loadout)| {
-                (
-                    character_id,
-                    (stats.clone(), inventory.clone(), loadout.clone()),
-                )
-            })
-            .collect::<Vec<(CharacterId, (comp::Stats, comp::Inventory, comp::Loadout))>>();
+            .map(
+                |(character_id, stats, inventory, loadout, pos, waypoint, quest_log)| {
+                    (
+                        character_id,
+                        (
+                            stats.clone(),
+                            inventory.clone(),
+                            loadout.clone(),
+                            pos.copied(),
+                            waypoint.copied(),
+                            quest_log.cloned(),
+                        ),
+                    )
+                },
+            )
+            .collect::<Vec<(CharacterId, CharacterUpdateData)>>();
 
         if let Err(e) = self.update_tx.as_ref().unwrap().send(updates) {
             error!(?e, "Could not send stats updates");
@@ -72,8 +91,19 @@ impl CharacterUpdater {
         stats: &comp::Stats,
         inventory: &comp::Inventory,
         loadout: &comp::Loadout,
+        pos: Option<&comp::Pos>,
+        waypoint: Option<&comp::Waypoint>,
+        quest_log: Option<&comp::QuestLog>,
     ) {
-        self.batch_update(std::iter::once((character_id, stats, inventory, loadout)));
+        self.batch_update(std::iter::once((
+            character_id,
+            stats,
+            inventory,
+            loadout,
+            pos,
+            waypoint,
+            quest_log,
+        )));
     }
 }
 
@@ -84,12 +114,15 @@ fn execute_batch_update(
     let mut inserted_items = Vec::<Arc<ItemId>>::new();
 
     if let Err(e) = connection.transaction::<_, super::error::Error, _>(|txn| {
-        for (character_id, (stats, inventory, loadout)) in updates {
+        for (character_id, (stats, inventory, loadout, pos, waypoint, quest_log)) in updates {
             inserted_items.append(&mut super::character::update(
                 character_id,
                 stats,
                 inventory,
                 loadout,
+                pos,
+                waypoint,
+                quest_log,
                 txn,
             )?);
         }
@@ -0,0 +1,234 @@
+//! Persistence of the economies of the sites
+//!
+//! The economies are simulated during world generation and then keep running
+//! while the server is up, so their state can't be regenerated from the seed.
+//! After every economy tick the economies of all sites are written to the
+//! database in a background thread, along with the time of the economies. On
+//! startup the saved economies replace the generated ones and the time goes on
+//! from where it was saved.
+extern crate diesel;
+
+use super::{
+    error::Error,
+    models::{EconomyTime, SiteEconomy},
+    schema, VelorenTransaction,
+};
+use crate::persistence::{establish_connection, VelorenConnection};
+use common::trade::SiteId;
+use crossbeam::channel;
+use diesel::prelude::*;
+use hashbrown::HashMap;
+use std::path::Path;
+use tracing::{error, trace, warn};
+use world::{index::Index, site::Economy};
+
+/// A resource which loads the saved economies of the sites on startup, and
+/// saves them in a background thread.
+pub struct EconomyPersistence {
+    saved: HashMap<SiteId, Economy>,
+    saved_time: Option<f32>,
+    update_tx: Option<channel::Sender<(Vec<(SiteId, Economy)>, f32)>>,
+    handle: Option<std::thread::JoinHandle<()>>,
+}
+
+impl EconomyPersistence {
+    pub fn new(db_dir: &Path) -> diesel::QueryResult<Self> {
+        let (update_tx, update_rx) = channel::unbounded::<(Vec<(SiteId, Economy)>, f32)>();
+
+        let mut conn = establish_connection(db_dir)?;
+
+        let (saved, saved_time) = conn.transaction(load_site_economies)?;
+        trace!(
+            "Loaded the economies of {} site(s) from the database",
+            saved.len()
+        );
+
+        let handle = std::thread::spawn(move || {
+            while let Ok((economies, time)) = update_rx.recv() {
+                trace!("Economy persistence update starting");
+                execute_update(economies, time, &mut conn);
+                trace!("Economy persistence update finished");
+            }
+        });
+
+        Ok(Self {
+            saved,
+            saved_time,
+            update_tx: Some(update_tx),
+            handle: Some(handle),
+        })
+    }
+
+    /// Replaces the generated economies of the sites with the saved ones.
+    /// Saved economies of sites that don't exist in the world are dropped.
+    pub fn restore(&mut self, index: &Index) {
+        for (id, site) in index.sites.iter() {
+            if let Some(economy) = self.saved.remove(&id.id()) {
+                *site.economy_mut() = economy;
+            }
+        }
+        if !self.saved.is_empty() {
+            warn!(
+                "Dropping the saved economies of {} site(s) that are not in the world",
+                self.saved.len()
+            );
+            self.saved.clear();
+        }
+    }
+
+    /// The time of the economies when they were saved, if they were
+    pub fn saved_time(&self) -> Option<f32> { self.saved_time }
+
+    /// Queues the current economies of all sites and their time to be written
+    /// to the database.
+    pub fn save(&self, index: &Index, time: f32) {
+        let economies = index
+            .sites
+            .iter()
+            .map(|(id, site)| (id.id(), site.economy().clone()))
+            .collect();
+
+        if let Err(e) = self.update_tx.as_ref().unwrap().send((economies, time)) {
+            error!(?e, "Could not send site economies");
+        }
+    }
+}
+
+impl Drop for EconomyPersistence {
+    fn drop(&mut self) {
+        drop(self.update_tx.take());
+        if let Err(e) = self.handle.take().unwrap().join() {
+            error!(?e, "Error from joining economy persistence thread");
+        }
+    }
+}
+
+/// Loads the saved economies and their time. Rows which fail to deserialize
+/// (for example after a change to the `Economy` format) are skipped, leaving
+/// the site with its generated economy.
+#[allow(clippy::type_complexity)]
+fn load_site_economies(
+    connection: VelorenTransaction,
+) -> QueryResult<(HashMap<SiteId, Economy>, Option<f32>)> {
+    use schema::{economy_time::dsl::*, site_economy::dsl::*};
+
+    let saved_time = economy_time
+        .find(0)
+        .first::<EconomyTime>(&*connection)
+        .optional()?
+        .map(|row| row.time as f32);
+
+    let rows = site_economy.load::<SiteEconomy>(&*connection)?;
+
+    let mut economies = HashMap::new();
+    for row in rows {
+        match serde_json::from_str::<Economy>(&row.economy_data) {
+            Ok(economy) => {
+                economies.insert(row.site_id as SiteId, economy);
+            },
+            Err(e) => warn!(
+                ?e,
+                site_id = row.site_id,
+                "Skipping saved site economy that could not be deserialized"
+            ),
+        }
+    }
+
+    Ok((economies, saved_time))
+}
+
+fn store_site_economies(
+    economies: Vec<(SiteId, Economy)>,
+    time: f32,
+    connection: VelorenTransaction,
+) -> Result<(), Error> {
+    use schema::{economy_time::dsl::*, site_economy::dsl::*};
+
+    diesel::replace_into(economy_time)
+        .values(&EconomyTime {
+            id: 0,
+            time: time as f64,
+        })
+        .execute(&*connection)?;
+
+    let rows = economies
+        .into_iter()
+        .map(|(id, economy)| {
+            Ok(SiteEconomy {
+                site_id: id as i64,
+                economy_data: serde_json::to_string(&economy)?,
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let expected_count = rows.len();
+    let upsert_count = diesel::replace_into(site_economy)
+        .values(&rows)
+        .execute(&*connection)?;
+
+    if upsert_count != expected_count {
+        return Err(Error::OtherError(format!(
+            "Expected site economy upsertions={}, actual={}",
+            expected_count, upsert_count
+        )));
+    }
+
+    Ok(())
+}
+
+fn execute_update(
+    economies: Vec<(SiteId, Economy)>,
+    time: f32,
+    connection: &mut VelorenConnection,
+) {
+    if let Err(e) =
+        connection.transaction::<_, Error, _>(|txn| store_site_economies(economies, time, txn))
+    {
+        error!(?e, "Error during economy persistence update transaction");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::run_migrations;
+    use common::trade::Good;
+    use std::fs;
+
+    #[test]
+    fn test_round_trip() {
+        let db_dir = std::env::temp_dir().join(format!(
+            "veloren-economy-persistence-test-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&db_dir);
+        fs::create_dir_all(&db_dir).unwrap();
+        run_migrations(&db_dir).unwrap();
+
+        let mut economy = Economy::default();
+        economy.pop = 7.0;
+        economy.stocks[Good::Wood] = 3.0;
+        {
+            let persistence = EconomyPersistence::new(&db_dir).unwrap();
+            assert!(persistence.saved.is_empty());
+            assert_eq!(persistence.saved_time(), None);
+            // Dropping the persistence waits for the update to be written
+            persistence
+                .update_tx
+                .as_ref()
+                .unwrap()
+                .send((vec![(4, economy)], 12.5))
+                .unwrap();
+        }
+
+        let persistence = EconomyPersistence::new(&db_dir).unwrap();
+        assert_eq!(persistence.saved_time(), Some(12.5));
+        assert_eq!(persistence.saved.len(), 1);
+        let saved = &persistence.saved[&4];
+        assert_eq!(saved.pop, 7.0);
+        assert_eq!(saved.stocks[Good::Wood], 3.0);
+
+        drop(persistence);
+        let _ = fs::remove_dir_all(&db_dir);
+    }
+}
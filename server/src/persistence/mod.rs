@@ -9,10 +9,12 @@
 pub(in crate::persistence) mod character;
 pub mod character_loader;
 pub mod character_updater;
+pub mod economy;
 mod error;
 mod json_models;
 mod models;
 mod schema;
+pub mod terrain;
 
 use common::comp;
 use diesel::{connection::SimpleConnection, prelude::*};
@@ -20,8 +22,18 @@ use diesel_migrations::embed_migrations;
 use std::{fs, path::Path};
 use tracing::info;
 
-/// A tuple of the components that are persisted to the DB for each character
-pub type PersistedComponents = (comp::Body, comp::Stats, comp::Inventory, comp::Loadout);
+/// A tuple of the components that are persisted to the DB for each character.
+/// The position and waypoint are missing for characters that haven't been
+/// saved in the world yet.
+pub type PersistedComponents = (
+    comp::Body,
+    comp::Stats,
+    comp::Inventory,
+    comp::Loadout,
+    Option<comp::Pos>,
+    Option<comp::Waypoint>,
+    comp::QuestLog,
+);
 
 // See: https://docs.rs/diesel_migrations/1.4.0/diesel_migrations/macro.embed_migrations.html
 // This macro is called at build-time, and produces the necessary migration info
@@ -1,6 +1,9 @@
 extern crate serde_json;
 
-use super::schema::{body, character, entity, item, stats};
+use super::schema::{
+    body, character, character_position, economy_time, entity, item, quest_log, site_economy,
+    skill, skill_group, stats, terrain_block,
+};
 
 #[derive(Debug, Insertable, PartialEq)]
 #[table_name = "entity"]
@@ -25,6 +28,19 @@ pub struct Character {
     pub alias: String,
 }
 
+#[derive(AsChangeset, Identifiable, Insertable, Queryable, Debug)]
+#[primary_key(character_id)]
+#[table_name = "character_position"]
+pub struct CharacterPosition {
+    pub character_id: i64,
+    pub pos_x: f32,
+    pub pos_y: f32,
+    pub pos_z: f32,
+    pub waypoint_x: Option<f32>,
+    pub waypoint_y: Option<f32>,
+    pub waypoint_z: Option<f32>,
+}
+
 #[primary_key(item_id)]
 #[table_name = "item"]
 #[derive(Debug, Insertable, Queryable, AsChangeset)]
@@ -56,3 +72,54 @@ pub struct Body {
     pub variant: String,
     pub body_data: String,
 }
+
+#[derive(Insertable, Queryable, Debug)]
+#[table_name = "skill_group"]
+pub struct SkillGroup {
+    pub character_id: i64,
+    pub skill_group_type: String,
+    pub exp: i32,
+    pub available_sp: i32,
+}
+
+#[derive(Insertable, Queryable, Debug)]
+#[table_name = "skill"]
+pub struct Skill {
+    pub character_id: i64,
+    pub skill_type: String,
+}
+
+#[derive(AsChangeset, Identifiable, Insertable, Queryable, Debug)]
+#[primary_key(x, y, z)]
+#[table_name = "terrain_block"]
+pub struct TerrainBlock {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub chunk_x: i32,
+    pub chunk_y: i32,
+    pub block_data: String,
+}
+
+#[derive(AsChangeset, Identifiable, Insertable, Queryable, Debug)]
+#[table_name = "economy_time"]
+pub struct EconomyTime {
+    pub id: i64,
+    pub time: f64,
+}
+
+#[derive(AsChangeset, Identifiable, Insertable, Queryable, Debug)]
+#[primary_key(site_id)]
+#[table_name = "site_economy"]
+pub struct SiteEconomy {
+    pub site_id: i64,
+    pub economy_data: String,
+}
+
+#[derive(AsChangeset, Identifiable, Insertable, Queryable, Debug)]
+#[primary_key(character_id)]
+#[table_name = "quest_log"]
+pub struct QuestLog {
+    pub character_id: i64,
+    pub quest_log_data: String,
+}
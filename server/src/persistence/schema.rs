@@ -14,6 +14,25 @@ table! {
     }
 }
 
+table! {
+    character_position (character_id) {
+        character_id -> BigInt,
+        pos_x -> Float,
+        pos_y -> Float,
+        pos_z -> Float,
+        waypoint_x -> Nullable<Float>,
+        waypoint_y -> Nullable<Float>,
+        waypoint_z -> Nullable<Float>,
+    }
+}
+
+table! {
+    economy_time (id) {
+        id -> BigInt,
+        time -> Double,
+    }
+}
+
 table! {
     entity (entity_id) {
         entity_id -> BigInt,
@@ -30,6 +49,36 @@ table! {
     }
 }
 
+table! {
+    quest_log (character_id) {
+        character_id -> BigInt,
+        quest_log_data -> Text,
+    }
+}
+
+table! {
+    site_economy (site_id) {
+        site_id -> BigInt,
+        economy_data -> Text,
+    }
+}
+
+table! {
+    skill (character_id, skill_type) {
+        character_id -> BigInt,
+        skill_type -> Text,
+    }
+}
+
+table! {
+    skill_group (character_id, skill_group_type) {
+        character_id -> BigInt,
+        skill_group_type -> Text,
+        exp -> Integer,
+        available_sp -> Integer,
+    }
+}
+
 table! {
     stats (stats_id) {
         stats_id -> BigInt,
@@ -41,7 +90,31 @@ table! {
     }
 }
 
+table! {
+    terrain_block (x, y, z) {
+        x -> Integer,
+        y -> Integer,
+        z -> Integer,
+        chunk_x -> Integer,
+        chunk_y -> Integer,
+        block_data -> Text,
+    }
+}
+
 joinable!(character -> body (character_id));
 joinable!(character -> stats (character_id));
 
-allow_tables_to_appear_in_same_query!(body, character, entity, item, stats,);
+allow_tables_to_appear_in_same_query!(
+    body,
+    character,
+    character_position,
+    economy_time,
+    entity,
+    item,
+    quest_log,
+    site_economy,
+    skill,
+    skill_group,
+    stats,
+    terrain_block,
+);
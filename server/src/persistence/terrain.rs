@@ -0,0 +1,294 @@
+//! Persistence of player-made terrain changes
+//!
+//! Chunks are always regenerated from the world generator when they are
+//! loaded, so any blocks that have been placed, mined or collected would
+//! be lost when a chunk is unloaded or the server restarts. To prevent this,
+//! every block modification applied to the [`TerrainGrid`] is recorded as a
+//! per-chunk delta, written to the database in a background thread, and
+//! re-applied to freshly generated chunks before they are inserted into the
+//! terrain.
+//!
+//! All deltas are loaded into memory on startup so that re-applying them
+//! never requires blocking on the database during a server tick. A delta holds
+//! at most one block per modified position, so the memory used grows with the
+//! number of distinct positions that were changed rather than with the number
+//! of changes.
+//!
+//! Changes are merged per chunk until the writer thread is ready for them, so
+//! a slow database holds back at most [`MAX_QUEUED_BATCHES`] batches and
+//! repeated changes to the same position are only written once.
+//!
+//! [`TerrainGrid`]: common::terrain::TerrainGrid
+extern crate diesel;
+
+use super::{error::Error, models::TerrainBlock, schema, VelorenTransaction};
+use crate::persistence::{establish_connection, VelorenConnection};
+use common::{
+    terrain::{Block, TerrainChunk, TerrainGrid},
+    vol::WriteVol,
+};
+use crossbeam::channel;
+use diesel::prelude::*;
+use hashbrown::HashMap;
+use std::{mem, path::Path};
+use tracing::{error, trace, warn};
+use vek::*;
+
+/// The set of modified blocks within a single chunk, keyed by their absolute
+/// world position
+pub type ChunkDelta = HashMap<Vec3<i32>, Block>;
+
+/// Block changes grouped by the key of their chunk
+type ChunkDeltas = HashMap<Vec2<i32>, ChunkDelta>;
+
+/// How many batches of changes can wait for the writer thread. Once the queue
+/// is full, new changes are merged into the pending batch instead.
+pub const MAX_QUEUED_BATCHES: usize = 4;
+
+/// Rows written by a single insert statement, which keeps the statements below
+/// the SQLite limit on bound variables
+const MAX_ROWS_PER_INSERT: usize = 1024;
+
+/// A resource which tracks every block that has been changed from its
+/// generated state, and persists those changes in a background thread.
+pub struct TerrainPersistence {
+    deltas: ChunkDeltas,
+    /// Changes that were not handed to the writer thread yet
+    pending: ChunkDeltas,
+    update_tx: Option<channel::Sender<ChunkDeltas>>,
+    handle: Option<std::thread::JoinHandle<()>>,
+}
+
+impl TerrainPersistence {
+    pub fn new(db_dir: &Path) -> diesel::QueryResult<Self> {
+        let (update_tx, update_rx) = channel::bounded::<ChunkDeltas>(MAX_QUEUED_BATCHES);
+
+        let mut conn = establish_connection(db_dir)?;
+
+        let deltas = conn.transaction(load_terrain_deltas)?;
+        trace!(
+            "Loaded terrain deltas for {} chunk(s) from the database",
+            deltas.len()
+        );
+
+        let handle = std::thread::spawn(move || {
+            while let Ok(changes) = update_rx.recv() {
+                trace!("Terrain persistence batch update starting");
+                execute_batch_update(changes, &mut conn);
+                trace!("Terrain persistence batch update finished");
+            }
+        });
+
+        Ok(Self {
+            deltas,
+            pending: HashMap::new(),
+            update_tx: Some(update_tx),
+            handle: Some(handle),
+        })
+    }
+
+    /// Records a set of block modifications that have been applied to the
+    /// terrain, and queues them to be written to the database.
+    pub fn record_changes<'a>(
+        &mut self,
+        changes: impl IntoIterator<Item = (&'a Vec3<i32>, &'a Block)>,
+    ) {
+        for (pos, block) in changes {
+            let key = TerrainGrid::chunk_key(*pos);
+            self.deltas.entry(key).or_default().insert(*pos, *block);
+            self.pending.entry(key).or_default().insert(*pos, *block);
+        }
+
+        self.send_pending();
+    }
+
+    /// Hands the pending changes to the writer thread, unless its queue is
+    /// full, in which case they are kept to be merged with the next changes.
+    fn send_pending(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let batch = mem::take(&mut self.pending);
+        match self.update_tx.as_ref().unwrap().try_send(batch) {
+            Ok(()) => {},
+            Err(channel::TrySendError::Full(batch)) => self.pending = batch,
+            Err(channel::TrySendError::Disconnected(_)) => {
+                error!("Could not send terrain changes, the writer thread stopped");
+            },
+        }
+    }
+
+    /// The persisted block at a world position, if it was changed from its
+    /// generated state
+    pub fn block_at(&self, pos: Vec3<i32>) -> Option<Block> {
+        self.deltas
+            .get(&TerrainGrid::chunk_key(pos))
+            .and_then(|delta| delta.get(&pos))
+            .copied()
+    }
+
+    /// Re-applies any persisted block changes to a freshly generated chunk.
+    pub fn apply_changes(&self, key: Vec2<i32>, chunk: &mut TerrainChunk) {
+        if let Some(delta) = self.deltas.get(&key) {
+            for (pos, block) in delta {
+                if let Err(e) = chunk.set(TerrainGrid::chunk_offs(*pos), *block) {
+                    warn!(?e, ?pos, "Failed to re-apply persisted block change");
+                }
+            }
+        }
+    }
+}
+
+impl Drop for TerrainPersistence {
+    fn drop(&mut self) {
+        let update_tx = self.update_tx.take().unwrap();
+        // Block until the remaining changes are queued, so none are lost
+        if !self.pending.is_empty() {
+            if let Err(e) = update_tx.send(mem::take(&mut self.pending)) {
+                error!(?e, "Could not send the remaining terrain changes");
+            }
+        }
+        drop(update_tx);
+        if let Err(e) = self.handle.take().unwrap().join() {
+            error!(?e, "Error from joining terrain persistence thread");
+        }
+    }
+}
+
+/// Loads every stored block change, grouped by chunk. Rows which fail to
+/// deserialize (for example after a change to the `Block` format) are
+/// skipped rather than preventing the server from starting.
+fn load_terrain_deltas(connection: VelorenTransaction) -> QueryResult<ChunkDeltas> {
+    use schema::terrain_block::dsl::*;
+
+    let blocks = terrain_block.load::<TerrainBlock>(&*connection)?;
+
+    let mut deltas = ChunkDeltas::new();
+    for db_block in blocks {
+        match serde_json::from_str::<Block>(&db_block.block_data) {
+            Ok(block) => {
+                deltas
+                    .entry(Vec2::new(db_block.chunk_x, db_block.chunk_y))
+                    .or_default()
+                    .insert(Vec3::new(db_block.x, db_block.y, db_block.z), block);
+            },
+            Err(e) => warn!(
+                ?e,
+                x = db_block.x,
+                y = db_block.y,
+                z = db_block.z,
+                "Skipping persisted block that could not be deserialized"
+            ),
+        }
+    }
+
+    Ok(deltas)
+}
+
+fn store_block_changes(changes: ChunkDeltas, connection: VelorenTransaction) -> Result<(), Error> {
+    use schema::terrain_block::dsl::*;
+
+    let db_blocks = changes
+        .into_iter()
+        .flat_map(|(chunk_key, delta)| {
+            delta.into_iter().map(move |(pos, block)| {
+                Ok(TerrainBlock {
+                    x: pos.x,
+                    y: pos.y,
+                    z: pos.z,
+                    chunk_x: chunk_key.x,
+                    chunk_y: chunk_key.y,
+                    block_data: serde_json::to_string(&block)?,
+                })
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let expected_count = db_blocks.len();
+    let mut upsert_count = 0;
+    for rows in db_blocks.chunks(MAX_ROWS_PER_INSERT) {
+        upsert_count += diesel::replace_into(terrain_block)
+            .values(rows)
+            .execute(&*connection)?;
+    }
+
+    if upsert_count != expected_count {
+        return Err(Error::OtherError(format!(
+            "Expected terrain block upsertions={}, actual={}",
+            expected_count, upsert_count
+        )));
+    }
+
+    Ok(())
+}
+
+fn execute_batch_update(changes: ChunkDeltas, connection: &mut VelorenConnection) {
+    if let Err(e) = connection.transaction::<_, Error, _>(|txn| store_block_changes(changes, txn)) {
+        error!(
+            ?e,
+            "Error during terrain persistence batch update transaction"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::run_migrations;
+    use common::{
+        terrain::{BlockKind, SpriteKind, TerrainChunkMeta},
+        vol::ReadVol,
+    };
+    use std::fs;
+
+    #[test]
+    fn test_round_trip() {
+        let db_dir = std::env::temp_dir().join(format!(
+            "veloren-terrain-persistence-test-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&db_dir);
+        fs::create_dir_all(&db_dir).unwrap();
+        run_migrations(&db_dir).unwrap();
+
+        let rock = Block::new(BlockKind::Rock, Rgb::new(10, 20, 30));
+        let apple = Block::air(SpriteKind::Apple);
+        let mined = Vec3::new(3, 4, 5);
+        let placed = Vec3::new(6, 7, 8);
+        // In another chunk
+        let far = Vec3::new(1000, 4, 5);
+        {
+            let mut persistence = TerrainPersistence::new(&db_dir).unwrap();
+            persistence.record_changes(vec![(&mined, &rock), (&placed, &rock)]);
+            // Only the last change of a position is kept
+            let air = Block::air(SpriteKind::Empty);
+            persistence.record_changes(vec![(&mined, &air), (&far, &apple)]);
+        }
+
+        let persistence = TerrainPersistence::new(&db_dir).unwrap();
+        let key = TerrainGrid::chunk_key(mined);
+        assert_eq!(persistence.deltas[&key].len(), 2);
+
+        let mut chunk = TerrainChunk::new(
+            0,
+            Block::new(BlockKind::Rock, Rgb::zero()),
+            Block::air(SpriteKind::Empty),
+            TerrainChunkMeta::void(),
+        );
+        persistence.apply_changes(key, &mut chunk);
+        let get = |chunk: &TerrainChunk, pos| *chunk.get(TerrainGrid::chunk_offs(pos)).unwrap();
+        assert_eq!(get(&chunk, mined), Block::air(SpriteKind::Empty));
+        assert_eq!(get(&chunk, placed), rock);
+
+        let far_key = TerrainGrid::chunk_key(far);
+        persistence.apply_changes(far_key, &mut chunk);
+        assert_eq!(get(&chunk, far), apple);
+
+        assert_eq!(persistence.block_at(placed), Some(rock));
+        assert_eq!(persistence.block_at(Vec3::new(6, 7, 9)), None);
+
+        drop(persistence);
+        let _ = fs::remove_dir_all(&db_dir);
+    }
+}
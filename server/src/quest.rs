@@ -0,0 +1,179 @@
+//! Tracks the progress of characters on their quests. Progress is recorded
+//! where the things the objectives ask for happen (kills, pickups, talking to
+//! NPCs and reaching sites), and every change to a quest log is sent to the
+//! client of the character. Items to collect are counted in the inventory
+//! rather than recorded as they are picked up.
+
+use crate::client::Client;
+use common::{
+    comp::{self, Inventory, Merchant, QuestLog},
+    msg::{
+        world_msg::{SiteInfo, SiteKind},
+        ServerGeneral,
+    },
+    npc::NpcKind,
+    quest::{NpcRole, QuestEvent, QuestNpc},
+};
+use specs::{Entity as EcsEntity, World, WorldExt};
+use vek::*;
+
+/// NPCs this close to the centre of a site (squared) belong to it
+const SITE_RANGE_SQR: f32 = 160.0 * 160.0;
+
+/// The sites of the world, which characters can be asked to reach
+pub struct QuestSites(pub Vec<SiteInfo>);
+
+impl QuestSites {
+    /// The site a position belongs to
+    fn site_at(&self, pos: Vec2<f32>) -> Option<SiteKind> {
+        self.0
+            .iter()
+            .find(|site| pos.distance_squared(site.wpos.map(|e| e as f32)) < SITE_RANGE_SQR)
+            .map(|site| site.kind)
+    }
+}
+
+/// Describes an NPC for the quests, from its body, its alignment and the site
+/// it is at. Players aren't NPCs.
+pub fn quest_npc(ecs: &World, entity: EcsEntity) -> Option<QuestNpc> {
+    if ecs.read_storage::<comp::Player>().contains(entity) {
+        return None;
+    }
+    let kind = NpcKind::from_body(ecs.read_storage::<comp::Body>().get(entity)?)?;
+    let alignment = ecs.read_storage::<comp::Alignment>().get(entity).copied();
+    let site = ecs
+        .read_storage::<comp::Pos>()
+        .get(entity)
+        .and_then(|pos| ecs.read_resource::<QuestSites>().site_at(pos.0.xy()));
+    let role = if ecs.read_storage::<Merchant>().contains(entity) {
+        Some(NpcRole::Merchant)
+    } else {
+        match (alignment, site) {
+            (Some(comp::Alignment::Npc), Some(SiteKind::Castle)) => Some(NpcRole::Guard),
+            (Some(comp::Alignment::Npc), Some(SiteKind::Town)) => Some(NpcRole::Villager),
+            (Some(comp::Alignment::Npc), _) => Some(NpcRole::Traveller),
+            (Some(comp::Alignment::Enemy), Some(SiteKind::Dungeon { .. })) => {
+                Some(NpcRole::Cultist)
+            },
+            _ => None,
+        }
+    };
+    Some(QuestNpc { kind, role })
+}
+
+/// Records the event in the quest log, and tells the client about the
+/// completed quests and the new state of the log
+pub fn record(log: &mut QuestLog, client: &mut Client, event: &QuestEvent) {
+    let completed = log.completed.len();
+    if log.record(event) {
+        for quest in &log.completed[completed..] {
+            client.send_msg(ServerGeneral::QuestCompleted(quest.clone()));
+        }
+        client.send_msg(ServerGeneral::QuestLog(log.clone()));
+    }
+}
+
+/// Records the event for the character of an entity, if it has one
+pub fn record_for(ecs: &World, entity: EcsEntity, event: &QuestEvent) {
+    let mut logs = ecs.write_storage::<QuestLog>();
+    let mut clients = ecs.write_storage::<Client>();
+    if let (Some(log), Some(client)) = (logs.get_mut(entity), clients.get_mut(entity)) {
+        record(log, client, event);
+    }
+}
+
+/// Records how many of the items the quests of the character ask for are in
+/// its inventory
+pub fn record_inventory(log: &mut QuestLog, client: &mut Client, inventory: &Inventory) {
+    for item in log.wanted_items() {
+        let amount = inventory
+            .slots()
+            .iter()
+            .flatten()
+            .filter(|held| held.item_definition_id() == item)
+            .map(|held| held.amount())
+            .sum();
+        record(log, client, &QuestEvent::Holds {
+            item: &item,
+            amount,
+        });
+    }
+}
+
+/// Records the items in the inventory of an entity for the quests of its
+/// character, if it has one
+pub fn record_held(ecs: &World, entity: EcsEntity) {
+    let inventories = ecs.read_storage::<Inventory>();
+    let mut logs = ecs.write_storage::<QuestLog>();
+    let mut clients = ecs.write_storage::<Client>();
+    if let (Some(inventory), Some(log), Some(client)) = (
+        inventories.get(entity),
+        logs.get_mut(entity),
+        clients.get_mut(entity),
+    ) {
+        record_inventory(log, client, inventory);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::trade::SitePrices;
+    use specs::Builder;
+
+    fn site(kind: SiteKind, wpos: Vec2<i32>) -> SiteInfo {
+        SiteInfo {
+            id: 0,
+            kind,
+            wpos,
+            name: None,
+        }
+    }
+
+    #[test]
+    fn npcs_get_the_role_of_where_they_are() {
+        let mut world = World::new();
+        world.register::<comp::Player>();
+        world.register::<comp::Body>();
+        world.register::<comp::Alignment>();
+        world.register::<comp::Pos>();
+        world.register::<Merchant>();
+        world.insert(QuestSites(vec![
+            site(SiteKind::Town, Vec2::zero()),
+            site(SiteKind::Castle, Vec2::new(1000, 0)),
+            site(SiteKind::Dungeon { difficulty: 1 }, Vec2::new(0, 1000)),
+        ]));
+        let mut npc = |alignment, x, y| {
+            world
+                .create_entity()
+                .with(comp::Body::Humanoid(comp::humanoid::Body::random()))
+                .with(alignment)
+                .with(comp::Pos(Vec3::new(x, y, 0.0)))
+                .build()
+        };
+        let villager = npc(comp::Alignment::Npc, 10.0, 0.0);
+        let merchant = npc(comp::Alignment::Npc, 0.0, 10.0);
+        let guard = npc(comp::Alignment::Npc, 1010.0, 0.0);
+        let traveller = npc(comp::Alignment::Npc, 500.0, 500.0);
+        let cultist = npc(comp::Alignment::Enemy, 0.0, 1010.0);
+        let bandit = npc(comp::Alignment::Enemy, 500.0, 500.0);
+        world
+            .write_storage()
+            .insert(merchant, Merchant {
+                prices: SitePrices::default(),
+            })
+            .unwrap();
+
+        let role = |entity| quest_npc(&world, entity).and_then(|npc| npc.role);
+        assert_eq!(role(villager), Some(NpcRole::Villager));
+        assert_eq!(role(merchant), Some(NpcRole::Merchant));
+        assert_eq!(role(guard), Some(NpcRole::Guard));
+        assert_eq!(role(traveller), Some(NpcRole::Traveller));
+        assert_eq!(role(cultist), Some(NpcRole::Cultist));
+        assert_eq!(role(bandit), None);
+        assert_eq!(
+            quest_npc(&world, bandit).map(|npc| npc.kind),
+            Some(NpcKind::Humanoid)
+        );
+    }
+}
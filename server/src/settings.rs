@@ -2,7 +2,9 @@ mod editable;
 
 pub use editable::EditableSetting;
 
+use crate::movement_validation::MovementValidationSettings;
 use authc::Uuid;
+use common::comp::PvpMode;
 use hashbrown::{HashMap, HashSet};
 use portpicker::pick_unused_port;
 use serde::{Deserialize, Serialize};
@@ -11,7 +13,7 @@ use std::{
     net::SocketAddr,
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use tracing::{error, warn};
 use world::sim::FileOpts;
@@ -21,6 +23,7 @@ const CONFIG_DIR: &str = "server_config";
 const SETTINGS_FILENAME: &str = "settings.ron";
 const WHITELIST_FILENAME: &str = "whitelist.ron";
 const BANLIST_FILENAME: &str = "banlist.ron";
+const BAN_HISTORY_FILENAME: &str = "ban_history.ron";
 const SERVER_DESCRIPTION_FILENAME: &str = "description.ron";
 const ADMINS_FILENAME: &str = "admins.ron";
 
@@ -32,7 +35,10 @@ pub struct Settings {
     pub auth_server_address: Option<String>,
     pub max_players: usize,
     pub world_seed: u32,
-    //pub pvp_enabled: bool,
+    pub pvp_mode: PvpMode,
+    /// Distance in blocks from the centre of a town in which players can't
+    /// damage each other when `pvp_mode` is `Zones`
+    pub pvp_safe_zone_radius: i32,
     pub server_name: String,
     pub start_time: f64,
     /// When set to None, loads the default map file (if available); otherwise,
@@ -42,6 +48,19 @@ pub struct Settings {
     pub banned_words_files: Vec<PathBuf>,
     pub max_player_group_size: u32,
     pub client_timeout: Duration,
+    pub movement_validation: MovementValidationSettings,
+    /// Address the admin API is served on, only local connections are
+    /// accepted by default. It has its own port rather than sharing the
+    /// metrics listener, since `metrics_address` is usually reachable from
+    /// other machines so that they can collect the metrics, while kicking,
+    /// banning and shutting down should not be.
+    pub admin_address: SocketAddr,
+    /// Token required to use the admin API, the admin API is disabled when this
+    /// is not set
+    pub admin_api_token: Option<String>,
+    /// Real time between two ticks of the economies of the sites, each of
+    /// which advances them by a season
+    pub economy_tick_period: Duration,
 }
 
 impl Default for Settings {
@@ -51,6 +70,8 @@ impl Default for Settings {
             metrics_address: SocketAddr::from(([0; 4], 14005)),
             auth_server_address: Some("https://auth.veloren.net".into()),
             world_seed: DEFAULT_WORLD_SEED,
+            pvp_mode: PvpMode::On,
+            pvp_safe_zone_radius: 200,
             server_name: "Veloren Alpha".into(),
             max_players: 100,
             start_time: 9.0 * 3600.0,
@@ -59,6 +80,10 @@ impl Default for Settings {
             banned_words_files: Vec::new(),
             max_player_group_size: 6,
             client_timeout: Duration::from_secs(40),
+            movement_validation: MovementValidationSettings::default(),
+            admin_address: SocketAddr::from(([127, 0, 0, 1], 14006)),
+            admin_api_token: None,
+            economy_tick_period: Duration::from_secs(600),
         }
     }
 }
@@ -134,6 +159,7 @@ impl Settings {
             start_time: 9.0 * 3600.0,
             max_view_distance: None,
             client_timeout: Duration::from_secs(180),
+            admin_api_token: None,
             ..load // Fill in remaining fields from server_settings.ron.
         }
     }
@@ -151,10 +177,74 @@ fn with_config_dir(path: &Path) -> PathBuf {
     path
 }
 
-#[derive(Deserialize, Serialize)]
+/// Seconds since the unix epoch, as stored in [`BanRecord`] and
+/// [`BanHistory`]
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct BanRecord {
     pub username_when_banned: String,
     pub reason: String,
+    /// `None` if the ban was issued from the server console, or before
+    /// issuers were recorded
+    #[serde(default)]
+    pub issued_by: Option<Uuid>,
+    /// unix timestamp in seconds
+    #[serde(default)]
+    pub issued_at: u64,
+    /// unix timestamp in seconds, `None` for permanent bans
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+impl BanRecord {
+    pub fn new(
+        username_when_banned: String,
+        reason: String,
+        issued_by: Option<Uuid>,
+        duration: Option<Duration>,
+    ) -> Self {
+        let issued_at = unix_timestamp();
+        Self {
+            username_when_banned,
+            reason,
+            issued_by,
+            issued_at,
+            expires_at: duration.map(|d| issued_at.saturating_add(d.as_secs())),
+        }
+    }
+
+    /// Time until the ban expires, `None` for permanent bans
+    pub fn remaining(&self) -> Option<Duration> {
+        self.expires_at
+            .map(|expires_at| Duration::from_secs(expires_at.saturating_sub(unix_timestamp())))
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .map_or(false, |expires_at| expires_at <= unix_timestamp())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum BanAction {
+    Ban(BanRecord),
+    Unban {
+        issued_by: Option<Uuid>,
+        /// unix timestamp in seconds
+        issued_at: u64,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BanHistoryEntry {
+    pub uuid: Uuid,
+    pub action: BanAction,
 }
 
 #[derive(Deserialize, Serialize, Default)]
@@ -165,6 +255,28 @@ pub struct Whitelist(HashSet<Uuid>);
 #[serde(transparent)]
 pub struct Banlist(HashMap<Uuid, BanRecord>);
 
+/// Every ban and unban ever issued, entries are only ever appended
+#[derive(Deserialize, Serialize, Default)]
+#[serde(transparent)]
+pub struct BanHistory(Vec<BanHistoryEntry>);
+
+impl Banlist {
+    pub fn has_expired(&self) -> bool { self.0.values().any(BanRecord::is_expired) }
+
+    /// Removes the bans that ran out, returning how many were removed
+    pub fn remove_expired(&mut self) -> usize {
+        let len = self.0.len();
+        self.0.retain(|_, record| !record.is_expired());
+        len - self.0.len()
+    }
+}
+
+impl BanHistory {
+    pub fn append(&mut self, uuid: Uuid, action: BanAction) {
+        self.0.push(BanHistoryEntry { uuid, action });
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 #[serde(transparent)]
 pub struct ServerDescription(String);
@@ -180,6 +292,7 @@ pub struct Admins(HashSet<Uuid>);
 pub struct EditableSettings {
     pub whitelist: Whitelist,
     pub banlist: Banlist,
+    pub ban_history: BanHistory,
     pub server_description: ServerDescription,
     pub admins: Admins,
 }
@@ -189,11 +302,24 @@ impl EditableSettings {
         Self {
             whitelist: Whitelist::load(data_dir),
             banlist: Banlist::load(data_dir),
+            ban_history: BanHistory::load(data_dir),
             server_description: ServerDescription::load(data_dir),
             admins: Admins::load(data_dir),
         }
     }
 
+    /// Loads the settings, failing instead of falling back to the defaults if
+    /// any of the files is missing or invalid
+    pub fn try_load(data_dir: &Path) -> Result<Self, String> {
+        Ok(Self {
+            whitelist: Whitelist::try_load(data_dir)?,
+            banlist: Banlist::try_load(data_dir)?,
+            ban_history: BanHistory::try_load(data_dir)?,
+            server_description: ServerDescription::try_load(data_dir)?,
+            admins: Admins::try_load(data_dir)?,
+        })
+    }
+
     pub fn singleplayer(data_dir: &Path) -> Self {
         let load = Self::load(data_dir);
         Self {
@@ -221,6 +347,10 @@ impl EditableSetting for Banlist {
     const FILENAME: &'static str = BANLIST_FILENAME;
 }
 
+impl EditableSetting for BanHistory {
+    const FILENAME: &'static str = BAN_HISTORY_FILENAME;
+}
+
 impl EditableSetting for ServerDescription {
     const FILENAME: &'static str = SERVER_DESCRIPTION_FILENAME;
 }
@@ -249,6 +379,12 @@ impl DerefMut for Banlist {
     fn deref_mut(&mut self) -> &mut Self::Target { &mut self.0 }
 }
 
+impl Deref for BanHistory {
+    type Target = [BanHistoryEntry];
+
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
 impl Deref for ServerDescription {
     type Target = String;
 
@@ -268,3 +404,34 @@ impl Deref for Admins {
 impl DerefMut for Admins {
     fn deref_mut(&mut self) -> &mut Self::Target { &mut self.0 }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(expires_at: Option<u64>) -> BanRecord {
+        BanRecord {
+            username_when_banned: "someone".to_owned(),
+            reason: String::new(),
+            issued_by: None,
+            issued_at: 0,
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn remove_expired_bans() {
+        let now = unix_timestamp();
+        let mut banlist = Banlist::default();
+        banlist.insert(Uuid::from_u128(1), record(None));
+        banlist.insert(Uuid::from_u128(2), record(Some(now - 1)));
+        banlist.insert(Uuid::from_u128(3), record(Some(now + 3600)));
+
+        assert!(banlist.has_expired());
+        assert_eq!(banlist.remove_expired(), 1);
+        assert!(!banlist.has_expired());
+        assert!(banlist.contains_key(&Uuid::from_u128(1)));
+        assert!(!banlist.contains_key(&Uuid::from_u128(2)));
+        assert!(banlist.contains_key(&Uuid::from_u128(3)));
+    }
+}
@@ -50,6 +50,15 @@ pub trait EditableSetting: Serialize + DeserializeOwned + Default {
         }
     }
 
+    /// Loads the setting without falling back to the default if the file is
+    /// missing or invalid
+    fn try_load(data_dir: &Path) -> Result<Self, String> {
+        let path = Self::get_path(data_dir);
+        let file = fs::File::open(&path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        ron::de::from_reader(file).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    }
+
     fn edit<R>(&mut self, data_dir: &Path, f: impl FnOnce(&mut Self) -> R) -> R {
         let path = Self::get_path(data_dir);
 
@@ -9,6 +9,7 @@ use common::{
     state::State,
     sync::{Uid, UidAllocator, WorldSyncExt},
     util::Dir,
+    weather::WeatherGrid,
 };
 use specs::{
     saveload::MarkerAllocator, Builder, Entity as EcsEntity, EntityBuilder as EcsEntityBuilder,
@@ -83,6 +84,13 @@ impl StateExt for State {
                     .get_mut(entity)
                     .map(|stats| stats.exp.change_by(xp));
             },
+            Effect::Buff { kind, data } => {
+                if let Ok(entry) = self.ecs().write_storage::<comp::Buffs>().entry(entity) {
+                    entry
+                        .or_insert_with(comp::Buffs::default)
+                        .add(comp::Buff::new(kind, data, comp::BuffSource::Item));
+                }
+            },
         }
     }
 
@@ -221,12 +229,15 @@ impl StateExt for State {
         // Tell the client its request was successful.
         if let Some(client) = self.ecs().write_storage::<Client>().get_mut(entity) {
             client.in_game = Some(ClientInGame::Character);
-            client.send_msg(ServerGeneral::CharacterSuccess)
+            client.send_msg(ServerGeneral::CharacterSuccess);
+            // Don't wait for the next weather update
+            let weather = self.ecs().read_resource::<WeatherGrid>().clone();
+            client.send_msg(ServerGeneral::WeatherUpdate(weather));
         }
     }
 
     fn update_character_data(&mut self, entity: EcsEntity, components: PersistedComponents) {
-        let (body, stats, inventory, loadout) = components;
+        let (body, stats, inventory, loadout, pos, waypoint, quest_log) = components;
 
         if let Some(player_uid) = self.read_component_copied::<Uid>(entity) {
             // Notify clients of a player list update
@@ -247,6 +258,21 @@ impl StateExt for State {
             self.write_component(entity, inventory);
             self.write_component(entity, loadout);
 
+            // Characters that were saved in the world continue where they left off,
+            // otherwise they stay at the spawn point
+            if let Some(pos) = pos {
+                self.write_component(entity, pos);
+                self.write_component(entity, comp::ForceUpdate);
+            }
+            if let Some(waypoint) = waypoint {
+                self.write_component(entity, waypoint);
+            }
+
+            if let Some(client) = self.ecs().write_storage::<Client>().get_mut(entity) {
+                client.send_msg(ServerGeneral::QuestLog(quest_log.clone()));
+            }
+            self.write_component(entity, quest_log);
+
             self.write_component(
                 entity,
                 comp::InventoryUpdate::new(comp::InventoryUpdateEvent::default()),
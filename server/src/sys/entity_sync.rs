@@ -4,6 +4,7 @@ use super::{
 };
 use crate::{
     client::{Client, RegionSubscription},
+    movement_validation::MovementReference,
     Tick,
 };
 use common::{
@@ -44,6 +45,7 @@ impl<'a> System<'a> for Sys {
         WriteStorage<'a, Last<Ori>>,
         WriteStorage<'a, Client>,
         WriteStorage<'a, ForceUpdate>,
+        WriteStorage<'a, MovementReference>,
         WriteStorage<'a, InventoryUpdate>,
         Write<'a, DeletedEntities>,
         Write<'a, Vec<Outcome>>,
@@ -71,6 +73,7 @@ impl<'a> System<'a> for Sys {
             mut last_ori,
             mut clients,
             mut force_updates,
+            mut movement_references,
             mut inventory_updates,
             mut deleted_entities,
             mut outcomes,
@@ -346,7 +349,11 @@ impl<'a> System<'a> for Sys {
         }
         outcomes.clear();
 
-        // Remove all force flags.
+        // Remove all force flags. Movement of entities the server moved is validated
+        // from their new position.
+        for (entity, _) in (&entities, &force_updates).join() {
+            movement_references.remove(entity);
+        }
         force_updates.clear();
         inventory_updates.clear();
 
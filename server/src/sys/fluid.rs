@@ -0,0 +1,24 @@
+use super::SysTimer;
+use crate::fluid::{FluidSim, MAX_FLUID_UPDATES};
+use common::{span, state::BlockChange, terrain::TerrainGrid};
+use specs::{ReadExpect, System, Write, WriteExpect};
+
+/// This system lets the water near changed blocks flow
+pub struct Sys;
+impl<'a> System<'a> for Sys {
+    type SystemData = (
+        ReadExpect<'a, TerrainGrid>,
+        Write<'a, BlockChange>,
+        WriteExpect<'a, FluidSim>,
+        Write<'a, SysTimer<Self>>,
+    );
+
+    fn run(&mut self, (terrain, mut block_change, mut fluid_sim, mut timer): Self::SystemData) {
+        span!(_guard, "run", "fluid::Sys::run");
+        timer.start();
+
+        fluid_sim.tick(&*terrain, &mut block_change, MAX_FLUID_UPDATES);
+
+        timer.end();
+    }
+}
@@ -5,13 +5,14 @@ use crate::{
     client::Client,
     login_provider::LoginProvider,
     metrics::{NetworkRequestMetrics, PlayerMetrics},
+    movement_validation::MovementValidation,
     persistence::character_loader::CharacterLoader,
     EditableSettings, Settings,
 };
 use common::{
     comp::{
         Admin, CanBuild, ChatMode, ChatType, ControlEvent, Controller, ForceUpdate, Ori, Player,
-        Pos, Stats, UnresolvedChatMsg, Vel,
+        Pos, PvpOptIn, Stats, UnresolvedChatMsg, Vel,
     },
     event::{EventBus, ServerEvent},
     msg::{
@@ -90,8 +91,9 @@ impl Sys {
         client: &mut Client,
         terrain: &ReadExpect<'_, TerrainGrid>,
         network_metrics: &ReadExpect<'_, NetworkRequestMetrics>,
+        player_metrics: &ReadExpect<'_, PlayerMetrics>,
         can_build: &ReadStorage<'_, CanBuild>,
-        force_updates: &ReadStorage<'_, ForceUpdate>,
+        movement_validation: &mut MovementValidation<'_>,
         stats: &mut WriteStorage<'_, Stats>,
         block_changes: &mut Write<'_, BlockChange>,
         positions: &mut WriteStorage<'_, Pos>,
@@ -168,12 +170,32 @@ impl Sys {
             },
             ClientGeneral::PlayerPhysics { pos, vel, ori } => {
                 if let Some(ClientInGame::Character) = client.in_game {
-                    if force_updates.get(entity).is_none()
+                    if movement_validation.force_updates.get(entity).is_none()
                         && stats.get(entity).map_or(true, |s| !s.is_dead)
                     {
-                        let _ = positions.insert(entity, pos);
-                        let _ = velocities.insert(entity, vel);
-                        let _ = orientations.insert(entity, ori);
+                        match movement_validation.validate(
+                            &settings.movement_validation,
+                            entity,
+                            positions.get(entity),
+                            &pos,
+                        ) {
+                            Ok(()) => {
+                                let _ = positions.insert(entity, pos);
+                                let _ = velocities.insert(entity, vel);
+                                let _ = orientations.insert(entity, ori);
+                            },
+                            Err(violation) => {
+                                debug!(?entity, ?violation, "Rejected implausible physics update");
+                                player_metrics
+                                    .movement_violations
+                                    .with_label_values(&[violation.label()])
+                                    .inc();
+                                // Move the client back to where the server thinks it is
+                                let _ = movement_validation
+                                    .force_updates
+                                    .insert(entity, ForceUpdate);
+                            },
+                        }
                     }
                 }
             },
@@ -218,20 +240,56 @@ impl Sys {
                 }
             },
             ClientGeneral::UnlockSkill(skill) => {
-                stats
-                    .get_mut(entity)
-                    .map(|s| s.skill_set.unlock_skill(skill));
+                // Validate against the skill group definitions and prerequisites before
+                // touching the stats, so rejected requests don't cause a sync
+                match stats
+                    .get(entity)
+                    .map(|s| s.skill_set.can_unlock_skill(skill))
+                {
+                    Some(Ok(_)) => {
+                        stats
+                            .get_mut(entity)
+                            .map(|s| s.skill_set.unlock_skill(skill));
+                    },
+                    Some(Err(error)) => {
+                        debug!(?entity, ?skill, ?error, "Rejected skill unlock request")
+                    },
+                    None => {},
+                }
             },
             ClientGeneral::RefundSkill(skill) => {
-                stats
-                    .get_mut(entity)
-                    .map(|s| s.skill_set.refund_skill(skill));
+                match stats
+                    .get(entity)
+                    .map(|s| s.skill_set.can_refund_skill(skill))
+                {
+                    Some(Ok(_)) => {
+                        stats
+                            .get_mut(entity)
+                            .map(|s| s.skill_set.refund_skill(skill));
+                    },
+                    Some(Err(error)) => {
+                        debug!(?entity, ?skill, ?error, "Rejected skill refund request")
+                    },
+                    None => {},
+                }
             },
             ClientGeneral::UnlockSkillGroup(skill_group_type) => {
                 stats
                     .get_mut(entity)
                     .map(|s| s.skill_set.unlock_skill_group(skill_group_type));
             },
+            ClientGeneral::InitiateTrade(counterparty) => {
+                server_emitter.emit(ServerEvent::InitiateTrade(entity, counterparty));
+            },
+            ClientGeneral::UpdatePendingTrade(trade_id, action) => {
+                server_emitter.emit(ServerEvent::ProcessTradeAction(entity, trade_id, action));
+            },
+            ClientGeneral::RequestSiteInfo(id) => {
+                server_emitter.emit(ServerEvent::RequestSiteInfo { entity, id });
+            },
+            ClientGeneral::Talk(npc) => {
+                server_emitter.emit(ServerEvent::Talk { entity, npc });
+            },
             _ => unreachable!("not a client_in_game msg"),
         }
         Ok(())
@@ -431,7 +489,7 @@ impl Sys {
         player_metrics: &ReadExpect<'_, PlayerMetrics>,
         uids: &ReadStorage<'_, Uid>,
         can_build: &ReadStorage<'_, CanBuild>,
-        force_updates: &ReadStorage<'_, ForceUpdate>,
+        movement_validation: &mut MovementValidation<'_>,
         stats: &mut WriteStorage<'_, Stats>,
         chat_modes: &ReadStorage<'_, ChatMode>,
         login_provider: &mut WriteExpect<'_, LoginProvider>,
@@ -494,8 +552,9 @@ impl Sys {
                     client,
                     terrain,
                     network_metrics,
+                    player_metrics,
                     can_build,
-                    force_updates,
+                    movement_validation,
                     stats,
                     block_changes,
                     positions,
@@ -560,12 +619,13 @@ impl<'a> System<'a> for Sys {
         Write<'a, SysTimer<Self>>,
         ReadStorage<'a, Uid>,
         ReadStorage<'a, CanBuild>,
-        ReadStorage<'a, ForceUpdate>,
+        MovementValidation<'a>,
         WriteStorage<'a, Stats>,
         ReadStorage<'a, ChatMode>,
         WriteExpect<'a, LoginProvider>,
         Write<'a, BlockChange>,
         WriteStorage<'a, Admin>,
+        ReadStorage<'a, PvpOptIn>,
         WriteStorage<'a, Pos>,
         WriteStorage<'a, Vel>,
         WriteStorage<'a, Ori>,
@@ -593,12 +653,13 @@ impl<'a> System<'a> for Sys {
             mut timer,
             uids,
             can_build,
-            force_updates,
+            mut movement_validation,
             mut stats,
             chat_modes,
             mut accounts,
             mut block_changes,
             mut admins,
+            pvp_opt_ins,
             mut positions,
             mut velocities,
             mut orientations,
@@ -618,12 +679,13 @@ impl<'a> System<'a> for Sys {
         let mut new_chat_msgs = Vec::new();
 
         // Player list to send new players.
-        let player_list = (&uids, &players, stats.maybe(), admins.maybe())
+        let player_list = (&entities, &uids, &players, stats.maybe(), admins.maybe())
             .join()
-            .map(|(uid, player, stats, admin)| {
+            .map(|(entity, uid, player, stats, admin)| {
                 (*uid, PlayerInfo {
                     is_online: true,
                     is_admin: admin.is_some(),
+                    pvp: pvp_opt_ins.contains(entity),
                     player_alias: player.alias.clone(),
                     character: stats.map(|stats| CharacterInfo {
                         name: stats.name.clone(),
@@ -654,7 +716,7 @@ impl<'a> System<'a> for Sys {
                     &player_metrics,
                     &uids,
                     &can_build,
-                    &force_updates,
+                    &mut movement_validation,
                     &mut stats,
                     &chat_modes,
                     &mut accounts,
@@ -710,6 +772,7 @@ impl<'a> System<'a> for Sys {
                         player_alias: player.alias.clone(),
                         is_online: true,
                         is_admin: admins.get(entity).is_some(),
+                        pvp: pvp_opt_ins.contains(entity),
                         character: None, // new players will be on character select.
                     }));
                 for client in (&mut clients).join().filter(|c| c.registered) {
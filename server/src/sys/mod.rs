@@ -1,13 +1,18 @@
 pub mod entity_sync;
+pub mod fluid;
 pub mod invite_timeout;
 pub mod message;
 pub mod object;
 pub mod persistence;
+pub mod quest;
+pub mod rtsim;
 pub mod sentinel;
 pub mod subscription;
 pub mod terrain;
 pub mod terrain_sync;
+pub mod trade_timeout;
 pub mod waypoint;
+pub mod weather;
 
 use specs::DispatcherBuilder;
 use std::{
@@ -23,8 +28,15 @@ pub type TerrainTimer = SysTimer<terrain::Sys>;
 pub type TerrainSyncTimer = SysTimer<terrain_sync::Sys>;
 pub type WaypointTimer = SysTimer<waypoint::Sys>;
 pub type InviteTimeoutTimer = SysTimer<invite_timeout::Sys>;
+pub type TradeTimeoutTimer = SysTimer<trade_timeout::Sys>;
 pub type PersistenceTimer = SysTimer<persistence::Sys>;
 pub type PersistenceScheduler = SysScheduler<persistence::Sys>;
+pub type WeatherTimer = SysTimer<weather::Sys>;
+pub type FluidTimer = SysTimer<fluid::Sys>;
+pub type RtSimTimer = SysTimer<rtsim::Sys>;
+pub type WeatherScheduler = SysScheduler<weather::Sys>;
+pub type QuestTimer = SysTimer<quest::Sys>;
+pub type QuestScheduler = SysScheduler<quest::Sys>;
 
 // System names
 // Note: commented names may be useful in the future
@@ -35,15 +47,25 @@ pub type PersistenceScheduler = SysScheduler<persistence::Sys>;
 const TERRAIN_SYS: &str = "server_terrain_sys";
 const WAYPOINT_SYS: &str = "server_waypoint_sys";
 const INVITE_TIMEOUT_SYS: &str = "server_invite_timeout_sys";
+const TRADE_TIMEOUT_SYS: &str = "server_trade_timeout_sys";
 const PERSISTENCE_SYS: &str = "server_persistence_sys";
 const OBJECT_SYS: &str = "server_object_sys";
+const WEATHER_SYS: &str = "server_weather_sys";
+const FLUID_SYS: &str = "server_fluid_sys";
+const RTSIM_SYS: &str = "server_rtsim_sys";
+const QUEST_SYS: &str = "server_quest_sys";
 
 pub fn add_server_systems(dispatch_builder: &mut DispatcherBuilder) {
     dispatch_builder.add(terrain::Sys, TERRAIN_SYS, &[]);
     dispatch_builder.add(waypoint::Sys, WAYPOINT_SYS, &[]);
     dispatch_builder.add(invite_timeout::Sys, INVITE_TIMEOUT_SYS, &[]);
+    dispatch_builder.add(trade_timeout::Sys, TRADE_TIMEOUT_SYS, &[]);
     dispatch_builder.add(persistence::Sys, PERSISTENCE_SYS, &[]);
     dispatch_builder.add(object::Sys, OBJECT_SYS, &[]);
+    dispatch_builder.add(weather::Sys, WEATHER_SYS, &[]);
+    dispatch_builder.add(fluid::Sys, FLUID_SYS, &[]);
+    dispatch_builder.add(rtsim::Sys, RTSIM_SYS, &[TERRAIN_SYS]);
+    dispatch_builder.add(quest::Sys, QUEST_SYS, &[]);
 }
 
 pub fn run_sync_systems(ecs: &mut specs::World) {
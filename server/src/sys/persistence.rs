@@ -3,7 +3,7 @@ use crate::{
     sys::{SysScheduler, SysTimer},
 };
 use common::{
-    comp::{Inventory, Loadout, Player, Stats},
+    comp::{Inventory, Loadout, Player, Pos, QuestLog, Stats, Waypoint},
     span,
 };
 use specs::{Join, ReadExpect, ReadStorage, System, Write};
@@ -17,6 +17,9 @@ impl<'a> System<'a> for Sys {
         ReadStorage<'a, Stats>,
         ReadStorage<'a, Inventory>,
         ReadStorage<'a, Loadout>,
+        ReadStorage<'a, Pos>,
+        ReadStorage<'a, Waypoint>,
+        ReadStorage<'a, QuestLog>,
         ReadExpect<'a, character_updater::CharacterUpdater>,
         Write<'a, SysScheduler<Self>>,
         Write<'a, SysTimer<Self>>,
@@ -29,6 +32,9 @@ impl<'a> System<'a> for Sys {
             player_stats,
             player_inventories,
             player_loadouts,
+            player_positions,
+            player_waypoints,
+            player_quest_logs,
             updater,
             mut scheduler,
             mut timer,
@@ -43,13 +49,18 @@ impl<'a> System<'a> for Sys {
                     &player_stats,
                     &player_inventories,
                     &player_loadouts,
+                    player_positions.maybe(),
+                    player_waypoints.maybe(),
+                    player_quest_logs.maybe(),
                 )
                     .join()
-                    .filter_map(|(player, stats, inventory, loadout)| {
-                        player
-                            .character_id
-                            .map(|id| (id, stats, inventory, loadout))
-                    }),
+                    .filter_map(
+                        |(player, stats, inventory, loadout, pos, waypoint, quest_log)| {
+                            player
+                                .character_id
+                                .map(|id| (id, stats, inventory, loadout, pos, waypoint, quest_log))
+                        },
+                    ),
             );
             timer.end();
         }
@@ -0,0 +1,80 @@
+use super::{SysScheduler, SysTimer};
+use crate::{
+    client::Client,
+    quest::{self, QuestSites},
+};
+use common::{
+    comp::{Inventory, Player, Pos, QuestLog},
+    quest::QuestEvent,
+    span,
+};
+use specs::{Join, ReadExpect, ReadStorage, System, Write, WriteStorage};
+use std::time::Duration;
+
+/// How often the positions and inventories of the characters are checked
+pub const QUEST_TICK: Duration = Duration::from_secs(2);
+/// Characters count as having reached a site within this distance of its
+/// centre
+const REACH_RANGE: f32 = 96.0;
+
+/// This system records the sites characters reach and the items they hold for
+/// their quests. Checking the inventories here also catches items that were
+/// dropped, traded or used up.
+pub struct Sys;
+impl<'a> System<'a> for Sys {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadStorage<'a, Pos>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Inventory>,
+        WriteStorage<'a, QuestLog>,
+        WriteStorage<'a, Client>,
+        ReadExpect<'a, QuestSites>,
+        Write<'a, SysScheduler<Self>>,
+        Write<'a, SysTimer<Self>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            positions,
+            players,
+            inventories,
+            mut quest_logs,
+            mut clients,
+            sites,
+            mut scheduler,
+            mut timer,
+        ): Self::SystemData,
+    ) {
+        span!(_guard, "run", "quest::Sys::run");
+        if !scheduler.should_run() {
+            return;
+        }
+        timer.start();
+
+        for (pos, _, inventory, log, client) in (
+            &positions,
+            &players,
+            inventories.maybe(),
+            &mut quest_logs,
+            &mut clients,
+        )
+            .join()
+        {
+            if log.active.is_empty() {
+                continue;
+            }
+            if let Some(inventory) = inventory {
+                quest::record_inventory(log, client, inventory);
+            }
+            for site in sites.0.iter() {
+                if pos.0.xy().distance_squared(site.wpos.map(|e| e as f32)) < REACH_RANGE.powi(2) {
+                    quest::record(log, client, &QuestEvent::Reached(site.kind));
+                }
+            }
+        }
+
+        timer.end();
+    }
+}
@@ -0,0 +1,108 @@
+use super::{terrain::npc_event, SysTimer};
+use common::{
+    comp::{Agent, Pos},
+    event::{EventBus, ServerEvent},
+    rtsim::RtSimEntity,
+    span,
+    state::DeltaTime,
+    terrain::TerrainGrid,
+    vol::ReadVol,
+};
+use specs::{Join, Read, ReadExpect, ReadStorage, System, Write, WriteExpect, WriteStorage};
+use world::rtsim::RtSim;
+
+/// Highest number of blocks an NPC is moved up when it is loaded inside
+/// solid terrain
+const MAX_SPAWN_CLIMB: i32 = 64;
+
+/// This system simulates the NPCs of rtsim, loading them as entities when
+/// their chunk is loaded and letting their agents follow their journeys
+pub struct Sys;
+impl<'a> System<'a> for Sys {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Read<'a, EventBus<ServerEvent>>,
+        Read<'a, DeltaTime>,
+        ReadExpect<'a, TerrainGrid>,
+        WriteExpect<'a, RtSim>,
+        ReadStorage<'a, RtSimEntity>,
+        ReadStorage<'a, Pos>,
+        WriteStorage<'a, Agent>,
+        Write<'a, SysTimer<Self>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            server_event_bus,
+            dt,
+            terrain,
+            mut rtsim,
+            rtsim_entities,
+            positions,
+            mut agents,
+            mut timer,
+        ): Self::SystemData,
+    ) {
+        span!(_guard, "run", "rtsim::Sys::run");
+        timer.start();
+
+        // Find out where the loaded NPCs got to
+        let mut present = vec![false; rtsim.npcs().count()];
+        for (rtsim_entity, pos) in (&rtsim_entities, &positions).join() {
+            if let Some(npc) = rtsim.get_mut(rtsim_entity.0) {
+                npc.wpos = pos.0;
+                present[rtsim_entity.0] = true;
+            }
+        }
+        // NPCs that died were already sent home by `handle_destroy`, so the
+        // entities that are gone were unloaded with their chunk
+        let unloaded = rtsim
+            .npcs()
+            .filter(|(id, npc)| npc.is_loaded && !present[*id])
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        for id in unloaded {
+            if let Some(npc) = rtsim.get_mut(id) {
+                npc.is_loaded = false;
+            }
+        }
+
+        rtsim.tick(dt.0);
+
+        // Lead the agents of loaded NPCs along their journeys
+        for (rtsim_entity, agent) in (&rtsim_entities, &mut agents).join() {
+            if let Some(target) = rtsim.target(rtsim_entity.0) {
+                agent.patrol_origin = Some(target);
+                agent.travel_to = Some(target);
+            }
+        }
+
+        // Load the NPCs whose chunk has been loaded
+        let mut server_emitter = server_event_bus.emitter();
+        let to_load = rtsim
+            .npcs()
+            .filter(|(_, npc)| !npc.is_loaded)
+            .filter(|(_, npc)| terrain.get(npc.wpos.map(|e| e.floor() as i32)).is_ok())
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        for id in to_load {
+            if let Some(npc) = rtsim.get_mut(id) {
+                // Simulated NPCs follow the rough altitude of the world, so
+                // make sure they don't appear inside the ground
+                let mut wpos = npc.wpos.map(|e| e.floor() as i32);
+                for _ in 0..MAX_SPAWN_CLIMB {
+                    if terrain.get(wpos).map_or(true, |block| !block.is_solid()) {
+                        break;
+                    }
+                    wpos.z += 1;
+                }
+                npc.wpos.z = wpos.z as f32;
+                npc.is_loaded = true;
+                server_emitter.emit(npc_event(npc.entity_info(), Some(RtSimEntity(id))));
+            }
+        }
+
+        timer.end();
+    }
+}
@@ -1,27 +1,31 @@
 use super::SysTimer;
-use crate::{chunk_generator::ChunkGenerator, client::Client, Tick};
+use crate::{
+    chunk_generator::ChunkGenerator, client::Client, persistence::terrain::TerrainPersistence, Tick,
+};
 use common::{
     comp::{self, bird_medium, Alignment, Player, Pos},
     event::{EventBus, ServerEvent},
-    generation::get_npc_name,
+    generation::{get_npc_name, EntityInfo},
     msg::ServerGeneral,
     npc::NPC_NAMES,
+    rtsim::RtSimEntity,
     span,
     state::TerrainChanges,
     terrain::TerrainGrid,
     LoadoutBuilder,
 };
 use rand::Rng;
-use specs::{Join, Read, ReadStorage, System, Write, WriteExpect, WriteStorage};
+use specs::{Join, Read, ReadExpect, ReadStorage, System, Write, WriteExpect, WriteStorage};
 use std::sync::Arc;
 use vek::*;
 
 /// This system will handle loading generated chunks and unloading
 /// unneeded chunks.
-///     1. Inserts newly generated chunks into the TerrainGrid
-///     2. Sends new chunks to nearby clients
-///     3. Handles the chunk's supplement (e.g. npcs)
-///     4. Removes chunks outside the range of players
+///     1. Re-applies persisted block changes to newly generated chunks
+///     2. Inserts newly generated chunks into the TerrainGrid
+///     3. Sends new chunks to nearby clients
+///     4. Handles the chunk's supplement (e.g. npcs)
+///     5. Removes chunks outside the range of players
 pub struct Sys;
 impl<'a> System<'a> for Sys {
     #[allow(clippy::type_complexity)] // TODO: Pending review in #587
@@ -30,6 +34,7 @@ impl<'a> System<'a> for Sys {
         Read<'a, Tick>,
         Write<'a, SysTimer<Self>>,
         WriteExpect<'a, ChunkGenerator>,
+        ReadExpect<'a, TerrainPersistence>,
         WriteExpect<'a, TerrainGrid>,
         Write<'a, TerrainChanges>,
         ReadStorage<'a, Pos>,
@@ -44,6 +49,7 @@ impl<'a> System<'a> for Sys {
             tick,
             mut timer,
             mut chunk_generator,
+            terrain_persistence,
             mut terrain,
             mut terrain_changes,
             positions,
@@ -59,7 +65,7 @@ impl<'a> System<'a> for Sys {
         // Fetch any generated `TerrainChunk`s and insert them into the terrain.
         // Also, send the chunk data to anybody that is close by.
         'insert_terrain_chunks: while let Some((key, res)) = chunk_generator.recv_new_chunk() {
-            let (chunk, supplement) = match res {
+            let (mut chunk, supplement) = match res {
                 Ok((chunk, supplement)) => (chunk, supplement),
                 Err(Some(entity)) => {
                     if let Some(client) = clients.get_mut(entity) {
@@ -74,6 +80,11 @@ impl<'a> System<'a> for Sys {
                     continue 'insert_terrain_chunks;
                 },
             };
+
+            // Re-apply any changes that were made to this chunk before it was last
+            // unloaded (or before the server restarted)
+            terrain_persistence.apply_changes(key, &mut chunk);
+
             // Send the chunk to all nearby players.
             for (view_distance, pos, client) in (&players, &positions, &mut clients)
                 .join()
@@ -112,84 +123,7 @@ impl<'a> System<'a> for Sys {
                     continue;
                 }
 
-                let mut body = entity.body;
-                let name = entity.name.unwrap_or_else(|| "Unnamed".to_string());
-                let alignment = entity.alignment;
-                let main_tool = entity.main_tool;
-                let mut stats = comp::Stats::new(name, body);
-                // let damage = stats.level.level() as i32; TODO: Make NPC base damage
-                // non-linearly depend on their level
-
-                let mut scale = entity.scale;
-
-                // TODO: Remove this and implement scaling or level depending on stuff like
-                // species instead
-                stats.level.set_level(
-                    entity.level.unwrap_or_else(|| {
-                        (rand::thread_rng().gen_range(1, 9) as f32 * scale) as u32
-                    }),
-                );
-
-                // Replace stuff if it's a boss
-                if entity.is_giant {
-                    if rand::random::<f32>() < 0.65 && entity.alignment != Alignment::Enemy {
-                        let body_new = comp::humanoid::Body::random();
-                        body = comp::Body::Humanoid(body_new);
-                        stats = comp::Stats::new(
-                            format!(
-                                "Gentle Giant {}",
-                                get_npc_name(&NPC_NAMES.humanoid, body_new.species)
-                            ),
-                            body,
-                        );
-                    }
-                    stats.level.set_level(rand::thread_rng().gen_range(30, 35));
-                    scale = 2.0 + rand::random::<f32>();
-                }
-
-                let loadout =
-                    LoadoutBuilder::build_loadout(body, alignment, main_tool, entity.is_giant)
-                        .build();
-
-                stats.update_max_hp(stats.body_type);
-
-                stats
-                    .health
-                    .set_to(stats.health.maximum(), comp::HealthSource::Revive);
-
-                let can_speak = match body {
-                    comp::Body::Humanoid(_) => alignment == comp::Alignment::Npc,
-                    comp::Body::BirdMedium(bird_medium) => match bird_medium.species {
-                        // Parrots like to have a word in this, too...
-                        bird_medium::Species::Parrot => alignment == comp::Alignment::Npc,
-                        _ => false,
-                    },
-                    _ => false,
-                };
-
-                // TODO: This code sets an appropriate base_damage for the enemy. This doesn't
-                // work because the damage is now saved in an ability
-                /*
-                if let Some(item::ItemKind::Tool(item::ToolData { base_damage, .. })) =
-                    &mut loadout.active_item.map(|i| i.item.kind)
-                {
-                    *base_damage = stats.level.level() as u32 * 3;
-                }
-                */
-                server_emitter.emit(ServerEvent::CreateNpc {
-                    pos: Pos(entity.pos),
-                    stats,
-                    loadout,
-                    agent: if entity.has_agency {
-                        Some(comp::Agent::new(entity.pos, can_speak, &body))
-                    } else {
-                        None
-                    },
-                    body,
-                    alignment,
-                    scale: comp::Scale(scale),
-                    drop_item: entity.loot_drop,
-                })
+                server_emitter.emit(npc_event(entity, None));
             }
         }
 
@@ -248,3 +182,86 @@ pub fn chunk_in_vd(
 
     adjusted_dist_sqr <= vd.pow(2)
 }
+
+/// The event that creates the entity for an NPC of a chunk supplement or rtsim
+pub fn npc_event(entity: EntityInfo, rtsim_entity: Option<RtSimEntity>) -> ServerEvent {
+    let mut body = entity.body;
+    let name = entity.name.unwrap_or_else(|| "Unnamed".to_string());
+    let alignment = entity.alignment;
+    let main_tool = entity.main_tool;
+    let mut stats = comp::Stats::new(name, body);
+    // let damage = stats.level.level() as i32; TODO: Make NPC base damage
+    // non-linearly depend on their level
+
+    let mut scale = entity.scale;
+
+    // TODO: Remove this and implement scaling or level depending on stuff like
+    // species instead
+    stats.level.set_level(
+        entity
+            .level
+            .unwrap_or_else(|| (rand::thread_rng().gen_range(1, 9) as f32 * scale) as u32),
+    );
+
+    // Replace stuff if it's a boss
+    if entity.is_giant {
+        if rand::random::<f32>() < 0.65 && entity.alignment != Alignment::Enemy {
+            let body_new = comp::humanoid::Body::random();
+            body = comp::Body::Humanoid(body_new);
+            stats = comp::Stats::new(
+                format!(
+                    "Gentle Giant {}",
+                    get_npc_name(&NPC_NAMES.humanoid, body_new.species)
+                ),
+                body,
+            );
+        }
+        stats.level.set_level(rand::thread_rng().gen_range(30, 35));
+        scale = 2.0 + rand::random::<f32>();
+    }
+
+    let loadout =
+        LoadoutBuilder::build_loadout(body, alignment, main_tool, entity.is_giant).build();
+
+    stats.update_max_hp(stats.body_type);
+
+    stats
+        .health
+        .set_to(stats.health.maximum(), comp::HealthSource::Revive);
+
+    let can_speak = match body {
+        comp::Body::Humanoid(_) => alignment == comp::Alignment::Npc,
+        comp::Body::BirdMedium(bird_medium) => match bird_medium.species {
+            // Parrots like to have a word in this, too...
+            bird_medium::Species::Parrot => alignment == comp::Alignment::Npc,
+            _ => false,
+        },
+        _ => false,
+    };
+
+    // TODO: This code sets an appropriate base_damage for the enemy. This doesn't
+    // work because the damage is now saved in an ability
+    /*
+    if let Some(item::ItemKind::Tool(item::ToolData { base_damage, .. })) =
+        &mut loadout.active_item.map(|i| i.item.kind)
+    {
+        *base_damage = stats.level.level() as u32 * 3;
+    }
+    */
+    ServerEvent::CreateNpc {
+        pos: Pos(entity.pos),
+        stats,
+        loadout,
+        agent: if entity.has_agency {
+            Some(comp::Agent::new(entity.pos, can_speak, &body))
+        } else {
+            None
+        },
+        body,
+        alignment,
+        scale: comp::Scale(scale),
+        drop_item: entity.loot_drop,
+        trading_information: entity.trading_information,
+        rtsim_entity,
+    }
+}
@@ -0,0 +1,61 @@
+use super::SysTimer;
+use crate::client::Client;
+use common::{
+    comp::Pos,
+    msg::ServerGeneral,
+    span,
+    sync::{Uid, UidAllocator},
+    trade::{TradeResult, Trades, MAX_TRADE_RANGE_SQR},
+};
+use specs::{saveload::MarkerAllocator, Read, ReadStorage, System, Write, WriteStorage};
+
+/// This system cancels trades nobody acted on for a while, and trades whose
+/// parties walked away from each other
+pub struct Sys;
+impl<'a> System<'a> for Sys {
+    #[allow(clippy::type_complexity)] // TODO: Pending review in #587
+    type SystemData = (
+        Read<'a, UidAllocator>,
+        Write<'a, Trades>,
+        ReadStorage<'a, Pos>,
+        WriteStorage<'a, Client>,
+        Write<'a, SysTimer<Self>>,
+    );
+
+    fn run(
+        &mut self,
+        (uid_allocator, mut trades, positions, mut clients, mut timer): Self::SystemData,
+    ) {
+        span!(_guard, "run", "trade_timeout::Sys::run");
+        timer.start();
+
+        let mut cancelled = trades.stale_trades(std::time::Instant::now());
+        cancelled.extend(trades.trades.iter().filter_map(|(id, trade)| {
+            let position = |uid: &Uid| {
+                uid_allocator
+                    .retrieve_entity_internal(uid.0)
+                    .and_then(|entity| positions.get(entity))
+            };
+            let in_range = match (position(&trade.parties[0]), position(&trade.parties[1])) {
+                (Some(a), Some(b)) => a.0.distance_squared(b.0) < MAX_TRADE_RANGE_SQR,
+                _ => false,
+            };
+            if in_range { None } else { Some(*id) }
+        }));
+
+        for id in cancelled {
+            if let Some(trade) = trades.remove(id) {
+                for uid in trade.parties.iter() {
+                    if let Some(client) = uid_allocator
+                        .retrieve_entity_internal(uid.0)
+                        .and_then(|entity| clients.get_mut(entity))
+                    {
+                        client.send_msg(ServerGeneral::FinishedTrade(TradeResult::Declined));
+                    }
+                }
+            }
+        }
+
+        timer.end();
+    }
+}
@@ -0,0 +1,206 @@
+use super::{SysScheduler, SysTimer};
+use crate::{
+    client::Client,
+    weather::{Extinguished, WeatherSim},
+};
+use common::{
+    comp::{object, Body, LightEmitter, Pos, WaypointArea},
+    msg::ServerGeneral,
+    span,
+    terrain::TerrainGrid,
+    vol::ReadVol,
+    weather::WeatherGrid,
+};
+use specs::{Entities, Join, ReadExpect, ReadStorage, System, Write, WriteExpect, WriteStorage};
+use std::time::Duration;
+use vek::*;
+
+/// How often the weather is simulated and sent to clients
+pub const WEATHER_TICK: Duration = Duration::from_secs(5);
+/// Rain intensity above which campfires are put out
+const EXTINGUISH_RAIN: f32 = 0.3;
+/// Campfires with a solid block at most this far above them are sheltered
+/// from the rain
+const SHELTER_HEIGHT: i32 = 16;
+
+/// This system simulates the weather, sends it to clients and lets the rain
+/// put out campfires
+pub struct Sys;
+impl<'a> System<'a> for Sys {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, TerrainGrid>,
+        Write<'a, WeatherGrid>,
+        WriteExpect<'a, WeatherSim>,
+        ReadStorage<'a, Pos>,
+        ReadStorage<'a, WaypointArea>,
+        WriteStorage<'a, Body>,
+        WriteStorage<'a, LightEmitter>,
+        WriteStorage<'a, Extinguished>,
+        WriteStorage<'a, Client>,
+        Write<'a, SysScheduler<Self>>,
+        Write<'a, SysTimer<Self>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            terrain,
+            mut weather,
+            mut sim,
+            positions,
+            waypoint_areas,
+            mut bodies,
+            mut light_emitters,
+            mut extinguished,
+            mut clients,
+            mut scheduler,
+            mut timer,
+        ): Self::SystemData,
+    ) {
+        span!(_guard, "run", "weather::Sys::run");
+        if !scheduler.should_run() {
+            return;
+        }
+        timer.start();
+
+        sim.tick(&mut weather, WEATHER_TICK.as_secs_f32());
+
+        let sheltered = |pos: &Pos| {
+            let pos = pos.0.map(|e| e.floor() as i32);
+            (1..=SHELTER_HEIGHT).any(|z| {
+                terrain
+                    .get(pos + Vec3::unit_z() * z)
+                    .map_or(false, |block| block.is_solid())
+            })
+        };
+
+        // Put out campfires in the rain
+        let put_out = (&entities, &positions, &bodies)
+            .join()
+            .filter(|(_, pos, body)| {
+                **body == Body::Object(object::Body::CampfireLit)
+                    && weather.get_interpolated(pos.0.xy()).rain > EXTINGUISH_RAIN
+                    && !sheltered(pos)
+            })
+            .map(|(entity, _, _)| entity)
+            .collect::<Vec<_>>();
+        for entity in put_out {
+            let light = light_emitters.remove(entity);
+            // The campfires of waypoints are tended to, so they are lit again later
+            if let (Some(light), true) = (light, waypoint_areas.contains(entity)) {
+                let _ = extinguished.insert(entity, Extinguished(light));
+            }
+            let _ = bodies.insert(entity, Body::Object(object::Body::Campfire));
+        }
+
+        // Light them again once the rain stopped
+        let relit = (&entities, &positions, &extinguished)
+            .join()
+            .filter(|(_, pos, _)| weather.get_interpolated(pos.0.xy()).rain <= 0.0)
+            .map(|(entity, _, _)| entity)
+            .collect::<Vec<_>>();
+        for entity in relit {
+            if let Some(Extinguished(light)) = extinguished.remove(entity) {
+                let _ = light_emitters.insert(entity, light);
+                let _ = bodies.insert(entity, Body::Object(object::Body::CampfireLit));
+            }
+        }
+
+        let msg = ServerGeneral::WeatherUpdate(weather.clone());
+        for client in (&mut clients).join().filter(|c| c.in_game.is_some()) {
+            client.send_msg(msg.clone());
+        }
+
+        timer.end();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sys::WeatherTimer;
+    use common::weather::CELL_SIZE;
+    use specs::{Builder, RunNow, WorldExt};
+
+    /// An open field with a wild campfire and the campfire of a waypoint
+    fn setup() -> (specs::World, [specs::Entity; 2]) {
+        let mut world = specs::World::new();
+        world.register::<Pos>();
+        world.register::<WaypointArea>();
+        world.register::<Body>();
+        world.register::<LightEmitter>();
+        world.register::<Extinguished>();
+        world.register::<Client>();
+        world.insert(TerrainGrid::new().unwrap());
+        world.insert(WeatherGrid::new(Vec2::one()));
+        // A simulation of a larger world doesn't touch the grid, so the
+        // weather stays as the tests set it
+        world.insert(WeatherSim::new(
+            &WeatherGrid::new(Vec2::broadcast(CELL_SIZE * 2)),
+            0,
+            |_| Default::default(),
+        ));
+        world.insert(SysScheduler::<Sys>::every(Duration::from_secs(0)));
+        world.insert(WeatherTimer::default());
+        let mut campfire = |waypoint| {
+            let builder = world
+                .create_entity()
+                .with(Pos(Vec3::new(10.0, 10.0, 0.0)))
+                .with(Body::Object(object::Body::CampfireLit))
+                .with(LightEmitter::default());
+            if waypoint {
+                builder.with(WaypointArea::default()).build()
+            } else {
+                builder.build()
+            }
+        };
+        let campfires = [campfire(false), campfire(true)];
+        (world, campfires)
+    }
+
+    fn set_rain(world: &specs::World, rain: f32) {
+        world
+            .write_resource::<WeatherGrid>()
+            .get_mut(Vec2::zero())
+            .unwrap()
+            .rain = rain;
+    }
+
+    fn is_lit(world: &specs::World, campfire: specs::Entity) -> bool {
+        world.read_storage::<Body>().get(campfire) == Some(&Body::Object(object::Body::CampfireLit))
+            && world.read_storage::<LightEmitter>().contains(campfire)
+    }
+
+    #[test]
+    fn campfires_burn_in_light_rain() {
+        let (world, campfires) = setup();
+        set_rain(&world, EXTINGUISH_RAIN * 0.5);
+        Sys.run_now(&world);
+        assert!(is_lit(&world, campfires[0]));
+        assert!(is_lit(&world, campfires[1]));
+    }
+
+    #[test]
+    fn rain_puts_out_campfires() {
+        let (world, campfires) = setup();
+        set_rain(&world, 1.0);
+        Sys.run_now(&world);
+        assert!(!is_lit(&world, campfires[0]));
+        assert!(!is_lit(&world, campfires[1]));
+        assert_eq!(
+            world.read_storage::<Body>().get(campfires[0]),
+            Some(&Body::Object(object::Body::Campfire))
+        );
+        assert!(!world.read_storage::<Extinguished>().contains(campfires[0]));
+        assert!(world.read_storage::<Extinguished>().contains(campfires[1]));
+
+        // Only the campfire of the waypoint is lit again once the rain stopped
+        set_rain(&world, 0.0);
+        Sys.run_now(&world);
+        assert!(!is_lit(&world, campfires[0]));
+        assert!(is_lit(&world, campfires[1]));
+    }
+}
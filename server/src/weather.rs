@@ -0,0 +1,243 @@
+//! Simulation of the regional weather. Every cell of the `WeatherGrid` holds
+//! some moisture, which evaporates depending on the climate of the cell, is
+//! carried around by the wind and falls as rain or snow once the clouds are
+//! thick enough.
+
+use common::{
+    comp::LightEmitter,
+    weather::{Weather, WeatherGrid},
+};
+use rand::{prelude::*, rngs::SmallRng};
+use specs::Component;
+use specs_idvs::IdvStorage;
+use std::f32::consts::PI;
+use vek::*;
+
+/// Moisture above which it starts raining
+const RAIN_THRESHOLD: f32 = 0.7;
+/// Moisture below which the sky is clear
+const CLEAR_THRESHOLD: f32 = 0.3;
+/// Moisture gained per second in a cell with a humidity of 1
+const EVAPORATION_RATE: f32 = 1.0 / 600.0;
+/// Moisture lost per second while it is raining at full intensity
+const PRECIPITATION_RATE: f32 = 1.0 / 120.0;
+/// Temperature below which it snows instead of raining, matching the
+/// temperature of snowy terrain
+const SNOW_TEMP: f32 = -0.6;
+/// Wind speed in blocks per second in a full storm
+const MAX_WIND_SPEED: f32 = 20.0;
+/// Seconds it takes the prevailing wind to turn around once
+const WIND_PERIOD: f32 = 4.0 * 3600.0;
+/// Seconds between the dry and wet periods of a cell
+const WEATHER_PERIOD: f32 = 3600.0;
+
+/// The climate of a weather cell, from the world simulation
+#[derive(Copy, Clone, Debug)]
+pub struct Climate {
+    /// Temperature from -1 (cold) to 1 (hot)
+    pub temp: f32,
+    /// Humidity from 0 (dry) to 1 (wet)
+    pub humidity: f32,
+}
+
+impl Default for Climate {
+    fn default() -> Self {
+        Self {
+            temp: 0.0,
+            humidity: 0.5,
+        }
+    }
+}
+
+struct Cell {
+    climate: Climate,
+    moisture: f32,
+    /// Offset of this cell in the weather and wind cycles, so that cells don't
+    /// change in lockstep
+    phase: f32,
+}
+
+pub struct WeatherSim {
+    size: Vec2<u32>,
+    cells: Vec<Cell>,
+    time: f32,
+}
+
+impl WeatherSim {
+    /// Creates a simulation for `grid`, with the climate of every cell given by
+    /// `climate`
+    pub fn new(grid: &WeatherGrid, seed: u32, climate: impl Fn(Vec2<u32>) -> Climate) -> Self {
+        let mut rng = SmallRng::seed_from_u64(seed as u64);
+        let cells = grid
+            .iter()
+            .map(|(cell, _)| Cell {
+                climate: climate(cell),
+                moisture: rng.gen_range(0.0, RAIN_THRESHOLD),
+                phase: rng.gen_range(0.0, 2.0 * PI),
+            })
+            .collect();
+        Self {
+            size: grid.size(),
+            cells,
+            time: 0.0,
+        }
+    }
+
+    /// The wind blowing everywhere in the world, which slowly changes
+    /// direction and strength
+    fn prevailing_wind(&self) -> Vec2<f32> {
+        let angle = self.time / WIND_PERIOD * 2.0 * PI;
+        let strength = 0.5 + 0.5 * (self.time / WEATHER_PERIOD * 2.0 * PI).sin();
+        Vec2::new(angle.cos(), angle.sin()) * strength * MAX_WIND_SPEED * 0.5
+    }
+
+    fn moisture_at(&self, pos: Vec2<f32>) -> f32 {
+        let max = self.size.map(|e| e as f32 - 1.0);
+        let pos = pos.clamped(Vec2::zero(), max);
+        let min_cell = pos.map(|e| e.floor() as u32);
+        let max_cell = (min_cell + 1).map2(self.size, |e, sz| e.min(sz - 1));
+        let t = pos - pos.floor();
+        let get = |cell: Vec2<u32>| self.cells[(cell.y * self.size.x + cell.x) as usize].moisture;
+
+        let below = Lerp::lerp(get(min_cell), get(Vec2::new(max_cell.x, min_cell.y)), t.x);
+        let above = Lerp::lerp(get(Vec2::new(min_cell.x, max_cell.y)), get(max_cell), t.x);
+        Lerp::lerp(below, above, t.y)
+    }
+
+    /// Advances the simulation by `dt` seconds and writes the new weather to
+    /// `grid`
+    pub fn tick(&mut self, grid: &mut WeatherGrid, dt: f32) {
+        if self.cells.is_empty() || grid.size() != self.size {
+            return;
+        }
+        self.time += dt;
+        let prevailing_wind = self.prevailing_wind();
+        let time = self.time;
+        let winds = self
+            .cells
+            .iter()
+            .map(|cell| {
+                let gust = 0.75 + 0.25 * (time / WEATHER_PERIOD * 8.0 * PI + cell.phase).sin();
+                prevailing_wind * gust * (1.0 + cell.climate.humidity)
+            })
+            .collect::<Vec<_>>();
+
+        // Carry the moisture along with the wind, by looking up where the air
+        // in each cell came from
+        let cell_size = WeatherGrid::cell_size();
+        let moved = (0..self.cells.len())
+            .map(|i| {
+                let cell = Vec2::new(i as u32 % self.size.x, i as u32 / self.size.x);
+                let from = cell.map(|e| e as f32) - winds[i] * dt / cell_size;
+                self.moisture_at(from)
+            })
+            .collect::<Vec<_>>();
+
+        for (((_, weather), cell), (moisture, wind)) in grid
+            .iter_mut()
+            .zip(self.cells.iter_mut())
+            .zip(moved.into_iter().zip(winds))
+        {
+            let season = 0.5 + 0.5 * (time / WEATHER_PERIOD * 2.0 * PI + cell.phase).sin();
+            let evaporation = cell.climate.humidity * season * EVAPORATION_RATE * 2.0;
+            let mut moisture = moisture + evaporation * (1.0 - moisture) * dt;
+
+            let precipitation = ((moisture - RAIN_THRESHOLD) / (1.0 - RAIN_THRESHOLD))
+                .max(0.0)
+                .min(1.0);
+            moisture -= precipitation * PRECIPITATION_RATE * dt;
+            cell.moisture = moisture.max(0.0).min(1.0);
+
+            let snowing = cell.climate.temp < SNOW_TEMP;
+            *weather = Weather {
+                cloud: ((cell.moisture - CLEAR_THRESHOLD) / (RAIN_THRESHOLD - CLEAR_THRESHOLD))
+                    .max(0.0)
+                    .min(1.0),
+                rain: if snowing { 0.0 } else { precipitation },
+                snow: if snowing { precipitation } else { 0.0 },
+                wind,
+            };
+        }
+    }
+}
+
+/// A campfire that was put out by the rain, and is lit again once it stopped.
+/// Only used for the campfires of waypoints.
+pub struct Extinguished(pub LightEmitter);
+
+impl Component for Extinguished {
+    type Storage = IdvStorage<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs the simulation for some hours and returns the strongest rain and
+    /// snow there was
+    fn run(climate: Climate) -> (f32, f32) {
+        let mut grid = WeatherGrid::new(Vec2::new(128, 128));
+        let mut sim = WeatherSim::new(&grid, 0, |_| climate);
+        let (mut rain, mut snow) = (0.0f32, 0.0f32);
+        for _ in 0..4 * 3600 / 5 {
+            sim.tick(&mut grid, 5.0);
+            for (_, weather) in grid.iter() {
+                rain = rain.max(weather.rain);
+                snow = snow.max(weather.snow);
+            }
+        }
+        (rain, snow)
+    }
+
+    #[test]
+    fn test_climate() {
+        let (rain, snow) = run(Climate {
+            temp: 0.5,
+            humidity: 1.0,
+        });
+        assert!(rain > 0.0);
+        assert!(snow <= 0.0);
+
+        let (rain, snow) = run(Climate {
+            temp: -1.0,
+            humidity: 1.0,
+        });
+        assert!(rain <= 0.0);
+        assert!(snow > 0.0);
+
+        // Without evaporation the clouds never get thick enough to rain
+        let (rain, snow) = run(Climate {
+            temp: 0.5,
+            humidity: 0.0,
+        });
+        assert!(rain.max(snow) <= 0.0);
+    }
+
+    #[test]
+    fn test_wind_carries_moisture() {
+        let mut grid = WeatherGrid::new(Vec2::new(16 * 32, 32));
+        let mut sim = WeatherSim::new(&grid, 0, |_| Climate {
+            temp: 0.0,
+            humidity: 0.0,
+        });
+        for (i, cell) in sim.cells.iter_mut().enumerate() {
+            cell.moisture = if i == 2 { 0.6 } else { 0.0 };
+        }
+        let center = |sim: &WeatherSim| {
+            let total = sim.cells.iter().map(|cell| cell.moisture).sum::<f32>();
+            sim.cells
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| i as f32 * cell.moisture)
+                .sum::<f32>()
+                / total
+        };
+        // The wind starts out blowing along the x axis
+        assert!(sim.prevailing_wind().x > 0.0);
+
+        for _ in 0..200 {
+            sim.tick(&mut grid, 5.0);
+        }
+        assert!(center(&sim) > 3.0);
+    }
+}
@@ -12,7 +12,8 @@ use comp::item::{
 
 #[derive(StructOpt)]
 struct Cli {
-    /// Available arguments: "armor_stats", "weapon_stats", "all_items"
+    /// Available arguments: "armor_stats", "weapon_stats", "all_items",
+    /// "loot_tables"
     function: String,
 }
 
@@ -157,6 +158,20 @@ fn all_items() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+fn loot_tables() -> Result<(), Box<dyn Error>> {
+    let problems = common::loot::validate_loot_tables();
+    for problem in &problems {
+        println!("{}", problem);
+    }
+
+    if problems.is_empty() {
+        println!("All loot tables are valid");
+        Ok(())
+    } else {
+        Err(format!("Found {} problems in the loot tables", problems.len()).into())
+    }
+}
+
 fn main() {
     let args = Cli::from_args();
     if args.function.eq_ignore_ascii_case("armor_stats") {
@@ -171,10 +186,14 @@ fn main() {
         if let Err(e) = all_items() {
             println!("Error: {}", e)
         }
+    } else if args.function.eq_ignore_ascii_case("loot_tables") {
+        if let Err(e) = loot_tables() {
+            println!("Error: {}", e)
+        }
     } else {
         println!(
             "Invalid argument, available \
-             arguments:\n\"armor_stats\"\n\"weapon_stats\"\n\"all_items\""
+             arguments:\n\"armor_stats\"\n\"weapon_stats\"\n\"all_items\"\n\"loot_tables\""
         )
     }
 }
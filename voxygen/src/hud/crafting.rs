@@ -1,7 +1,7 @@
 use super::{
     img_ids::{Imgs, ImgsRot},
     item_imgs::ItemImgs,
-    TEXT_COLOR, TEXT_DULL_RED_COLOR, TEXT_GRAY_COLOR, UI_HIGHLIGHT_0, UI_MAIN,
+    QUALITY_COMMON, TEXT_COLOR, TEXT_DULL_RED_COLOR, TEXT_GRAY_COLOR, UI_HIGHLIGHT_0, UI_MAIN,
 };
 use crate::{
     hud::get_quality_col,
@@ -9,15 +9,19 @@ use crate::{
     ui::{fonts::ConrodVoxygenFonts, ImageFrame, Tooltip, TooltipManager, Tooltipable},
 };
 use client::{self, Client};
-use common::comp::{
-    item::{ItemDesc, Quality},
-    Inventory,
+use common::{
+    comp::{
+        item::{ItemDesc, ItemTag, Quality},
+        Inventory,
+    },
+    recipe::{CraftingStation, RecipeInput},
 };
 use conrod_core::{
     color,
     widget::{self, Button, Image, Rectangle, Scrollbar, Text},
     widget_ids, Color, Colorable, Labelable, Positionable, Sizeable, Widget, WidgetCommon,
 };
+use std::borrow::Cow;
 widget_ids! {
     pub struct Ids {
         window,
@@ -40,6 +44,7 @@ widget_ids! {
         ingredient_img[],
         req_text[],
         ingredients_txt,
+        station_txt,
         output_img_frame,
         output_img,
         output_amount,
@@ -203,7 +208,8 @@ impl<'a> Widget for Crafting<'a> {
         match &state.selected_recipe {
             None => {},
             Some(recipe) => {
-                let can_perform = client.available_recipes().contains(recipe.as_str());
+                let can_perform = client.available_recipes().contains(recipe.as_str())
+                    && client.is_near_station(recipe);
                 // Ingredients Text
                 Text::new(&self.localized_strings.get("hud.crafting.ingredients"))
                     .top_left_with_margins_on(state.ids.align_ing, 10.0, 5.0)
@@ -211,6 +217,27 @@ impl<'a> Widget for Crafting<'a> {
                     .font_size(self.fonts.cyri.scale(18))
                     .color(TEXT_COLOR)
                     .set(state.ids.ingredients_txt, ui);
+                // Station Text
+                if let Some(station) = client
+                    .recipe_book()
+                    .get(recipe.as_str())
+                    .and_then(|r| r.station)
+                {
+                    let station_text = self
+                        .localized_strings
+                        .get("hud.crafting.station")
+                        .replace("{station}", station_name(station, &self.localized_strings));
+                    Text::new(&station_text)
+                        .right_from(state.ids.ingredients_txt, 10.0)
+                        .font_id(self.fonts.cyri.conrod_id)
+                        .font_size(self.fonts.cyri.scale(12))
+                        .color(if client.is_near_station(recipe) {
+                            TEXT_COLOR
+                        } else {
+                            TEXT_DULL_RED_COLOR
+                        })
+                        .set(state.ids.station_txt, ui);
+                }
                 // Craft button
                 if Button::image(self.imgs.button)
                     .w_h(105.0, 25.0)
@@ -390,10 +417,10 @@ impl<'a> Widget for Crafting<'a> {
                 });
             };
             // Widget generation for every ingredient
-            for (i, (item_def, amount)) in recipe.inputs.iter().enumerate() {
+            for (i, (input, amount)) in recipe.inputs().enumerate() {
                 // Grey color for images and text if their amount is too low to craft the item
-                let item_count_in_inventory = self.inventory.item_count(item_def);
-                let col = if item_count_in_inventory >= u64::from(*amount.max(&1)) {
+                let item_count_in_inventory = self.inventory.input_count(input);
+                let col = if item_count_in_inventory >= u64::from(amount.max(1)) {
                     TEXT_COLOR
                 } else {
                     TEXT_DULL_RED_COLOR
@@ -408,13 +435,57 @@ impl<'a> Widget for Crafting<'a> {
                 // Catalysts/Tools"
                 let frame_offset = if i == 0 {
                     10.0
-                } else if *amount == 0 {
+                } else if amount == 0 {
                     5.0
                 } else {
                     0.0
                 };
-                let quality_col = get_quality_col(&**item_def);
-                let quality_col_img = match &item_def.quality {
+                // Tags are shown as the first item with the tag the character has
+                let (name, quality, quality_col, img, title, desc) = match input {
+                    RecipeInput::Item(item_def) => {
+                        let (title, desc) = super::util::item_text(&**item_def);
+                        (
+                            item_def.name(),
+                            item_def.quality,
+                            get_quality_col(&**item_def),
+                            self.item_imgs
+                                .img_id_or_not_found_img((&*item_def.kind()).into()),
+                            title,
+                            desc,
+                        )
+                    },
+                    RecipeInput::Tag(tag) => {
+                        let name = tag_name(*tag, &self.localized_strings);
+                        match self
+                            .inventory
+                            .slots()
+                            .iter()
+                            .flatten()
+                            .find(|item| item.has_tag(*tag))
+                        {
+                            Some(item) => {
+                                let (title, desc) = super::util::item_text(item);
+                                (
+                                    name,
+                                    item.quality(),
+                                    get_quality_col(item),
+                                    self.item_imgs.img_id_or_not_found_img(item.kind().into()),
+                                    title,
+                                    desc,
+                                )
+                            },
+                            None => (
+                                name,
+                                Quality::Common,
+                                QUALITY_COMMON,
+                                self.imgs.not_found,
+                                name,
+                                Cow::Borrowed(""),
+                            ),
+                        }
+                    },
+                };
+                let quality_col_img = match quality {
                     Quality::Low => self.imgs.inv_slot_grey,
                     Quality::Common => self.imgs.inv_slot,
                     Quality::Moderate => self.imgs.inv_slot_green,
@@ -425,35 +496,31 @@ impl<'a> Widget for Crafting<'a> {
                     _ => self.imgs.inv_slot_red,
                 };
                 let frame = Image::new(quality_col_img).w_h(25.0, 25.0);
-                let frame = if *amount == 0 {
+                let frame = if amount == 0 {
                     frame.down_from(state.ids.req_text[i], 10.0 + frame_offset)
                 } else {
                     frame.down_from(frame_pos, 10.0 + frame_offset)
                 };
                 frame.set(state.ids.ingredient_frame[i], ui);
                 //Item Image
-                let (title, desc) = super::util::item_text(&**item_def);
-                Button::image(
-                    self.item_imgs
-                        .img_id_or_not_found_img((&*item_def.kind()).into()),
-                )
-                .w_h(22.0, 22.0)
-                .middle_of(state.ids.ingredient_frame[i])
-                .with_tooltip(
-                    self.tooltip_manager,
-                    title,
-                    &*desc,
-                    &item_tooltip,
-                    quality_col,
-                )
-                .set(state.ids.ingredient_img[i], ui);
+                Button::image(img)
+                    .w_h(22.0, 22.0)
+                    .middle_of(state.ids.ingredient_frame[i])
+                    .with_tooltip(
+                        self.tooltip_manager,
+                        title,
+                        &*desc,
+                        &item_tooltip,
+                        quality_col,
+                    )
+                    .set(state.ids.ingredient_img[i], ui);
                 // Ingredients text and amount
                 // Don't show inventory amounts above 999 to avoid the widget clipping
                 let over9k = "99+";
                 let in_inv: &str = &item_count_in_inventory.to_string();
                 // Show Ingredients
                 // Align "Required" Text below last ingredient
-                if *amount == 0 {
+                if amount == 0 {
                     // Catalysts/Tools
                     Text::new(&self.localized_strings.get("hud.crafting.tool_cata"))
                         .down_from(state.ids.ingredient_frame[i - 1], 20.0)
@@ -461,7 +528,7 @@ impl<'a> Widget for Crafting<'a> {
                         .font_size(self.fonts.cyri.scale(14))
                         .color(TEXT_COLOR)
                         .set(state.ids.req_text[i], ui);
-                    Text::new(&item_def.name())
+                    Text::new(name)
                         .right_from(state.ids.ingredient_frame[i], 10.0)
                         .font_id(self.fonts.cyri.conrod_id)
                         .font_size(self.fonts.cyri.scale(14))
@@ -472,7 +539,7 @@ impl<'a> Widget for Crafting<'a> {
                     let input = format!(
                         "{}x {} ({})",
                         amount,
-                        &item_def.name(),
+                        name,
                         if item_count_in_inventory > 99 {
                             over9k
                         } else {
@@ -513,3 +580,23 @@ impl<'a> Widget for Crafting<'a> {
         events
     }
 }
+
+fn tag_name(tag: ItemTag, localized_strings: &VoxygenLocalization) -> &str {
+    localized_strings.get(match tag {
+        ItemTag::Wood => "hud.crafting.tag.wood",
+        ItemTag::Stone => "hud.crafting.tag.stone",
+        ItemTag::Ore => "hud.crafting.tag.ore",
+        ItemTag::Leather => "hud.crafting.tag.leather",
+        ItemTag::Cloth => "hud.crafting.tag.cloth",
+        ItemTag::Gem => "hud.crafting.tag.gem",
+    })
+}
+
+fn station_name(station: CraftingStation, localized_strings: &VoxygenLocalization) -> &str {
+    localized_strings.get(match station {
+        CraftingStation::Anvil => "hud.crafting.station.anvil",
+        CraftingStation::Cauldron => "hud.crafting.station.cauldron",
+        CraftingStation::Campfire => "hud.crafting.station.campfire",
+        CraftingStation::CraftingBench => "hud.crafting.station.crafting_bench",
+    })
+}
@@ -9,7 +9,10 @@ use crate::{
 };
 use client::{self, Client};
 use common::{
-    comp::{group::Role, Stats},
+    comp::{
+        group::{InviteKind, Role},
+        Stats,
+    },
     sync::{Uid, WorldSyncExt},
 };
 use conrod_core::{
@@ -168,7 +171,7 @@ impl<'a> Widget for Group<'a> {
                 .unwrap_or_else(|| format!("Npc<{}>", uid)),
         };
 
-        let open_invite = self.client.group_invite();
+        let open_invite = self.client.invite();
 
         let my_uid = self.client.uid();
 
@@ -182,7 +185,7 @@ impl<'a> Widget for Group<'a> {
                 .crop_kids()
                 .set(state.ids.bg, ui);
         }
-        if let Some((_, timeout_start, timeout_dur)) = open_invite {
+        if let Some((_, timeout_start, timeout_dur, _)) = open_invite {
             // Group Menu button
             Button::image(self.imgs.group_icon)
                 .w_h(49.0, 26.0)
@@ -612,7 +615,7 @@ impl<'a> Widget for Group<'a> {
                 // into the maximum group size.
             }
         }
-        if let Some((invite_uid, _, _)) = open_invite {
+        if let Some((invite_uid, _, _, kind)) = open_invite {
             self.show.group = true; // Auto open group menu
             // TODO: add group name here too
             // Invite text
@@ -620,7 +623,11 @@ impl<'a> Widget for Group<'a> {
             let name = uid_to_name_text(invite_uid, &self.client);
             let invite_text = self
                 .localized_strings
-                .get("hud.group.invite_to_join")
+                .get(match kind {
+                    InviteKind::Group => "hud.group.invite_to_join",
+                    InviteKind::Trade => "hud.group.invite_to_trade",
+                    InviteKind::Quest => "hud.group.invite_to_quest",
+                })
                 .replace("{name}", &name);
             Text::new(&invite_text)
                 .mid_top_with_margin_on(state.ids.bg, 5.0)
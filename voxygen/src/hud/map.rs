@@ -4,15 +4,25 @@ use super::{
 };
 use crate::{
     i18n::VoxygenLocalization,
-    ui::{fonts::ConrodVoxygenFonts, img_ids, ImageSlider},
+    settings::MapFilters,
+    ui::{fonts::ConrodVoxygenFonts, img_ids, ImageSlider, ToggleButton},
     GlobalState,
 };
 use client::{self, Client};
-use common::{comp, terrain::TerrainChunkSize, vol::RectVolSize};
+use common::{
+    assets::Asset,
+    comp::{self, item::ItemDef},
+    msg::world_msg::{PoiKind, SiteKind},
+    npc::NpcKind,
+    quest::{NpcRole, Objective, QuestDef, QuestNpc},
+    terrain::TerrainChunkSize,
+    trade::SiteId,
+    vol::RectVolSize,
+};
 use conrod_core::{
-    color, position,
-    widget::{self, Button, Image, Rectangle, Text},
-    widget_ids, Colorable, Positionable, Sizeable, Widget, WidgetCommon,
+    color, image, position,
+    widget::{self, Button, Image, PointPath, Rectangle, Text},
+    widget_ids, Color, Colorable, Positionable, Sizeable, Widget, WidgetCommon,
 };
 use specs::WorldExt;
 use vek::*;
@@ -31,9 +41,174 @@ widget_ids! {
         map_title,
         qlog_title,
         zoom_slider,
+        track_lines[],
+        markers[],
+        marker_label,
+        filter_title,
+        filter_towns,
+        filter_towns_txt,
+        filter_dungeons,
+        filter_dungeons_txt,
+        filter_castles,
+        filter_castles_txt,
+        filter_caves,
+        filter_caves_txt,
+        filter_tracks,
+        filter_tracks_txt,
+        quest_titles[],
+        quest_objectives[],
     }
 }
 
+const TRACK_COLOR: Color = Color::Rgba(0.55, 0.4, 0.25, 0.9);
+
+/// A site or place of interest shown on the map and the minimap
+pub struct Marker {
+    pub wpos: Vec2<i32>,
+    pub img: image::Id,
+    pub label: String,
+    /// The town the marker stands for, whose economy can be requested
+    pub site: Option<SiteId>,
+}
+
+fn site_name(site: &SiteKind, localized_strings: &VoxygenLocalization) -> String {
+    localized_strings
+        .get(match site {
+            SiteKind::Town => "hud.map.town",
+            SiteKind::Dungeon { .. } => "hud.map.dungeon",
+            SiteKind::Castle => "hud.map.castle",
+        })
+        .to_owned()
+}
+
+/// Names an NPC by its role, or by its kind if any NPC of the kind will do
+fn npc_name(npc: QuestNpc, localized_strings: &VoxygenLocalization) -> String {
+    let key = match npc.role {
+        Some(NpcRole::Merchant) => "hud.quest.npc.merchant",
+        Some(NpcRole::Guard) => "hud.quest.npc.guard",
+        Some(NpcRole::Villager) => "hud.quest.npc.villager",
+        Some(NpcRole::Traveller) => "hud.quest.npc.traveller",
+        Some(NpcRole::Cultist) => "hud.quest.npc.cultist",
+        None => match npc.kind {
+            NpcKind::Humanoid => "hud.quest.npc.humanoid",
+            NpcKind::Wolf => "hud.quest.npc.wolf",
+            NpcKind::Pig => "hud.quest.npc.pig",
+            NpcKind::Duck => "hud.quest.npc.duck",
+            NpcKind::Ogre => "hud.quest.npc.ogre",
+            NpcKind::Archaeos => "hud.quest.npc.archaeos",
+            NpcKind::StoneGolem => "hud.quest.npc.stone_golem",
+            NpcKind::Reddragon => "hud.quest.npc.red_dragon",
+            NpcKind::Crocodile => "hud.quest.npc.crocodile",
+        },
+    };
+    localized_strings.get(key).to_owned()
+}
+
+/// Describes a quest objective for the quest log
+fn objective_text(objective: &Objective, localized_strings: &VoxygenLocalization) -> String {
+    match objective {
+        Objective::Kill { npc, .. } => localized_strings
+            .get("hud.quest.kill")
+            .replace("{npc}", &npc_name(*npc, localized_strings)),
+        Objective::Collect { item, .. } => {
+            let name = ItemDef::load(item).map_or_else(|_| item.clone(), |def| def.name.clone());
+            localized_strings
+                .get("hud.quest.collect")
+                .replace("{item}", &name)
+        },
+        Objective::Reach { site } => localized_strings
+            .get("hud.quest.reach")
+            .replace("{site}", &site_name(site, localized_strings)),
+        Objective::Talk { npc } => localized_strings
+            .get("hud.quest.talk")
+            .replace("{npc}", &npc_name(*npc, localized_strings)),
+    }
+}
+
+/// Collects the markers of the sites and places of interest which pass the
+/// map filters
+pub fn markers(
+    client: &Client,
+    imgs: &Imgs,
+    localized_strings: &VoxygenLocalization,
+    filters: &MapFilters,
+) -> Vec<Marker> {
+    let label = |name: &Option<String>, kind: String| match name {
+        Some(name) => format!("{}\n{}", name, kind),
+        None => kind,
+    };
+
+    let sites = client
+        .sites()
+        .iter()
+        .filter(|site| match site.kind {
+            SiteKind::Town => filters.towns,
+            SiteKind::Dungeon { .. } => filters.dungeons,
+            SiteKind::Castle => filters.castles,
+        })
+        .map(|site| {
+            let (img, kind) = match site.kind {
+                // Towns show their population once the client asked for their
+                // economy
+                SiteKind::Town => (imgs.coin_ico, match client.site_economy(site.id) {
+                    Some(economy) => format!(
+                        "{}\n{}: {}",
+                        localized_strings.get("hud.map.town"),
+                        localized_strings.get("hud.map.population"),
+                        economy.population
+                    ),
+                    None => localized_strings.get("hud.map.town").to_owned(),
+                }),
+                SiteKind::Dungeon { difficulty } => (
+                    if difficulty > 3 {
+                        imgs.skull_2
+                    } else {
+                        imgs.skull
+                    },
+                    format!(
+                        "{} ({}: {})",
+                        localized_strings.get("hud.map.dungeon"),
+                        localized_strings.get("hud.map.difficulty"),
+                        difficulty
+                    ),
+                ),
+                SiteKind::Castle => (
+                    imgs.protection_ico,
+                    localized_strings.get("hud.map.castle").to_owned(),
+                ),
+            };
+            Marker {
+                wpos: site.wpos,
+                img,
+                label: label(&site.name, kind),
+                site: matches!(site.kind, SiteKind::Town).then_some(site.id),
+            }
+        });
+
+    let pois = client
+        .pois()
+        .iter()
+        .filter(|poi| match poi.kind {
+            PoiKind::Cave => filters.caves,
+        })
+        .map(|poi| {
+            let (img, kind) = match poi.kind {
+                PoiKind::Cave => (
+                    imgs.lantern_bg,
+                    localized_strings.get("hud.map.cave").to_owned(),
+                ),
+            };
+            Marker {
+                wpos: poi.wpos,
+                img,
+                label: label(&poi.name, kind),
+                site: None,
+            }
+        });
+
+    sites.chain(pois).collect()
+}
+
 #[derive(WidgetCommon)]
 pub struct Map<'a> {
     _show: &'a Show,
@@ -78,10 +253,13 @@ impl<'a> Map<'a> {
 
 pub struct State {
     ids: Ids,
+    hovered_site: Option<SiteId>,
 }
 
 pub enum Event {
     MapZoom(f64),
+    MapFilters(MapFilters),
+    RequestSiteEconomy(SiteId),
     Close,
 }
 
@@ -93,6 +271,7 @@ impl<'a> Widget for Map<'a> {
     fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
         State {
             ids: Ids::new(id_gen),
+            hovered_site: None,
         }
     }
 
@@ -149,6 +328,65 @@ impl<'a> Widget for Map<'a> {
             .color(TEXT_COLOR)
             .set(state.ids.qlog_title, ui);
 
+        // Active Quests
+        let quests = self
+            .client
+            .quest_log()
+            .active
+            .iter()
+            .filter_map(|progress| {
+                QuestDef::load(&progress.quest)
+                    .ok()
+                    .map(|def| (def, progress))
+            })
+            .collect::<Vec<_>>();
+        if state.ids.quest_titles.len() < quests.len() {
+            state.update(|s| {
+                let mut id_gen = ui.widget_id_generator();
+                s.ids.quest_titles.resize(quests.len(), &mut id_gen);
+                s.ids.quest_objectives.resize(quests.len(), &mut id_gen);
+            });
+        }
+        let mut above = state.ids.qlog_title;
+        for ((def, progress), (title_id, objectives_id)) in quests.iter().zip(
+            state
+                .ids
+                .quest_titles
+                .iter()
+                .zip(state.ids.quest_objectives.iter()),
+        ) {
+            Text::new(&def.title)
+                .down_from(above, 16.0)
+                .x_place_on(state.ids.qlog_align, position::Place::Start(Some(12.0)))
+                .w(208.0)
+                .font_id(self.fonts.cyri.conrod_id)
+                .font_size(self.fonts.cyri.scale(16))
+                .color(TEXT_COLOR)
+                .set(*title_id, ui);
+            let objectives = def
+                .objectives
+                .iter()
+                .zip(progress.objectives.iter())
+                .map(|(objective, done)| {
+                    format!(
+                        "{} ({}/{})",
+                        objective_text(objective, self.localized_strings),
+                        done,
+                        objective.required()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            Text::new(&objectives)
+                .down_from(*title_id, 4.0)
+                .w(208.0)
+                .font_id(self.fonts.cyri.conrod_id)
+                .font_size(self.fonts.cyri.scale(13))
+                .color(TEXT_COLOR)
+                .set(*objectives_id, ui);
+            above = *objectives_id;
+        }
+
         // X-Button
         if Button::image(self.imgs.close_button)
             .w_h(24.0, 25.0)
@@ -212,6 +450,164 @@ impl<'a> Widget for Map<'a> {
             .source_rectangle(rect_src)
             .set(state.ids.grid, ui);
 
+        // Sites, places of interest and the tracks between them
+        const MAP_SIZE: f64 = 760.0;
+        let filters = self.global_state.settings.gameplay.map_filters;
+        let map_center = ui.xy_of(state.ids.grid).unwrap_or([0.0, 0.0]);
+        let scale = MAP_SIZE / (w_src * TerrainChunkSize::RECT_SIZE.x as f64);
+        let to_map = |wpos: Vec2<i32>| {
+            let pos =
+                (wpos.map(|e| e as f64) - Vec2::from(player_pos).map(|e: f32| e as f64)) * scale;
+            if pos.map(|e| e.abs()).reduce_partial_max() < MAP_SIZE / 2.0 {
+                Some(pos)
+            } else {
+                None
+            }
+        };
+
+        // Tracks leaving the map are cut into the runs which are on it
+        let track_runs = if filters.tracks {
+            let mut runs = Vec::new();
+            for track in self.client.tracks() {
+                let mut run = Vec::new();
+                for pos in track.iter().map(|wpos| to_map(*wpos)) {
+                    match pos {
+                        Some(pos) => run.push([map_center[0] + pos.x, map_center[1] + pos.y]),
+                        None if run.len() > 1 => runs.push(std::mem::take(&mut run)),
+                        None => run.clear(),
+                    }
+                }
+                if run.len() > 1 {
+                    runs.push(run);
+                }
+            }
+            runs
+        } else {
+            Vec::new()
+        };
+        if state.ids.track_lines.len() < track_runs.len() {
+            state.update(|s| {
+                s.ids
+                    .track_lines
+                    .resize(track_runs.len(), &mut ui.widget_id_generator())
+            });
+        }
+        for (run, id) in track_runs.into_iter().zip(state.ids.track_lines.iter()) {
+            PointPath::abs(run)
+                .color(TRACK_COLOR)
+                .thickness(2.0)
+                .parent(state.ids.grid)
+                .graphics_for(state.ids.grid)
+                .set(*id, ui);
+        }
+
+        let markers = markers(self.client, self.imgs, self.localized_strings, &filters)
+            .into_iter()
+            .filter_map(|marker| to_map(marker.wpos).map(|pos| (pos, marker)))
+            .collect::<Vec<_>>();
+        if state.ids.markers.len() < markers.len() {
+            state.update(|s| {
+                s.ids
+                    .markers
+                    .resize(markers.len(), &mut ui.widget_id_generator())
+            });
+        }
+        let mut hovered = None;
+        for ((pos, marker), id) in markers.iter().zip(state.ids.markers.iter()) {
+            Image::new(marker.img)
+                .x_y_position_relative_to(
+                    state.ids.grid,
+                    position::Relative::Scalar(pos.x),
+                    position::Relative::Scalar(pos.y),
+                )
+                .w_h(20.0, 20.0)
+                .floating(true)
+                .set(*id, ui);
+            if ui.widget_input(*id).mouse().is_some() {
+                hovered = Some((*id, marker));
+            }
+        }
+        if let Some((id, marker)) = hovered {
+            Text::new(&marker.label)
+                .up_from(id, 4.0)
+                .font_id(self.fonts.cyri.conrod_id)
+                .font_size(self.fonts.cyri.scale(16))
+                .color(TEXT_COLOR)
+                .floating(true)
+                .set(state.ids.marker_label, ui);
+        }
+        // Ask for the economy of a town whenever it starts being hovered, so
+        // its label is up to date
+        let hovered_site = hovered.and_then(|(_, marker)| marker.site);
+        if hovered_site != state.hovered_site {
+            if let Some(site) = hovered_site {
+                events.push(Event::RequestSiteEconomy(site));
+            }
+            state.update(|s| s.hovered_site = hovered_site);
+        }
+
+        // Map Filters
+        Text::new(self.localized_strings.get("hud.map.filters"))
+            .mid_bottom_with_margin_on(state.ids.qlog_align, 150.0)
+            .font_id(self.fonts.cyri.conrod_id)
+            .font_size(self.fonts.cyri.scale(18))
+            .color(TEXT_COLOR)
+            .set(state.ids.filter_title, ui);
+        let mut new_filters = filters;
+        let toggles = [
+            (
+                &mut new_filters.towns,
+                state.ids.filter_towns,
+                state.ids.filter_towns_txt,
+                "hud.map.town",
+            ),
+            (
+                &mut new_filters.dungeons,
+                state.ids.filter_dungeons,
+                state.ids.filter_dungeons_txt,
+                "hud.map.dungeon",
+            ),
+            (
+                &mut new_filters.castles,
+                state.ids.filter_castles,
+                state.ids.filter_castles_txt,
+                "hud.map.castle",
+            ),
+            (
+                &mut new_filters.caves,
+                state.ids.filter_caves,
+                state.ids.filter_caves_txt,
+                "hud.map.cave",
+            ),
+            (
+                &mut new_filters.tracks,
+                state.ids.filter_tracks,
+                state.ids.filter_tracks_txt,
+                "hud.map.tracks",
+            ),
+        ];
+        let mut above = state.ids.filter_title;
+        for (value, button_id, text_id, key) in toggles.iter_mut() {
+            **value = ToggleButton::new(**value, self.imgs.checkbox, self.imgs.checkbox_checked)
+                .w_h(18.0, 18.0)
+                .down_from(above, 8.0)
+                .x_place_on(state.ids.qlog_align, position::Place::Start(Some(20.0)))
+                .hover_images(self.imgs.checkbox_mo, self.imgs.checkbox_checked_mo)
+                .press_images(self.imgs.checkbox_press, self.imgs.checkbox_checked)
+                .set(*button_id, ui);
+            Text::new(self.localized_strings.get(*key))
+                .right_from(*button_id, 10.0)
+                .font_id(self.fonts.cyri.conrod_id)
+                .font_size(self.fonts.cyri.scale(14))
+                .graphics_for(*button_id)
+                .color(TEXT_COLOR)
+                .set(*text_id, ui);
+            above = *button_id;
+        }
+        if new_filters != filters {
+            events.push(Event::MapFilters(new_filters));
+        }
+
         if let Some(new_val) = ImageSlider::discrete(
             self.global_state.settings.gameplay.map_zoom as i32,
             1,
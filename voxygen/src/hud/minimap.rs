@@ -1,8 +1,12 @@
 use super::{
     img_ids::{Imgs, ImgsRot},
-    Show, TEXT_COLOR, UI_HIGHLIGHT_0, UI_MAIN,
+    map, Show, TEXT_COLOR, UI_HIGHLIGHT_0, UI_MAIN,
+};
+use crate::{
+    i18n::VoxygenLocalization,
+    ui::{fonts::ConrodVoxygenFonts, img_ids},
+    GlobalState,
 };
-use crate::ui::{fonts::ConrodVoxygenFonts, img_ids};
 use client::{self, Client};
 use common::{comp, terrain::TerrainChunkSize, vol::RectVolSize};
 use conrod_core::{
@@ -28,6 +32,8 @@ widget_ids! {
         mmap_east,
         mmap_south,
         mmap_west,
+        markers[],
+        marker_label,
     }
 }
 
@@ -44,9 +50,12 @@ pub struct MiniMap<'a> {
     #[conrod(common_builder)]
     common: widget::CommonBuilder,
     ori: Vec3<f32>,
+    localized_strings: &'a VoxygenLocalization,
+    global_state: &'a GlobalState,
 }
 
 impl<'a> MiniMap<'a> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         show: &'a Show,
         client: &'a Client,
@@ -55,6 +64,8 @@ impl<'a> MiniMap<'a> {
         world_map: &'a (img_ids::Rotations, Vec2<u32>),
         fonts: &'a ConrodVoxygenFonts,
         ori: Vec3<f32>,
+        localized_strings: &'a VoxygenLocalization,
+        global_state: &'a GlobalState,
     ) -> Self {
         Self {
             show,
@@ -65,6 +76,8 @@ impl<'a> MiniMap<'a> {
             fonts,
             common: widget::CommonBuilder::default(),
             ori,
+            localized_strings,
+            global_state,
         }
     }
 }
@@ -222,6 +235,62 @@ impl<'a> Widget for MiniMap<'a> {
                 .parent(ui.window)
                 .set(state.ids.indicator, ui);
 
+            // Sites and places of interest, turned with the map
+            let map_half = map_size.x * SCALE / 2.0;
+            let scale = map_size.x * SCALE / (w_src * TerrainChunkSize::RECT_SIZE.x as f64);
+            let right = Vec2::unit_x().rotated_z(self.ori.x as f64);
+            let up = Vec2::unit_y().rotated_z(self.ori.x as f64);
+            let markers = map::markers(
+                self.client,
+                self.imgs,
+                self.localized_strings,
+                &self.global_state.settings.gameplay.map_filters,
+            )
+            .into_iter()
+            .filter_map(|marker| {
+                let rel = (marker.wpos.map(|e| e as f64)
+                    - Vec2::from(player_pos).map(|e: f32| e as f64))
+                    * scale;
+                let pos = right * rel.x + up * rel.y;
+                if pos.map(|e| e.abs()).reduce_partial_max() < map_half {
+                    Some((pos, marker))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+            if state.ids.markers.len() < markers.len() {
+                state.update(|s| {
+                    s.ids
+                        .markers
+                        .resize(markers.len(), &mut ui.widget_id_generator())
+                });
+            }
+            let mut hovered = None;
+            for ((pos, marker), id) in markers.iter().zip(state.ids.markers.iter()) {
+                Image::new(marker.img)
+                    .x_y_position_relative_to(
+                        state.ids.grid,
+                        position::Relative::Scalar(pos.x),
+                        position::Relative::Scalar(pos.y),
+                    )
+                    .w_h(12.0 * SCALE, 12.0 * SCALE)
+                    .floating(true)
+                    .set(*id, ui);
+                if ui.widget_input(*id).mouse().is_some() {
+                    hovered = Some((*id, &marker.label));
+                }
+            }
+            if let Some((id, label)) = hovered {
+                Text::new(label)
+                    .down_from(id, 4.0)
+                    .font_id(self.fonts.cyri.conrod_id)
+                    .font_size(self.fonts.cyri.scale(14))
+                    .color(TEXT_COLOR)
+                    .floating(true)
+                    .set(state.ids.marker_label, ui);
+            }
+
             // Compass directions
             let dirs = [
                 (Vec2::new(0.0, 1.0), state.ids.mmap_north, "N", true),
@@ -17,6 +17,7 @@ mod skillbar;
 mod slots;
 mod social;
 mod spell;
+mod trade;
 mod util;
 
 pub use hotbar::{SlotContents as HotbarSlotContents, State as HotbarState};
@@ -41,6 +42,7 @@ use settings_window::{SettingsTab, SettingsWindow};
 use skillbar::Skillbar;
 use social::{Social, SocialTab};
 use spell::Spell;
+use trade::Trade;
 
 use crate::{
     ecs::{comp as vcomp, comp::HpFloaterList},
@@ -51,6 +53,7 @@ use crate::{
         camera::{self, Camera},
         lod,
     },
+    settings::MapFilters,
     ui::{fonts::ConrodVoxygenFonts, img_ids::Rotations, slot, Graphic, Ingameable, ScaleMode, Ui},
     window::{Event as WinEvent, FullScreenSettings, GameInput},
     GlobalState,
@@ -63,6 +66,7 @@ use common::{
     span,
     sync::Uid,
     terrain::TerrainChunk,
+    trade::TradeAction,
     vol::RectRasterableVol,
 };
 use conrod_core::{
@@ -244,6 +248,7 @@ widget_ids! {
         small_window,
         social_window,
         crafting_window,
+        trade_window,
         settings_window,
         group_window,
 
@@ -308,6 +313,8 @@ pub enum Event {
     ChangeGamma(f32),
     ChangeAmbiance(f32),
     MapZoom(f64),
+    MapFilters(MapFilters),
+    RequestSiteEconomy(common::trade::SiteId),
     AdjustWindowSize([u16; 2]),
     ChangeFullscreenMode(FullScreenSettings),
     ToggleParticlesEnabled(bool),
@@ -342,6 +349,7 @@ pub enum Event {
     ChangeAutoWalkBehavior(PressBehavior),
     ChangeStopAutoWalkOnInput(bool),
     CraftRecipe(String),
+    TradeAction(TradeAction),
     InviteMember(common::sync::Uid),
     AcceptInvite,
     DeclineInvite,
@@ -399,6 +407,7 @@ pub struct Show {
     intro: bool,
     help: bool,
     crafting: bool,
+    trade: bool,
     debug: bool,
     bag: bool,
     social: bool,
@@ -456,6 +465,15 @@ impl Show {
         }
     }
 
+    fn trade(&mut self, open: bool) {
+        if !self.esc_menu {
+            self.trade = open;
+            self.bag = open;
+            self.map = false;
+            self.want_grab = !open;
+        }
+    }
+
     fn spell(&mut self, open: bool) {
         if !self.esc_menu {
             self.social = false;
@@ -662,6 +680,7 @@ impl Hud {
                 open_windows: Windows::None,
                 map: false,
                 crafting: false,
+                trade: false,
                 ui: true,
                 social: false,
                 spell: false,
@@ -1769,6 +1788,8 @@ impl Hud {
             &self.world_map,
             &self.fonts,
             camera.get_orientation(),
+            &self.voxygen_i18n,
+            &global_state,
         )
         .set(self.ids.minimap, ui_widgets)
         {
@@ -1817,6 +1838,7 @@ impl Hud {
         let stats = ecs.read_storage::<comp::Stats>();
         let loadouts = ecs.read_storage::<comp::Loadout>();
         let energies = ecs.read_storage::<comp::Energy>();
+        let buffs = ecs.read_storage::<comp::Buffs>();
         let character_states = ecs.read_storage::<comp::CharacterState>();
         let controllers = ecs.read_storage::<comp::Controller>();
         let inventories = ecs.read_storage::<comp::Inventory>();
@@ -1844,6 +1866,7 @@ impl Hud {
                 &stats,
                 &loadout,
                 &energy,
+                buffs.get(entity),
                 &character_state,
                 self.pulse,
                 &controller,
@@ -1893,6 +1916,44 @@ impl Hud {
             }
         }
 
+        // Trade
+        match (client.pending_trade(), self.show.trade) {
+            (Some(_), false) => self.show.trade(true),
+            (None, true) => {
+                self.show.trade(false);
+                if !self.show.social {
+                    self.show.want_grab = true;
+                    self.force_ungrab = false;
+                } else {
+                    self.force_ungrab = true
+                };
+            },
+            _ => {},
+        }
+        if let (Some(trade), true) = (client.pending_trade(), self.show.trade) {
+            if let Some(inventory) = inventories.get(entity) {
+                for event in Trade::new(
+                    client,
+                    trade,
+                    &self.imgs,
+                    &self.fonts,
+                    &self.voxygen_i18n,
+                    &self.rot_imgs,
+                    tooltip_manager,
+                    &self.item_imgs,
+                    &inventory,
+                )
+                .set(self.ids.trade_window, ui_widgets)
+                {
+                    match event {
+                        trade::Event::TradeAction(action) => {
+                            events.push(Event::TradeAction(action));
+                        },
+                    }
+                }
+            }
+        }
+
         // Don't put NPC messages in chat box.
         self.new_messages
             .retain(|m| !matches!(m.chat_type, comp::ChatType::Npc(_, _)));
@@ -2184,6 +2245,12 @@ impl Hud {
                     map::Event::MapZoom(map_zoom) => {
                         events.push(Event::MapZoom(map_zoom));
                     },
+                    map::Event::MapFilters(map_filters) => {
+                        events.push(Event::MapFilters(map_filters));
+                    },
+                    map::Event::RequestSiteEconomy(site) => {
+                        events.push(Event::RequestSiteEconomy(site));
+                    },
                 }
             }
         }
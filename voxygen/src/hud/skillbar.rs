@@ -20,7 +20,7 @@ use common::comp::{
         tool::{Tool, ToolKind},
         Hands, ItemKind,
     },
-    CharacterState, ControllerInputs, Energy, Inventory, Loadout, Stats,
+    BuffKind, Buffs, CharacterState, ControllerInputs, Energy, Inventory, Loadout, Stats,
 };
 use conrod_core::{
     color,
@@ -106,6 +106,8 @@ widget_ids! {
         level_message_bg,
         death_bg,
         hurt_bg,
+        buffs_text,
+        debuffs_text,
     }
 }
 
@@ -124,6 +126,7 @@ pub struct Skillbar<'a> {
     stats: &'a Stats,
     loadout: &'a Loadout,
     energy: &'a Energy,
+    buffs: Option<&'a Buffs>,
     character_state: &'a CharacterState,
     controller: &'a ControllerInputs,
     inventory: &'a Inventory,
@@ -149,6 +152,7 @@ impl<'a> Skillbar<'a> {
         stats: &'a Stats,
         loadout: &'a Loadout,
         energy: &'a Energy,
+        buffs: Option<&'a Buffs>,
         character_state: &'a CharacterState,
         pulse: f32,
         controller: &'a ControllerInputs,
@@ -168,6 +172,7 @@ impl<'a> Skillbar<'a> {
             stats,
             loadout,
             energy,
+            buffs,
             current_resource: ResourceType::Mana,
             common: widget::CommonBuilder::default(),
             character_state,
@@ -1279,11 +1284,55 @@ impl<'a> Widget for Skillbar<'a> {
                 .color(TEXT_COLOR)
                 .set(state.ids.energy_text, ui);
         }
-    }
-
-    // Buffs
-    // Add debuff slots above the health bar
-    // Add buff slots above the mana bar
 
-    // Debuffs
+        // Buffs
+        // List debuffs above the health bar and buffs above the energy bar
+        if let Some(buffs) = self.buffs {
+            let (buff_lines, debuff_lines): (Vec<_>, Vec<_>) = buffs
+                .buffs
+                .iter()
+                .map(|buff| {
+                    let name = localized_strings.get(match buff.kind {
+                        BuffKind::Regeneration => "hud.buff.regeneration",
+                        BuffKind::Poisoned => "hud.buff.poisoned",
+                        BuffKind::Burning => "hud.buff.burning",
+                        BuffKind::Slowed => "hud.buff.slowed",
+                        BuffKind::Protected => "hud.buff.protected",
+                    });
+                    let line = match buff.time {
+                        Some(time) => format!("{} {}s", name, time.as_secs() + 1),
+                        None => name.to_string(),
+                    };
+                    (buff.kind.is_buff(), line)
+                })
+                .partition(|(is_buff, _)| *is_buff);
+            let join = |lines: Vec<(bool, String)>| {
+                lines
+                    .into_iter()
+                    .map(|(_, line)| line)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            if !debuff_lines.is_empty() {
+                Text::new(&join(debuff_lines))
+                    .up_from(state.ids.healthbar_bg, 4.0 * scale)
+                    .align_middle_x_of(state.ids.healthbar_bg)
+                    .center_justify()
+                    .font_size(self.fonts.cyri.scale(12))
+                    .font_id(self.fonts.cyri.conrod_id)
+                    .color(CRITICAL_HP_COLOR)
+                    .set(state.ids.debuffs_text, ui);
+            }
+            if !buff_lines.is_empty() {
+                Text::new(&join(buff_lines))
+                    .up_from(state.ids.energybar_bg, 4.0 * scale)
+                    .align_middle_x_of(state.ids.energybar_bg)
+                    .center_justify()
+                    .font_size(self.fonts.cyri.scale(12))
+                    .font_id(self.fonts.cyri.conrod_id)
+                    .color(HP_COLOR)
+                    .set(state.ids.buffs_text, ui);
+            }
+        }
+    }
 }
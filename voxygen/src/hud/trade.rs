@@ -0,0 +1,408 @@
+use super::{
+    img_ids::{Imgs, ImgsRot},
+    item_imgs::ItemImgs,
+    TEXT_COLOR, TEXT_GRAY_COLOR, UI_HIGHLIGHT_0, UI_MAIN,
+};
+use crate::{
+    hud::get_quality_col,
+    i18n::VoxygenLocalization,
+    ui::{fonts::ConrodVoxygenFonts, ImageFrame, Tooltip, TooltipManager, Tooltipable},
+};
+use client::{self, Client, ClientTrade};
+use common::{
+    comp::{
+        item::{ItemDesc, Quality},
+        Inventory, Item,
+    },
+    trade::{TradeAction, TradePhase},
+};
+use conrod_core::{
+    color,
+    widget::{self, Button, Image, Rectangle, Scrollbar, Text},
+    widget_ids, Color, Colorable, Labelable, Positionable, Sizeable, Widget, WidgetCommon,
+};
+
+widget_ids! {
+    pub struct Ids {
+        window,
+        window_frame,
+        close,
+        title_main,
+        title_ours,
+        align_ours,
+        scrollbar_ours,
+        title_theirs,
+        align_theirs,
+        scrollbar_theirs,
+        phase_txt,
+        accepted_txt,
+        btn_accept,
+        btn_decline,
+        item_frames[],
+        item_imgs[],
+        item_texts[],
+        item_removes[],
+    }
+}
+
+pub enum Event {
+    TradeAction(TradeAction),
+}
+
+#[derive(WidgetCommon)]
+pub struct Trade<'a> {
+    client: &'a Client,
+    trade: &'a ClientTrade,
+    imgs: &'a Imgs,
+    fonts: &'a ConrodVoxygenFonts,
+    localized_strings: &'a std::sync::Arc<VoxygenLocalization>,
+    rot_imgs: &'a ImgsRot,
+    tooltip_manager: &'a mut TooltipManager,
+    item_imgs: &'a ItemImgs,
+    inventory: &'a Inventory,
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+}
+#[allow(clippy::too_many_arguments)]
+impl<'a> Trade<'a> {
+    pub fn new(
+        client: &'a Client,
+        trade: &'a ClientTrade,
+        imgs: &'a Imgs,
+        fonts: &'a ConrodVoxygenFonts,
+        localized_strings: &'a std::sync::Arc<VoxygenLocalization>,
+        rot_imgs: &'a ImgsRot,
+        tooltip_manager: &'a mut TooltipManager,
+        item_imgs: &'a ItemImgs,
+        inventory: &'a Inventory,
+    ) -> Self {
+        Self {
+            client,
+            trade,
+            imgs,
+            fonts,
+            localized_strings,
+            rot_imgs,
+            tooltip_manager,
+            item_imgs,
+            inventory,
+            common: widget::CommonBuilder::default(),
+        }
+    }
+}
+
+pub struct State {
+    ids: Ids,
+}
+
+impl<'a> Widget for Trade<'a> {
+    type Event = Vec<Event>;
+    type State = State;
+    type Style = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        State {
+            ids: Ids::new(id_gen),
+        }
+    }
+
+    #[allow(clippy::unused_unit)] // TODO: Pending review in #587
+    fn style(&self) -> Self::Style { () }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { state, ui, .. } = args;
+
+        let mut events = Vec::new();
+
+        let trade = &self.trade.trade;
+        let ours = match self.client.uid().and_then(|uid| trade.which_party(uid)) {
+            Some(ours) => ours,
+            None => return events,
+        };
+        // Prices are only known when trading with a merchant, which lets the
+        // client pick from its stock
+        let is_merchant = self.trade.prices.is_some();
+        let can_change = trade.phase == TradePhase::Mutate;
+
+        // Our whole inventory, then what the counterparty shows
+        let rows: Vec<(usize, &Item, bool)> = self
+            .inventory
+            .slots()
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, item)| Some((slot, item.as_ref()?, true)))
+            .chain(
+                self.trade
+                    .counterparty_items
+                    .iter()
+                    .map(|(slot, item)| (*slot, item, false)),
+            )
+            .collect();
+        if state.ids.item_frames.len() < rows.len() {
+            state.update(|state| {
+                let id_gen = &mut ui.widget_id_generator();
+                state.ids.item_frames.resize(rows.len(), id_gen);
+                state.ids.item_imgs.resize(rows.len(), id_gen);
+                state.ids.item_texts.resize(rows.len(), id_gen);
+                state.ids.item_removes.resize(rows.len(), id_gen);
+            });
+        }
+        let ids = &state.ids;
+
+        // Tooltips
+        let item_tooltip = Tooltip::new({
+            let edge = &self.rot_imgs.tt_side;
+            let corner = &self.rot_imgs.tt_corner;
+            ImageFrame::new(
+                [edge.cw180, edge.none, edge.cw270, edge.cw90],
+                [corner.none, corner.cw270, corner.cw90, corner.cw180],
+                Color::Rgba(0.08, 0.07, 0.04, 1.0),
+                5.0,
+            )
+        })
+        .title_font_size(self.fonts.cyri.scale(15))
+        .parent(ui.window)
+        .desc_font_size(self.fonts.cyri.scale(12))
+        .font_id(self.fonts.cyri.conrod_id)
+        .desc_text_color(TEXT_COLOR);
+
+        Image::new(self.imgs.crafting_window)
+            .bottom_right_with_margins_on(ui.window, 308.0, 450.0)
+            .color(Some(UI_MAIN))
+            .w_h(422.0, 460.0)
+            .set(ids.window, ui);
+        Image::new(self.imgs.crafting_frame)
+            .middle_of(ids.window)
+            .color(Some(UI_HIGHLIGHT_0))
+            .w_h(422.0, 460.0)
+            .set(ids.window_frame, ui);
+        // Closing the window declines the trade
+        if Button::image(self.imgs.close_button)
+            .w_h(24.0, 25.0)
+            .hover_image(self.imgs.close_button_hover)
+            .press_image(self.imgs.close_button_press)
+            .top_right_with_margins_on(ids.window, 0.0, 0.0)
+            .set(ids.close, ui)
+            .was_clicked()
+        {
+            events.push(Event::TradeAction(TradeAction::Decline));
+        }
+
+        // Title
+        Text::new(&self.localized_strings.get("hud.trade"))
+            .mid_top_with_margin_on(ids.window_frame, 9.0)
+            .font_id(self.fonts.cyri.conrod_id)
+            .font_size(self.fonts.cyri.scale(20))
+            .color(TEXT_COLOR)
+            .set(ids.title_main, ui);
+
+        // Alignment
+        Rectangle::fill_with([203.0, 300.0], color::TRANSPARENT)
+            .top_left_with_margins_on(ids.window_frame, 74.0, 5.0)
+            .scroll_kids_vertically()
+            .set(ids.align_ours, ui);
+        Rectangle::fill_with([203.0, 300.0], color::TRANSPARENT)
+            .top_right_with_margins_on(ids.window_frame, 74.0, 5.0)
+            .scroll_kids_vertically()
+            .set(ids.align_theirs, ui);
+
+        // Items of both parties, with the quantity offered
+        let mut previous: [Option<widget::Id>; 2] = [None, None];
+        for (i, (slot, item, is_ours)) in rows.into_iter().enumerate() {
+            let (side, offerer) = if is_ours { (0, ours) } else { (1, 1 - ours) };
+            let offered = trade.offers[offerer].get(&slot).copied().unwrap_or(0);
+            // Only merchants let the client pick from their items
+            let can_pick = can_change && (is_ours || is_merchant);
+
+            let quality_col_img = match item.quality() {
+                Quality::Low => self.imgs.inv_slot_grey,
+                Quality::Common => self.imgs.inv_slot,
+                Quality::Moderate => self.imgs.inv_slot_green,
+                Quality::High => self.imgs.inv_slot_blue,
+                Quality::Epic => self.imgs.inv_slot_purple,
+                Quality::Legendary => self.imgs.inv_slot_gold,
+                Quality::Artifact => self.imgs.inv_slot_orange,
+                _ => self.imgs.inv_slot_red,
+            };
+            let frame = Image::new(quality_col_img).w_h(30.0, 30.0);
+            let frame = match previous[side] {
+                Some(previous) => frame.down_from(previous, 5.0),
+                None if is_ours => frame.top_left_with_margins_on(ids.align_ours, 5.0, 5.0),
+                None => frame.top_left_with_margins_on(ids.align_theirs, 5.0, 5.0),
+            };
+            frame.set(ids.item_frames[i], ui);
+            previous[side] = Some(ids.item_frames[i]);
+
+            // Clicking the item offers or asks for one more of it
+            let (title, desc) = super::util::item_text(item);
+            if Button::image(self.item_imgs.img_id_or_not_found_img(item.kind().into()))
+                .w_h(27.0, 27.0)
+                .middle_of(ids.item_frames[i])
+                .with_tooltip(
+                    self.tooltip_manager,
+                    title,
+                    &*desc,
+                    &item_tooltip,
+                    get_quality_col(item),
+                )
+                .set(ids.item_imgs[i], ui)
+                .was_clicked()
+                && can_pick
+                && offered < item.amount()
+            {
+                events.push(Event::TradeAction(TradeAction::AddItem {
+                    slot,
+                    quantity: 1,
+                    ours: is_ours,
+                }));
+            }
+
+            let mut text = format!("{}\n{}/{}", item.name(), offered, item.amount());
+            if let Some(prices) = &self.trade.prices {
+                // The merchant sells its own items and buys ours
+                let price = if is_ours {
+                    prices.buy_price(item)
+                } else {
+                    prices.sell_price(item)
+                };
+                text.push_str("  ");
+                text.push_str(
+                    &self
+                        .localized_strings
+                        .get("hud.trade.price")
+                        .replace("{price}", &format!("{:.1}", price)),
+                );
+            }
+            Text::new(&text)
+                .right_from(ids.item_frames[i], 5.0)
+                .font_id(self.fonts.cyri.conrod_id)
+                .font_size(self.fonts.cyri.scale(12))
+                .color(if offered > 0 {
+                    TEXT_COLOR
+                } else {
+                    TEXT_GRAY_COLOR
+                })
+                .set(ids.item_texts[i], ui);
+
+            if can_pick
+                && offered > 0
+                && Button::image(self.imgs.button)
+                    .w_h(20.0, 20.0)
+                    .hover_image(self.imgs.button_hover)
+                    .press_image(self.imgs.button_press)
+                    .label("-")
+                    .label_color(TEXT_COLOR)
+                    .label_font_size(self.fonts.cyri.scale(12))
+                    .label_font_id(self.fonts.cyri.conrod_id)
+                    .right_from(ids.item_frames[i], 150.0)
+                    .set(ids.item_removes[i], ui)
+                    .was_clicked()
+            {
+                events.push(Event::TradeAction(TradeAction::RemoveItem {
+                    slot,
+                    quantity: 1,
+                    ours: is_ours,
+                }));
+            }
+        }
+
+        // Scrollbars
+        Scrollbar::y_axis(ids.align_ours)
+            .thickness(5.0)
+            .rgba(0.33, 0.33, 0.33, 1.0)
+            .set(ids.scrollbar_ours, ui);
+        Scrollbar::y_axis(ids.align_theirs)
+            .thickness(5.0)
+            .rgba(0.33, 0.33, 0.33, 1.0)
+            .set(ids.scrollbar_theirs, ui);
+
+        // Titles of both offers
+        Text::new(&self.localized_strings.get("hud.trade.our_offer"))
+            .mid_top_with_margin_on(ids.align_ours, -22.0)
+            .font_id(self.fonts.cyri.conrod_id)
+            .font_size(self.fonts.cyri.scale(14))
+            .color(TEXT_COLOR)
+            .parent(ids.window)
+            .set(ids.title_ours, ui);
+        Text::new(&self.localized_strings.get(if is_merchant {
+            "hud.trade.merchant_stock"
+        } else {
+            "hud.trade.their_offer"
+        }))
+        .mid_top_with_margin_on(ids.align_theirs, -22.0)
+        .font_id(self.fonts.cyri.conrod_id)
+        .font_size(self.fonts.cyri.scale(14))
+        .color(TEXT_COLOR)
+        .parent(ids.window)
+        .set(ids.title_theirs, ui);
+
+        // Phase of the trade and whether the counterparty accepted it
+        Text::new(&self.localized_strings.get(match trade.phase {
+            TradePhase::Mutate => "hud.trade.phase.mutate",
+            TradePhase::Review => "hud.trade.phase.review",
+            TradePhase::Complete => "hud.trade.phase.complete",
+        }))
+        .down_from(ids.align_ours, 10.0)
+        .font_id(self.fonts.cyri.conrod_id)
+        .font_size(self.fonts.cyri.scale(14))
+        .color(TEXT_COLOR)
+        .set(ids.phase_txt, ui);
+        if trade.accept_flags[1 - ours] {
+            Text::new(&self.localized_strings.get("hud.trade.has_accepted"))
+                .down_from(ids.phase_txt, 5.0)
+                .font_id(self.fonts.cyri.conrod_id)
+                .font_size(self.fonts.cyri.scale(12))
+                .color(TEXT_COLOR)
+                .set(ids.accepted_txt, ui);
+        }
+
+        // Accepting locks the offers in, accepting again completes the trade
+        let can_accept = !trade.accept_flags[ours] && trade.phase != TradePhase::Complete;
+        if Button::image(self.imgs.button)
+            .w_h(105.0, 25.0)
+            .hover_image(
+                can_accept
+                    .then_some(self.imgs.button_hover)
+                    .unwrap_or(self.imgs.button),
+            )
+            .press_image(
+                can_accept
+                    .then_some(self.imgs.button_press)
+                    .unwrap_or(self.imgs.button),
+            )
+            .label(&self.localized_strings.get(if can_accept {
+                "hud.trade.accept"
+            } else {
+                "hud.trade.waiting"
+            }))
+            .label_y(conrod_core::position::Relative::Scalar(1.0))
+            .label_color(can_accept.then_some(TEXT_COLOR).unwrap_or(TEXT_GRAY_COLOR))
+            .label_font_size(self.fonts.cyri.scale(12))
+            .label_font_id(self.fonts.cyri.conrod_id)
+            .image_color(can_accept.then_some(TEXT_COLOR).unwrap_or(TEXT_GRAY_COLOR))
+            .bottom_left_with_margins_on(ids.window_frame, 10.0, 60.0)
+            .set(ids.btn_accept, ui)
+            .was_clicked()
+            && can_accept
+        {
+            events.push(Event::TradeAction(TradeAction::Accept(trade.phase)));
+        }
+        if Button::image(self.imgs.button)
+            .w_h(105.0, 25.0)
+            .hover_image(self.imgs.button_hover)
+            .press_image(self.imgs.button_press)
+            .label(&self.localized_strings.get("hud.trade.decline"))
+            .label_y(conrod_core::position::Relative::Scalar(1.0))
+            .label_color(TEXT_COLOR)
+            .label_font_size(self.fonts.cyri.scale(12))
+            .label_font_id(self.fonts.cyri.conrod_id)
+            .bottom_right_with_margins_on(ids.window_frame, 10.0, 60.0)
+            .set(ids.btn_decline, ui)
+            .was_clicked()
+        {
+            events.push(Event::TradeAction(TradeAction::Decline));
+        }
+
+        events
+    }
+}
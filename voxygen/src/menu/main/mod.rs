@@ -125,11 +125,20 @@ impl PlayState for MainMenuState {
                             client::Error::NotOnWhitelist => {
                                 localized_strings.get("main.login.not_on_whitelist").into()
                             },
-                            client::Error::Banned(reason) => format!(
-                                "{}: {}",
-                                localized_strings.get("main.login.banned"),
-                                reason
-                            ),
+                            client::Error::Banned { reason, remaining } => match remaining {
+                                Some(remaining) => format!(
+                                    "{}: {}\n{} {}",
+                                    localized_strings.get("main.login.banned"),
+                                    reason,
+                                    localized_strings.get("main.login.ban_expires_in"),
+                                    format_ban_duration(remaining),
+                                ),
+                                None => format!(
+                                    "{}: {}",
+                                    localized_strings.get("main.login.banned"),
+                                    reason
+                                ),
+                            },
                             client::Error::InvalidCharacter => {
                                 localized_strings.get("main.login.invalid_character").into()
                             },
@@ -151,6 +160,9 @@ impl PlayState for MainMenuState {
                             client::Error::Other(e) => {
                                 format!("{}: {}", localized_strings.get("common.error"), e)
                             },
+                            client::Error::Replay(e) => {
+                                format!("{}: {:?}", localized_strings.get("common.error"), e)
+                            },
                             client::Error::AuthClientError(e) => match e {
                                 client::AuthClientError::JsonError(e) => format!(
                                     "{}: {}",
@@ -278,6 +290,17 @@ impl PlayState for MainMenuState {
     }
 }
 
+/// e.g. `2d 3h 15m`, rounded up to whole minutes
+fn format_ban_duration(duration: std::time::Duration) -> String {
+    let minutes = (duration.as_secs() + 59) / 60;
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h {}m", days, hours, minutes),
+    }
+}
+
 fn attempt_login(
     global_state: &mut GlobalState,
     username: String,
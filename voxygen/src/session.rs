@@ -21,8 +21,11 @@ use common::{
     },
     event::EventBus,
     outcome::Outcome,
+    quest::QuestDef,
     span,
+    sync::Uid,
     terrain::{Block, BlockKind},
+    trade::TradeResult,
     util::Dir,
     vol::ReadVol,
 };
@@ -31,6 +34,11 @@ use std::{cell::RefCell, rc::Rc, sync::Arc, time::Duration};
 use tracing::{error, info};
 use vek::*;
 
+/// The title of a quest, falling back to its asset specifier
+fn quest_title(quest: &str) -> String {
+    QuestDef::load(quest).map_or_else(|_| quest.to_owned(), |def| def.title.clone())
+}
+
 /// The action to perform after a tick
 enum TickAction {
     // Continue executing
@@ -178,6 +186,35 @@ impl SessionState {
                     global_state.settings.save_to_file_warn();
                 },
                 client::Event::Outcome(outcome) => outcomes.push(outcome),
+                client::Event::QuestStarted(quest) => {
+                    self.hud.new_message(ChatMsg {
+                        message: self
+                            .voxygen_i18n
+                            .get("hud.quest.started")
+                            .replace("{quest}", &quest_title(&quest)),
+                        chat_type: ChatType::Meta,
+                    });
+                },
+                client::Event::QuestCompleted(quest) => {
+                    self.hud.new_message(ChatMsg {
+                        message: self
+                            .voxygen_i18n
+                            .get("hud.quest.completed")
+                            .replace("{quest}", &quest_title(&quest)),
+                        chat_type: ChatType::Meta,
+                    });
+                },
+                client::Event::TradeComplete(result) => {
+                    let key = match result {
+                        TradeResult::Completed => "hud.trade.result.completed",
+                        TradeResult::Declined => "hud.trade.result.declined",
+                        TradeResult::NotEnoughSpace => "hud.trade.result.nospace",
+                    };
+                    self.hud.new_message(ChatMsg {
+                        message: self.voxygen_i18n.get(key).to_string(),
+                        chat_type: ChatType::Meta,
+                    });
+                },
             }
         }
 
@@ -531,7 +568,21 @@ impl PlayState for SessionState {
                                 });
 
                                 if let Some(entity) = entity {
-                                    client.pick_up(entity);
+                                    // Talk to NPCs, pick up everything else
+                                    let npc = {
+                                        let ecs = client.state().ecs();
+                                        if ecs.read_storage::<comp::Item>().contains(entity) {
+                                            None
+                                        } else {
+                                            ecs.read_storage::<comp::Body>()
+                                                .get(entity)
+                                                .and(ecs.read_storage::<Uid>().get(entity).copied())
+                                        }
+                                    };
+                                    match npc {
+                                        Some(uid) => client.talk(uid),
+                                        None => client.pick_up(entity),
+                                    }
                                 }
                             }
                         }
@@ -581,16 +632,27 @@ impl PlayState for SessionState {
                                 self.target_entity.map(|e| (e, std::time::Instant::now()));
                         }
                     },
+                    Event::InputUpdate(GameInput::Trade, true) => {
+                        // Trade with merchants right away, invite other players
+                        let mut client = self.client.borrow_mut();
+                        let target = self
+                            .target_entity
+                            .or_else(|| self.selected_entity.map(|(e, _)| e))
+                            .and_then(|e| client.state().read_component_copied::<Uid>(e));
+                        if let Some(uid) = target {
+                            client.initiate_trade(uid);
+                        }
+                    },
                     Event::InputUpdate(GameInput::AcceptGroupInvite, true) => {
                         let mut client = self.client.borrow_mut();
-                        if client.group_invite().is_some() {
-                            client.accept_group_invite();
+                        if client.invite().is_some() {
+                            client.accept_invite();
                         }
                     },
                     Event::InputUpdate(GameInput::DeclineGroupInvite, true) => {
                         let mut client = self.client.borrow_mut();
-                        if client.group_invite().is_some() {
-                            client.decline_group_invite();
+                        if client.invite().is_some() {
+                            client.decline_invite();
                         }
                     },
                     Event::AnalogGameInput(input) => match input {
@@ -960,6 +1022,10 @@ impl PlayState for SessionState {
                         global_state.settings.gameplay.map_zoom = map_zoom;
                         global_state.settings.save_to_file_warn();
                     },
+                    HudEvent::MapFilters(map_filters) => {
+                        global_state.settings.gameplay.map_filters = map_filters;
+                        global_state.settings.save_to_file_warn();
+                    },
                     HudEvent::ChangeGamma(new_gamma) => {
                         global_state.settings.graphics.gamma = new_gamma;
                         global_state.settings.save_to_file_warn();
@@ -1024,14 +1090,20 @@ impl PlayState for SessionState {
                     HudEvent::CraftRecipe(r) => {
                         self.client.borrow_mut().craft_recipe(&r);
                     },
+                    HudEvent::RequestSiteEconomy(site) => {
+                        self.client.borrow_mut().request_site_economy(site);
+                    },
+                    HudEvent::TradeAction(action) => {
+                        self.client.borrow_mut().perform_trade_action(action);
+                    },
                     HudEvent::InviteMember(uid) => {
                         self.client.borrow_mut().send_group_invite(uid);
                     },
                     HudEvent::AcceptInvite => {
-                        self.client.borrow_mut().accept_group_invite();
+                        self.client.borrow_mut().accept_invite();
                     },
                     HudEvent::DeclineInvite => {
-                        self.client.borrow_mut().decline_group_invite();
+                        self.client.borrow_mut().decline_invite();
                     },
                     HudEvent::KickMember(uid) => {
                         self.client.borrow_mut().kick_from_group(uid);
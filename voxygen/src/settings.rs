@@ -172,6 +172,7 @@ impl ControlSettings {
             GameInput::Slot10 => KeyMouse::Key(VirtualKeyCode::Q),
             GameInput::SwapLoadout => KeyMouse::Key(VirtualKeyCode::LAlt),
             GameInput::Select => KeyMouse::Key(VirtualKeyCode::Y),
+            GameInput::Trade => KeyMouse::Key(VirtualKeyCode::R),
             GameInput::AcceptGroupInvite => KeyMouse::Key(VirtualKeyCode::U),
             GameInput::DeclineGroupInvite => KeyMouse::Key(VirtualKeyCode::I),
         }
@@ -242,6 +243,7 @@ impl Default for ControlSettings {
             GameInput::Slot10,
             GameInput::SwapLoadout,
             GameInput::Select,
+            GameInput::Trade,
             GameInput::AcceptGroupInvite,
             GameInput::DeclineGroupInvite,
         ];
@@ -513,6 +515,7 @@ pub struct GameplaySettings {
     pub auto_walk_behavior: PressBehavior,
     pub stop_auto_walk_on_input: bool,
     pub map_zoom: f64,
+    pub map_filters: MapFilters,
     pub loading_tips: bool,
 }
 
@@ -543,11 +546,36 @@ impl Default for GameplaySettings {
             auto_walk_behavior: PressBehavior::Toggle,
             stop_auto_walk_on_input: true,
             map_zoom: 4.0,
+            map_filters: MapFilters::default(),
             loading_tips: true,
         }
     }
 }
 
+/// `MapFilters` stores which kinds of markers are shown on the map and the
+/// minimap.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MapFilters {
+    pub towns: bool,
+    pub dungeons: bool,
+    pub castles: bool,
+    pub caves: bool,
+    pub tracks: bool,
+}
+
+impl Default for MapFilters {
+    fn default() -> Self {
+        Self {
+            towns: true,
+            dungeons: true,
+            castles: true,
+            caves: true,
+            tracks: true,
+        }
+    }
+}
+
 /// `NetworkingSettings` stores server and networking settings.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
@@ -73,6 +73,7 @@ pub enum GameInput {
     AutoWalk,
     CycleCamera,
     Select,
+    Trade,
     AcceptGroupInvite,
     DeclineGroupInvite,
 }
@@ -134,6 +135,7 @@ impl GameInput {
             GameInput::Slot10 => "gameinput.slot10",
             GameInput::SwapLoadout => "gameinput.swaploadout",
             GameInput::Select => "gameinput.select",
+            GameInput::Trade => "gameinput.trade",
             GameInput::AcceptGroupInvite => "gameinput.acceptgroupinvite",
             GameInput::DeclineGroupInvite => "gameinput.declinegroupinvite",
         }
@@ -191,6 +193,7 @@ impl GameInput {
             GameInput::Slot9,
             GameInput::Slot10,
             GameInput::SwapLoadout,
+            GameInput::Trade,
         ]
         .iter()
         .copied()
@@ -5,7 +5,7 @@ mod econ;
 use self::{Occupation::*, Stock::*};
 use crate::{
     config::CONFIG,
-    sim::WorldSim,
+    sim::{generate_name, WorldSim},
     site::{Castle, Dungeon, Settlement, Site as WorldSite},
     util::{attempt, seed_expan, MapVec, CARDINALS, NEIGHBORS},
     Index,
@@ -45,6 +45,8 @@ pub struct Civs {
     places: Store<Place>,
 
     tracks: Store<Track>,
+    /// The chunks each cave runs through, from one entrance to the other
+    caves: Vec<Vec<Vec2<i32>>>,
     /// We use this hasher (FxHasher64) because
     /// (1) we don't care about DDOS attacks (ruling out SipHash);
     /// (2) we care about determinism across computers (ruling out AAHash);
@@ -198,7 +200,7 @@ impl Civs {
                 });
 
             let mut rng = ctx.reseed().rng;
-            let site = index.sites.insert(match &sim_site.kind {
+            let site = match &sim_site.kind {
                 SiteKind::Settlement => {
                     WorldSite::settlement(Settlement::generate(wpos, Some(ctx.sim), &mut rng))
                 },
@@ -208,7 +210,9 @@ impl Civs {
                 SiteKind::Castle => {
                     WorldSite::castle(Castle::generate(wpos, Some(ctx.sim), &mut rng))
                 },
-            });
+            };
+            // Named once it's generated, so that the name doesn't change the site
+            let site = index.sites.insert(site.with_name(generate_name(&mut rng)));
             let site_ref = &index.sites[site];
 
             let radius_chunks =
@@ -229,7 +233,7 @@ impl Civs {
     }
 
     // TODO: Move this
-    fn generate_cave(&self, ctx: &mut GenCtx<impl Rng>) {
+    fn generate_cave(&mut self, ctx: &mut GenCtx<impl Rng>) {
         let mut pos = ctx
             .sim
             .get_size()
@@ -290,12 +294,30 @@ impl Civs {
                 chunk.spawn_rate = 0.0;
             }
         }
+
+        self.caves
+            .push(path.into_iter().map(|(pos, _)| pos).collect());
     }
 
     pub fn place(&self, id: Id<Place>) -> &Place { self.places.get(id) }
 
     pub fn sites(&self) -> impl Iterator<Item = &Site> + '_ { self.sites.values() }
 
+    /// Return an iterator over the chunks each cave runs through
+    pub fn caves(&self) -> impl Iterator<Item = &[Vec2<i32>]> + '_ {
+        self.caves.iter().map(|cave| cave.as_slice())
+    }
+
+    /// Return an iterator over the tracks between sites, with the sites they
+    /// lead from and to. Track paths are made of chunk positions.
+    pub fn tracks(&self) -> impl Iterator<Item = (Id<Site>, Id<Site>, &Path<Vec2<i32>>)> + '_ {
+        self.track_map.iter().flat_map(move |(a, dests)| {
+            dests
+                .iter()
+                .map(move |(b, track)| (*a, *b, &self.tracks.get(*track).path))
+        })
+    }
+
     #[allow(dead_code)]
     #[allow(clippy::print_literal)] // TODO: Pending review in #587
     fn display_info(&self) {
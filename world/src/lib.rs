@@ -38,7 +38,10 @@ use crate::{
 use common::{
     comp::{self, bird_medium, quadruped_low, quadruped_medium, quadruped_small},
     generation::{ChunkSupplement, EntityInfo},
-    msg::WorldMapMsg,
+    msg::{
+        world_msg::{self, PoiInfo, PoiKind, SiteInfo},
+        WorldMapMsg,
+    },
     terrain::{Block, BlockKind, SpriteKind, TerrainChunk, TerrainChunkMeta, TerrainChunkSize},
     vol::{ReadVol, RectVolSize, WriteVol},
 };
@@ -87,7 +90,57 @@ impl World {
         // TODO
     }
 
-    pub fn get_map_data(&self, index: IndexRef) -> WorldMapMsg { self.sim.get_map(index) }
+    pub fn get_map_data(&self, index: IndexRef) -> WorldMapMsg {
+        let chunk_wpos = |chunk_pos: Vec2<i32>| {
+            chunk_pos.map2(TerrainChunkSize::RECT_SIZE, |e, sz: u32| {
+                e * sz as i32 + sz as i32 / 2
+            })
+        };
+
+        WorldMapMsg {
+            sites: index
+                .sites
+                .iter()
+                .map(|(id, site)| SiteInfo {
+                    id: id.id(),
+                    kind: match &site.kind {
+                        site::SiteKind::Settlement(_) => world_msg::SiteKind::Town,
+                        site::SiteKind::Dungeon(dungeon) => world_msg::SiteKind::Dungeon {
+                            difficulty: dungeon.difficulty(),
+                        },
+                        site::SiteKind::Castle(_) => world_msg::SiteKind::Castle,
+                    },
+                    wpos: site.get_origin(),
+                    name: Some(site.name().to_owned()),
+                })
+                .collect(),
+            // Caves reach the surface at both of their ends
+            pois: self
+                .civs()
+                .caves()
+                .flat_map(|cave| {
+                    cave.first()
+                        .into_iter()
+                        .chain(cave.last().filter(|_| cave.len() > 1))
+                })
+                .map(|entrance| PoiInfo {
+                    kind: PoiKind::Cave,
+                    wpos: chunk_wpos(*entrance),
+                    name: None,
+                })
+                .collect(),
+            tracks: self
+                .civs()
+                .tracks()
+                .map(|(_, _, path)| {
+                    path.iter()
+                        .map(|chunk_pos| chunk_wpos(*chunk_pos))
+                        .collect()
+                })
+                .collect(),
+            ..self.sim.get_map(index)
+        }
+    }
 
     pub fn sample_columns(
         &self,
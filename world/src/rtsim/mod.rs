@@ -1 +1,383 @@
+//! Real-time simulation of the NPCs that live in the world (rtsim). Every
+//! settlement has a population of travellers that persists while nobody is
+//! around to see them. While their chunk isn't loaded they are simulated at low
+//! fidelity and walk along the tracks between sites, and once players come near
+//! the server loads them as full entities.
 
+use crate::{civ::SiteKind, World};
+use common::{
+    comp::{self, humanoid},
+    generation::EntityInfo,
+    rtsim::RtSimId,
+    terrain::TerrainChunkSize,
+    vol::RectVolSize,
+};
+use rand::{prelude::*, rngs::SmallRng};
+use vek::*;
+
+/// Number of travellers that call each settlement their home
+const NPCS_PER_SETTLEMENT: usize = 8;
+/// Speed of travellers while they are simulated, in blocks per second
+const TRAVEL_SPEED: f32 = 4.0;
+/// Seconds travellers rest in a site before they set off again
+const REST_TIME: f32 = 300.0;
+/// Horizontal distance in blocks at which a traveller has reached a point of
+/// its path
+const ARRIVAL_DIST: f32 = 8.0;
+
+struct Site {
+    wpos: Vec3<f32>,
+    /// Tracks leading away from this site
+    tracks: Vec<usize>,
+}
+
+struct Track {
+    /// The sites the track leads from and to
+    sites: [usize; 2],
+    path: Vec<Vec3<f32>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Journey {
+    /// Resting in a site until the time runs out
+    Resting { site: usize, time_left: f32 },
+    /// Walking to a site along the points of a path
+    Travelling {
+        dest: usize,
+        path: Vec<Vec3<f32>>,
+        next: usize,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct Npc {
+    pub body: comp::Body,
+    /// The site the NPC returns to after it died
+    pub home: usize,
+    pub wpos: Vec3<f32>,
+    pub journey: Journey,
+    /// Whether the NPC exists as an entity. Loaded NPCs are moved by the
+    /// server, which keeps `wpos` up to date.
+    pub is_loaded: bool,
+}
+
+pub struct RtSim {
+    sites: Vec<Site>,
+    tracks: Vec<Track>,
+    npcs: Vec<Npc>,
+    rng: SmallRng,
+}
+
+impl Default for RtSim {
+    fn default() -> Self {
+        Self {
+            sites: Vec::new(),
+            tracks: Vec::new(),
+            npcs: Vec::new(),
+            rng: SmallRng::seed_from_u64(0),
+        }
+    }
+}
+
+impl RtSim {
+    /// Settles the travellers of every settlement in their home
+    pub fn generate(world: &World, seed: u32) -> Self {
+        let mut rng = SmallRng::seed_from_u64(seed as u64);
+        let chunk_wpos = |chunk_pos: Vec2<i32>| {
+            let sim_chunk = world.sim().get(chunk_pos);
+            let offset = sim_chunk.map_or(Vec2::zero(), |chunk| chunk.path.0.offset);
+            let wpos = chunk_pos.map2(TerrainChunkSize::RECT_SIZE, |e, sz: u32| {
+                e * sz as i32 + sz as i32 / 2
+            }) + offset.map(i32::from);
+            wpos.map(|e| e as f32)
+                .with_z(sim_chunk.map_or(0.0, |chunk| chunk.alt))
+        };
+
+        let mut sites = world
+            .civs()
+            .sites()
+            .map(|site| Site {
+                wpos: chunk_wpos(site.center),
+                tracks: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+        let mut tracks = Vec::new();
+        for (a, b, path) in world.civs().tracks() {
+            let track = Track {
+                sites: [a.id() as usize, b.id() as usize],
+                path: path
+                    .iter()
+                    .map(|chunk_pos| chunk_wpos(*chunk_pos))
+                    .collect(),
+            };
+            // Tracks can be walked in both directions
+            for site in track.sites.iter() {
+                sites[*site].tracks.push(tracks.len());
+            }
+            tracks.push(track);
+        }
+
+        let mut npcs = Vec::new();
+        for (home, site) in world.civs().sites().enumerate() {
+            if !matches!(site.kind, SiteKind::Settlement) {
+                continue;
+            }
+            for _ in 0..NPCS_PER_SETTLEMENT {
+                let species = *humanoid::ALL_SPECIES.choose(&mut rng).unwrap();
+                npcs.push(Npc {
+                    body: comp::Body::Humanoid(humanoid::Body::random_with(&mut rng, &species)),
+                    home,
+                    wpos: sites[home].wpos,
+                    journey: Journey::Resting {
+                        site: home,
+                        // Don't have everybody leave at once
+                        time_left: rng.gen_range(0.0, REST_TIME),
+                    },
+                    is_loaded: false,
+                });
+            }
+        }
+
+        Self {
+            sites,
+            tracks,
+            npcs,
+            rng,
+        }
+    }
+
+    pub fn npcs(&self) -> impl Iterator<Item = (RtSimId, &Npc)> { self.npcs.iter().enumerate() }
+
+    pub fn get(&self, id: RtSimId) -> Option<&Npc> { self.npcs.get(id) }
+
+    pub fn get_mut(&mut self, id: RtSimId) -> Option<&mut Npc> { self.npcs.get_mut(id) }
+
+    /// The position the NPC is heading to, either the next point of its path
+    /// or the site it rests in
+    pub fn target(&self, id: RtSimId) -> Option<Vec3<f32>> {
+        self.npcs.get(id).map(|npc| match &npc.journey {
+            Journey::Resting { site, .. } => self.sites[*site].wpos,
+            Journey::Travelling { path, next, .. } => path[*next],
+        })
+    }
+
+    /// Brings an NPC that died back to life in its home
+    pub fn respawn(&mut self, id: RtSimId) {
+        if let Some(npc) = self.npcs.get_mut(id) {
+            npc.wpos = self.sites[npc.home].wpos;
+            npc.journey = Journey::Resting {
+                site: npc.home,
+                time_left: REST_TIME,
+            };
+            npc.is_loaded = false;
+        }
+    }
+
+    /// Advances the journeys of all NPCs by `dt` seconds. Only NPCs that
+    /// aren't loaded are moved, the others just notice where they got to.
+    pub fn tick(&mut self, dt: f32) {
+        let Self {
+            sites,
+            tracks,
+            npcs,
+            rng,
+        } = self;
+        for npc in npcs.iter_mut() {
+            let mut travel = TRAVEL_SPEED * dt;
+            loop {
+                match &mut npc.journey {
+                    Journey::Resting { site, time_left } => {
+                        *time_left -= dt;
+                        if *time_left > 0.0 {
+                            break;
+                        }
+                        let site = *site;
+                        npc.journey = match sites[site].tracks.choose(rng) {
+                            Some(track) => {
+                                let track = &tracks[*track];
+                                let mut path = track.path.clone();
+                                let dest = if track.sites[0] == site {
+                                    track.sites[1]
+                                } else {
+                                    path.reverse();
+                                    track.sites[0]
+                                };
+                                path.push(sites[dest].wpos);
+                                Journey::Travelling {
+                                    dest,
+                                    path,
+                                    next: 0,
+                                }
+                            },
+                            None => Journey::Resting {
+                                site,
+                                time_left: REST_TIME,
+                            },
+                        };
+                        break;
+                    },
+                    Journey::Travelling { dest, path, next } => {
+                        let target = path[*next];
+                        if !npc.is_loaded {
+                            let dist = target.xy().distance(npc.wpos.xy());
+                            let step = travel.min(dist);
+                            if dist > 0.0 {
+                                npc.wpos = Lerp::lerp(npc.wpos, target, step / dist);
+                            }
+                            travel -= step;
+                        }
+                        if target.xy().distance_squared(npc.wpos.xy()) > ARRIVAL_DIST.powi(2) {
+                            break;
+                        }
+                        *next += 1;
+                        if *next == path.len() {
+                            npc.journey = Journey::Resting {
+                                site: *dest,
+                                time_left: REST_TIME,
+                            };
+                            break;
+                        }
+                        // Unloaded NPCs keep walking with the time that is left
+                        if npc.is_loaded || travel <= 0.0 {
+                            break;
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
+
+impl Npc {
+    /// The entity this NPC is loaded as
+    pub fn entity_info(&self) -> EntityInfo {
+        EntityInfo::at(self.wpos)
+            .with_body(self.body)
+            .with_alignment(comp::Alignment::Npc)
+            .with_automatic_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two sites 100 blocks apart with a straight track between them, and an
+    /// NPC that rests in the first one for `time_left` seconds
+    fn setup(time_left: f32) -> RtSim {
+        RtSim {
+            sites: vec![
+                Site {
+                    wpos: Vec3::zero(),
+                    tracks: vec![0],
+                },
+                Site {
+                    wpos: Vec3::new(100.0, 0.0, 0.0),
+                    tracks: vec![0],
+                },
+            ],
+            tracks: vec![Track {
+                sites: [0, 1],
+                path: vec![Vec3::new(50.0, 0.0, 0.0)],
+            }],
+            npcs: vec![Npc {
+                body: comp::Body::Humanoid(humanoid::Body::random()),
+                home: 0,
+                wpos: Vec3::zero(),
+                journey: Journey::Resting { site: 0, time_left },
+                is_loaded: false,
+            }],
+            rng: SmallRng::seed_from_u64(0),
+        }
+    }
+
+    fn assert_at(rtsim: &RtSim, x: f32) {
+        let wpos = rtsim.get(0).unwrap().wpos;
+        assert!(
+            wpos.distance(Vec3::new(x, 0.0, 0.0)) < 0.01,
+            "NPC is at {:?} instead of {}",
+            wpos,
+            x
+        );
+    }
+
+    #[test]
+    fn npcs_rest_travel_and_arrive() {
+        let mut rtsim = setup(10.0);
+        rtsim.tick(5.0);
+        assert!(matches!(rtsim.get(0).unwrap().journey, Journey::Resting {
+            site: 0,
+            ..
+        }));
+        assert_at(&rtsim, 0.0);
+
+        // Once rested, the NPC heads to the other site along the track
+        rtsim.tick(5.0);
+        assert!(matches!(
+            rtsim.get(0).unwrap().journey,
+            Journey::Travelling { dest: 1, next: 0, .. }
+        ));
+        assert_eq!(rtsim.target(0), Some(Vec3::new(50.0, 0.0, 0.0)));
+
+        rtsim.tick(40.0 / TRAVEL_SPEED);
+        assert_at(&rtsim, 40.0);
+        assert!(matches!(
+            rtsim.get(0).unwrap().journey,
+            Journey::Travelling { next: 0, .. }
+        ));
+
+        // Walking further than the destination ends the journey there
+        rtsim.tick(100.0 / TRAVEL_SPEED);
+        assert_at(&rtsim, 100.0);
+        assert!(matches!(rtsim.get(0).unwrap().journey, Journey::Resting {
+            site: 1,
+            ..
+        }));
+    }
+
+    #[test]
+    fn npcs_keep_walking_past_points() {
+        let mut rtsim = setup(0.0);
+        rtsim.tick(0.0);
+
+        // Reaching the point of the track leaves 10 blocks to walk towards
+        // the site
+        rtsim.tick(60.0 / TRAVEL_SPEED);
+        assert_at(&rtsim, 60.0);
+        assert!(matches!(
+            rtsim.get(0).unwrap().journey,
+            Journey::Travelling { next: 1, .. }
+        ));
+        assert_eq!(rtsim.target(0), Some(Vec3::new(100.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn loaded_npcs_are_moved_by_the_server() {
+        let mut rtsim = setup(0.0);
+        rtsim.tick(0.0);
+        rtsim.get_mut(0).unwrap().is_loaded = true;
+
+        rtsim.tick(40.0 / TRAVEL_SPEED);
+        assert_at(&rtsim, 0.0);
+
+        // The NPC heads on once the server brought it close to the point
+        rtsim.get_mut(0).unwrap().wpos = Vec3::new(45.0, 0.0, 0.0);
+        rtsim.tick(1.0);
+        assert_at(&rtsim, 45.0);
+        assert_eq!(rtsim.target(0), Some(Vec3::new(100.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn dead_npcs_respawn_at_home() {
+        let mut rtsim = setup(0.0);
+        rtsim.tick(0.0);
+        rtsim.get_mut(0).unwrap().is_loaded = true;
+        rtsim.get_mut(0).unwrap().wpos = Vec3::new(45.0, 0.0, 0.0);
+
+        rtsim.respawn(0);
+        let npc = rtsim.get(0).unwrap();
+        assert!(!npc.is_loaded);
+        assert!(matches!(npc.journey, Journey::Resting { site: 0, .. }));
+        assert_at(&rtsim, 0.0);
+    }
+}
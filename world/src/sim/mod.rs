@@ -7,6 +7,7 @@ mod way;
 
 // Reexports
 use self::erosion::Compute;
+pub(crate) use self::location::generate_name;
 pub use self::{
     diffusion::diffusion,
     erosion::{
@@ -1516,6 +1517,9 @@ impl WorldSim {
             rgba: v,
             alt: alts,
             horizons,
+            sites: Vec::new(),
+            pois: Vec::new(),
+            tracks: Vec::new(),
         }
     }
 
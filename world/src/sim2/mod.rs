@@ -12,7 +12,7 @@ use tracing::debug;
 
 const MONTH: f32 = 30.0;
 const YEAR: f32 = 12.0 * MONTH;
-const TICK_PERIOD: f32 = 3.0 * MONTH; // 3 months
+pub const TICK_PERIOD: f32 = 3.0 * MONTH; // 3 months
 const HISTORY_DAYS: f32 = 500.0 * YEAR; // 500 years
 
 const GENERATE_CSV: bool = false;
@@ -59,27 +59,28 @@ pub fn simulate(index: &mut Index, world: &mut WorldSim) {
         if let Some(f) = f.as_mut() {
             if i % 5 == 0 {
                 let site = index.sites.values().next().unwrap();
-                write!(f, "{},", site.economy.pop).unwrap();
+                let economy = site.economy();
+                write!(f, "{},", economy.pop).unwrap();
                 for g in Good::list() {
-                    write!(f, "{:?},", site.economy.values[*g].unwrap_or(-1.0)).unwrap();
+                    write!(f, "{:?},", economy.values[*g].unwrap_or(-1.0)).unwrap();
                 }
                 for g in Good::list() {
-                    write!(f, "{:?},", site.economy.labor_values[*g].unwrap_or(-1.0)).unwrap();
+                    write!(f, "{:?},", economy.labor_values[*g].unwrap_or(-1.0)).unwrap();
                 }
                 for g in Good::list() {
-                    write!(f, "{:?},", site.economy.stocks[*g]).unwrap();
+                    write!(f, "{:?},", economy.stocks[*g]).unwrap();
                 }
                 for g in Good::list() {
-                    write!(f, "{:?},", site.economy.marginal_surplus[*g]).unwrap();
+                    write!(f, "{:?},", economy.marginal_surplus[*g]).unwrap();
                 }
                 for l in Labor::list() {
-                    write!(f, "{:?},", site.economy.labors[*l] * site.economy.pop).unwrap();
+                    write!(f, "{:?},", economy.labors[*l] * economy.pop).unwrap();
                 }
                 for l in Labor::list() {
-                    write!(f, "{:?},", site.economy.productivity[*l]).unwrap();
+                    write!(f, "{:?},", economy.productivity[*l]).unwrap();
                 }
                 for l in Labor::list() {
-                    write!(f, "{:?},", site.economy.yields[*l]).unwrap();
+                    write!(f, "{:?},", economy.yields[*l]).unwrap();
                 }
                 writeln!(f).unwrap();
             }
@@ -88,13 +89,19 @@ pub fn simulate(index: &mut Index, world: &mut WorldSim) {
 }
 
 pub fn tick(index: &mut Index, _world: &mut WorldSim, dt: f32) {
-    for site in index.sites.ids() {
-        tick_site_economy(index, site, dt);
-    }
+    tick_sites(index, index.time, dt);
 
     index.time += dt;
 }
 
+/// Advances the economies of all sites by `dt` from `time`. The index isn't
+/// changed otherwise, so this also works on the index of a running world.
+pub fn tick_sites(index: &Index, time: f32, dt: f32) {
+    for site in index.sites.ids() {
+        tick_site_economy(index, site, time, dt);
+    }
+}
+
 /// Simulate a site's economy. This simulation is roughly equivalent to the
 /// Lange-Lerner model's solution to the socialist calculation problem. The
 /// simulation begins by assigning arbitrary values to each commodity and then
@@ -112,41 +119,41 @@ pub fn tick(index: &mut Index, _world: &mut WorldSim, dt: f32) {
 /// dynamically react to environmental changes. If a product becomes available
 /// through a mechanism such as trade, an entire arm of the economy may
 /// materialise to take advantage of this.
-pub fn tick_site_economy(index: &mut Index, site: Id<Site>, dt: f32) {
-    let site = &mut index.sites[site];
+pub fn tick_site_economy(index: &Index, site: Id<Site>, time: f32, dt: f32) {
+    let mut economy = index.sites[site].economy_mut();
+    let economy = &mut *economy;
 
-    let orders = site.economy.get_orders();
-    let productivity = site.economy.get_productivity();
+    let orders = economy.get_orders();
+    let productivity = economy.get_productivity();
 
     let mut demand = MapVec::from_default(0.0);
     for (labor, orders) in &orders {
         let scale = if let Some(labor) = labor {
-            site.economy.labors[*labor]
+            economy.labors[*labor]
         } else {
             1.0
-        } * site.economy.pop;
+        } * economy.pop;
         for (good, amount) in orders {
             demand[*good] += *amount * scale;
         }
     }
 
-    let mut supply = site.economy.stocks.clone(); //MapVec::from_default(0.0);
+    let mut supply = economy.stocks.clone(); //MapVec::from_default(0.0);
     for (labor, (output_good, _)) in productivity.iter() {
-        supply[*output_good] +=
-            site.economy.yields[labor] * site.economy.labors[labor] * site.economy.pop;
+        supply[*output_good] += economy.yields[labor] * economy.labors[labor] * economy.pop;
     }
 
-    let stocks = &site.economy.stocks;
-    site.economy.surplus = demand
+    let stocks = &economy.stocks;
+    economy.surplus = demand
         .clone()
         .map(|g, demand| supply[g] + stocks[g] - demand);
-    site.economy.marginal_surplus = demand.clone().map(|g, demand| supply[g] - demand);
+    economy.marginal_surplus = demand.clone().map(|g, demand| supply[g] - demand);
 
     // Update values according to the surplus of each stock
     // Note that values are used for workforce allocation and are not the same thing
     // as price
-    let values = &mut site.economy.values;
-    site.economy.surplus.iter().for_each(|(good, surplus)| {
+    let values = &mut economy.values;
+    economy.surplus.iter().for_each(|(good, surplus)| {
         // Value rationalisation
         let val = 2.0f32.powf(1.0 - *surplus / demand[good]);
         let smooth = 0.8;
@@ -177,29 +184,29 @@ pub fn tick_site_economy(index: &mut Index, site: Id<Site>, dt: f32) {
 
     // Redistribute workforce according to relative good values
     let labor_ratios = productivity.clone().map(|labor, (output_good, _)| {
-        site.economy.values[output_good].unwrap_or(0.0)
-            * site.economy.productivity[labor]
+        economy.values[output_good].unwrap_or(0.0)
+            * economy.productivity[labor]
         //(site.economy.prices[output_good] - site.economy.material_costs[output_good]) * site.economy.yields[labor]
         //* demand[output_good] / supply[output_good].max(0.001)
     });
     let labor_ratio_sum = labor_ratios.iter().map(|(_, r)| *r).sum::<f32>().max(0.01);
     productivity.iter().for_each(|(labor, _)| {
         let smooth = 0.8;
-        site.economy.labors[labor] = smooth * site.economy.labors[labor]
+        economy.labors[labor] = smooth * economy.labors[labor]
             + (1.0 - smooth)
                 * (labor_ratios[labor].max(labor_ratio_sum / 1000.0) / labor_ratio_sum);
     });
 
     // Production
-    let stocks_before = site.economy.stocks.clone();
+    let stocks_before = economy.stocks.clone();
     let mut total_labor_values = MapVec::<_, f32>::default();
     let mut total_outputs = MapVec::<_, f32>::default();
     for (labor, orders) in orders.iter() {
         let scale = if let Some(labor) = labor {
-            site.economy.labors[*labor]
+            economy.labors[*labor]
         } else {
             1.0
-        } * site.economy.pop;
+        } * economy.pop;
 
         // For each order, we try to find the minimum satisfaction rate - this limits
         // how much we can produce! For example, if we need 0.25 fish and
@@ -226,26 +233,26 @@ pub fn tick_site_economy(index: &mut Index, site: Id<Site>, dt: f32) {
             let used = quantity * labor_productivity;
 
             // Material cost of each factor of production
-            total_materials_cost += used * site.economy.labor_values[*good].unwrap_or(0.0);
+            total_materials_cost += used * economy.labor_values[*good].unwrap_or(0.0);
 
             // Deplete stocks accordingly
-            site.economy.stocks[*good] = (site.economy.stocks[*good] - used).max(0.0);
+            economy.stocks[*good] = (economy.stocks[*good] - used).max(0.0);
         }
 
         // Industries produce things
         if let Some(labor) = labor {
             let (stock, rate) = productivity[*labor];
-            let workers = site.economy.labors[*labor] * site.economy.pop;
+            let workers = economy.labors[*labor] * economy.pop;
             let final_rate = rate;
             let yield_per_worker =
                 labor_productivity * final_rate * (1.0 + workers / 100.0).min(3.0);
-            site.economy.yields[*labor] = yield_per_worker;
-            site.economy.productivity[*labor] = labor_productivity;
+            economy.yields[*labor] = yield_per_worker;
+            economy.productivity[*labor] = labor_productivity;
             let total_output = yield_per_worker * workers;
-            site.economy.stocks[stock] += total_output;
+            economy.stocks[stock] += total_output;
 
             // Materials cost per unit
-            site.economy.material_costs[stock] = total_materials_cost / total_output.max(0.001);
+            economy.material_costs[stock] = total_materials_cost / total_output.max(0.001);
             // Labor costs
             let wages = 1.0;
             let total_labor_cost = workers * wages;
@@ -256,7 +263,7 @@ pub fn tick_site_economy(index: &mut Index, site: Id<Site>, dt: f32) {
     }
 
     // Update labour values per unit
-    site.economy.labor_values = total_labor_values.map(|stock, tlv| {
+    economy.labor_values = total_labor_values.map(|stock, tlv| {
         let total_output = total_outputs[stock];
         if total_output > 0.01 {
             Some(tlv / total_outputs[stock])
@@ -266,21 +273,21 @@ pub fn tick_site_economy(index: &mut Index, site: Id<Site>, dt: f32) {
     });
 
     // Decay stocks
-    site.economy
+    economy
         .stocks
         .iter_mut()
         .for_each(|(c, v)| *v *= 1.0 - c.decay_rate());
 
     // Decay stocks
-    site.economy.replenish(index.time);
+    economy.replenish(time);
 
     // Births/deaths
     const NATURAL_BIRTH_RATE: f32 = 0.05;
     const DEATH_RATE: f32 = 0.005;
-    let birth_rate = if site.economy.surplus[Good::Food] > 0.0 {
+    let birth_rate = if economy.surplus[Good::Food] > 0.0 {
         NATURAL_BIRTH_RATE
     } else {
         0.0
     };
-    site.economy.pop += dt / YEAR * site.economy.pop * (birth_rate - DEATH_RATE);
+    economy.pop += dt / YEAR * economy.pop * (birth_rate - DEATH_RATE);
 }
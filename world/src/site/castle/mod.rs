@@ -9,9 +9,11 @@ use crate::{
     IndexRef,
 };
 use common::{
-    generation::ChunkSupplement,
-    terrain::{Block, BlockKind, SpriteKind},
-    vol::{BaseVol, ReadVol, RectSizedVol, WriteVol},
+    comp::{self, humanoid, Item},
+    generation::{ChunkSupplement, EntityInfo},
+    loot::{LootSpec, LOOT_CONFIG},
+    terrain::{Block, BlockKind, SpriteKind, TerrainChunkSize},
+    vol::{BaseVol, ReadVol, RectSizedVol, RectVolSize, WriteVol},
 };
 use core::f32;
 use rand::prelude::*;
@@ -417,11 +419,50 @@ impl Castle {
     pub fn apply_supplement<'a>(
         &'a self,
         // NOTE: Used only for dynamic elements like chests and entities!
-        _dynamic_rng: &mut impl Rng,
-        _wpos2d: Vec2<i32>,
-        _get_column: impl FnMut(Vec2<i32>) -> Option<&'a ColumnSample<'a>>,
-        _supplement: &mut ChunkSupplement,
+        dynamic_rng: &mut impl Rng,
+        wpos2d: Vec2<i32>,
+        mut get_column: impl FnMut(Vec2<i32>) -> Option<&'a ColumnSample<'a>>,
+        supplement: &mut ChunkSupplement,
     ) {
-        // TODO
+        // A guard stands in front of every keep
+        for keep in &self.keeps {
+            let guard_wpos = self.origin + keep.offset + Vec2::new(keep.locus + 4, 0);
+            let offs = guard_wpos - wpos2d;
+            if offs.x < 0
+                || offs.y < 0
+                || offs.x >= TerrainChunkSize::RECT_SIZE.x as i32
+                || offs.y >= TerrainChunkSize::RECT_SIZE.y as i32
+            {
+                continue;
+            }
+            let col_sample = if let Some(col_sample) = get_column(offs) {
+                col_sample
+            } else {
+                continue;
+            };
+
+            let mut entity = EntityInfo::at(Vec3::new(
+                guard_wpos.x as f32,
+                guard_wpos.y as f32,
+                col_sample.alt + 3.0,
+            ))
+            .with_body(comp::Body::Humanoid(humanoid::Body::random()))
+            .with_alignment(if self.evil {
+                comp::Alignment::Enemy
+            } else {
+                comp::Alignment::Npc
+            })
+            .with_name("Castle Guard")
+            .with_main_tool(Item::new_from_asset_expect(
+                match dynamic_rng.gen_range(0, 2) {
+                    0 => "common.items.npc_weapons.sword.zweihander_sword_0",
+                    _ => "common.items.npc_weapons.sword.starter_sword",
+                },
+            ));
+            if let Some(table) = LOOT_CONFIG.site_table("castle", 0) {
+                entity = entity.with_loot_drop(LootSpec::LootTable(table.to_owned()));
+            }
+            supplement.add_entity(entity);
+        }
     }
 }
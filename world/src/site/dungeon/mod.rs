@@ -8,11 +8,10 @@ use crate::{
     IndexRef,
 };
 use common::{
-    assets::Asset,
     astar::Astar,
     comp::{self},
     generation::{ChunkSupplement, EntityInfo},
-    lottery::Lottery,
+    loot::{LootSpec, LOOT_CONFIG},
     store::{Id, Store},
     terrain::{Block, BlockKind, SpriteKind, Structure, TerrainChunkSize},
     vol::{BaseVol, ReadVol, RectSizedVol, RectVolSize, WriteVol},
@@ -32,6 +31,7 @@ pub struct Dungeon {
     #[allow(dead_code)]
     noise: RandomField,
     floors: Vec<Floor>,
+    difficulty: u32,
 }
 
 pub struct GenCtx<'a, R: Rng> {
@@ -48,9 +48,22 @@ const ALT_OFFSET: i32 = -2;
 
 const LEVELS: usize = 5;
 
+/// Difficulty of the dungeons furthest away from the centre of the world
+const MAX_DIFFICULTY: u32 = 5;
+
 impl Dungeon {
     #[allow(clippy::let_and_return)] // TODO: Pending review in #587
     pub fn generate(wpos: Vec2<i32>, sim: Option<&WorldSim>, rng: &mut impl Rng) -> Self {
+        // Players spawn in a town close to the centre of the world, so dungeons
+        // get harder the further away from it they are
+        let difficulty = sim.map_or(1, |sim| {
+            let center = (sim.get_size().map(|e| e as i32) / 2)
+                .map2(TerrainChunkSize::RECT_SIZE, |e, sz: u32| e * sz as i32);
+            let remoteness = (wpos.map(|e| e as f32).distance(center.map(|e| e as f32))
+                / center.x.max(1) as f32)
+                .min(1.0);
+            1 + (remoteness * (MAX_DIFFICULTY - 1) as f32).round() as u32
+        });
         let mut ctx = GenCtx { sim, rng };
         let this = Self {
             origin: wpos - TILE_SIZE / 2,
@@ -68,6 +81,7 @@ impl Dungeon {
                     Some(floor)
                 })
                 .collect(),
+            difficulty,
         };
 
         this
@@ -77,6 +91,10 @@ impl Dungeon {
 
     pub fn radius(&self) -> f32 { 1200.0 }
 
+    /// How hard the dungeon is, from 1 to `MAX_DIFFICULTY`. This is only shown
+    /// to players and doesn't change what spawns in the dungeon.
+    pub fn difficulty(&self) -> u32 { self.difficulty }
+
     #[allow(clippy::needless_update)] // TODO: Pending review in #587
     pub fn spawn_rules(&self, wpos: Vec2<i32>) -> SpawnRules {
         SpawnRules {
@@ -225,6 +243,7 @@ struct Floor {
     hollow_depth: i32,
     #[allow(dead_code)]
     stair_tile: Vec2<i32>,
+    level: i32,
     final_level: bool,
 }
 
@@ -258,6 +277,7 @@ impl Floor {
             solid_depth: if level == 0 { 80 } else { 32 },
             hollow_depth: 30,
             stair_tile: new_stair_tile - tile_offset,
+            level,
             final_level,
         };
 
@@ -471,14 +491,7 @@ impl Floor {
                         && !tile_is_pillar
                     {
                         // Bad
-                        let chosen =
-                            Lottery::<String>::load_expect(match dynamic_rng.gen_range(0, 5) {
-                                0 => "common.loot_tables.loot_table_humanoids",
-                                1 => "common.loot_tables.loot_table_armor_misc",
-                                _ => "common.loot_tables.loot_table_cultists",
-                            });
-                        let chosen = chosen.choose();
-                        let entity = EntityInfo::at(
+                        let mut entity = EntityInfo::at(
                             tile_wcenter.map(|e| e as f32)
                             // Randomly displace them a little
                             + Vec3::<u32>::iota()
@@ -489,7 +502,6 @@ impl Floor {
                         .with_alignment(comp::Alignment::Enemy)
                         .with_body(comp::Body::Humanoid(comp::humanoid::Body::random()))
                         .with_name("Cultist Acolyte")
-                        .with_loot_drop(comp::Item::new_from_asset_expect(chosen))
                         .with_main_tool(comp::Item::new_from_asset_expect(match dynamic_rng.gen_range(0, 6) {
                             0 => "common.items.npc_weapons.axe.malachite_axe-0",
                             1 => "common.items.npc_weapons.sword.cultist_purp_2h-0",
@@ -499,6 +511,9 @@ impl Floor {
                             _ => "common.items.npc_weapons.bow.horn_longbow-0",
                         }));
 
+                        if let Some(table) = LOOT_CONFIG.site_table("dungeon", self.level as u32) {
+                            entity = entity.with_loot_drop(LootSpec::LootTable(table.to_owned()));
+                        }
                         supplement.add_entity(entity);
                     }
 
@@ -517,19 +532,18 @@ impl Floor {
                             boss_spawn_tile + if boss_tile_is_pillar { 1 } else { 0 };
 
                         if tile_pos == boss_spawn_tile && tile_wcenter.xy() == wpos2d {
-                            let chosen = Lottery::<String>::load_expect(
-                                "common.loot_tables.loot_table_boss_cultist-leader",
-                            );
-                            let chosen = chosen.choose();
-                            let entity = EntityInfo::at(tile_wcenter.map(|e| e as f32))
+                            let mut entity = EntityInfo::at(tile_wcenter.map(|e| e as f32))
                                 .with_level(dynamic_rng.gen_range(1, 5))
                                 .with_alignment(comp::Alignment::Enemy)
                                 .with_body(comp::Body::Golem(comp::golem::Body::random_with(
                                     dynamic_rng,
                                     &comp::golem::Species::StoneGolem,
                                 )))
-                                .with_name("Stonework Defender".to_string())
-                                .with_loot_drop(comp::Item::new_from_asset_expect(chosen));
+                                .with_name("Stonework Defender".to_string());
+                            if let Some(table) = LOOT_CONFIG.site_boss_table("dungeon") {
+                                entity =
+                                    entity.with_loot_drop(LootSpec::LootTable(table.to_owned()));
+                            }
 
                             supplement.add_entity(entity);
                         }
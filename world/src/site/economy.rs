@@ -1,23 +1,12 @@
 use crate::util::{DHashMap, MapVec};
+use common::trade::{EconomyInfo, SiteId, SitePrices, TradingInformation};
+use serde::{Deserialize, Serialize};
 
-#[repr(u8)]
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub enum Good {
-    Wheat = 0,
-    Flour = 1,
-    Meat = 2,
-    Fish = 3,
-    Game = 4,
-    Food = 5,
-    Logs = 6,
-    Wood = 7,
-    Rock = 8,
-    Stone = 9,
-}
+pub use common::trade::Good;
 use Good::*;
 
 #[repr(u8)]
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum Labor {
     Farmer = 0,
     Lumberjack = 1,
@@ -28,6 +17,14 @@ pub enum Labor {
 }
 use Labor::*;
 
+/// Share of the stocks of a site that its merchants have for sale
+const MERCHANT_STOCK_SHARE: f32 = 0.1;
+/// Most of one good that a merchant has for sale
+const MAX_MERCHANT_STOCK: f32 = 50.0;
+/// Coins a merchant has per inhabitant of its site
+const MERCHANT_COINS_PER_POP: f32 = 2.0;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Economy {
     pub pop: f32,
 
@@ -100,6 +97,53 @@ impl Economy {
         .map(|l, (good, v)| (good, v * (1.0 + self.labors[l])))
     }
 
+    /// Prices for the merchants of the site, from the values of its goods
+    pub fn get_site_prices(&self) -> SitePrices {
+        SitePrices {
+            values: self
+                .values
+                .iter()
+                .filter_map(|(good, value)| value.map(|value| (good, value)))
+                .collect(),
+        }
+    }
+
+    pub fn get_trading_information(&self) -> TradingInformation {
+        TradingInformation {
+            prices: self.get_site_prices(),
+            stock: self
+                .stocks
+                .iter()
+                .map(|(good, stock)| {
+                    (
+                        good,
+                        (stock * MERCHANT_STOCK_SHARE).min(MAX_MERCHANT_STOCK) as u32,
+                    )
+                })
+                .filter(|(good, amount)| *amount > 0 && good.items().next().is_some())
+                .collect(),
+            coins: (self.pop * MERCHANT_COINS_PER_POP) as u32,
+        }
+    }
+
+    /// What clients get to know about the economy of the site
+    pub fn get_information(&self, id: SiteId) -> EconomyInfo {
+        EconomyInfo {
+            id,
+            population: self.pop.floor() as u32,
+            stock: self
+                .stocks
+                .iter()
+                .map(|(good, stock)| (good, *stock))
+                .collect(),
+            values: self
+                .values
+                .iter()
+                .filter_map(|(good, value)| value.map(|value| (good, value)))
+                .collect(),
+        }
+    }
+
     pub fn replenish(&mut self, time: f32) {
         //use rand::Rng;
         for (i, (g, v)) in [
@@ -120,32 +164,6 @@ impl Economy {
     }
 }
 
-impl Default for Good {
-    fn default() -> Self {
-        Good::Rock // Arbitrary
-    }
-}
-
-impl Good {
-    pub fn list() -> &'static [Self] {
-        static GOODS: [Good; 10] = [
-            Wheat, Flour, Meat, Fish, Game, Food, Logs, Wood, Rock, Stone,
-        ];
-
-        &GOODS
-    }
-
-    pub fn decay_rate(&self) -> f32 {
-        match self {
-            Food => 0.2,
-            Wheat => 0.1,
-            Meat => 0.25,
-            Fish => 0.2,
-            _ => 0.0,
-        }
-    }
-}
-
 impl Labor {
     pub fn list() -> &'static [Self] {
         static LABORS: [Labor; 6] = [Farmer, Lumberjack, Miner, Fisher, Hunter, Cook];
@@ -18,6 +18,7 @@ use common::{
 };
 use rand::Rng;
 use serde::Deserialize;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use vek::*;
 
 #[derive(Deserialize)]
@@ -37,7 +38,10 @@ impl Default for SpawnRules {
 
 pub struct Site {
     pub kind: SiteKind,
-    pub economy: Economy,
+    name: String,
+    /// The economy keeps running while the world is shared with the threads
+    /// that generate chunks, so it is behind a lock
+    economy: RwLock<Economy>,
 }
 
 pub enum SiteKind {
@@ -50,24 +54,42 @@ impl Site {
     pub fn settlement(s: Settlement) -> Self {
         Self {
             kind: SiteKind::Settlement(s),
-            economy: Economy::default(),
+            name: String::new(),
+            economy: RwLock::new(Economy::default()),
         }
     }
 
     pub fn dungeon(d: Dungeon) -> Self {
         Self {
             kind: SiteKind::Dungeon(d),
-            economy: Economy::default(),
+            name: String::new(),
+            economy: RwLock::new(Economy::default()),
         }
     }
 
     pub fn castle(c: Castle) -> Self {
         Self {
             kind: SiteKind::Castle(c),
-            economy: Economy::default(),
+            name: String::new(),
+            economy: RwLock::new(Economy::default()),
         }
     }
 
+    pub fn with_name(mut self, name: String) -> Self {
+        self.name = name;
+        self
+    }
+
+    pub fn name(&self) -> &str { &self.name }
+
+    pub fn economy(&self) -> RwLockReadGuard<'_, Economy> {
+        self.economy.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn economy_mut(&self) -> RwLockWriteGuard<'_, Economy> {
+        self.economy.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
     pub fn radius(&self) -> f32 {
         match &self.kind {
             SiteKind::Settlement(s) => s.radius(),
@@ -116,7 +138,7 @@ impl Site {
     ) {
         match &self.kind {
             SiteKind::Settlement(s) => {
-                s.apply_supplement(dynamic_rng, wpos2d, get_column, supplement)
+                s.apply_supplement(dynamic_rng, wpos2d, get_column, supplement, &self.economy())
             },
             SiteKind::Dungeon(d) => d.apply_supplement(dynamic_rng, wpos2d, get_column, supplement),
             SiteKind::Castle(c) => c.apply_supplement(dynamic_rng, wpos2d, get_column, supplement),
@@ -5,7 +5,7 @@ use self::{
     building::{Building, House, Keep},
     town::{District, Town},
 };
-use super::SpawnRules;
+use super::{economy::Economy, SpawnRules};
 use crate::{
     column::ColumnSample,
     sim::WorldSim,
@@ -841,6 +841,7 @@ impl Settlement {
         wpos2d: Vec2<i32>,
         mut get_column: impl FnMut(Vec2<i32>) -> Option<&'a ColumnSample<'a>>,
         supplement: &mut ChunkSupplement,
+        economy: &Economy,
     ) {
         for y in 0..TerrainChunkSize::RECT_SIZE.y as i32 {
             for x in 0..TerrainChunkSize::RECT_SIZE.x as i32 {
@@ -866,6 +867,8 @@ impl Settlement {
                     let is_human: bool;
                     let is_dummy =
                         RandomField::new(self.seed + 1).chance(Vec3::from(wpos2d), 1.0 / 15.0);
+                    let is_merchant =
+                        RandomField::new(self.seed + 2).chance(Vec3::from(wpos2d), 1.0 / 4.0);
                     let entity = EntityInfo::at(entity_wpos)
                         .with_body(match dynamic_rng.gen_range(0, 5) {
                             _ if is_dummy => {
@@ -925,9 +928,17 @@ impl Settlement {
                             ))
                         })
                         .do_if(is_dummy, |e| e.with_name("Training Dummy"))
-                        .do_if(!is_dummy, |e| e.with_automatic_name());
+                        .do_if(!is_dummy, |e| e.with_automatic_name())
+                        .do_if(is_human && is_merchant, |e| {
+                            let name = format!("{} the Merchant", e.name.as_deref().unwrap_or(""));
+                            e.with_name(name)
+                                .with_trading_information(economy.get_trading_information())
+                        });
 
-                    supplement.add_entity(entity);
+                    // The other villagers are travellers simulated by rtsim
+                    if !is_human || is_merchant {
+                        supplement.add_entity(entity);
+                    }
                 }
             }
         }
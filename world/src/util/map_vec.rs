@@ -1,7 +1,9 @@
 use crate::util::DHashMap;
+use serde::{Deserialize, Serialize};
 use std::hash::Hash;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(deserialize = "K: Eq + Hash + Deserialize<'de>, T: Deserialize<'de>"))]
 pub struct MapVec<K, T> {
     /// We use this hasher (FxHasher32) because
     /// (1) we don't care about DDOS attacks (ruling out SipHash);